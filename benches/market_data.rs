@@ -0,0 +1,83 @@
+//! Binary feed encoding and decoding of trades against `serde_json`
+//!
+//! Run with `cargo bench --features benchmark --bench market_data`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rustflow::marketdata::binary;
+use rustflow::marketdata::FeedEncoder;
+use rustflow::{OrderSide, Trade};
+
+const TRADE_COUNT: u64 = 1_000;
+
+fn trades() -> Vec<Trade> {
+    (1..=TRADE_COUNT)
+        .map(|id| Trade::new(id, 10_000 + id % 50, 1 + id % 7, 1_700_000_000_000_000_000 + id, id * 2, id * 2 + 1, 1001, 1002, OrderSide::Buy, "BTC-USD".to_string()))
+        .collect()
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let trades = trades();
+    let mut group = c.benchmark_group("encode_trade");
+    group.throughput(Throughput::Elements(TRADE_COUNT));
+
+    group.bench_function("binary", |b| {
+        let mut buf = Vec::with_capacity(128 * trades.len());
+        b.iter(|| {
+            buf.clear();
+            let mut encoder = FeedEncoder::new();
+            for trade in &trades {
+                encoder.encode_trade(trade, &mut buf).unwrap();
+            }
+            black_box(buf.len())
+        });
+    });
+    group.bench_function("serde_json", |b| {
+        let mut buf = Vec::with_capacity(256 * trades.len());
+        b.iter(|| {
+            buf.clear();
+            for trade in &trades {
+                serde_json::to_writer(&mut buf, trade).unwrap();
+                buf.push(b'\n');
+            }
+            black_box(buf.len())
+        });
+    });
+
+    group.finish();
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let trades = trades();
+    let mut binary_buf = Vec::new();
+    let mut encoder = FeedEncoder::new();
+    let mut json_lines = Vec::new();
+    for trade in &trades {
+        encoder.encode_trade(trade, &mut binary_buf).unwrap();
+        json_lines.push(serde_json::to_vec(trade).unwrap());
+    }
+
+    let mut group = c.benchmark_group("decode_trade");
+    group.throughput(Throughput::Elements(TRADE_COUNT));
+
+    group.bench_function("binary", |b| {
+        b.iter(|| {
+            let mut offset = 0;
+            while let Some((message, length)) = binary::decode(&binary_buf[offset..]).unwrap() {
+                black_box(message);
+                offset += length;
+            }
+        });
+    });
+    group.bench_function("serde_json", |b| {
+        b.iter(|| {
+            for line in &json_lines {
+                black_box(serde_json::from_slice::<Trade>(line).unwrap());
+            }
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode, bench_decode);
+criterion_main!(benches);
@@ -0,0 +1,58 @@
+//! Latency histogram recording and percentile queries
+//!
+//! Run with `cargo bench --features benchmark --bench metrics`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rustflow::utils::metrics::Histogram;
+
+const SAMPLE_COUNT: u64 = 10_000;
+
+/// Latency-like values in nanoseconds: mostly a few microseconds with a long tail
+fn latencies(count: u64) -> Vec<u64> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    (0..count)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            match state % 100 {
+                0 => 100_000 + state % 10_000_000,
+                1..=9 => 10_000 + state % 90_000,
+                _ => 500 + state % 9_500,
+            }
+        })
+        .collect()
+}
+
+fn bench_observe(c: &mut Criterion) {
+    let values = latencies(SAMPLE_COUNT);
+    let mut group = c.benchmark_group("histogram_observe");
+    group.throughput(Throughput::Elements(SAMPLE_COUNT));
+    group.bench_function("latencies", |b| {
+        b.iter(|| {
+            let mut histogram = Histogram::new();
+            for &value in &values {
+                histogram.observe(value);
+            }
+            black_box(histogram.count())
+        });
+    });
+    group.finish();
+}
+
+fn bench_percentile(c: &mut Criterion) {
+    let mut group = c.benchmark_group("histogram_percentile");
+    for count in [1_000, 100_000] {
+        let mut histogram = Histogram::new();
+        for value in latencies(count) {
+            histogram.observe(value);
+        }
+        group.bench_with_input(BenchmarkId::new("p99", count), &histogram, |b, histogram| {
+            b.iter(|| black_box(histogram.percentile(99.0)));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_observe, bench_percentile);
+criterion_main!(benches);
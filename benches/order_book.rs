@@ -0,0 +1,188 @@
+//! Order book operations on generated books
+//!
+//! Run with `cargo bench --features benchmark --bench order_book`.
+
+use std::collections::BTreeMap;
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use rustflow::{Matcher, Order, OrderBook, OrderSide, OrderType, Price, Quantity};
+
+const SYMBOL: &str = "BTC-USD";
+const MID_PRICE: u64 = 100_000;
+const ORDERS_PER_LEVEL: u64 = 4;
+const FLOW_SIZE: u64 = 1_000;
+
+/// Deterministic pseudo-random numbers, so every run sees the same books
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: u64) -> u64 {
+        self.0 = self.0.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+        (self.0 >> 33) % bound
+    }
+}
+
+/// Resting limit orders on `levels` one-tick price levels per side of the mid price
+fn resting_orders(levels: u64) -> Vec<Order> {
+    let mut rng = Lcg(levels);
+    let mut orders = Vec::new();
+    for level in 0..levels {
+        for side in [OrderSide::Buy, OrderSide::Sell] {
+            let price = match side {
+                OrderSide::Buy => MID_PRICE - 1 - level,
+                OrderSide::Sell => MID_PRICE + 1 + level,
+            };
+            for _ in 0..ORDERS_PER_LEVEL {
+                let id = orders.len() as u64 + 1;
+                let quantity = 1 + rng.next(10);
+                orders.push(Order::new_limit(id, price, quantity, side, 1 + rng.next(50), id, None, SYMBOL.to_string()));
+            }
+        }
+    }
+    orders
+}
+
+fn book(levels: u64) -> OrderBook {
+    let mut book = OrderBook::new(SYMBOL);
+    for order in resting_orders(levels) {
+        book.process_order(order);
+    }
+    book
+}
+
+/// Incoming orders of one type, alternating sides, with prices near the touch
+fn order_flow(order_type: OrderType, first_id: u64) -> Vec<Order> {
+    let mut rng = Lcg(first_id);
+    (0..FLOW_SIZE)
+        .map(|i| {
+            let side = if i % 2 == 0 { OrderSide::Buy } else { OrderSide::Sell };
+            // Positive offsets cross the spread, negative ones stay passive
+            let offset = rng.next(20) as i64 - 8;
+            let price = match side {
+                OrderSide::Buy => MID_PRICE as i64 + offset,
+                OrderSide::Sell => MID_PRICE as i64 - offset,
+            } as u64;
+            let order_type = match order_type {
+                OrderType::Stop(_) => OrderType::Stop(Price(price)),
+                OrderType::StopLimit(_, _) => OrderType::StopLimit(Price(price), Price(price)),
+                other => other,
+            };
+            let id = first_id + i;
+            Order::new(id, order_type, price, 1 + rng.next(5), side, 1 + rng.next(50), id, None, SYMBOL.to_string())
+        })
+        .collect()
+}
+
+fn bench_process_order(c: &mut Criterion) {
+    const LEVELS: u64 = 200;
+    let first_id = 2 * LEVELS * ORDERS_PER_LEVEL + 1;
+    let order_types = [
+        ("limit", OrderType::Limit),
+        ("market", OrderType::Market),
+        ("ioc", OrderType::IOC),
+        ("fok", OrderType::FOK),
+        ("stop", OrderType::Stop(Price::ZERO)),
+        ("stop_limit", OrderType::StopLimit(Price::ZERO, Price::ZERO)),
+    ];
+
+    let mut group = c.benchmark_group("process_order");
+    group.throughput(Throughput::Elements(FLOW_SIZE));
+    for (name, order_type) in order_types {
+        let flow = order_flow(order_type, first_id);
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || (book(LEVELS), flow.clone()),
+                |(mut book, flow)| {
+                    for order in flow {
+                        black_box(book.process_order(order));
+                    }
+                    book
+                },
+                BatchSize::PerIteration,
+            );
+        });
+    }
+    group.finish();
+}
+
+fn bench_cancel_order(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cancel_order");
+    for levels in [10, 100, 1_000] {
+        let order_count = 2 * levels * ORDERS_PER_LEVEL;
+        // Cancel in a scattered order rather than level by level
+        let mut rng = Lcg(levels);
+        let mut ids: Vec<u64> = (1..=order_count).collect();
+        for i in (1..ids.len()).rev() {
+            ids.swap(i, rng.next(i as u64 + 1) as usize);
+        }
+
+        group.throughput(Throughput::Elements(order_count));
+        group.bench_with_input(BenchmarkId::new("levels", levels), &ids, |b, ids| {
+            b.iter_batched(
+                || book(levels),
+                |mut book| {
+                    for &id in ids {
+                        black_box(book.cancel_order(id));
+                    }
+                    book
+                },
+                BatchSize::PerIteration,
+            );
+        });
+    }
+    group.finish();
+}
+
+fn bench_queries(c: &mut Criterion) {
+    let book = book(1_000);
+
+    let mut group = c.benchmark_group("market_depth");
+    for levels in [5, 10, 100] {
+        group.bench_with_input(BenchmarkId::from_parameter(levels), &levels, |b, &levels| {
+            b.iter(|| black_box(book.market_depth(levels)));
+        });
+    }
+    group.finish();
+
+    // Roughly 1, 10 and 100 levels of the generated book
+    let mut group = c.benchmark_group("calculate_slippage");
+    for quantity in [20, 200, 2_000] {
+        group.bench_with_input(BenchmarkId::from_parameter(quantity), &quantity, |b, &quantity| {
+            b.iter(|| black_box(book.calculate_slippage(OrderSide::Buy, Quantity(quantity))));
+        });
+    }
+    group.finish();
+}
+
+fn bench_simulate_order_match(c: &mut Criterion) {
+    let matcher = Matcher::new();
+    let mut group = c.benchmark_group("simulate_order_match");
+    for levels in [10, 100, 1_000] {
+        let mut bids: BTreeMap<Price, Vec<Order>> = BTreeMap::new();
+        let mut asks: BTreeMap<Price, Vec<Order>> = BTreeMap::new();
+        for mut order in resting_orders(levels) {
+            order.accept().unwrap();
+            let side = match order.side {
+                OrderSide::Buy => &mut bids,
+                OrderSide::Sell => &mut asks,
+            };
+            side.entry(order.price).or_default().push(order);
+        }
+        // A fill-or-kill order that takes the first few levels
+        let order = Order::new(0, OrderType::FOK, MID_PRICE + 5, 20, OrderSide::Buy, 1, 0, None, SYMBOL.to_string());
+
+        group.bench_with_input(BenchmarkId::new("levels", levels), &order, |b, order| {
+            b.iter(|| black_box(matcher.simulate_order_match(order, &bids, &asks)));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_process_order,
+    bench_cancel_order,
+    bench_queries,
+    bench_simulate_order_match
+);
+criterion_main!(benches);
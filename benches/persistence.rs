@@ -0,0 +1,34 @@
+//! Writing the trade history to disk
+//!
+//! Run with `cargo bench --features benchmark --bench persistence`.
+
+use std::fs;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rustflow::{OrderSide, Trade, TradeStore};
+
+fn bench_flush(c: &mut Criterion) {
+    let dir = std::env::temp_dir();
+    let mut group = c.benchmark_group("trade_store_flush");
+    group.sample_size(20);
+
+    for count in [1_000u64, 10_000] {
+        let path = dir.join(format!("rustflow_bench_trades_{}_{}.json", std::process::id(), count));
+        let path = path.to_str().unwrap().to_string();
+        let mut store = TradeStore::with_file(&path, false).unwrap();
+        let trades = (1..=count)
+            .map(|id| Trade::new(id, 10_000 + id % 50, 1 + id % 7, 1_700_000_000_000_000_000 + id, id * 2, id * 2 + 1, 1001, 1002, OrderSide::Buy, "BTC-USD".to_string()))
+            .collect();
+        store.add_trades(trades).unwrap();
+
+        group.throughput(Throughput::Elements(count));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &store, |b, store| {
+            b.iter(|| store.flush().unwrap());
+        });
+        fs::remove_file(&path).unwrap();
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_flush);
+criterion_main!(benches);
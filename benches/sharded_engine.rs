@@ -0,0 +1,45 @@
+//! Throughput of the sharded engine with 1, 2, 4 and 8 shards
+//!
+//! Run with `cargo bench --features benchmark --bench sharded_engine`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rustflow::{Order, OrderSide, ShardedEngine};
+
+const SYMBOL_COUNT: u64 = 32;
+const ORDER_COUNT: u64 = 20_000;
+
+/// Crossing limit orders spread evenly across symbols
+fn order_flow() -> Vec<Order> {
+    (1..=ORDER_COUNT)
+        .map(|id| {
+            let side = if id % 2 == 0 { OrderSide::Buy } else { OrderSide::Sell };
+            let price = 10_000 + (id * 7) % 20;
+            Order::new_limit(id, price, 1 + id % 5, side, id % 100, id, None, format!("SYM{}-USD", id % SYMBOL_COUNT))
+        })
+        .collect()
+}
+
+fn bench_shards(c: &mut Criterion) {
+    let flow = order_flow();
+    let mut group = c.benchmark_group("sharded_engine");
+    group.throughput(Throughput::Elements(ORDER_COUNT));
+    group.sample_size(20);
+
+    for shards in [1, 2, 4, 8] {
+        group.bench_with_input(BenchmarkId::from_parameter(shards), &shards, |b, &shards| {
+            b.iter(|| {
+                let engine = ShardedEngine::new(shards);
+                for order in &flow {
+                    engine.submit(order.clone()).unwrap();
+                }
+                // Wait until every order has been processed
+                engine.events().iter().take(flow.len()).count()
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_shards);
+criterion_main!(benches);
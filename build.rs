@@ -0,0 +1,12 @@
+use std::env;
+
+fn main() {
+    // Use the vendored protoc unless the environment provides one
+    if env::var_os("PROTOC").is_none() {
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("No vendored protoc for this platform");
+        env::set_var("PROTOC", protoc);
+    }
+
+    println!("cargo:rerun-if-changed=proto/rustflow.proto");
+    tonic_prost_build::compile_protos("proto/rustflow.proto").expect("Failed to compile protobuf definitions");
+}
@@ -0,0 +1,57 @@
+use std::env;
+
+use rustflow::backtest::{self, Backtest};
+use rustflow::persistence::trade_store::TradeStore;
+
+/// Sample order flow used when no file is given on the command line
+const SAMPLE_ORDER_FLOW: &str = "\
+timestamp,event,order_id,symbol,side,order_type,price,quantity,user_id
+1000,new,1,BTC-USD,buy,limit,9900,2,1001
+1100,new,2,BTC-USD,sell,limit,10100,3,2001
+1200,new,3,BTC-USD,sell,limit,10200,2,2002
+1300,new,4,BTC-USD,buy,limit,10150,4,1002
+1400,new,5,BTC-USD,sell,market,,1,2003
+1500,cancel,1,BTC-USD
+1600,new,6,BTC-USD,buy,ioc,10300,3,1003
+";
+
+fn main() {
+    println!("RustFlow Backtest Example");
+    println!("=========================\n");
+    
+    // Load order flow from a CSV or JSON lines file if one is given
+    let events = match env::args().nth(1) {
+        Some(path) => match backtest::load_events(&path) {
+            Ok(events) => {
+                println!("Loaded {} events from {}", events.len(), path);
+                events
+            }
+            Err(e) => {
+                eprintln!("Failed to load {}: {}", path, e);
+                return;
+            }
+        },
+        None => {
+            println!("No input file given, replaying built-in sample order flow");
+            backtest::event::parse_csv(SAMPLE_ORDER_FLOW.as_bytes()).unwrap()
+        }
+    };
+    
+    let mut backtest = Backtest::with_trade_store(TradeStore::new());
+    let report = match backtest.run(&events) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Backtest failed: {}", e);
+            return;
+        }
+    };
+    
+    println!("\n-- Backtest Report --");
+    println!("{}", report.summary());
+    
+    for symbol in report.symbols.keys() {
+        if let Some(book) = backtest.book(symbol) {
+            book.print_book(5);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use rustflow::{Order, OrderBook, OrderSide, OrderType};
+use rustflow::{Order, OrderBook, OrderSide, Price, Quantity};
 use rustflow::utils::time;
 use rustflow::persistence::trade_store::TradeStore;
 
@@ -21,16 +21,17 @@ fn main() {
     
     // Create an order book for BTC-USD
     let mut book = OrderBook::new("BTC-USD");
+    let instrument = book.instrument().clone();
     let mut order_id = 0;
     
     // Helper to create unique order IDs
-    let next_id = || {
+    let mut next_id = || {
         order_id += 1;
         order_id
     };
     
     // Helper to create a timestamp
-    let timestamp = || time::current_timestamp_nanos();
+    let timestamp = time::current_timestamp_nanos;
     
     // Add some buy orders
     let buy_orders = vec![
@@ -77,7 +78,7 @@ fn main() {
     ];
     
     for order in buy_orders {
-        println!("\nAdding Buy Order: {} @ ${:.2}", order.quantity, order.price as f64 / 100.0);
+        println!("\nAdding Buy Order: {} @ {}", order.quantity, instrument.format_price(order.price));
         let trades = book.process_order(order);
         if !trades.is_empty() {
             println!("  - Order resulted in {} trade(s)", trades.len());
@@ -133,7 +134,7 @@ fn main() {
     ];
     
     for order in sell_orders {
-        println!("\nAdding Sell Order: {} @ ${:.2}", order.quantity, order.price as f64 / 100.0);
+        println!("\nAdding Sell Order: {} @ {}", order.quantity, instrument.format_price(order.price));
         let trades = book.process_order(order);
         if !trades.is_empty() {
             println!("  - Order resulted in {} trade(s)", trades.len());
@@ -155,14 +156,14 @@ fn main() {
         None, 
         "BTC-USD".to_string()
     );
-    println!("\nAdding Matching Buy Order: {} @ ${:.2}", matching_buy.quantity, matching_buy.price as f64 / 100.0);
+    println!("\nAdding Matching Buy Order: {} @ {}", matching_buy.quantity, instrument.format_price(matching_buy.price));
     
     let trades = book.process_order(matching_buy);
     trade_store.add_trades(trades.clone()).unwrap();
     
     println!("\n-- Trades Executed --");
     for trade in &trades {
-        println!("Trade: {} @ ${:.2}", trade.quantity, trade.price as f64 / 100.0);
+        println!("Trade: {} @ {}", trade.quantity, instrument.format_price(trade.price));
     }
     
     println!("\n-- After Matching --");
@@ -185,21 +186,32 @@ fn main() {
     
     println!("\n-- Market Order Trades --");
     for trade in &trades {
-        println!("Trade: {} @ ${:.2}", trade.quantity, trade.price as f64 / 100.0);
+        println!("Trade: {} @ {}", trade.quantity, instrument.format_price(trade.price));
     }
     
     println!("\n-- Final Order Book --");
     book.print_book(5);
     
     // Calculate slippage for a larger order
-    if let Some((avg_price, slippage)) = book.calculate_slippage(OrderSide::Buy, 10) {
+    if let Some((avg_price, slippage)) = book.calculate_slippage(OrderSide::Buy, Quantity(10)) {
         println!("\nSlippage Analysis for Buy 10 BTC:");
-        println!("Average Execution Price: ${:.2}", avg_price as f64 / 100.0);
+        println!("Average Execution Price: {}", instrument.format_price(avg_price));
         println!("Slippage: {:.2}%", slippage);
     } else {
         println!("\nNot enough liquidity to calculate slippage");
     }
     
+    // Show the full execution ladder, including partial liquidity
+    let estimate = book.estimate_execution(OrderSide::Buy, Quantity(10));
+    println!("\nExecution Ladder for Buy 10 BTC:");
+    for level in &estimate.levels {
+        println!("  {} @ {} (of {} available)", level.quantity, instrument.format_price(level.price), level.available);
+    }
+    println!("Filled: {}, Unfilled: {}", estimate.filled_quantity, estimate.unfilled_quantity);
+    if let Some(impact) = estimate.impact_bps() {
+        println!("Market Impact: {:.1} bps", impact);
+    }
+    
     // Print trade statistics
     println!("\n-- Trade Statistics --");
     let trades = trade_store.get_all_trades();
@@ -212,6 +224,6 @@ fn main() {
     
     let avg_prices = trade_store.average_price_by_symbol();
     for (symbol, price) in avg_prices {
-        println!("Average price for {}: ${:.2}", symbol, price / 100.0);
+        println!("Average price for {}: {}", symbol, instrument.format_price(Price(price.round() as u64)));
     }
 }
@@ -0,0 +1,53 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Holdings of one asset by one user, in the asset's smallest unit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Balance {
+    /// Amount free to trade or withdraw
+    pub available: u128,
+    /// Amount held for open orders
+    pub reserved: u128,
+}
+
+impl Balance {
+    /// Returns the available and reserved amounts together
+    pub fn total(&self) -> u128 {
+        self.available + self.reserved
+    }
+
+    /// Returns true if nothing is held
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+}
+
+/// Errors raised by the account ledger
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LedgerError {
+    /// The user does not have enough of an asset available
+    InsufficientFunds {
+        user_id: u64,
+        asset: String,
+        required: u128,
+        available: u128,
+    },
+    /// The engine was started without a ledger
+    Disabled,
+}
+
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedgerError::InsufficientFunds { user_id, asset, required, available } => write!(
+                f,
+                "Insufficient {} for user {}: {} required, {} available",
+                asset, user_id, required, available
+            ),
+            LedgerError::Disabled => write!(f, "Account ledger is not enabled"),
+        }
+    }
+}
+
+impl std::error::Error for LedgerError {}
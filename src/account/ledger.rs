@@ -0,0 +1,620 @@
+use std::collections::{BTreeMap, HashMap};
+
+use log::warn;
+
+use crate::account::balance::{Balance, LedgerError};
+use crate::core::order_book::OrderBook;
+use crate::fees::schedule::fee_amount;
+use crate::models::instrument::Instrument;
+use crate::models::order::{Order, OrderSide, OrderType};
+use crate::models::price::{Price, Quantity};
+use crate::models::trade::Trade;
+use crate::models::trade_adjustment::TradeAdjustment;
+
+/// Funds held for one order
+#[derive(Debug, Clone)]
+struct Reservation {
+    user_id: u64,
+    asset: String,
+    /// Amount still reserved
+    amount: u128,
+}
+
+/// Balances of every user and the funds reserved for their open orders
+///
+/// Symbols are split into base and quote assets with
+/// `Instrument::split_symbol`. Amounts are in each asset's smallest unit:
+/// the quantity precision of an instrument for its base asset and the price
+/// precision for its quote asset, so instruments sharing an asset should use
+/// the same precision for it. Quote amounts are rounded up.
+///
+/// A sell reserves its quantity of the base asset. A buy reserves, in the
+/// quote asset, the cost of each fill it would get from the resting orders it
+/// crosses and of any remainder at its limit price, each with the highest fee
+/// the symbol can charge, so settling a trade never leaves a user short. Each
+/// trade moves both legs out of the reservations at once, resting buys are
+/// kept covered as they fill, and whatever an order leaves reserved is
+/// released when it stops being active.
+#[derive(Debug, Default)]
+pub struct Ledger {
+    balances: HashMap<(u64, String), Balance>,
+    /// Reservations by symbol and order ID
+    reservations: HashMap<(String, u64), Reservation>,
+}
+
+impl Ledger {
+    /// Creates a ledger without any balances
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an amount of an asset to a user's available balance
+    pub fn deposit(&mut self, user_id: u64, asset: &str, amount: u128) {
+        self.balance_mut(user_id, asset).available += amount;
+    }
+
+    /// Removes an amount of an asset from a user's available balance
+    pub fn withdraw(&mut self, user_id: u64, asset: &str, amount: u128) -> Result<(), LedgerError> {
+        let balance = self.balance_mut(user_id, asset);
+        if balance.available < amount {
+            return Err(LedgerError::InsufficientFunds {
+                user_id,
+                asset: asset.to_ascii_uppercase(),
+                required: amount,
+                available: balance.available,
+            });
+        }
+        balance.available -= amount;
+        Ok(())
+    }
+
+    /// Returns a user's balance of an asset
+    pub fn balance(&self, user_id: u64, asset: &str) -> Balance {
+        self.balances
+            .get(&(user_id, asset.to_ascii_uppercase()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns every non-empty balance of a user, by asset
+    pub fn balances(&self, user_id: u64) -> Vec<(String, Balance)> {
+        let mut balances: Vec<(String, Balance)> = self
+            .balances
+            .iter()
+            .filter(|((user, _), balance)| *user == user_id && !balance.is_empty())
+            .map(|((_, asset), balance)| (asset.clone(), *balance))
+            .collect();
+        balances.sort_by(|a, b| a.0.cmp(&b.0));
+        balances
+    }
+
+    /// Returns the amount still reserved for an order
+    pub fn reserved_for(&self, symbol: &str, order_id: u64) -> u128 {
+        self.reservations
+            .get(&(symbol.to_string(), order_id))
+            .map_or(0, |reservation| reservation.amount)
+    }
+
+    /// Reserves the funds a new order needs before it is matched in `book`
+    ///
+    /// Buys reserve fees at `fee_rate`, the highest rate in the symbol. Market
+    /// and stop buys reserve what their quantity would cost against the book
+    /// as it is now.
+    pub fn reserve(&mut self, order: &Order, book: &OrderBook, fee_rate: i64) -> Result<(), LedgerError> {
+        let (base, quote) = Instrument::split_symbol(&order.symbol);
+        let asset = match order.side {
+            OrderSide::Sell => base,
+            OrderSide::Buy => quote,
+        };
+        let amount = required_amount(order, book, fee_rate);
+
+        let balance = self.balance_mut(order.user_id, &asset);
+        if balance.available < amount {
+            return Err(LedgerError::InsufficientFunds {
+                user_id: order.user_id,
+                asset,
+                required: amount,
+                available: balance.available,
+            });
+        }
+        balance.available -= amount;
+        balance.reserved += amount;
+        self.reservations.insert(
+            (order.symbol.clone(), order.id),
+            Reservation {
+                user_id: order.user_id,
+                asset,
+                amount,
+            },
+        );
+        Ok(())
+    }
+
+    /// Checks that an open order can be amended to `amended` before it is re-matched in `book`
+    pub fn check_amend(&self, amended: &Order, book: &OrderBook, fee_rate: i64) -> Result<(), LedgerError> {
+        let Some(reservation) = self.reservations.get(&(amended.symbol.clone(), amended.id)) else {
+            return Ok(());
+        };
+        let required = required_amount(amended, book, fee_rate);
+        let available = self.balance(amended.user_id, &reservation.asset).available;
+        if required > reservation.amount + available {
+            return Err(LedgerError::InsufficientFunds {
+                user_id: amended.user_id,
+                asset: reservation.asset.clone(),
+                required: required - reservation.amount,
+                available,
+            });
+        }
+        Ok(())
+    }
+
+    /// Resizes the reservation of an order for `amended` before it is re-matched in `book`
+    ///
+    /// Call `check_amend` first; without enough funds the reservation only grows by what is available.
+    pub fn amend(&mut self, amended: &Order, book: &OrderBook, fee_rate: i64) {
+        let key = (amended.symbol.clone(), amended.id);
+        let Some(reservation) = self.reservations.get(&key) else {
+            return;
+        };
+        let available = self.balance(reservation.user_id, &reservation.asset).available;
+        let required = required_amount(amended, book, fee_rate).min(reservation.amount + available);
+        self.resize(&key, required);
+    }
+
+    /// Settles both legs of a trade out of the orders' reservations
+    ///
+    /// The buyer's reservation for the filled quantity is released and the
+    /// trade's cost moved to the seller; the seller's reserved base asset
+    /// moves to the buyer. Fees recorded on the trade are charged in the quote
+    /// asset. If that would leave a user short, such as when a reservation
+    /// does not cover the fill, no balance changes and an error is returned.
+    pub fn settle(&mut self, trade: &Trade, instrument: &Instrument) -> Result<(), LedgerError> {
+        let (base, quote) = Instrument::split_symbol(&trade.symbol);
+        let buy_key = (trade.symbol.clone(), trade.buy_order_id);
+        let sell_key = (trade.symbol.clone(), trade.sell_order_id);
+        let quantity = trade.quantity.0 as u128;
+
+        // The buyer's reservation pays for the fill and any fee on it
+        let buy_release = self.reservations.get(&buy_key).map_or(0, |reservation| {
+            let cost = quote_amount(trade.price, trade.quantity, instrument);
+            cost.saturating_add(trade.buy_fee.max(0) as u128).min(reservation.amount)
+        });
+        let sell_release = self
+            .reservations
+            .get(&sell_key)
+            .map_or(0, |reservation| quantity.min(reservation.amount));
+
+        // Released funds become available before the trade's legs and fees are taken from them
+        let mut changes = BTreeMap::new();
+        add_trade_changes(&mut changes, trade, 1, instrument);
+        *changes.entry((trade.buy_user_id, quote)).or_insert(0) += buy_release as i128;
+        *changes.entry((trade.sell_user_id, base)).or_insert(0) += sell_release as i128;
+        for ((user_id, asset), change) in &changes {
+            let available = self.balance(*user_id, asset).available;
+            if *change < 0 && change.unsigned_abs() > available {
+                return Err(LedgerError::InsufficientFunds {
+                    user_id: *user_id,
+                    asset: asset.clone(),
+                    required: change.unsigned_abs(),
+                    available,
+                });
+            }
+        }
+
+        for (key, released) in [(buy_key, buy_release), (sell_key, sell_release)] {
+            if let Some(reservation) = self.reservations.get_mut(&key) {
+                reservation.amount -= released;
+                let (user_id, asset) = (reservation.user_id, reservation.asset.clone());
+                self.balance_mut(user_id, &asset).reserved -= released;
+            }
+        }
+        for ((user_id, asset), change) in changes {
+            let balance = self.balance_mut(user_id, &asset);
+            match u128::try_from(change) {
+                Ok(change) => balance.available += change,
+                Err(_) => balance.available -= change.unsigned_abs(),
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every user still has what a bust or correction takes back from them
+    pub fn check_adjustment(&self, adjustment: &TradeAdjustment, instrument: &Instrument) -> Result<(), LedgerError> {
+        for ((user_id, asset), change) in adjustment_changes(adjustment, instrument) {
+            let available = self.balance(user_id, &asset).available;
+            if change < 0 && change.unsigned_abs() > available {
+                return Err(LedgerError::InsufficientFunds {
+                    user_id,
+                    asset,
+                    required: change.unsigned_abs(),
+                    available,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Reverses the settlement of a busted or corrected trade and settles its correction
+    ///
+    /// Only available balances change, since the orders' reservations were
+    /// used up when the trade first settled. Call `check_adjustment` first;
+    /// without enough funds a balance only drops to zero.
+    pub fn adjust(&mut self, adjustment: &TradeAdjustment, instrument: &Instrument) {
+        for ((user_id, asset), change) in adjustment_changes(adjustment, instrument) {
+            let balance = self.balance_mut(user_id, &asset);
+            match u128::try_from(change) {
+                Ok(change) => balance.available += change,
+                Err(_) => {
+                    if balance.available < change.unsigned_abs() {
+                        warn!("Adjustment of trade {} overdraws {} of user {}", adjustment.trade_id, asset, user_id);
+                    }
+                    balance.available = balance.available.saturating_sub(change.unsigned_abs());
+                }
+            }
+        }
+    }
+
+    /// Keeps the reservation of an order in step with `book` after it was processed
+    ///
+    /// Whatever an order still has reserved is released once it is no longer
+    /// active. A resting limit order is resized to cover its remaining
+    /// quantity with fees at `fee_rate`, since fills can round its cost up;
+    /// returns false, changing nothing, if its user cannot cover it.
+    pub fn track_order(&mut self, order: &Order, book: &OrderBook, fee_rate: i64) -> bool {
+        if !order.is_active() {
+            self.release(&order.symbol, order.id);
+            return true;
+        }
+        let key = (order.symbol.clone(), order.id);
+        let Some(reservation) = self.reservations.get(&key).filter(|_| order.order_type == OrderType::Limit) else {
+            return true;
+        };
+        let required = required_amount(order, book, fee_rate);
+        if required > reservation.amount + self.balance(reservation.user_id, &reservation.asset).available {
+            return false;
+        }
+        self.resize(&key, required);
+        true
+    }
+
+    /// Releases everything still reserved for an order
+    pub fn release(&mut self, symbol: &str, order_id: u64) {
+        if let Some(reservation) = self.reservations.remove(&(symbol.to_string(), order_id)) {
+            let balance = self.balance_mut(reservation.user_id, &reservation.asset);
+            balance.reserved -= reservation.amount;
+            balance.available += reservation.amount;
+        }
+    }
+
+    /// Moves funds between a user's available balance and an order's reservation
+    /// so that `amount` is reserved; the user must have enough available
+    fn resize(&mut self, key: &(String, u64), amount: u128) {
+        let Some(reservation) = self.reservations.get_mut(key) else {
+            return;
+        };
+        let current = std::mem::replace(&mut reservation.amount, amount);
+        let (user_id, asset) = (reservation.user_id, reservation.asset.clone());
+        let balance = self.balance_mut(user_id, &asset);
+        balance.available = balance.available + current - amount;
+        balance.reserved = balance.reserved + amount - current;
+    }
+
+    fn balance_mut(&mut self, user_id: u64, asset: &str) -> &mut Balance {
+        self.balances
+            .entry((user_id, asset.to_ascii_uppercase()))
+            .or_default()
+    }
+}
+
+/// Returns the funds an order needs reserved for its remaining quantity if it is matched in `book` now
+///
+/// A buy pays for each fill from the resting orders it crosses and any
+/// remainder at its limit price separately, since each trade's cost and fee
+/// are rounded up on their own.
+fn required_amount(order: &Order, book: &OrderBook, fee_rate: i64) -> u128 {
+    if order.side == OrderSide::Sell {
+        return order.remaining_quantity.0 as u128;
+    }
+    let instrument = book.instrument();
+    let limit_price = match order.order_type {
+        OrderType::Market | OrderType::Stop(_) => None,
+        _ => Some(order.price),
+    };
+    let fills = book.maker_fills(OrderSide::Buy, order.remaining_quantity, limit_price);
+    let filled: Quantity = fills.iter().map(|&(_, quantity)| quantity).sum();
+    let resting = limit_price.map(|price| (price, order.remaining_quantity.saturating_sub(filled)));
+    fills
+        .into_iter()
+        .chain(resting)
+        .fold(0u128, |amount, (price, quantity)| {
+            let cost = quote_amount(price, quantity, instrument);
+            amount
+                .saturating_add(cost)
+                .saturating_add(fee_amount(cost, fee_rate).max(0) as u128)
+        })
+}
+
+/// Returns the net change a bust or correction makes to each user's available balances, by user and asset
+fn adjustment_changes(adjustment: &TradeAdjustment, instrument: &Instrument) -> BTreeMap<(u64, String), i128> {
+    let mut changes = BTreeMap::new();
+    add_trade_changes(&mut changes, &adjustment.previous, -1, instrument);
+    if let Some(corrected) = &adjustment.corrected {
+        add_trade_changes(&mut changes, corrected, 1, instrument);
+    }
+    changes.retain(|_, change| *change != 0);
+    changes
+}
+
+/// Adds what a trade moves between its buyer and seller, fees included, times `sign` to `changes`
+fn add_trade_changes(changes: &mut BTreeMap<(u64, String), i128>, trade: &Trade, sign: i128, instrument: &Instrument) {
+    let (base, quote) = Instrument::split_symbol(&trade.symbol);
+    let cost = quote_amount(trade.price, trade.quantity, instrument) as i128;
+    let quantity = trade.quantity.0 as i128;
+    for (user_id, asset, amount) in [
+        (trade.buy_user_id, &quote, -cost - trade.buy_fee as i128),
+        (trade.sell_user_id, &quote, cost - trade.sell_fee as i128),
+        (trade.buy_user_id, &base, quantity),
+        (trade.sell_user_id, &base, -quantity),
+    ] {
+        *changes.entry((user_id, asset.clone())).or_insert(0) += sign * amount;
+    }
+}
+
+/// Returns the cost of a quantity at a price in the quote asset's smallest unit
+fn quote_amount(price: Price, quantity: Quantity, instrument: &Instrument) -> u128 {
+    instrument.quote_units(price.notional(quantity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(id: u64, side: OrderSide, price: u64, quantity: u64, user_id: u64) -> Order {
+        Order::new_limit(id, price, quantity, side, user_id, id, None, "BTC-USD".to_string())
+    }
+
+    fn funded() -> Ledger {
+        let mut ledger = Ledger::new();
+        ledger.deposit(1, "USD", 100_000);
+        ledger.deposit(2, "btc", 50);
+        ledger
+    }
+
+    #[test]
+    fn test_reserve_and_release() {
+        let book = OrderBook::new("BTC-USD");
+        let mut ledger = funded();
+
+        ledger.reserve(&limit(1, OrderSide::Buy, 1_000, 60, 1), &book, 0).unwrap();
+        assert_eq!(ledger.balance(1, "USD"), Balance { available: 40_000, reserved: 60_000 });
+        assert_eq!(
+            ledger.reserve(&limit(2, OrderSide::Buy, 1_000, 41, 1), &book, 0),
+            Err(LedgerError::InsufficientFunds {
+                user_id: 1,
+                asset: "USD".to_string(),
+                required: 41_000,
+                available: 40_000,
+            })
+        );
+        assert!(ledger.reserve(&limit(3, OrderSide::Sell, 1_000, 51, 2), &book, 0).is_err());
+        assert!(ledger.withdraw(1, "USD", 40_001).is_err());
+
+        ledger.release("BTC-USD", 1);
+        assert_eq!(ledger.balance(1, "USD"), Balance { available: 100_000, reserved: 0 });
+        assert_eq!(ledger.reserved_for("BTC-USD", 1), 0);
+    }
+
+    #[test]
+    fn test_trades_settle_both_legs() {
+        let mut book = OrderBook::new("BTC-USD");
+        let mut ledger = funded();
+
+        let sell = limit(1, OrderSide::Sell, 900, 30, 2);
+        ledger.reserve(&sell, &book, 0).unwrap();
+        book.process_order(sell);
+        let buy = limit(2, OrderSide::Buy, 1_000, 40, 1);
+        ledger.reserve(&buy, &book, 0).unwrap();
+        let mut trades = book.process_order(buy);
+        trades[0].buy_fee = 30;
+        trades[0].sell_fee = -5;
+        for trade in &trades {
+            ledger.settle(trade, book.instrument()).unwrap();
+        }
+        assert!(ledger.track_order(book.get_order(2).unwrap(), &book, 0));
+
+        // The buyer paid 900 a unit plus its fee and keeps 10 reserved at its limit of 1000
+        assert_eq!(ledger.balance(1, "USD"), Balance { available: 62_970, reserved: 10_000 });
+        assert_eq!(ledger.balance(1, "BTC"), Balance { available: 30, reserved: 0 });
+        assert_eq!(ledger.balance(2, "USD"), Balance { available: 27_005, reserved: 0 });
+        assert_eq!(ledger.balance(2, "BTC"), Balance { available: 20, reserved: 0 });
+        assert_eq!(ledger.balances(2), vec![("BTC".to_string(), ledger.balance(2, "BTC")), ("USD".to_string(), ledger.balance(2, "USD"))]);
+
+        // Amending the rest down to 5 more releases half of it
+        let mut amended = book.get_order(2).unwrap().clone();
+        amended.quantity = Quantity(35);
+        amended.remaining_quantity = Quantity(5);
+        ledger.check_amend(&amended, &book, 0).unwrap();
+        ledger.amend(&amended, &book, 0);
+        assert_eq!(ledger.balance(1, "USD"), Balance { available: 67_970, reserved: 5_000 });
+
+        amended.quantity = Quantity(110);
+        amended.remaining_quantity = Quantity(80);
+        assert!(ledger.check_amend(&amended, &book, 0).is_err());
+    }
+
+    #[test]
+    fn test_adjustments_reverse_settlement() {
+        let mut book = OrderBook::new("BTC-USD");
+        let mut ledger = funded();
+
+        let sell = limit(1, OrderSide::Sell, 900, 30, 2);
+        ledger.reserve(&sell, &book, 0).unwrap();
+        book.process_order(sell);
+        let buy = limit(2, OrderSide::Buy, 1_000, 40, 1);
+        ledger.reserve(&buy, &book, 0).unwrap();
+        let mut trade = book.process_order(buy).remove(0);
+        (trade.buy_fee, trade.sell_fee) = (30, -5);
+        ledger.settle(&trade, book.instrument()).unwrap();
+        ledger.track_order(book.get_order(2).unwrap(), &book, 0);
+
+        let mut corrected = trade.clone();
+        corrected.price = Price(800);
+        let mut adjustment = TradeAdjustment {
+            id: 1,
+            trade_id: trade.id,
+            symbol: trade.symbol.clone(),
+            reason: String::new(),
+            timestamp: 0,
+            previous: trade,
+            corrected: Some(corrected.clone()),
+            last_trade: None,
+        };
+        ledger.check_adjustment(&adjustment, book.instrument()).unwrap();
+        ledger.adjust(&adjustment, book.instrument());
+        assert_eq!(ledger.balance(1, "USD"), Balance { available: 65_970, reserved: 10_000 });
+        assert_eq!(ledger.balance(2, "USD").available, 24_005);
+
+        // A bust takes the base asset back from the buyer, who must still have it
+        adjustment.previous = corrected;
+        adjustment.corrected = None;
+        ledger.withdraw(1, "BTC", 30).unwrap();
+        assert_eq!(
+            ledger.check_adjustment(&adjustment, book.instrument()),
+            Err(LedgerError::InsufficientFunds {
+                user_id: 1,
+                asset: "BTC".to_string(),
+                required: 30,
+                available: 0,
+            })
+        );
+        ledger.deposit(1, "BTC", 30);
+        ledger.check_adjustment(&adjustment, book.instrument()).unwrap();
+        ledger.adjust(&adjustment, book.instrument());
+        assert_eq!(ledger.balance(1, "USD"), Balance { available: 90_000, reserved: 10_000 });
+        assert_eq!(ledger.balance(2, "USD").available, 0);
+        assert_eq!(ledger.balance(2, "BTC").available, 50);
+    }
+
+    #[test]
+    fn test_market_buy_reserves_estimated_cost() {
+        let mut book = OrderBook::with_instrument(Instrument::with_precision("BTC-USD", 2, 2).unwrap());
+        let mut ledger = funded();
+
+        // 0.30 BTC at 9.00 and 0.20 BTC at 10.00
+        for (id, price, quantity) in [(1, 900, 30), (2, 1_000, 20)] {
+            let sell = limit(id, OrderSide::Sell, price, quantity, 2);
+            ledger.reserve(&sell, &book, 0).unwrap();
+            book.process_order(sell);
+        }
+        let market = Order::new_market(3, 40, OrderSide::Buy, 1, 3, None, "BTC-USD".to_string());
+        ledger.reserve(&market, &book, 0).unwrap();
+        assert_eq!(ledger.reserved_for("BTC-USD", 3), 370);
+
+        let trades = book.process_order(market);
+        for trade in &trades {
+            ledger.settle(trade, book.instrument()).unwrap();
+        }
+        ledger.track_order(book.get_order(3).unwrap(), &book, 0);
+        assert_eq!(ledger.balance(1, "USD"), Balance { available: 99_630, reserved: 0 });
+        assert_eq!(ledger.balance(2, "USD").available, 370);
+        assert_eq!(ledger.balance(2, "BTC"), Balance { available: 0, reserved: 10 });
+    }
+
+    #[test]
+    fn test_buys_reserve_rounding_and_fees() {
+        let mut book = OrderBook::with_instrument(Instrument::with_precision("BTC-USD", 2, 2).unwrap());
+        let mut ledger = Ledger::new();
+        ledger.deposit(1, "USD", 8);
+        ledger.deposit(2, "BTC", 3);
+
+        // Three fills of 0.01 BTC at 1.01 cost 0.02 each, plus up to 0.01 of fees at 10%
+        for id in 1..=3 {
+            let sell = limit(id, OrderSide::Sell, 101, 1, 2);
+            ledger.reserve(&sell, &book, 0).unwrap();
+            book.process_order(sell);
+        }
+        let market = Order::new_market(4, 3, OrderSide::Buy, 1, 4, None, "BTC-USD".to_string());
+        assert_eq!(
+            ledger.reserve(&market, &book, 100_000),
+            Err(LedgerError::InsufficientFunds {
+                user_id: 1,
+                asset: "USD".to_string(),
+                required: 9,
+                available: 8,
+            })
+        );
+        ledger.deposit(1, "USD", 1);
+        ledger.reserve(&market, &book, 100_000).unwrap();
+
+        let totals = |ledger: &Ledger| {
+            [(1, "USD"), (1, "BTC"), (2, "USD"), (2, "BTC")].map(|(user_id, asset)| ledger.balance(user_id, asset).total())
+        };
+        let mut trades = book.process_order(market);
+        for trade in &mut trades {
+            trade.buy_fee = 1;
+            ledger.settle(trade, book.instrument()).unwrap();
+        }
+        ledger.track_order(book.get_order(4).unwrap(), &book, 100_000);
+        assert_eq!(totals(&ledger), [0, 3, 6, 0]);
+    }
+
+    #[test]
+    fn test_resting_buy_stays_covered() {
+        let mut book = OrderBook::with_instrument(Instrument::with_precision("BTC-USD", 2, 2).unwrap());
+        let mut ledger = Ledger::new();
+        ledger.deposit(1, "USD", 4);
+        ledger.deposit(2, "BTC", 3);
+
+        // 0.03 BTC at 1.01 reserves 0.04, but a fill of 0.01 costs 0.02
+        let buy = limit(1, OrderSide::Buy, 101, 3, 1);
+        ledger.reserve(&buy, &book, 0).unwrap();
+        book.process_order(buy);
+        let sell = limit(2, OrderSide::Sell, 101, 1, 2);
+        ledger.reserve(&sell, &book, 0).unwrap();
+        let trades = book.process_order(sell);
+        ledger.settle(&trades[0], book.instrument()).unwrap();
+        assert_eq!(ledger.reserved_for("BTC-USD", 1), 2);
+
+        // The remaining 0.02 BTC needs 0.03, which the buyer no longer has
+        let before = ledger.balance(1, "USD");
+        assert!(!ledger.track_order(book.get_order(1).unwrap(), &book, 0));
+        assert_eq!(ledger.balance(1, "USD"), before);
+        ledger.deposit(1, "USD", 1);
+        assert!(ledger.track_order(book.get_order(1).unwrap(), &book, 0));
+        assert_eq!(ledger.balance(1, "USD"), Balance { available: 0, reserved: 3 });
+    }
+
+    #[test]
+    fn test_short_reservation_is_not_settled() {
+        let mut book = OrderBook::with_instrument(Instrument::with_precision("BTC-USD", 2, 2).unwrap());
+        let mut ledger = Ledger::new();
+        ledger.deposit(1, "USD", 4);
+        ledger.deposit(2, "BTC", 1);
+
+        // A fee the reservation was not sized for cannot be paid
+        let sell = limit(1, OrderSide::Sell, 101, 1, 2);
+        ledger.reserve(&sell, &book, 0).unwrap();
+        book.process_order(sell);
+        let buy = limit(2, OrderSide::Buy, 101, 1, 1);
+        ledger.reserve(&buy, &book, 0).unwrap();
+        ledger.withdraw(1, "USD", 2).unwrap();
+        let mut trades = book.process_order(buy);
+        trades[0].buy_fee = 1;
+
+        let totals = |ledger: &Ledger| {
+            [(1, "USD"), (1, "BTC"), (2, "USD"), (2, "BTC")].map(|(user_id, asset)| ledger.balance(user_id, asset).total())
+        };
+        let before = totals(&ledger);
+        assert_eq!(
+            ledger.settle(&trades[0], book.instrument()),
+            Err(LedgerError::InsufficientFunds {
+                user_id: 1,
+                asset: "USD".to_string(),
+                required: 1,
+                available: 0,
+            })
+        );
+        assert_eq!(totals(&ledger), before);
+        assert_eq!(ledger.reserved_for("BTC-USD", 2), 2);
+    }
+}
@@ -0,0 +1,7 @@
+// Export account components
+pub mod balance;
+pub mod ledger;
+
+// Re-export main components
+pub use balance::{Balance, LedgerError};
+pub use ledger::Ledger;
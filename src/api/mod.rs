@@ -0,0 +1,9 @@
+// Export API components
+pub mod rest;
+pub mod server;
+pub mod websocket;
+
+// Re-export main components
+pub use rest::{BustTradeRequest, CorrectTradeRequest, NewOrderRequest, OrderResponse};
+pub use server::ApiServer;
+pub use websocket::MarketDataMessage;
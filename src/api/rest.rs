@@ -0,0 +1,275 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::api::server::ApiState;
+use crate::core::handle::{DepthSnapshot, EngineError};
+use crate::models::order::{Order, OrderError, OrderSide, OrderType};
+use crate::models::price::{Price, Quantity};
+use crate::models::stats::OrderBookStats;
+use crate::models::trade::Trade;
+use crate::models::trade_adjustment::{AdjustmentError, TradeAdjustment};
+use crate::utils::time;
+
+/// Depth levels returned when the request does not say
+pub const DEFAULT_DEPTH_LEVELS: usize = 10;
+
+/// Trades returned when the request does not say
+pub const DEFAULT_TRADE_LIMIT: usize = 50;
+
+/// Upper bound on requested depth levels and trades
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// Body of `POST /orders`; the server assigns the order ID
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewOrderRequest {
+    pub user_id: u64,
+    pub symbol: String,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    /// Limit price (ignored for market and stop orders)
+    #[serde(default)]
+    pub price: Price,
+    pub quantity: Quantity,
+    #[serde(default)]
+    pub client_order_id: Option<String>,
+}
+
+/// The state of an order after a request, with any trades it caused
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderResponse {
+    pub order: Order,
+    #[serde(default)]
+    pub trades: Vec<Trade>,
+}
+
+/// Body of `POST /books/{symbol}/trades/{trade_id}/bust`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BustTradeRequest {
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// Body of `POST /books/{symbol}/trades/{trade_id}/correct`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CorrectTradeRequest {
+    pub price: Price,
+    pub quantity: Quantity,
+    #[serde(default)]
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DepthQuery {
+    levels: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct TradesQuery {
+    limit: Option<usize>,
+}
+
+/// An error response with a JSON `{"error": ...}` body
+#[derive(Debug)]
+pub(crate) struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, message)
+    }
+}
+
+impl From<EngineError> for ApiError {
+    fn from(e: EngineError) -> Self {
+        let status = match &e {
+            EngineError::Closed | EngineError::Busy => StatusCode::SERVICE_UNAVAILABLE,
+            EngineError::Order(OrderError::UnknownOrder { .. }) => StatusCode::NOT_FOUND,
+            EngineError::Order(OrderError::DuplicateOrder { .. }) => StatusCode::CONFLICT,
+            EngineError::Order(_) => StatusCode::BAD_REQUEST,
+            EngineError::Risk(_) | EngineError::Ledger(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            EngineError::Adjustment(AdjustmentError::UnknownTrade { .. }) => StatusCode::NOT_FOUND,
+            EngineError::Adjustment(AdjustmentError::Busted { .. } | AdjustmentError::Stale { .. }) => StatusCode::CONFLICT,
+            EngineError::Adjustment(AdjustmentError::InvalidCorrection { .. }) => StatusCode::BAD_REQUEST,
+        };
+        Self::new(status, e.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(serde_json::json!({ "error": self.message })),
+        )
+            .into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+/// `POST /orders`
+pub(crate) async fn submit_order(
+    State(state): State<Arc<ApiState>>,
+    Json(request): Json<NewOrderRequest>,
+) -> ApiResult<OrderResponse> {
+    if request.quantity.is_zero() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "Quantity must be positive",
+        ));
+    }
+
+    let order_id = state.next_order_id.fetch_add(1, Ordering::Relaxed);
+    let order = Order::new(
+        order_id,
+        request.order_type,
+        request.price,
+        request.quantity,
+        request.side,
+        request.user_id,
+        time::current_timestamp_nanos(),
+        request.client_order_id,
+        request.symbol,
+    );
+    let symbol = order.symbol.clone();
+
+    let ack = state.engine.submit(order).await?;
+    // Record straight away so the trades are visible to the caller's next request
+    state.record_trades(&ack.trades);
+
+    let order = state
+        .engine
+        .order(&symbol, order_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Unknown order {}", order_id)))?;
+    Ok(Json(OrderResponse {
+        order,
+        trades: ack.trades,
+    }))
+}
+
+/// `GET /orders/{symbol}/{order_id}`
+pub(crate) async fn get_order(
+    State(state): State<Arc<ApiState>>,
+    Path((symbol, order_id)): Path<(String, u64)>,
+) -> ApiResult<Order> {
+    state
+        .engine
+        .order(&symbol, order_id)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("Unknown order {}", order_id)))
+}
+
+/// `DELETE /orders/{symbol}/{order_id}`
+pub(crate) async fn cancel_order(
+    State(state): State<Arc<ApiState>>,
+    Path((symbol, order_id)): Path<(String, u64)>,
+) -> ApiResult<Order> {
+    let canceled = state.engine.cancel(&symbol, order_id).await?;
+    let order = state
+        .engine
+        .order(&symbol, order_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("Unknown order {}", order_id)))?;
+
+    if !canceled {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            format!(
+                "Order {} is {} and cannot be canceled",
+                order_id, order.status
+            ),
+        ));
+    }
+    Ok(Json(order))
+}
+
+/// `GET /users/{user_id}/orders`
+pub(crate) async fn open_orders(
+    State(state): State<Arc<ApiState>>,
+    Path(user_id): Path<u64>,
+) -> ApiResult<Vec<Order>> {
+    Ok(Json(state.engine.open_orders(user_id).await?))
+}
+
+/// `GET /books/{symbol}/depth?levels=N`
+pub(crate) async fn depth(
+    State(state): State<Arc<ApiState>>,
+    Path(symbol): Path<String>,
+    Query(query): Query<DepthQuery>,
+) -> ApiResult<DepthSnapshot> {
+    let levels = query
+        .levels
+        .unwrap_or(DEFAULT_DEPTH_LEVELS)
+        .min(MAX_PAGE_SIZE);
+    Ok(Json(state.engine.depth(&symbol, levels).await?))
+}
+
+/// `GET /books/{symbol}/trades?limit=N`, newest first
+pub(crate) async fn recent_trades(
+    State(state): State<Arc<ApiState>>,
+    Path(symbol): Path<String>,
+    Query(query): Query<TradesQuery>,
+) -> ApiResult<Vec<Trade>> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_TRADE_LIMIT)
+        .min(MAX_PAGE_SIZE);
+    Ok(Json(state.recent_trades(&symbol, limit)))
+}
+
+/// `GET /books/{symbol}/stats`
+pub(crate) async fn stats(
+    State(state): State<Arc<ApiState>>,
+    Path(symbol): Path<String>,
+) -> ApiResult<OrderBookStats> {
+    state
+        .engine
+        .stats(&symbol)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("Unknown symbol {}", symbol)))
+}
+
+/// `POST /books/{symbol}/trades/{trade_id}/bust`
+pub(crate) async fn bust_trade(
+    State(state): State<Arc<ApiState>>,
+    Path((symbol, trade_id)): Path<(String, u64)>,
+    Json(request): Json<BustTradeRequest>,
+) -> ApiResult<TradeAdjustment> {
+    Ok(Json(state.adjust_trade(&symbol, trade_id, None, &request.reason).await?))
+}
+
+/// `POST /books/{symbol}/trades/{trade_id}/correct`
+pub(crate) async fn correct_trade(
+    State(state): State<Arc<ApiState>>,
+    Path((symbol, trade_id)): Path<(String, u64)>,
+    Json(request): Json<CorrectTradeRequest>,
+) -> ApiResult<TradeAdjustment> {
+    let correction = Some((request.price, request.quantity));
+    Ok(Json(state.adjust_trade(&symbol, trade_id, correction, &request.reason).await?))
+}
+
+/// `GET /books/{symbol}/adjustments`, oldest first
+pub(crate) async fn adjustments(
+    State(state): State<Arc<ApiState>>,
+    Path(symbol): Path<String>,
+) -> ApiResult<Vec<TradeAdjustment>> {
+    Ok(Json(state.adjustments(&symbol)))
+}
@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex};
+
+use axum::routing::{get, post};
+use axum::Router;
+use log::{info, warn};
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+
+use crate::api::{rest, websocket};
+use crate::core::handle::{EngineError, EngineHandle, TradeEvent};
+use crate::models::price::{Price, Quantity};
+use crate::models::trade::Trade;
+use crate::models::trade_adjustment::{AdjustmentError, TradeAdjustment};
+use crate::persistence::trade_store::{RecordError, TradeStore};
+use crate::utils::time;
+
+/// State shared by all HTTP handlers
+pub(crate) struct ApiState {
+    pub(crate) engine: EngineHandle,
+    pub(crate) next_order_id: AtomicU64,
+    /// Trade history per symbol; trade IDs are only unique within a book
+    trades: Mutex<HashMap<String, TradeStore>>,
+    /// Held while a trade adjustment is applied, so two are never built from the same history
+    adjusting: tokio::sync::Mutex<()>,
+}
+
+impl ApiState {
+    /// Records trades in the history of their symbol
+    pub(crate) fn record_trades(&self, trades: &[Trade]) {
+        let mut stores = self.trades.lock().unwrap();
+        for trade in trades {
+            let store = stores.entry(trade.symbol.clone()).or_default();
+            if let Err(e) = store.add_trade(trade.clone()) {
+                warn!("Failed to record trade {}: {}", trade.id, e);
+            }
+        }
+    }
+
+    /// Returns up to `limit` of the most recent trades for a symbol that still stand, newest first
+    pub(crate) fn recent_trades(&self, symbol: &str, limit: usize) -> Vec<Trade> {
+        self.trades
+            .lock()
+            .unwrap()
+            .get(symbol)
+            .map(|store| {
+                store
+                    .effective_recent_trades(symbol, limit)
+                    .into_iter()
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Busts a trade, or corrects it to a price and quantity, in the engine
+    /// and records the adjustment in the trade history
+    pub(crate) async fn adjust_trade(
+        &self,
+        symbol: &str,
+        trade_id: u64,
+        correction: Option<(Price, Quantity)>,
+        reason: &str,
+    ) -> Result<TradeAdjustment, EngineError> {
+        let _adjusting = self.adjusting.lock().await;
+        let adjustment = {
+            let stores = self.trades.lock().unwrap();
+            let store = stores
+                .get(symbol)
+                .ok_or(AdjustmentError::UnknownTrade { trade_id })?;
+            let timestamp = time::current_timestamp_nanos();
+            match correction {
+                Some((price, quantity)) => store.correct_trade(trade_id, price, quantity, reason, timestamp)?,
+                None => store.bust_trade(trade_id, reason, timestamp)?,
+            }
+        };
+
+        let adjustment = self.engine.adjust_trade(adjustment).await?;
+        let mut stores = self.trades.lock().unwrap();
+        let Some(store) = stores.get_mut(symbol) else {
+            return Ok(adjustment);
+        };
+        match store.add_adjustment(adjustment.clone()) {
+            Ok(recorded) => Ok(recorded),
+            Err(RecordError::Rejected(e)) => Err(e.into()),
+            Err(RecordError::Io(e)) => {
+                // The adjustment is kept in memory and saved with the next write
+                warn!("Failed to save adjustment of trade {}: {}", trade_id, e);
+                Ok(store.adjustments().last().cloned().unwrap_or(adjustment))
+            }
+        }
+    }
+
+    /// Returns the busts and corrections of a symbol's trades, oldest first
+    pub(crate) fn adjustments(&self, symbol: &str) -> Vec<TradeAdjustment> {
+        self.trades
+            .lock()
+            .unwrap()
+            .get(symbol)
+            .map(|store| store.adjustments().to_vec())
+            .unwrap_or_default()
+    }
+}
+
+/// HTTP server exposing REST order entry and market data plus WebSocket feeds
+///
+/// REST endpoints:
+/// - `POST /orders` submits an order
+/// - `GET /orders/{symbol}/{order_id}` and `DELETE /orders/{symbol}/{order_id}` query and cancel an order
+/// - `GET /users/{user_id}/orders` lists a user's open orders
+/// - `GET /books/{symbol}/depth`, `/trades` and `/stats` return market data
+/// - `POST /books/{symbol}/trades/{trade_id}/bust` and `/correct` bust or
+///   correct a trade, and `GET /books/{symbol}/adjustments` lists both
+///
+/// `GET /ws/{symbol}` upgrades to a WebSocket streaming trades, best bid and
+/// offer changes and L2 snapshots for the symbol.
+#[derive(Clone)]
+pub struct ApiServer {
+    state: Arc<ApiState>,
+}
+
+impl ApiServer {
+    /// Creates a server for the given engine and starts recording its trades
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn new(engine: EngineHandle) -> Self {
+        let trades = engine.subscribe_trades();
+        let state = Arc::new(ApiState {
+            engine,
+            next_order_id: AtomicU64::new(1),
+            trades: Mutex::new(HashMap::new()),
+            adjusting: tokio::sync::Mutex::new(()),
+        });
+        tokio::spawn(record_trades(Arc::downgrade(&state), trades));
+
+        Self { state }
+    }
+
+    /// Returns the router with every endpoint, for serving or testing
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/orders", post(rest::submit_order))
+            .route(
+                "/orders/{symbol}/{order_id}",
+                get(rest::get_order).delete(rest::cancel_order),
+            )
+            .route("/users/{user_id}/orders", get(rest::open_orders))
+            .route("/books/{symbol}/depth", get(rest::depth))
+            .route("/books/{symbol}/trades", get(rest::recent_trades))
+            .route("/books/{symbol}/stats", get(rest::stats))
+            .route("/books/{symbol}/trades/{trade_id}/bust", post(rest::bust_trade))
+            .route("/books/{symbol}/trades/{trade_id}/correct", post(rest::correct_trade))
+            .route("/books/{symbol}/adjustments", get(rest::adjustments))
+            .route("/ws/{symbol}", get(websocket::market_data))
+            .with_state(Arc::clone(&self.state))
+    }
+
+    /// Serves HTTP until the listener fails
+    pub async fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        info!("API listening on {}", listener.local_addr()?);
+        axum::serve(listener, self.router()).await
+    }
+}
+
+/// Keeps the trade history up to date with trades from every order entry path
+async fn record_trades(
+    state: std::sync::Weak<ApiState>,
+    mut trades: broadcast::Receiver<TradeEvent>,
+) {
+    loop {
+        let event = trades.recv().await;
+        let Some(state) = state.upgrade() else {
+            break;
+        };
+        match event {
+            Ok(event) => state.record_trades(&[event.trade]),
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!("Trade history missed {} trades", missed);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode};
+    use futures_util::StreamExt;
+    use http_body_util::BodyExt;
+    use serde::de::DeserializeOwned;
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+    use tower::ServiceExt;
+
+    use crate::api::rest::OrderResponse;
+    use crate::api::websocket::MarketDataMessage;
+    use crate::core::handle::DepthSnapshot;
+    use crate::models::order::{Order, OrderStatus};
+    use crate::models::price::{Price, Quantity};
+    use crate::models::stats::OrderBookStats;
+
+    async fn call(
+        router: &Router,
+        method: Method,
+        uri: &str,
+        body: Option<&str>,
+    ) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(
+                body.map(|body| Body::from(body.to_string()))
+                    .unwrap_or_default(),
+            )
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null),
+        )
+    }
+
+    async fn get<T: DeserializeOwned>(router: &Router, uri: &str) -> T {
+        let (status, body) = call(router, Method::GET, uri, None).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        serde_json::from_value(body).unwrap()
+    }
+
+    async fn submit(
+        router: &Router,
+        user_id: u64,
+        side: &str,
+        order_type: &str,
+        price: u64,
+        quantity: u64,
+    ) -> OrderResponse {
+        let body = format!(
+            r#"{{"user_id":{},"symbol":"BTC-USD","side":"{}","order_type":"{}","price":{},"quantity":{}}}"#,
+            user_id, side, order_type, price, quantity
+        );
+        let (status, body) = call(router, Method::POST, "/orders", Some(&body)).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        serde_json::from_value(body).unwrap()
+    }
+
+    async fn next_message<S>(socket: &mut S) -> MarketDataMessage
+    where
+        S: futures_util::Stream<Item = Result<WsMessage, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("Timed out waiting for market data")
+            .unwrap()
+            .unwrap();
+        serde_json::from_str(message.to_text().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_order_entry_endpoints() {
+        let router = ApiServer::new(EngineHandle::spawn()).router();
+
+        let resting = submit(&router, 1, "Sell", "Limit", 10100, 5).await;
+        assert_eq!(resting.order.status, OrderStatus::New);
+        let buy = submit(&router, 2, "Buy", "Limit", 9900, 3).await;
+
+        let fill = submit(&router, 2, "Buy", "IOC", 10100, 2).await;
+        assert_eq!(fill.order.status, OrderStatus::Filled);
+        assert_eq!(fill.trades[0].sell_order_id, resting.order.id);
+
+        let order: Order = get(&router, &format!("/orders/BTC-USD/{}", resting.order.id)).await;
+        assert_eq!(order.status, OrderStatus::PartiallyFilled);
+        assert_eq!(order.remaining_quantity, Quantity(3));
+
+        let open: Vec<Order> = get(&router, "/users/2/orders").await;
+        assert_eq!(
+            open.iter().map(|o| o.id).collect::<Vec<_>>(),
+            vec![buy.order.id]
+        );
+
+        let uri = format!("/orders/BTC-USD/{}", buy.order.id);
+        let (status, body) = call(&router, Method::DELETE, &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "Canceled");
+        let (status, body) = call(&router, Method::DELETE, &uri, None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(body["error"].as_str().unwrap().contains("Canceled"));
+
+        let (status, _) = call(&router, Method::GET, "/orders/BTC-USD/999", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let zero = r#"{"user_id":1,"symbol":"BTC-USD","side":"Buy","order_type":"Limit","price":1,"quantity":0}"#;
+        let (status, body) = call(&router, Method::POST, "/orders", Some(zero)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Quantity must be positive");
+    }
+
+    #[tokio::test]
+    async fn test_market_data_endpoints() {
+        let router = ApiServer::new(EngineHandle::spawn()).router();
+        submit(&router, 1, "Sell", "Limit", 10100, 5).await;
+        submit(&router, 1, "Sell", "Limit", 10200, 5).await;
+        submit(&router, 2, "Buy", "Limit", 9900, 4).await;
+        submit(&router, 2, "Buy", "Market", 0, 1).await;
+        submit(&router, 2, "Buy", "Market", 0, 2).await;
+
+        let depth: DepthSnapshot = get(&router, "/books/BTC-USD/depth?levels=1").await;
+        assert_eq!(depth.bids, vec![(Price(9900), Quantity(4))]);
+        assert_eq!(depth.asks, vec![(Price(10100), Quantity(2))]);
+
+        let trades: Vec<crate::models::trade::Trade> =
+            get(&router, "/books/BTC-USD/trades?limit=1").await;
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].quantity, Quantity(2));
+        let trades: Vec<crate::models::trade::Trade> = get(&router, "/books/BTC-USD/trades").await;
+        assert_eq!(trades.len(), 2);
+
+        let stats: OrderBookStats = get(&router, "/books/BTC-USD/stats").await;
+        assert_eq!(stats.trade_count, 2);
+        assert_eq!(stats.volume, Quantity(3));
+        assert_eq!(stats.best_bid, Some(Price(9900)));
+
+        let (status, _) = call(&router, Method::GET, "/books/ETH-USD/stats", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let empty: Vec<crate::models::trade::Trade> = get(&router, "/books/ETH-USD/trades").await;
+        assert!(empty.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_trade_adjustment_endpoints() {
+        let router = ApiServer::new(EngineHandle::spawn()).router();
+        submit(&router, 1, "Sell", "Limit", 10100, 5).await;
+        let first = submit(&router, 2, "Buy", "Limit", 10100, 3).await.trades[0].id;
+        let second = submit(&router, 2, "Buy", "Limit", 10100, 2).await.trades[0].id;
+
+        let correct = r#"{"price":10000,"quantity":3,"reason":"Off-market price"}"#;
+        let uri = format!("/books/BTC-USD/trades/{}/correct", first);
+        let (status, body) = call(&router, Method::POST, &uri, Some(correct)).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["corrected"]["price"], 10000);
+
+        let uri = format!("/books/BTC-USD/trades/{}/bust", second);
+        let (status, _) = call(&router, Method::POST, &uri, Some(r#"{"reason":"Erroneous"}"#)).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(&router, Method::POST, &uri, Some("{}")).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, _) = call(&router, Method::POST, "/books/BTC-USD/trades/99/bust", Some("{}")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let stats: OrderBookStats = get(&router, "/books/BTC-USD/stats").await;
+        assert_eq!((stats.volume, stats.trade_count), (Quantity(3), 1));
+        assert_eq!(stats.last_trade_price, Some(Price(10000)));
+        let trades: Vec<crate::models::trade::Trade> = get(&router, "/books/BTC-USD/trades").await;
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].price, Price(10000));
+        let adjustments: Vec<TradeAdjustment> = get(&router, "/books/BTC-USD/adjustments").await;
+        assert_eq!(adjustments.iter().map(|a| (a.trade_id, a.is_bust())).collect::<Vec<_>>(), vec![(first, false), (second, true)]);
+        assert_eq!(adjustments[0].previous.price, Price(10100));
+    }
+
+    #[tokio::test]
+    async fn test_websocket_streams_trades_bbo_and_l2() {
+        let engine = EngineHandle::spawn();
+        let server = ApiServer::new(engine.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = server.router();
+        tokio::spawn(async move { server.serve(listener).await });
+
+        submit(&router, 1, "Sell", "Limit", 10100, 5).await;
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws/BTC-USD", addr))
+            .await
+            .unwrap();
+
+        // Snapshot on connect
+        assert_eq!(
+            next_message(&mut socket).await,
+            MarketDataMessage::Bbo {
+                symbol: "BTC-USD".to_string(),
+                bid: None,
+                ask: Some((Price(10100), Quantity(5))),
+            }
+        );
+        assert!(matches!(next_message(&mut socket).await, MarketDataMessage::L2(depth) if depth.asks.len() == 1));
+
+        // Another symbol's changes are not streamed
+        let eth = r#"{"user_id":3,"symbol":"ETH-USD","side":"Buy","order_type":"Limit","price":1,"quantity":1}"#;
+        let (status, _) = call(&router, Method::POST, "/orders", Some(eth)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // A trade that only shrinks the best ask changes the BBO and L2
+        submit(&router, 2, "Buy", "IOC", 10100, 2).await;
+        let mut messages = Vec::new();
+        for _ in 0..3 {
+            messages.push(next_message(&mut socket).await);
+        }
+        assert!(messages
+            .iter()
+            .any(|m| matches!(m, MarketDataMessage::Trade(t) if t.quantity == Quantity(2))));
+        assert!(messages.iter().any(|m| matches!(
+            m,
+            MarketDataMessage::Bbo {
+                ask: Some((_, Quantity(3))),
+                ..
+            }
+        )));
+        assert!(messages.iter().any(
+            |m| matches!(m, MarketDataMessage::L2(depth) if depth.asks == vec![(Price(10100), Quantity(3))])
+        ));
+    }
+}
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, State};
+use axum::response::Response;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::api::server::ApiState;
+use crate::core::handle::{DepthSnapshot, BOOK_UPDATE_LEVELS};
+use crate::models::price::{Price, Quantity};
+use crate::models::trade::Trade;
+
+/// A market data message sent on a symbol's WebSocket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketDataMessage {
+    /// A trade on the symbol
+    Trade(Trade),
+    /// The best bid and offer, sent on connect and whenever either changes
+    Bbo {
+        symbol: String,
+        bid: Option<(Price, Quantity)>,
+        ask: Option<(Price, Quantity)>,
+    },
+    /// The top levels of the book, sent on connect and after every change
+    L2(DepthSnapshot),
+    /// The connection fell behind and missed some updates
+    Lagged { missed: u64 },
+}
+
+impl MarketDataMessage {
+    /// Builds the best bid and offer of a depth snapshot
+    pub fn bbo(depth: &DepthSnapshot) -> Self {
+        MarketDataMessage::Bbo {
+            symbol: depth.symbol.clone(),
+            bid: depth.bids.first().copied(),
+            ask: depth.asks.first().copied(),
+        }
+    }
+}
+
+/// `GET /ws/{symbol}`
+pub(crate) async fn market_data(
+    State(state): State<Arc<ApiState>>,
+    Path(symbol): Path<String>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    upgrade.on_upgrade(move |socket| async move {
+        if let Err(e) = stream_market_data(state, symbol.clone(), socket).await {
+            debug!("Market data stream for {} ended: {}", symbol, e);
+        }
+    })
+}
+
+async fn stream_market_data(
+    state: Arc<ApiState>,
+    symbol: String,
+    mut socket: WebSocket,
+) -> Result<(), axum::Error> {
+    // Subscribe before the snapshot so no change in between is lost
+    let mut trades = state.engine.subscribe_trades();
+    let mut books = state.engine.subscribe_books();
+
+    let Ok(depth) = state.engine.depth(&symbol, BOOK_UPDATE_LEVELS).await else {
+        return Ok(());
+    };
+    let mut bbo = MarketDataMessage::bbo(&depth);
+    send(&mut socket, &bbo).await?;
+    send(&mut socket, &MarketDataMessage::L2(depth)).await?;
+
+    loop {
+        // Trades are published before the book update they cause, so poll them first
+        tokio::select! {
+            biased;
+            event = trades.recv() => match event {
+                Ok(event) if event.trade.symbol == symbol => {
+                    send(&mut socket, &MarketDataMessage::Trade(event.trade)).await?;
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    send(&mut socket, &MarketDataMessage::Lagged { missed }).await?;
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            update = books.recv() => match update {
+                Ok(depth) if depth.symbol == symbol => {
+                    let latest = MarketDataMessage::bbo(&depth);
+                    if latest != bbo {
+                        bbo = latest;
+                        send(&mut socket, &bbo).await?;
+                    }
+                    send(&mut socket, &MarketDataMessage::L2(depth)).await?;
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    send(&mut socket, &MarketDataMessage::Lagged { missed }).await?;
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            message = socket.recv() => match message {
+                // Pings are answered by the socket itself; client data is ignored
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e),
+            },
+        }
+    }
+}
+
+async fn send(socket: &mut WebSocket, message: &MarketDataMessage) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(message).map_err(axum::Error::new)?;
+    socket.send(Message::Text(text.into())).await
+}
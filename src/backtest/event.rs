@@ -0,0 +1,301 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::order::{Order, OrderSide, OrderType};
+use crate::models::price::{Price, Quantity};
+
+/// A single recorded order flow event to be replayed through an order book
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum HistoricalEvent {
+    /// Submission of a new order
+    New {
+        /// Original event timestamp (in nanoseconds)
+        timestamp: u64,
+        /// Order identifier
+        order_id: u64,
+        /// Symbol/ticker the order is for
+        symbol: String,
+        /// Order side (buy or sell)
+        side: OrderSide,
+        /// Type of the order
+        order_type: OrderType,
+        /// Limit price (ignored for market and stop orders)
+        #[serde(default)]
+        price: Price,
+        /// Order quantity
+        quantity: Quantity,
+        /// User or account identifier
+        user_id: u64,
+        /// Optional client-provided order identifier
+        #[serde(default)]
+        client_order_id: Option<String>,
+    },
+    /// Cancellation of a previously submitted order
+    Cancel {
+        /// Original event timestamp (in nanoseconds)
+        timestamp: u64,
+        /// Identifier of the order to cancel
+        order_id: u64,
+        /// Symbol/ticker the order is for
+        symbol: String,
+    },
+}
+
+impl HistoricalEvent {
+    /// Returns the recorded timestamp of the event
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            HistoricalEvent::New { timestamp, .. } => *timestamp,
+            HistoricalEvent::Cancel { timestamp, .. } => *timestamp,
+        }
+    }
+
+    /// Returns the symbol the event applies to
+    pub fn symbol(&self) -> &str {
+        match self {
+            HistoricalEvent::New { symbol, .. } => symbol,
+            HistoricalEvent::Cancel { symbol, .. } => symbol,
+        }
+    }
+
+    /// Builds the order described by a `New` event, stamped with the given time
+    /// Returns None for cancel events
+    pub fn to_order(&self, timestamp: u64) -> Option<Order> {
+        match self {
+            HistoricalEvent::New {
+                order_id,
+                symbol,
+                side,
+                order_type,
+                price,
+                quantity,
+                user_id,
+                client_order_id,
+                ..
+            } => Some(Order::new(
+                *order_id,
+                *order_type,
+                *price,
+                *quantity,
+                *side,
+                *user_id,
+                timestamp,
+                client_order_id.clone(),
+                symbol.clone(),
+            )),
+            HistoricalEvent::Cancel { .. } => None,
+        }
+    }
+
+    /// Parses a single CSV record
+    ///
+    /// Columns: `timestamp,event,order_id,symbol,side,order_type,price,quantity,user_id[,client_order_id]`.
+    /// Cancel records only need the first four columns.
+    pub fn from_csv_record(line: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+
+        let field = |index: usize, name: &str| -> Result<&str, String> {
+            fields
+                .get(index)
+                .copied()
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| format!("Missing {} column", name))
+        };
+        let number = |index: usize, name: &str| -> Result<u64, String> {
+            field(index, name)?
+                .parse::<u64>()
+                .map_err(|e| format!("Invalid {}: {}", name, e))
+        };
+
+        let timestamp = number(0, "timestamp")?;
+        let order_id = number(2, "order_id")?;
+        let symbol = field(3, "symbol")?.to_string();
+
+        match field(1, "event")?.to_ascii_lowercase().as_str() {
+            "new" => {
+                let order_type: OrderType = field(5, "order_type")?.parse()?;
+                let price = match order_type {
+                    OrderType::Limit | OrderType::IOC | OrderType::FOK => Price(number(6, "price")?),
+                    _ => fields.get(6).and_then(|p| p.parse().ok()).unwrap_or_default(),
+                };
+
+                Ok(HistoricalEvent::New {
+                    timestamp,
+                    order_id,
+                    symbol,
+                    side: field(4, "side")?.parse()?,
+                    order_type,
+                    price,
+                    quantity: Quantity(number(7, "quantity")?),
+                    user_id: number(8, "user_id")?,
+                    client_order_id: field(9, "client_order_id").ok().map(str::to_string),
+                })
+            }
+            "cancel" => Ok(HistoricalEvent::Cancel {
+                timestamp,
+                order_id,
+                symbol,
+            }),
+            other => Err(format!("Unknown event type: {}", other)),
+        }
+    }
+}
+
+/// Parses CSV order flow from a reader
+///
+/// Blank lines, `#` comments and a leading header row are skipped.
+pub fn parse_csv<R: BufRead>(reader: R) -> io::Result<Vec<HistoricalEvent>> {
+    let mut events = Vec::new();
+
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        // Skip the header row if present
+        if line_number == 0 && trimmed.starts_with("timestamp") {
+            continue;
+        }
+
+        let event = HistoricalEvent::from_csv_record(trimmed).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {}", line_number + 1, e),
+            )
+        })?;
+        events.push(event);
+    }
+
+    Ok(events)
+}
+
+/// Parses JSON lines order flow (one event object per line) from a reader
+pub fn parse_json_lines<R: BufRead>(reader: R) -> io::Result<Vec<HistoricalEvent>> {
+    let mut events = Vec::new();
+
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let event = serde_json::from_str::<HistoricalEvent>(&line).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {}", line_number + 1, e),
+            )
+        })?;
+        events.push(event);
+    }
+
+    Ok(events)
+}
+
+/// Loads order flow events from a CSV file
+pub fn load_csv(file_path: &str) -> io::Result<Vec<HistoricalEvent>> {
+    parse_csv(BufReader::new(File::open(file_path)?))
+}
+
+/// Loads order flow events from a JSON lines file
+pub fn load_json_lines(file_path: &str) -> io::Result<Vec<HistoricalEvent>> {
+    parse_json_lines(BufReader::new(File::open(file_path)?))
+}
+
+/// Loads order flow events, choosing the format from the file extension
+/// (`.csv` for CSV, anything else is treated as JSON lines)
+pub fn load_events(file_path: &str) -> io::Result<Vec<HistoricalEvent>> {
+    if file_path.to_ascii_lowercase().ends_with(".csv") {
+        load_csv(file_path)
+    } else {
+        load_json_lines(file_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv() {
+        let data = "\
+timestamp,event,order_id,symbol,side,order_type,price,quantity,user_id
+# resting liquidity
+100,new,1,BTC-USD,sell,limit,10100,5,2001
+200,new,2,BTC-USD,buy,market,,3,1001,client-2
+300,cancel,1,BTC-USD
+";
+        let events = parse_csv(data.as_bytes()).unwrap();
+        assert_eq!(events.len(), 3);
+
+        match &events[1] {
+            HistoricalEvent::New { order_type, quantity, client_order_id, .. } => {
+                assert_eq!(*order_type, OrderType::Market);
+                assert_eq!(*quantity, Quantity(3));
+                assert_eq!(client_order_id.as_deref(), Some("client-2"));
+            }
+            other => panic!("Unexpected event: {:?}", other),
+        }
+
+        assert_eq!(
+            events[2],
+            HistoricalEvent::Cancel { timestamp: 300, order_id: 1, symbol: "BTC-USD".to_string() }
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_reports_line_number() {
+        let data = "100,new,1,BTC-USD,sell,limit,10100,5,2001\n200,new,2,BTC-USD,buy,limit,,3,1001\n";
+        let err = parse_csv(data.as_bytes()).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn test_parse_json_lines() {
+        let data = r#"{"event":"new","timestamp":100,"order_id":1,"symbol":"BTC-USD","side":"Sell","order_type":"Limit","price":10100,"quantity":5,"user_id":2001}
+
+{"event":"new","timestamp":150,"order_id":2,"symbol":"BTC-USD","side":"Buy","order_type":{"StopLimit":[10200,10150]},"quantity":1,"user_id":1001}
+{"event":"cancel","timestamp":200,"order_id":1,"symbol":"BTC-USD"}
+"#;
+        let events = parse_json_lines(data.as_bytes()).unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].timestamp(), 100);
+        assert_eq!(events[2].symbol(), "BTC-USD");
+
+        let stop_limit = events[1].to_order(175).unwrap();
+        assert_eq!(stop_limit.order_type, OrderType::StopLimit(Price(10200), Price(10150)));
+        assert_eq!(stop_limit.price, Price(10150));
+        assert_eq!(stop_limit.timestamp, 175);
+    }
+
+    #[test]
+    fn test_event_to_order() {
+        let event = HistoricalEvent::New {
+            timestamp: 100,
+            order_id: 7,
+            symbol: "BTC-USD".to_string(),
+            side: OrderSide::Buy,
+            order_type: OrderType::Market,
+            price: Price::ZERO,
+            quantity: Quantity(4),
+            user_id: 1001,
+            client_order_id: None,
+        };
+
+        let order = event.to_order(500).unwrap();
+        assert_eq!(order.id, 7);
+        assert_eq!(order.price, Price::MAX);
+        assert_eq!(order.timestamp, 500);
+
+        let cancel = HistoricalEvent::Cancel { timestamp: 100, order_id: 7, symbol: "BTC-USD".to_string() };
+        assert!(cancel.to_order(500).is_none());
+    }
+}
@@ -0,0 +1,9 @@
+// Export backtesting components
+pub mod event;
+pub mod report;
+pub mod runner;
+
+// Re-export main components
+pub use event::{HistoricalEvent, load_csv, load_events, load_json_lines};
+pub use report::{BacktestReport, SymbolSummary, UserFills};
+pub use runner::Backtest;
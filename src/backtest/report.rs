@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::price::{Price, Quantity};
+use crate::models::trade::Trade;
+
+/// Fill and P&L summary for a single user over a backtest
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserFills {
+    /// User or account identifier
+    pub user_id: u64,
+    /// Number of fills the user participated in
+    pub fill_count: u64,
+    /// Total quantity bought
+    pub bought_quantity: Quantity,
+    /// Total quantity sold
+    pub sold_quantity: Quantity,
+    /// Total notional paid for purchases
+    pub bought_notional: u128,
+    /// Total notional received for sales
+    pub sold_notional: u128,
+    /// Net position per symbol (positive is long)
+    pub positions: HashMap<String, i128>,
+    /// P&L marked to each symbol's last trade price, ignoring latency and fees
+    pub pnl: i128,
+}
+
+/// Trading summary for a single symbol over a backtest
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SymbolSummary {
+    /// Symbol/ticker this summary is for
+    pub symbol: String,
+    /// Number of trades executed
+    pub trade_count: u64,
+    /// Total quantity traded, saturating at `u64::MAX`
+    pub volume: Quantity,
+    /// Exact total quantity traded, used for VWAP once `volume` saturates
+    #[serde(default)]
+    pub total_quantity: u128,
+    /// Total notional traded (price * quantity)
+    pub notional: u128,
+    /// Price of the most recent trade, used to mark positions
+    pub last_price: Option<Price>,
+}
+
+impl SymbolSummary {
+    /// Returns the volume-weighted average trade price
+    pub fn vwap(&self) -> Option<f64> {
+        if self.total_quantity > 0 {
+            Some(self.notional as f64 / self.total_quantity as f64)
+        } else {
+            None
+        }
+    }
+}
+
+/// Results of replaying order flow through the engine
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BacktestReport {
+    /// Number of events replayed
+    pub events_processed: u64,
+    /// Number of new orders submitted
+    pub orders_submitted: u64,
+    /// Number of cancels that removed an order from a book
+    pub cancels_accepted: u64,
+    /// Number of cancels referencing unknown orders
+    pub cancels_rejected: u64,
+    /// Total number of trades executed
+    pub trade_count: u64,
+    /// Total quantity traded across all symbols
+    pub volume: Quantity,
+    /// Per-symbol trading summaries
+    pub symbols: HashMap<String, SymbolSummary>,
+    /// Per-user fills and P&L
+    pub users: HashMap<u64, UserFills>,
+}
+
+impl BacktestReport {
+    /// Builds the trade-derived parts of a report from a set of trades
+    ///
+    /// Trades are applied in (timestamp, id) order so the last trade price of
+    /// each symbol is well defined regardless of the input order.
+    pub fn from_trades<'a, I>(trades: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Trade>,
+    {
+        let mut trades: Vec<&Trade> = trades.into_iter().collect();
+        trades.sort_by_key(|trade| (trade.timestamp, trade.id));
+
+        let mut report = Self::default();
+        // Cash flow per (user, symbol): negative for purchases, positive for sales
+        let mut cash_flows: HashMap<(u64, String), i128> = HashMap::new();
+
+        for trade in trades {
+            let notional = trade.value();
+            let signed_notional = i128::try_from(notional).unwrap_or(i128::MAX);
+            let quantity = trade.quantity.0 as i128;
+
+            report.trade_count += 1;
+            report.volume += trade.quantity;
+
+            let summary = report
+                .symbols
+                .entry(trade.symbol.clone())
+                .or_insert_with(|| SymbolSummary {
+                    symbol: trade.symbol.clone(),
+                    ..Default::default()
+                });
+            summary.trade_count += 1;
+            summary.volume += trade.quantity;
+            summary.total_quantity += trade.quantity.0 as u128;
+            summary.notional = summary.notional.saturating_add(notional);
+            summary.last_price = Some(trade.price);
+
+            let buyer = report.users.entry(trade.buy_user_id).or_default();
+            buyer.user_id = trade.buy_user_id;
+            buyer.fill_count += 1;
+            buyer.bought_quantity += trade.quantity;
+            buyer.bought_notional = buyer.bought_notional.saturating_add(notional);
+            *buyer.positions.entry(trade.symbol.clone()).or_insert(0) += quantity;
+            let cash = cash_flows.entry((trade.buy_user_id, trade.symbol.clone())).or_insert(0);
+            *cash = cash.saturating_sub(signed_notional);
+
+            let seller = report.users.entry(trade.sell_user_id).or_default();
+            seller.user_id = trade.sell_user_id;
+            seller.fill_count += 1;
+            seller.sold_quantity += trade.quantity;
+            seller.sold_notional = seller.sold_notional.saturating_add(notional);
+            *seller.positions.entry(trade.symbol.clone()).or_insert(0) -= quantity;
+            let cash = cash_flows.entry((trade.sell_user_id, trade.symbol.clone())).or_insert(0);
+            *cash = cash.saturating_add(signed_notional);
+        }
+
+        // Mark open positions to the last trade price of each symbol
+        for ((user_id, symbol), cash) in cash_flows {
+            let mark = report.symbols[&symbol].last_price.unwrap_or_default().0 as i128;
+            if let Some(user) = report.users.get_mut(&user_id) {
+                // Saturate rather than wrap for values beyond the i128 range
+                let position = user.positions.get(&symbol).copied().unwrap_or(0);
+                let pnl = cash.saturating_add(position.saturating_mul(mark));
+                user.pnl = user.pnl.saturating_add(pnl);
+            }
+        }
+
+        report
+    }
+
+    /// Returns the volume-weighted average price for a symbol
+    pub fn vwap(&self, symbol: &str) -> Option<f64> {
+        self.symbols.get(symbol).and_then(|summary| summary.vwap())
+    }
+
+    /// Returns the fills for a user
+    pub fn user(&self, user_id: u64) -> Option<&UserFills> {
+        self.users.get(&user_id)
+    }
+
+    /// Generate a human-readable summary of the backtest
+    pub fn summary(&self) -> String {
+        let mut lines = vec![format!(
+            "Events: {}, Orders: {}, Cancels: {} ({} rejected), Trades: {}, Volume: {}",
+            self.events_processed,
+            self.orders_submitted,
+            self.cancels_accepted,
+            self.cancels_rejected,
+            self.trade_count,
+            self.volume
+        )];
+
+        let mut symbols: Vec<&SymbolSummary> = self.symbols.values().collect();
+        symbols.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        for summary in symbols {
+            lines.push(format!(
+                "{} - Trades: {}, Volume: {}, VWAP: {:.2}",
+                summary.symbol,
+                summary.trade_count,
+                summary.volume,
+                summary.vwap().unwrap_or(0.0)
+            ));
+        }
+
+        let mut users: Vec<&UserFills> = self.users.values().collect();
+        users.sort_by_key(|user| user.user_id);
+        for user in users {
+            lines.push(format!(
+                "User {} - Fills: {}, Bought: {}, Sold: {}, P&L: {}",
+                user.user_id, user.fill_count, user.bought_quantity, user.sold_quantity, user.pnl
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::order::OrderSide;
+
+    fn trade(id: u64, price: u64, quantity: u64, timestamp: u64, buyer: u64, seller: u64) -> Trade {
+        Trade::new(id, price, quantity, timestamp, id * 10, id * 10 + 1, buyer, seller, OrderSide::Buy, "BTC-USD".to_string())
+    }
+
+    #[test]
+    fn test_report_from_trades() {
+        let trades = vec![
+            trade(1, 100, 2, 10, 1001, 2001),
+            trade(2, 110, 3, 20, 2001, 1001),
+        ];
+        let report = BacktestReport::from_trades(&trades);
+
+        assert_eq!(report.trade_count, 2);
+        assert_eq!(report.volume, Quantity(5));
+        assert_eq!(report.vwap("BTC-USD"), Some(106.0));
+        assert_eq!(report.symbols["BTC-USD"].last_price, Some(Price(110)));
+
+        let user = report.user(1001).unwrap();
+        assert_eq!(user.fill_count, 2);
+        assert_eq!(user.bought_quantity, Quantity(2));
+        assert_eq!(user.sold_quantity, Quantity(3));
+        assert_eq!(user.positions["BTC-USD"], -1);
+        // Bought 2 @ 100, sold 3 @ 110, short 1 marked at 110
+        assert_eq!(user.pnl, -200 + 330 - 110);
+    }
+
+    #[test]
+    fn test_pnl_is_zero_sum() {
+        let trades = vec![
+            trade(3, 105, 1, 30, 1003, 1001),
+            trade(1, 100, 4, 10, 1001, 1002),
+            trade(2, 120, 2, 20, 1002, 1003),
+        ];
+        let report = BacktestReport::from_trades(&trades);
+
+        // Last price follows trade time, not input order
+        assert_eq!(report.symbols["BTC-USD"].last_price, Some(Price(105)));
+
+        let total: i128 = report.users.values().map(|user| user.pnl).sum();
+        assert_eq!(total, 0);
+    }
+
+    #[test]
+    fn test_empty_report() {
+        let report = BacktestReport::from_trades(Vec::<&Trade>::new());
+        assert_eq!(report.trade_count, 0);
+        assert!(report.vwap("BTC-USD").is_none());
+        assert!(report.summary().starts_with("Events: 0"));
+    }
+    
+    mod overflow {
+        use super::*;
+        use proptest::prelude::*;
+        
+        proptest! {
+            #[test]
+            fn extreme_trades_do_not_overflow(
+                fills in prop::collection::vec(
+                    (prop_oneof![Just(u64::MAX), 1u64..=u64::MAX], prop_oneof![Just(u64::MAX), 1u64..=u64::MAX], 1u64..4, 1u64..4),
+                    1..20,
+                ),
+            ) {
+                let trades: Vec<Trade> = fills
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &(price, quantity, buyer, seller))| trade(i as u64 + 1, price, quantity, i as u64, buyer, seller))
+                    .collect();
+                let report = BacktestReport::from_trades(&trades);
+                
+                let min_price = fills.iter().map(|fill| fill.0).min().unwrap() as f64;
+                let max_price = fills.iter().map(|fill| fill.0).max().unwrap() as f64;
+                let exact: Option<u128> = trades.iter().try_fold(0u128, |total, trade| total.checked_add(trade.value()));
+                match exact {
+                    Some(notional) => {
+                        prop_assert_eq!(report.symbols["BTC-USD"].notional, notional);
+                        let vwap = report.vwap("BTC-USD").unwrap();
+                        prop_assert!(vwap >= min_price * (1.0 - 1e-9) && vwap <= max_price * (1.0 + 1e-9));
+                    }
+                    // Beyond the u128 range the notional saturates instead of wrapping
+                    None => prop_assert_eq!(report.symbols["BTC-USD"].notional, u128::MAX),
+                }
+                prop_assert_eq!(report.trade_count, trades.len() as u64);
+                let _ = report.summary();
+            }
+        }
+    }
+}
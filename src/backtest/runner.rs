@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::io;
+
+use log::debug;
+
+use crate::backtest::event::HistoricalEvent;
+use crate::backtest::report::BacktestReport;
+use crate::core::order_book::OrderBook;
+use crate::models::instrument::Instrument;
+use crate::models::trade::Trade;
+use crate::persistence::trade_store::TradeStore;
+use crate::utils::time::SimulatedClock;
+
+/// Replays recorded order flow through per-symbol order books
+///
+/// Orders are stamped with a simulated clock driven by the event timestamps,
+/// and every resulting trade is recorded in a `TradeStore`. Trade IDs are
+/// reassigned so they are unique across all symbols in the store.
+pub struct Backtest {
+    /// Order books by symbol, created on first use
+    books: HashMap<String, OrderBook>,
+    /// Store receiving every executed trade
+    trade_store: TradeStore,
+    /// Simulated clock driven by event timestamps
+    clock: SimulatedClock,
+    /// Last assigned trade ID
+    last_trade_id: u64,
+    /// Event counters
+    events_processed: u64,
+    orders_submitted: u64,
+    cancels_accepted: u64,
+    cancels_rejected: u64,
+}
+
+impl Backtest {
+    /// Creates a new backtest recording trades into an in-memory store
+    pub fn new() -> Self {
+        Self::with_trade_store(TradeStore::new())
+    }
+
+    /// Creates a new backtest recording trades into the given store
+    pub fn with_trade_store(trade_store: TradeStore) -> Self {
+        let last_trade_id = trade_store
+            .get_all_trades()
+            .iter()
+            .map(|trade| trade.id)
+            .max()
+            .unwrap_or(0);
+
+        Self {
+            books: HashMap::new(),
+            trade_store,
+            clock: SimulatedClock::default(),
+            last_trade_id,
+            events_processed: 0,
+            orders_submitted: 0,
+            cancels_accepted: 0,
+            cancels_rejected: 0,
+        }
+    }
+
+    /// Registers an instrument so its book uses the instrument's precision
+    /// Symbols that are not registered get a book with the default precision
+    pub fn add_instrument(&mut self, instrument: Instrument) {
+        self.books
+            .insert(instrument.symbol.clone(), OrderBook::with_instrument(instrument));
+    }
+
+    /// Replays a single event and returns the trades it produced
+    pub fn process_event(&mut self, event: &HistoricalEvent) -> io::Result<Vec<Trade>> {
+        let now = self.clock.advance_to(event.timestamp());
+        self.events_processed += 1;
+
+        let book = self
+            .books
+            .entry(event.symbol().to_string())
+            .or_insert_with(|| OrderBook::new(event.symbol()));
+
+        let mut trades = match event {
+            HistoricalEvent::New { .. } => {
+                self.orders_submitted += 1;
+                match event.to_order(now) {
+                    Some(order) => book.process_order(order),
+                    None => Vec::new(),
+                }
+            }
+            HistoricalEvent::Cancel { order_id, .. } => {
+                if book.cancel_order(*order_id) {
+                    self.cancels_accepted += 1;
+                } else {
+                    debug!("Cancel for unknown order {} at {}", order_id, now);
+                    self.cancels_rejected += 1;
+                }
+                Vec::new()
+            }
+        };
+
+        for trade in &mut trades {
+            self.last_trade_id += 1;
+            trade.id = self.last_trade_id;
+        }
+
+        if !trades.is_empty() {
+            self.trade_store.add_trades(trades.clone())?;
+        }
+
+        Ok(trades)
+    }
+
+    /// Replays all events in order and returns the resulting report
+    pub fn run<'a, I>(&mut self, events: I) -> io::Result<BacktestReport>
+    where
+        I: IntoIterator<Item = &'a HistoricalEvent>,
+    {
+        for event in events {
+            self.process_event(event)?;
+        }
+
+        Ok(self.report())
+    }
+
+    /// Builds a report from the trades recorded so far
+    pub fn report(&self) -> BacktestReport {
+        let mut report = BacktestReport::from_trades(self.trade_store.get_all_trades());
+        report.events_processed = self.events_processed;
+        report.orders_submitted = self.orders_submitted;
+        report.cancels_accepted = self.cancels_accepted;
+        report.cancels_rejected = self.cancels_rejected;
+        report
+    }
+
+    /// Returns the order book for a symbol, if any events referenced it
+    pub fn book(&self, symbol: &str) -> Option<&OrderBook> {
+        self.books.get(symbol)
+    }
+
+    /// Returns the store holding all executed trades
+    pub fn trade_store(&self) -> &TradeStore {
+        &self.trade_store
+    }
+
+    /// Returns the simulated clock
+    pub fn clock(&self) -> &SimulatedClock {
+        &self.clock
+    }
+}
+
+impl Default for Backtest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::event::parse_csv;
+    use crate::models::price::Quantity;
+
+    const ORDER_FLOW: &str = "\
+timestamp,event,order_id,symbol,side,order_type,price,quantity,user_id
+100,new,1,BTC-USD,sell,limit,10100,5,2001
+110,new,2,BTC-USD,sell,limit,10200,5,2002
+120,new,3,ETH-USD,sell,limit,2000,10,2001
+200,new,4,BTC-USD,buy,limit,10200,7,1001
+210,new,5,ETH-USD,buy,market,,4,1001
+300,cancel,2,BTC-USD
+310,cancel,99,BTC-USD
+";
+
+    #[test]
+    fn test_backtest_run() {
+        let events = parse_csv(ORDER_FLOW.as_bytes()).unwrap();
+        let mut backtest = Backtest::new();
+        let report = backtest.run(&events).unwrap();
+
+        assert_eq!(report.events_processed, 7);
+        assert_eq!(report.orders_submitted, 5);
+        assert_eq!(report.cancels_accepted, 1);
+        assert_eq!(report.cancels_rejected, 1);
+
+        // BTC: 5 @ 10100 + 2 @ 10200, ETH: 4 @ 2000
+        assert_eq!(report.trade_count, 3);
+        assert_eq!(report.volume, Quantity(11));
+        assert_eq!(report.symbols["BTC-USD"].volume, Quantity(7));
+        assert_eq!(report.vwap("ETH-USD"), Some(2000.0));
+
+        let buyer = report.user(1001).unwrap();
+        assert_eq!(buyer.bought_quantity, Quantity(11));
+        assert_eq!(buyer.positions["BTC-USD"], 7);
+        // Marked at the last BTC price of 10200
+        assert_eq!(buyer.pnl, 7 * 10200 - (5 * 10100 + 2 * 10200));
+
+        assert_eq!(report.user(2001).unwrap().sold_quantity, Quantity(9));
+        assert_eq!(backtest.clock().now(), 310);
+    }
+
+    #[test]
+    fn test_trade_ids_unique_across_symbols() {
+        let events = parse_csv(ORDER_FLOW.as_bytes()).unwrap();
+        let mut backtest = Backtest::new();
+        backtest.run(&events).unwrap();
+
+        // Each book numbers its own trades from 1, the store must keep all of them
+        assert_eq!(backtest.trade_store().count(), 3);
+        assert!(backtest.book("ETH-USD").is_some());
+    }
+
+    #[test]
+    fn test_simulated_clock_stamps_orders() {
+        let events = parse_csv(
+            "500,new,1,BTC-USD,sell,limit,100,1,2001\n400,new,2,BTC-USD,sell,limit,100,1,2002\n".as_bytes(),
+        )
+        .unwrap();
+        let mut backtest = Backtest::new();
+        backtest.run(&events).unwrap();
+
+        // The out-of-order event does not move the clock backwards
+        let book = backtest.book("BTC-USD").unwrap();
+        assert_eq!(book.get_order(1).unwrap().timestamp, 500);
+        assert_eq!(book.get_order(2).unwrap().timestamp, 500);
+    }
+
+    #[test]
+    fn test_registered_instrument_precision() {
+        let mut backtest = Backtest::new();
+        backtest.add_instrument(Instrument::with_precision("BTC-USD", 2, 8).unwrap());
+        backtest.run(&parse_csv(ORDER_FLOW.as_bytes()).unwrap()).unwrap();
+
+        assert_eq!(backtest.book("BTC-USD").unwrap().instrument().quantity_decimals, 8);
+        assert_eq!(backtest.book("ETH-USD").unwrap().instrument().quantity_decimals, 0);
+    }
+}
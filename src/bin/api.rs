@@ -0,0 +1,32 @@
+//! HTTP server exposing the REST and WebSocket API
+//!
+//! Usage: `rustflow-api [ADDRESS]`
+//!
+//! Market orders are collared at `RUSTFLOW_MARKET_COLLAR_BPS` basis points
+//! from the best price when that variable is set.
+//!
+//! The API trusts the `user_id` in each request, so it should only be
+//! reachable through an authenticating proxy.
+
+use std::env;
+use std::io;
+
+use tokio::net::TcpListener;
+
+use rustflow::api::ApiServer;
+use rustflow::core::handle::{EngineConfig, EngineHandle};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+
+#[tokio::main]
+async fn main() -> io::Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let address = env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+    let listener = TcpListener::bind(&address).await?;
+
+    let config = EngineConfig::from_env().map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    ApiServer::new(EngineHandle::with_config(config)).serve(listener).await
+}
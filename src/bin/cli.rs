@@ -0,0 +1,58 @@
+//! Interactive command line for order books
+//!
+//! Usage: `rustflow [SCRIPT]`
+//!
+//! Without a script, commands are read from standard input, with a prompt when
+//! it is a terminal. With a script, each command is echoed before its output and
+//! the first failing command ends the run with a non-zero exit code.
+
+use std::env;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::process::ExitCode;
+
+use rustflow::cli::{Control, Session};
+
+const PROMPT: &str = "rustflow> ";
+
+fn main() -> ExitCode {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    let mut session = Session::new();
+    let mut stdout = io::stdout();
+
+    if let Some(script) = env::args().nth(1) {
+        return match session.run_script(&script, &mut stdout, true) {
+            Ok(_) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal();
+    if interactive {
+        println!("RustFlow order book shell, type help for commands");
+    }
+    let mut lines = stdin.lock().lines();
+    loop {
+        if interactive {
+            print!("{}", PROMPT);
+            let _ = stdout.flush();
+        }
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            Some(Err(e)) => {
+                eprintln!("Error: {}", e);
+                return ExitCode::FAILURE;
+            }
+            None => return ExitCode::SUCCESS,
+        };
+        match session.execute(&line, &mut stdout) {
+            Ok(Control::Continue) => {}
+            Ok(Control::Quit) => return ExitCode::SUCCESS,
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+}
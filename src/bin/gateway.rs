@@ -0,0 +1,41 @@
+//! TCP order entry gateway speaking newline-delimited JSON
+//!
+//! Usage: `rustflow-gateway [ADDRESS] [CREDENTIALS_FILE]`
+//!
+//! Market orders are collared at `RUSTFLOW_MARKET_COLLAR_BPS` basis points
+//! from the best price when that variable is set.
+//!
+//! The credentials file holds one `user_id:token` per line. Without it any
+//! user may log on, which is only suitable for local testing.
+
+use std::env;
+use std::io;
+
+use log::{info, warn};
+use tokio::net::TcpListener;
+
+use rustflow::core::handle::{EngineConfig, EngineHandle};
+use rustflow::gateway::{load_credentials, Gateway, GatewayConfig};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:7001";
+
+#[tokio::main]
+async fn main() -> io::Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let mut args = env::args().skip(1);
+    let address = args.next().unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+    let config = match args.next() {
+        Some(path) => GatewayConfig::with_credentials(load_credentials(&path)?),
+        None => {
+            warn!("No credentials file given, accepting any user");
+            GatewayConfig::new()
+        }
+    };
+
+    let listener = TcpListener::bind(&address).await?;
+    info!("RustFlow gateway listening on {}", listener.local_addr()?);
+
+    let engine = EngineConfig::from_env().map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    Gateway::new(EngineHandle::with_config(engine), config).serve(listener).await
+}
@@ -0,0 +1,33 @@
+//! gRPC server exposing the `rustflow.v1.MatchingEngine` service
+//!
+//! Usage: `rustflow-grpc [ADDRESS]`
+//!
+//! Market orders are collared at `RUSTFLOW_MARKET_COLLAR_BPS` basis points
+//! from the best price when that variable is set.
+//!
+//! The service trusts the `user_id` in each request, so it should only be
+//! reachable by trusted internal clients.
+
+use std::env;
+use std::error::Error;
+
+use tokio::net::TcpListener;
+
+use rustflow::core::handle::{EngineConfig, EngineHandle};
+use rustflow::grpc::GrpcServer;
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:50051";
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let address = env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+    let listener = TcpListener::bind(&address).await?;
+
+    let config = EngineConfig::from_env()?;
+    GrpcServer::new(EngineHandle::with_config(config)).serve(listener).await?;
+    Ok(())
+}
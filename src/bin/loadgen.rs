@@ -0,0 +1,98 @@
+//! Open-loop load generator for the engine
+//!
+//! Usage: `rustflow-loadgen [OPTIONS]`
+//!
+//! Sends orders at a fixed rate to an in-process engine, or to a gateway with
+//! `--gateway`, and prints throughput and latency percentiles. Latency is
+//! measured from each message's scheduled send time, so a stall is charged to
+//! every message it delays.
+
+use std::env;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use rustflow::core::handle::EngineHandle;
+use rustflow::loadgen::{run_gateway, run_in_process, GatewayTarget, LoadConfig};
+
+const USAGE: &str = "\
+Usage: rustflow-loadgen [OPTIONS]
+
+Options:
+  --rate <N>             Messages per second (default 10000)
+  --duration <SECONDS>   How long to send for (default 10)
+  --symbols <A,B,...>    Symbols to spread orders across (default BTC-USD)
+  --mix <KIND=WEIGHT,..> Weights of limit, market, ioc, fok and cancel
+                         (default limit=60,market=5,ioc=7,fok=3,cancel=25)
+  --seed <N>             Seed of the order generator (default 1)
+  --gateway <ADDRESS>    Send to a gateway instead of an in-process engine
+  --user <ID>            User of the first gateway session (default 1)
+  --token <TOKEN>        Logon token for every gateway session
+  --connections <N>      Gateway sessions, logged on as consecutive users (default 1)";
+
+fn parse_args() -> Result<(LoadConfig, Option<GatewayTarget>), String> {
+    let mut config = LoadConfig::new(10_000, Duration::from_secs(10));
+    let mut target: Option<GatewayTarget> = None;
+    let (mut user_id, mut token, mut connections) = (None, None, None);
+
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("Missing value for {}", flag))?;
+        let number = |value: &str| value.parse::<u64>().map_err(|_| format!("Invalid number '{}' for {}", value, flag));
+        match flag.as_str() {
+            "--rate" => config.rate = number(&value)?.max(1),
+            "--duration" => {
+                let seconds: f64 = value.parse().map_err(|_| format!("Invalid duration '{}'", value))?;
+                config.duration = Duration::try_from_secs_f64(seconds).map_err(|e| e.to_string())?;
+            }
+            "--symbols" => config = config.with_symbols(value.split(',').map(str::to_string).collect()),
+            "--mix" => config = config.with_mix(value.parse()?),
+            "--seed" => config = config.with_seed(number(&value)?),
+            "--gateway" => target = Some(GatewayTarget::new(&value)),
+            "--user" => user_id = Some(number(&value)?),
+            "--token" => token = Some(value),
+            "--connections" => connections = Some(number(&value)? as usize),
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+
+    if let Some(target) = target.as_mut() {
+        target.user_id = user_id.unwrap_or(target.user_id);
+        target.token = token.unwrap_or_default();
+        target.connections = connections.unwrap_or(target.connections).max(1);
+    } else if user_id.is_some() || token.is_some() || connections.is_some() {
+        return Err("--user, --token and --connections need --gateway".to_string());
+    }
+    Ok((config, target))
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    let (config, target) = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    println!(
+        "Sending {} messages over {:.1} s to {}",
+        config.message_count(),
+        config.duration.as_secs_f64(),
+        target.as_ref().map_or("an in-process engine", |target| target.address.as_str())
+    );
+    let report = match &target {
+        Some(target) => match run_gateway(target, &config).await {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => run_in_process(EngineHandle::spawn(), &config).await,
+    };
+    println!("{}", report.summary());
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,256 @@
+use std::str::FromStr;
+
+use crate::models::order::OrderSide;
+
+/// Default number of levels shown by `book`
+pub const DEFAULT_BOOK_DEPTH: usize = 10;
+
+/// Default number of trades shown by `trades`
+pub const DEFAULT_TRADE_LIMIT: usize = 20;
+
+/// Command reference printed by `help`
+pub const HELP: &str = "\
+Commands:
+  create <SYMBOL> [PRICE_DECIMALS QUANTITY_DECIMALS]  Create an order book
+  books                                               List order books
+  user <USER_ID>                                      Set the user for new orders
+  buy|sell <SYMBOL> <QUANTITY> limit <PRICE>          Submit a limit order
+  buy|sell <SYMBOL> <QUANTITY> market                 Submit a market order
+  buy|sell <SYMBOL> <QUANTITY> ioc|fok <PRICE>        Submit an immediate-or-cancel or fill-or-kill order
+  buy|sell <SYMBOL> <QUANTITY> stop <STOP>            Submit a stop order
+  buy|sell <SYMBOL> <QUANTITY> stop_limit <STOP> <LIMIT>
+                                                      Submit a stop-limit order
+  cancel <SYMBOL> <ORDER_ID>                          Cancel an order
+  amend <SYMBOL> <ORDER_ID> [price=<PRICE>] [qty=<QUANTITY>]
+                                                      Change an order's price and/or quantity
+  book <SYMBOL> [DEPTH]                               Print the order book
+  orders <SYMBOL>                                     List active orders
+  stats <SYMBOL>                                      Show book statistics
+  trades <SYMBOL> [LIMIT]                             Show the most recent trades
+  save <FILE>                                         Save the session as a replayable script
+  load <FILE>                                         Replace the session with a saved one
+  run <FILE>                                          Run a script in this session
+  help                                                Show this help
+  quit | exit                                         Leave
+
+Prices and quantities are decimals in the book's precision. Lines starting with # are comments.";
+
+/// The order type and prices of a `buy` or `sell` command, as typed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderSpec {
+    Limit(String),
+    Market,
+    IOC(String),
+    FOK(String),
+    Stop(String),
+    StopLimit(String, String),
+}
+
+/// A parsed REPL command
+///
+/// Prices and quantities stay as decimal strings until the session knows the
+/// precision of the book they are for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Create {
+        symbol: String,
+        precision: Option<(u32, u32)>,
+    },
+    Books,
+    User(u64),
+    Submit {
+        side: OrderSide,
+        symbol: String,
+        quantity: String,
+        spec: OrderSpec,
+    },
+    Cancel {
+        symbol: String,
+        order_id: u64,
+    },
+    Amend {
+        symbol: String,
+        order_id: u64,
+        price: Option<String>,
+        quantity: Option<String>,
+    },
+    Book {
+        symbol: String,
+        depth: usize,
+    },
+    Orders(String),
+    Stats(String),
+    Trades {
+        symbol: String,
+        limit: usize,
+    },
+    Save(String),
+    Load(String),
+    Run(String),
+    Help,
+    Quit,
+}
+
+impl Command {
+    /// Parses one line, returning `None` for blank lines and comments
+    pub fn parse(line: &str) -> Result<Option<Self>, String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or_default().to_ascii_lowercase();
+        let args: Vec<&str> = words.collect();
+
+        let command = match (name.as_str(), args.as_slice()) {
+            ("create", [symbol]) => Command::Create {
+                symbol: symbol.to_string(),
+                precision: None,
+            },
+            ("create", [symbol, price_decimals, quantity_decimals]) => Command::Create {
+                symbol: symbol.to_string(),
+                precision: Some((number(price_decimals)?, number(quantity_decimals)?)),
+            },
+            ("books", []) => Command::Books,
+            ("user", [user_id]) => Command::User(number(user_id)?),
+            ("buy" | "sell", [symbol, quantity, order_type, prices @ ..]) => Command::Submit {
+                side: if name == "buy" { OrderSide::Buy } else { OrderSide::Sell },
+                symbol: symbol.to_string(),
+                quantity: quantity.to_string(),
+                spec: order_spec(order_type, prices)?,
+            },
+            ("cancel", [symbol, order_id]) => Command::Cancel {
+                symbol: symbol.to_string(),
+                order_id: number(order_id)?,
+            },
+            ("amend", [symbol, order_id, changes @ ..]) if !changes.is_empty() => {
+                let mut price = None;
+                let mut quantity = None;
+                for change in changes {
+                    match change.split_once('=') {
+                        Some(("price", value)) => price = Some(value.to_string()),
+                        Some(("qty" | "quantity", value)) => quantity = Some(value.to_string()),
+                        _ => return Err(format!("Expected price=<PRICE> or qty=<QUANTITY>, got '{}'", change)),
+                    }
+                }
+                Command::Amend {
+                    symbol: symbol.to_string(),
+                    order_id: number(order_id)?,
+                    price,
+                    quantity,
+                }
+            }
+            ("book", [symbol]) => Command::Book {
+                symbol: symbol.to_string(),
+                depth: DEFAULT_BOOK_DEPTH,
+            },
+            ("book", [symbol, depth]) => Command::Book {
+                symbol: symbol.to_string(),
+                depth: number(depth)?,
+            },
+            ("orders", [symbol]) => Command::Orders(symbol.to_string()),
+            ("stats", [symbol]) => Command::Stats(symbol.to_string()),
+            ("trades", [symbol]) => Command::Trades {
+                symbol: symbol.to_string(),
+                limit: DEFAULT_TRADE_LIMIT,
+            },
+            ("trades", [symbol, limit]) => Command::Trades {
+                symbol: symbol.to_string(),
+                limit: number(limit)?,
+            },
+            ("save", [path]) => Command::Save(path.to_string()),
+            ("load", [path]) => Command::Load(path.to_string()),
+            ("run", [path]) => Command::Run(path.to_string()),
+            ("help", []) => Command::Help,
+            ("quit" | "exit", []) => Command::Quit,
+            _ => return Err(format!("Invalid command '{}', type help for usage", line)),
+        };
+        Ok(Some(command))
+    }
+
+    /// Returns true if the command changes the session, so it belongs in a saved session
+    pub fn changes_state(&self) -> bool {
+        matches!(
+            self,
+            Command::Create { .. }
+                | Command::User(_)
+                | Command::Submit { .. }
+                | Command::Cancel { .. }
+                | Command::Amend { .. }
+        )
+    }
+}
+
+fn order_spec(order_type: &str, prices: &[&str]) -> Result<OrderSpec, String> {
+    let order_type = order_type.to_ascii_lowercase().replace('_', "");
+    let spec = match (order_type.as_str(), prices) {
+        ("limit", [price]) => OrderSpec::Limit(price.to_string()),
+        ("market", []) => OrderSpec::Market,
+        ("ioc", [price]) => OrderSpec::IOC(price.to_string()),
+        ("fok", [price]) => OrderSpec::FOK(price.to_string()),
+        ("stop", [stop]) => OrderSpec::Stop(stop.to_string()),
+        ("stoplimit", [stop, limit]) => OrderSpec::StopLimit(stop.to_string(), limit.to_string()),
+        _ => {
+            return Err(format!(
+                "Invalid order type '{} {}', expected limit, market, ioc, fok, stop or stop_limit with its prices",
+                order_type,
+                prices.join(" ")
+            ))
+        }
+    };
+    Ok(spec)
+}
+
+fn number<T: FromStr>(s: &str) -> Result<T, String> {
+    s.parse().map_err(|_| format!("Invalid number '{}'", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(Command::parse("  # comment").unwrap(), None);
+        assert_eq!(Command::parse("").unwrap(), None);
+        assert_eq!(
+            Command::parse("create BTC-USD 2 4").unwrap(),
+            Some(Command::Create {
+                symbol: "BTC-USD".to_string(),
+                precision: Some((2, 4)),
+            })
+        );
+        assert_eq!(
+            Command::parse("SELL AAPL 10 stop_limit 149.50 149.00").unwrap(),
+            Some(Command::Submit {
+                side: OrderSide::Sell,
+                symbol: "AAPL".to_string(),
+                quantity: "10".to_string(),
+                spec: OrderSpec::StopLimit("149.50".to_string(), "149.00".to_string()),
+            })
+        );
+        assert_eq!(
+            Command::parse("amend AAPL 3 qty=5").unwrap(),
+            Some(Command::Amend {
+                symbol: "AAPL".to_string(),
+                order_id: 3,
+                price: None,
+                quantity: Some("5".to_string()),
+            })
+        );
+        assert_eq!(
+            Command::parse("book AAPL").unwrap(),
+            Some(Command::Book {
+                symbol: "AAPL".to_string(),
+                depth: DEFAULT_BOOK_DEPTH,
+            })
+        );
+
+        assert!(Command::parse("buy AAPL 10 market 150").is_err());
+        assert!(Command::parse("buy AAPL 10 limit").is_err());
+        assert!(Command::parse("amend AAPL 3").is_err());
+        assert!(Command::parse("amend AAPL 3 size=5").is_err());
+        assert!(Command::parse("cancel AAPL three").is_err());
+        assert!(Command::parse("frobnicate").is_err());
+    }
+}
@@ -0,0 +1,7 @@
+// Export CLI components
+pub mod command;
+pub mod session;
+
+// Re-export main components
+pub use command::{Command, OrderSpec};
+pub use session::{CliError, Control, Session};
@@ -0,0 +1,503 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+
+use crate::cli::command::{Command, OrderSpec, HELP};
+use crate::core::order_book::OrderBook;
+use crate::models::instrument::Instrument;
+use crate::models::order::{Order, OrderError, OrderType};
+use crate::models::price::{Price, Quantity};
+use crate::models::trade::Trade;
+use crate::persistence::trade_store::TradeStore;
+use crate::utils::time;
+
+/// User that new orders belong to until a `user` command
+pub const DEFAULT_USER_ID: u64 = 1;
+
+/// Errors raised by a command
+#[derive(Debug)]
+pub enum CliError {
+    /// The command was invalid or could not be carried out
+    Command(String),
+    /// Reading a script or writing output failed
+    Io(io::Error),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Command(message) => write!(f, "{}", message),
+            CliError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> Self {
+        CliError::Io(e)
+    }
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::Command(message)
+    }
+}
+
+impl From<OrderError> for CliError {
+    fn from(e: OrderError) -> Self {
+        CliError::Command(e.to_string())
+    }
+}
+
+/// Whether to keep reading commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    Quit,
+}
+
+/// An order book and the trades it produced
+struct Market {
+    book: OrderBook,
+    trades: TradeStore,
+}
+
+/// The state of a REPL session: order books, their trades and the commands that built them
+///
+/// Order IDs are assigned in sequence across books, so replaying a saved
+/// session rebuilds the same books with the same order and trade IDs.
+pub struct Session {
+    markets: BTreeMap<String, Market>,
+    user_id: u64,
+    next_order_id: u64,
+    journal: Vec<String>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Session {
+    /// Creates a session without any books
+    pub fn new() -> Self {
+        Self {
+            markets: BTreeMap::new(),
+            user_id: DEFAULT_USER_ID,
+            next_order_id: 1,
+            journal: Vec::new(),
+        }
+    }
+
+    /// Creates a session from a file written by `save`
+    pub fn load(file_path: &str) -> Result<Self, CliError> {
+        let mut session = Self::new();
+        session.run_script(file_path, &mut io::sink(), false)?;
+        Ok(session)
+    }
+
+    /// Returns the order book for a symbol
+    pub fn book(&self, symbol: &str) -> Option<&OrderBook> {
+        self.markets.get(symbol).map(|market| &market.book)
+    }
+
+    /// Returns the trades of a symbol
+    pub fn trades(&self, symbol: &str) -> Option<&TradeStore> {
+        self.markets.get(symbol).map(|market| &market.trades)
+    }
+
+    /// Returns the user that new orders belong to
+    pub fn user_id(&self) -> u64 {
+        self.user_id
+    }
+
+    /// Returns the commands that changed the session, in order
+    pub fn journal(&self) -> &[String] {
+        &self.journal
+    }
+
+    /// Executes one line, writing its output
+    ///
+    /// `book` prints through `OrderBook::print_book`, so its output always goes to stdout.
+    pub fn execute(&mut self, line: &str, out: &mut dyn Write) -> Result<Control, CliError> {
+        let Some(command) = Command::parse(line)? else {
+            return Ok(Control::Continue);
+        };
+        let control = self.apply(&command, out)?;
+        if command.changes_state() {
+            self.journal.push(line.trim().to_string());
+        }
+        Ok(control)
+    }
+
+    /// Runs every line of a script file, stopping at the first failing command
+    ///
+    /// With `echo`, each command is written before its output.
+    pub fn run_script(&mut self, file_path: &str, out: &mut dyn Write, echo: bool) -> Result<Control, CliError> {
+        let file = File::open(file_path).map_err(|e| CliError::Command(format!("{}: {}", file_path, e)))?;
+        self.run_lines(file_path, BufReader::new(file), out, echo)
+    }
+
+    /// Runs every line from a reader, stopping at the first failing command
+    pub fn run_lines<R: BufRead>(
+        &mut self,
+        name: &str,
+        reader: R,
+        out: &mut dyn Write,
+        echo: bool,
+    ) -> Result<Control, CliError> {
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if echo && !matches!(Command::parse(&line), Ok(None)) {
+                writeln!(out, "> {}", line.trim())?;
+            }
+            match self.execute(&line, out) {
+                Ok(Control::Continue) => {}
+                Ok(Control::Quit) => return Ok(Control::Quit),
+                Err(e) => return Err(CliError::Command(format!("{}:{}: {}", name, index + 1, e))),
+            }
+        }
+        Ok(Control::Continue)
+    }
+
+    fn apply(&mut self, command: &Command, out: &mut dyn Write) -> Result<Control, CliError> {
+        match command {
+            Command::Create { symbol, precision } => {
+                if self.markets.contains_key(symbol) {
+                    return Err(CliError::Command(format!("Book {} already exists", symbol)));
+                }
+                let instrument = match precision {
+                    Some((price_decimals, quantity_decimals)) => {
+                        Instrument::with_precision(symbol, *price_decimals, *quantity_decimals).map_err(CliError::Command)?
+                    }
+                    None => Instrument::new(symbol),
+                };
+                writeln!(
+                    out,
+                    "Created {} with {} price and {} quantity decimals",
+                    symbol, instrument.price_decimals, instrument.quantity_decimals
+                )?;
+                self.markets.insert(
+                    symbol.clone(),
+                    Market {
+                        book: OrderBook::with_instrument(instrument),
+                        trades: TradeStore::new(),
+                    },
+                );
+            }
+            Command::Books => {
+                if self.markets.is_empty() {
+                    writeln!(out, "No books")?;
+                }
+                for market in self.markets.values() {
+                    writeln!(out, "{}", market.book.stats().summary())?;
+                }
+            }
+            Command::User(user_id) => {
+                self.user_id = *user_id;
+                writeln!(out, "Orders now belong to user {}", user_id)?;
+            }
+            Command::Submit { side, symbol, quantity, spec } => {
+                let order_id = self.next_order_id;
+                let user_id = self.user_id;
+                let market = self.market_mut(symbol)?;
+                let instrument = market.book.instrument().clone();
+
+                let quantity = instrument.parse_quantity(quantity)?;
+                if quantity.is_zero() {
+                    return Err(CliError::Command("Quantity must be positive".to_string()));
+                }
+                let (order_type, price) = match spec {
+                    OrderSpec::Limit(price) => (OrderType::Limit, instrument.parse_price(price)?),
+                    OrderSpec::Market => (OrderType::Market, Price::ZERO),
+                    OrderSpec::IOC(price) => (OrderType::IOC, instrument.parse_price(price)?),
+                    OrderSpec::FOK(price) => (OrderType::FOK, instrument.parse_price(price)?),
+                    OrderSpec::Stop(stop) => (OrderType::Stop(instrument.parse_price(stop)?), Price::ZERO),
+                    OrderSpec::StopLimit(stop, limit) => {
+                        let limit = instrument.parse_price(limit)?;
+                        (OrderType::StopLimit(instrument.parse_price(stop)?, limit), limit)
+                    }
+                };
+
+                let order = Order::new(
+                    order_id,
+                    order_type,
+                    price,
+                    quantity,
+                    *side,
+                    user_id,
+                    time::current_timestamp_nanos(),
+                    None,
+                    symbol.clone(),
+                );
+                let trades = market.book.process_order(order);
+                market.trades.add_trades(trades.clone())?;
+                self.next_order_id += 1;
+                self.write_result(symbol, order_id, &trades, out)?;
+            }
+            Command::Cancel { symbol, order_id } => {
+                let book = &mut self.market_mut(symbol)?.book;
+                if book.cancel_order(*order_id) {
+                    writeln!(out, "Order {} Canceled", order_id)?;
+                } else {
+                    let order = book
+                        .get_order(*order_id)
+                        .ok_or(OrderError::UnknownOrder { order_id: *order_id })?;
+                    writeln!(out, "Order {} is {} and cannot be canceled", order_id, order.status)?;
+                }
+            }
+            Command::Amend { symbol, order_id, price, quantity } => {
+                let market = self.market_mut(symbol)?;
+                let instrument = market.book.instrument().clone();
+                let price = price.as_deref().map(|price| instrument.parse_price(price)).transpose()?;
+                let quantity = quantity
+                    .as_deref()
+                    .map(|quantity| instrument.parse_quantity(quantity))
+                    .transpose()?;
+
+                let trades = market
+                    .book
+                    .amend_order(*order_id, price, quantity, time::current_timestamp_nanos())?;
+                market.trades.add_trades(trades.clone())?;
+                self.write_result(symbol, *order_id, &trades, out)?;
+            }
+            Command::Book { symbol, depth } => {
+                out.flush()?;
+                self.market(symbol)?.book.print_book(*depth);
+            }
+            Command::Orders(symbol) => {
+                let book = &self.market(symbol)?.book;
+                let mut orders: Vec<&Order> = book
+                    .all_orders()
+                    .into_iter()
+                    .filter(|order| !order.status.is_terminal())
+                    .collect();
+                orders.sort_by_key(|order| order.id);
+                if orders.is_empty() {
+                    writeln!(out, "No active orders")?;
+                }
+                for order in orders {
+                    writeln!(out, "{}", describe_order(book.instrument(), order))?;
+                }
+            }
+            Command::Stats(symbol) => {
+                let stats = self.market(symbol)?.book.stats();
+                writeln!(out, "{}", stats.summary())?;
+                writeln!(
+                    out,
+                    "Last: {}, Bid orders: {}, Ask orders: {}",
+                    stats
+                        .last_trade_price
+                        .map_or_else(|| "None".to_string(), |price| stats.instrument.format_price(price)),
+                    stats.bid_order_count,
+                    stats.ask_order_count
+                )?;
+            }
+            Command::Trades { symbol, limit } => {
+                let market = self.market(symbol)?;
+                let trades = market.trades.get_recent_trades(symbol, *limit);
+                if trades.is_empty() {
+                    writeln!(out, "No trades")?;
+                }
+                for trade in trades {
+                    writeln!(out, "{}", describe_trade(market.book.instrument(), trade))?;
+                }
+            }
+            Command::Save(file_path) => {
+                let mut contents = String::from("# rustflow session\n");
+                for line in &self.journal {
+                    contents.push_str(line);
+                    contents.push('\n');
+                }
+                fs::write(file_path, contents)?;
+                writeln!(out, "Saved {} commands to {}", self.journal.len(), file_path)?;
+            }
+            Command::Load(file_path) => {
+                *self = Self::load(file_path)?;
+                writeln!(out, "Loaded {} commands from {}", self.journal.len(), file_path)?;
+            }
+            Command::Run(file_path) => {
+                // A quit in the script ends the script, not the session
+                self.run_script(file_path, out, true)?;
+            }
+            Command::Help => writeln!(out, "{}", HELP)?,
+            Command::Quit => return Ok(Control::Quit),
+        }
+        Ok(Control::Continue)
+    }
+
+    fn market(&self, symbol: &str) -> Result<&Market, CliError> {
+        self.markets
+            .get(symbol)
+            .ok_or_else(|| CliError::Command(format!("Unknown book {}, create it first", symbol)))
+    }
+
+    fn market_mut(&mut self, symbol: &str) -> Result<&mut Market, CliError> {
+        self.markets
+            .get_mut(symbol)
+            .ok_or_else(|| CliError::Command(format!("Unknown book {}, create it first", symbol)))
+    }
+
+    /// Writes the state of an order after a submit or amend, followed by its trades
+    fn write_result(&self, symbol: &str, order_id: u64, trades: &[Trade], out: &mut dyn Write) -> Result<(), CliError> {
+        let book = &self.market(symbol)?.book;
+        match book.get_order(order_id) {
+            Some(order) => writeln!(out, "{}", describe_order(book.instrument(), order))?,
+            None => writeln!(out, "Order {} Rejected", order_id)?,
+        }
+        for trade in trades {
+            writeln!(out, "  {}", describe_trade(book.instrument(), trade))?;
+        }
+        Ok(())
+    }
+}
+
+fn describe_order(instrument: &Instrument, order: &Order) -> String {
+    let order_type = match order.order_type {
+        OrderType::Limit => format!("limit {}", instrument.price_to_string(order.price)),
+        OrderType::Market => "market".to_string(),
+        OrderType::IOC => format!("ioc {}", instrument.price_to_string(order.price)),
+        OrderType::FOK => format!("fok {}", instrument.price_to_string(order.price)),
+        OrderType::Stop(stop) => format!("stop {}", instrument.price_to_string(stop)),
+        OrderType::StopLimit(stop, limit) => format!(
+            "stop_limit {} {}",
+            instrument.price_to_string(stop),
+            instrument.price_to_string(limit)
+        ),
+    };
+    let filled: Quantity = order.quantity - order.remaining_quantity;
+    format!(
+        "Order {} {}: {} {} {}, filled {}, user {}",
+        order.id,
+        order.status,
+        order.side,
+        instrument.format_quantity(order.quantity),
+        order_type,
+        instrument.format_quantity(filled),
+        order.user_id
+    )
+}
+
+fn describe_trade(instrument: &Instrument, trade: &Trade) -> String {
+    format!(
+        "Trade {}: {} @ {} buy order {} (user {}) sell order {} (user {})",
+        trade.id,
+        instrument.format_quantity(trade.quantity),
+        instrument.format_price(trade.price),
+        trade.buy_order_id,
+        trade.buy_user_id,
+        trade.sell_order_id,
+        trade.sell_user_id
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::order::OrderStatus;
+
+    fn run(session: &mut Session, lines: &str) -> String {
+        let mut out = Vec::new();
+        session
+            .run_lines("test", lines.as_bytes(), &mut out, false)
+            .unwrap_or_else(|e| panic!("{}", e));
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_order_types_cancel_and_amend() {
+        let mut session = Session::new();
+        let output = run(
+            &mut session,
+            "create AAPL
+            sell AAPL 100 limit 150.00
+            sell AAPL 50 limit 150.50
+            user 2
+            buy AAPL 30 market
+            buy AAPL 100 fok 150.00
+            buy AAPL 100 ioc 150.00
+            buy AAPL 10 limit 149.00
+            sell AAPL 5 stop 148.00
+            buy AAPL 5 stop_limit 152.00 152.00
+            amend AAPL 6 price=149.50 qty=20
+            cancel AAPL 2",
+        );
+
+        assert!(output.contains("Order 3 Filled: Buy 30 market, filled 30, user 2"));
+        assert!(output.contains("Trade 1: 30 @ 150.00 buy order 3 (user 2) sell order 1 (user 1)"));
+        assert!(output.contains("Order 4 Canceled: Buy 100 fok 150.00, filled 0, user 2"));
+        assert!(output.contains("Order 5 Canceled: Buy 100 ioc 150.00, filled 70, user 2"));
+        assert!(output.contains("Order 2 Canceled"));
+
+        let book = session.book("AAPL").unwrap();
+        assert_eq!(book.get_order(1).unwrap().status, OrderStatus::Filled);
+        assert_eq!(book.best_bid(), Some(Price(14950)));
+        // The stop orders triggered on entry, filling 5 of order 6 and 5 of order 2
+        assert_eq!(book.get_order(6).unwrap().remaining_quantity, Quantity(15));
+        assert_eq!(book.get_order(7).unwrap().status, OrderStatus::Filled);
+        assert_eq!(book.get_order(8).unwrap().status, OrderStatus::Filled);
+        assert_eq!(book.best_ask(), None);
+        assert_eq!(session.trades("AAPL").unwrap().count(), 4);
+        assert_eq!(session.journal().len(), 12);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut session = Session::new();
+        let mut out = Vec::new();
+        assert!(session.execute("buy AAPL 10 limit 150", &mut out).is_err());
+        session.execute("create AAPL", &mut out).unwrap();
+        assert!(session.execute("create AAPL", &mut out).is_err());
+        assert!(session.execute("buy AAPL 0 limit 150", &mut out).is_err());
+        assert!(session.execute("buy AAPL 10 limit 150.001", &mut out).is_err());
+        assert!(session.execute("cancel AAPL 9", &mut out).is_err());
+        assert!(session.execute("amend AAPL 9 qty=5", &mut out).is_err());
+        assert_eq!(session.journal(), ["create AAPL"]);
+
+        let error = session
+            .run_lines("script.txt", "books\n\nfrobnicate\nbooks".as_bytes(), &mut out, false)
+            .unwrap_err();
+        assert!(error.to_string().starts_with("script.txt:3: Invalid command 'frobnicate'"));
+        assert_eq!(session.execute("quit", &mut out).unwrap(), Control::Quit);
+    }
+
+    #[test]
+    fn test_save_load_and_run() {
+        let mut session = Session::new();
+        run(
+            &mut session,
+            "create BTC-USD 2 4
+            user 7
+            sell BTC-USD 0.5 limit 30000.00
+            buy BTC-USD 0.2 limit 30000.00
+            stats BTC-USD
+            trades BTC-USD",
+        );
+
+        let path = std::env::temp_dir().join(format!("rustflow_cli_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        run(&mut session, &format!("save {}", path));
+
+        let mut loaded = Session::new();
+        let output = run(&mut loaded, &format!("load {}", path));
+        assert_eq!(output, format!("Loaded 4 commands from {}\n", path));
+        assert_eq!(loaded.journal(), session.journal());
+        assert_eq!(loaded.user_id(), 7);
+        let book = loaded.book("BTC-USD").unwrap();
+        assert_eq!(book.get_order(1).unwrap().remaining_quantity, Quantity(3000));
+        assert_eq!(loaded.trades("BTC-USD").unwrap().get_trade(1).unwrap().quantity, Quantity(2000));
+
+        // Running the same script on top replays it in the current session
+        let mut out = Vec::new();
+        let error = loaded.run_script(path, &mut out, true).unwrap_err();
+        assert!(error.to_string().contains(":2: Book BTC-USD already exists"));
+        assert!(String::from_utf8(out).unwrap().starts_with("> create BTC-USD 2 4\n"));
+
+        fs::remove_file(path).unwrap();
+    }
+}
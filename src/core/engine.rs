@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::io;
+use std::thread::{self, JoinHandle};
+
+use crossbeam::channel::{self, Receiver, Sender};
+use log::{debug, warn};
+
+use crate::core::order_book::OrderBook;
+use crate::models::instrument::Instrument;
+use crate::models::order::{Order, OrderStatus};
+use crate::models::trade::Trade;
+
+/// A request sent to the shard that owns a symbol
+#[derive(Debug, Clone)]
+pub enum EngineCommand {
+    /// Submit a new order
+    Submit(Order),
+    /// Cancel a resting order
+    Cancel { symbol: String, order_id: u64 },
+    /// Register an instrument so its book uses the instrument's precision
+    AddInstrument(Instrument),
+    /// Stop the worker thread
+    Shutdown,
+}
+
+/// A result published by a shard after processing a command
+///
+/// Every event carries a sequence number that increases by one for each
+/// command processed for its symbol, so consumers can detect gaps and
+/// reassemble the per-symbol order even though shards interleave.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EngineEvent {
+    /// An order was processed by its book
+    OrderProcessed {
+        symbol: String,
+        sequence: u64,
+        order_id: u64,
+        status: OrderStatus,
+        trades: Vec<Trade>,
+    },
+    /// A cancel request was processed by its book
+    CancelProcessed {
+        symbol: String,
+        sequence: u64,
+        order_id: u64,
+        canceled: bool,
+    },
+    /// An order was rejected before reaching a book
+    OrderRejected {
+        symbol: String,
+        sequence: u64,
+        order_id: u64,
+        reason: String,
+    },
+}
+
+impl EngineEvent {
+    /// Returns the symbol the event applies to
+    pub fn symbol(&self) -> &str {
+        match self {
+            EngineEvent::OrderProcessed { symbol, .. } => symbol,
+            EngineEvent::CancelProcessed { symbol, .. } => symbol,
+            EngineEvent::OrderRejected { symbol, .. } => symbol,
+        }
+    }
+
+    /// Returns the per-symbol sequence number of the event
+    pub fn sequence(&self) -> u64 {
+        match self {
+            EngineEvent::OrderProcessed { sequence, .. } => *sequence,
+            EngineEvent::CancelProcessed { sequence, .. } => *sequence,
+            EngineEvent::OrderRejected { sequence, .. } => *sequence,
+        }
+    }
+
+    /// Returns the trades produced by the command, if any
+    pub fn trades(&self) -> &[Trade] {
+        match self {
+            EngineEvent::OrderProcessed { trades, .. } => trades,
+            _ => &[],
+        }
+    }
+}
+
+/// The books and queue owned by a single worker thread
+struct Shard {
+    books: HashMap<String, OrderBook>,
+    sequences: HashMap<String, u64>,
+    events: Sender<EngineEvent>,
+}
+
+impl Shard {
+    /// Processes commands until shutdown and returns the shard's books
+    fn run(mut self, commands: Receiver<EngineCommand>) -> HashMap<String, OrderBook> {
+        for command in commands.iter() {
+            let event = match command {
+                EngineCommand::Submit(order) => self.submit(order),
+                EngineCommand::Cancel { symbol, order_id } => {
+                    let canceled = self.book(&symbol).cancel_order(order_id);
+                    EngineEvent::CancelProcessed {
+                        sequence: self.next_sequence(&symbol),
+                        symbol,
+                        order_id,
+                        canceled,
+                    }
+                }
+                EngineCommand::AddInstrument(instrument) => {
+                    self.books
+                        .entry(instrument.symbol.clone())
+                        .or_insert_with(|| OrderBook::with_instrument(instrument));
+                    continue;
+                }
+                EngineCommand::Shutdown => break,
+            };
+
+            // The consumer may have gone away, the books are still returned on shutdown
+            if self.events.send(event).is_err() {
+                debug!("Engine event receiver dropped");
+            }
+        }
+
+        self.books
+    }
+
+    fn submit(&mut self, order: Order) -> EngineEvent {
+        let symbol = order.symbol.clone();
+        let order_id = order.id;
+        let book = self.book(&symbol);
+
+        if book.get_order(order_id).is_some() {
+            return EngineEvent::OrderRejected {
+                sequence: self.next_sequence(&symbol),
+                symbol,
+                order_id,
+                reason: format!("Duplicate order ID {}", order_id),
+            };
+        }
+
+        let trades = book.process_order(order);
+        let status = book
+            .get_order(order_id)
+            .map(|order| order.status)
+            .unwrap_or(OrderStatus::Rejected);
+
+        EngineEvent::OrderProcessed {
+            sequence: self.next_sequence(&symbol),
+            symbol,
+            order_id,
+            status,
+            trades,
+        }
+    }
+
+    fn book(&mut self, symbol: &str) -> &mut OrderBook {
+        self.books
+            .entry(symbol.to_string())
+            .or_insert_with(|| OrderBook::new(symbol))
+    }
+
+    fn next_sequence(&mut self, symbol: &str) -> u64 {
+        let sequence = self.sequences.entry(symbol.to_string()).or_insert(0);
+        *sequence += 1;
+        *sequence
+    }
+}
+
+/// A multi-threaded matching engine that partitions symbols across shards
+///
+/// Each shard is a worker thread that owns the books for its symbols and
+/// consumes commands from its own crossbeam queue. A symbol always maps to
+/// the same shard, so commands for one symbol are processed in the order
+/// they were submitted and their events are published in that order.
+pub struct ShardedEngine {
+    /// Command queues, one per shard
+    senders: Vec<Sender<EngineCommand>>,
+    /// Worker threads, joined on shutdown
+    workers: Vec<JoinHandle<HashMap<String, OrderBook>>>,
+    /// Events published by all shards
+    events: Receiver<EngineEvent>,
+}
+
+impl ShardedEngine {
+    /// Starts an engine with the given number of shards (at least one)
+    pub fn new(shard_count: usize) -> Self {
+        let shard_count = shard_count.max(1);
+        let (event_sender, events) = channel::unbounded();
+        let mut senders = Vec::with_capacity(shard_count);
+        let mut workers = Vec::with_capacity(shard_count);
+
+        for index in 0..shard_count {
+            let (sender, receiver) = channel::unbounded();
+            let shard = Shard {
+                books: HashMap::new(),
+                sequences: HashMap::new(),
+                events: event_sender.clone(),
+            };
+
+            let worker = thread::Builder::new()
+                .name(format!("rustflow-shard-{}", index))
+                .spawn(move || shard.run(receiver))
+                .expect("Failed to spawn engine shard");
+
+            senders.push(sender);
+            workers.push(worker);
+        }
+
+        Self {
+            senders,
+            workers,
+            events,
+        }
+    }
+
+    /// Returns the number of shards
+    pub fn shard_count(&self) -> usize {
+        self.senders.len()
+    }
+
+    /// Returns the shard that owns a symbol
+    ///
+    /// Uses FNV-1a so the assignment is stable across runs and processes.
+    pub fn shard_for(&self, symbol: &str) -> usize {
+        let hash = symbol.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        (hash % self.senders.len() as u64) as usize
+    }
+
+    /// Registers an instrument with the shard that owns its symbol
+    pub fn add_instrument(&self, instrument: Instrument) -> io::Result<()> {
+        let shard = self.shard_for(&instrument.symbol);
+        self.send(shard, EngineCommand::AddInstrument(instrument))
+    }
+
+    /// Queues an order for matching
+    pub fn submit(&self, order: Order) -> io::Result<()> {
+        let shard = self.shard_for(&order.symbol);
+        self.send(shard, EngineCommand::Submit(order))
+    }
+
+    /// Queues a cancel request
+    pub fn cancel(&self, symbol: &str, order_id: u64) -> io::Result<()> {
+        let shard = self.shard_for(symbol);
+        self.send(
+            shard,
+            EngineCommand::Cancel {
+                symbol: symbol.to_string(),
+                order_id,
+            },
+        )
+    }
+
+    /// Returns the queue of events published by all shards
+    pub fn events(&self) -> &Receiver<EngineEvent> {
+        &self.events
+    }
+
+    /// Stops all shards after they drain their queues and returns every book by symbol
+    pub fn shutdown(mut self) -> HashMap<String, OrderBook> {
+        self.stop()
+    }
+
+    fn send(&self, shard: usize, command: EngineCommand) -> io::Result<()> {
+        self.senders[shard].send(command).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                format!("Engine shard {} has stopped", shard),
+            )
+        })
+    }
+
+    fn stop(&mut self) -> HashMap<String, OrderBook> {
+        for sender in &self.senders {
+            // A shard that already stopped has nothing left to drain
+            let _ = sender.send(EngineCommand::Shutdown);
+        }
+
+        let mut books = HashMap::new();
+        for (index, worker) in self.workers.drain(..).enumerate() {
+            match worker.join() {
+                Ok(shard_books) => books.extend(shard_books),
+                Err(_) => warn!("Engine shard {} panicked", index),
+            }
+        }
+        books
+    }
+}
+
+impl Drop for ShardedEngine {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::order::OrderSide;
+    use crate::models::price::{Price, Quantity};
+
+    const SYMBOLS: [&str; 6] = ["BTC-USD", "ETH-USD", "SOL-USD", "ADA-USD", "XRP-USD", "DOT-USD"];
+
+    fn limit(id: u64, symbol: &str, side: OrderSide, price: u64, quantity: u64) -> Order {
+        Order::new_limit(id, price, quantity, side, 1000 + id % 7, id, None, symbol.to_string())
+    }
+
+    #[test]
+    fn test_symbols_map_to_stable_shards() {
+        let engine = ShardedEngine::new(4);
+        for symbol in SYMBOLS {
+            assert!(engine.shard_for(symbol) < 4);
+            assert_eq!(engine.shard_for(symbol), ShardedEngine::new(4).shard_for(symbol));
+        }
+        assert_eq!(ShardedEngine::new(0).shard_count(), 1);
+    }
+
+    #[test]
+    fn test_orders_match_on_their_shard() {
+        let engine = ShardedEngine::new(2);
+        engine.add_instrument(Instrument::with_precision("BTC-USD", 2, 8).unwrap()).unwrap();
+        engine.submit(limit(1, "BTC-USD", OrderSide::Sell, 10100, 5)).unwrap();
+        engine.submit(limit(2, "ETH-USD", OrderSide::Sell, 2000, 5)).unwrap();
+        engine.submit(limit(3, "BTC-USD", OrderSide::Buy, 10100, 2)).unwrap();
+        engine.cancel("ETH-USD", 2).unwrap();
+
+        let events: Vec<EngineEvent> = engine.events().iter().take(4).collect();
+        let fill = events
+            .iter()
+            .find(|event| matches!(event, EngineEvent::OrderProcessed { order_id: 3, .. }))
+            .unwrap();
+        assert_eq!(fill.trades().len(), 1);
+        assert_eq!(fill.trades()[0].price, Price(10100));
+        assert!(events.contains(&EngineEvent::CancelProcessed {
+            symbol: "ETH-USD".to_string(),
+            sequence: 2,
+            order_id: 2,
+            canceled: true,
+        }));
+
+        let books = engine.shutdown();
+        assert_eq!(books["BTC-USD"].instrument().quantity_decimals, 8);
+        assert_eq!(books["BTC-USD"].get_order(1).unwrap().remaining_quantity, Quantity(3));
+        assert!(books["ETH-USD"].best_ask().is_none());
+    }
+
+    #[test]
+    fn test_per_symbol_ordering_is_deterministic() {
+        // The same flow produces the same per-symbol events for any shard count
+        let flow: Vec<Order> = (1..=600)
+            .map(|id| {
+                let symbol = SYMBOLS[id as usize % SYMBOLS.len()];
+                let side = if id % 2 == 0 { OrderSide::Buy } else { OrderSide::Sell };
+                limit(id, symbol, side, 100 + id % 5, 1 + id % 3)
+            })
+            .collect();
+
+        let run = |shards: usize| {
+            let engine = ShardedEngine::new(shards);
+            for order in &flow {
+                engine.submit(order.clone()).unwrap();
+            }
+
+            let mut by_symbol: HashMap<String, Vec<EngineEvent>> = HashMap::new();
+            for event in engine.events().iter().take(flow.len()) {
+                by_symbol.entry(event.symbol().to_string()).or_default().push(event);
+            }
+            by_symbol
+        };
+
+        let single = run(1);
+        for shards in [2, 4, 8] {
+            assert_eq!(run(shards), single);
+        }
+        for events in single.values() {
+            let sequences: Vec<u64> = events.iter().map(EngineEvent::sequence).collect();
+            assert_eq!(sequences, (1..=events.len() as u64).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_duplicate_order_is_rejected() {
+        let engine = ShardedEngine::new(1);
+        engine.submit(limit(1, "BTC-USD", OrderSide::Sell, 100, 1)).unwrap();
+        engine.submit(limit(1, "BTC-USD", OrderSide::Buy, 100, 1)).unwrap();
+
+        let events: Vec<EngineEvent> = engine.events().iter().take(2).collect();
+        assert!(matches!(events[1], EngineEvent::OrderRejected { order_id: 1, sequence: 2, .. }));
+    }
+}
@@ -0,0 +1,1344 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+use crate::account::{Balance, Ledger, LedgerError};
+use crate::core::order_book::OrderBook;
+use crate::fees::{FeeEngine, FeeSchedule};
+use crate::models::instrument::Instrument;
+use crate::models::order::{Order, OrderError, OrderSide, OrderStatus};
+use crate::models::price::{Price, Quantity};
+use crate::models::protection::MarketOrderProtection;
+use crate::models::stats::OrderBookStats;
+use crate::models::trade::Trade;
+use crate::models::trade_adjustment::{AdjustmentError, TradeAdjustment};
+use crate::risk::{RiskLimits, RiskManager, RiskReject, RiskScope};
+use crate::utils::time;
+
+/// Default number of requests that may be queued before callers wait
+pub const DEFAULT_QUEUE_CAPACITY: usize = 1024;
+
+/// Number of trades buffered for slow subscribers before they miss events
+pub const TRADE_EVENT_CAPACITY: usize = 4096;
+
+/// Number of book updates buffered for slow subscribers before they miss events
+pub const BOOK_UPDATE_CAPACITY: usize = 1024;
+
+/// Number of price levels per side in published book updates
+pub const BOOK_UPDATE_LEVELS: usize = 10;
+
+/// Environment variable holding the market order collar of the server binaries, in basis points
+pub const MARKET_COLLAR_ENV: &str = "RUSTFLOW_MARKET_COLLAR_BPS";
+
+/// Settings of an engine task
+#[derive(Debug)]
+pub struct EngineConfig {
+    /// Number of requests that may be queued before callers wait
+    capacity: usize,
+    /// Balances checked and settled for every order, if any
+    ledger: Option<Ledger>,
+    /// Collar on market orders in symbols without their own
+    market_protection: Option<MarketOrderProtection>,
+    /// Collars on market orders in individual symbols
+    symbol_protection: HashMap<String, MarketOrderProtection>,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EngineConfig {
+    /// Creates a config with the default queue capacity, no ledger and no collars
+    pub fn new() -> Self {
+        Self {
+            capacity: DEFAULT_QUEUE_CAPACITY,
+            ledger: None,
+            market_protection: None,
+            symbol_protection: HashMap::new(),
+        }
+    }
+
+    /// Creates a config whose market order collar, if any, comes from `MARKET_COLLAR_ENV`
+    pub fn from_env() -> Result<Self, String> {
+        let config = Self::new();
+        match std::env::var(MARKET_COLLAR_ENV) {
+            Ok(value) => {
+                let basis_points = value
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|_| format!("{} must be a number of basis points, got '{}'", MARKET_COLLAR_ENV, value))?;
+                Ok(config.with_market_protection(MarketOrderProtection::with_max_basis_points(basis_points)))
+            }
+            Err(_) => Ok(config),
+        }
+    }
+
+    /// Sets the number of requests that may be queued before callers wait
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Rejects orders whose users cannot pay for them, reserving funds in `ledger`
+    pub fn with_ledger(mut self, ledger: Ledger) -> Self {
+        self.ledger = Some(ledger);
+        self
+    }
+
+    /// Protects market orders in every symbol without a collar of its own
+    pub fn with_market_protection(mut self, protection: MarketOrderProtection) -> Self {
+        self.market_protection = Some(protection);
+        self
+    }
+
+    /// Protects market orders in one symbol
+    pub fn with_symbol_protection(mut self, symbol: &str, protection: MarketOrderProtection) -> Self {
+        self.symbol_protection.insert(symbol.to_string(), protection);
+        self
+    }
+}
+
+/// Errors returned by an `EngineHandle`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineError {
+    /// The engine task has stopped
+    Closed,
+    /// The request queue is full (only returned by the `try_` methods)
+    Busy,
+    /// The order book rejected the request
+    Order(OrderError),
+    /// A pre-trade risk check rejected the order
+    Risk(RiskReject),
+    /// The account ledger rejected the request
+    Ledger(LedgerError),
+    /// A trade cannot be busted or corrected
+    Adjustment(AdjustmentError),
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::Closed => write!(f, "Engine has stopped"),
+            EngineError::Busy => write!(f, "Engine queue is full"),
+            EngineError::Order(e) => write!(f, "{}", e),
+            EngineError::Risk(e) => write!(f, "Risk check failed: {}", e),
+            EngineError::Ledger(e) => write!(f, "{}", e),
+            EngineError::Adjustment(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+impl From<OrderError> for EngineError {
+    fn from(e: OrderError) -> Self {
+        EngineError::Order(e)
+    }
+}
+
+impl From<RiskReject> for EngineError {
+    fn from(e: RiskReject) -> Self {
+        EngineError::Risk(e)
+    }
+}
+
+impl From<LedgerError> for EngineError {
+    fn from(e: LedgerError) -> Self {
+        EngineError::Ledger(e)
+    }
+}
+
+impl From<AdjustmentError> for EngineError {
+    fn from(e: AdjustmentError) -> Self {
+        EngineError::Adjustment(e)
+    }
+}
+
+/// The outcome of submitting an order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderAck {
+    /// Identifier of the submitted order
+    pub order_id: u64,
+    /// Status of the order after matching
+    pub status: OrderStatus,
+    /// Trades executed while matching
+    pub trades: Vec<Trade>,
+}
+
+/// A trade published by the engine to every subscriber
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TradeEvent {
+    /// The executed trade
+    pub trade: Trade,
+    /// The incoming order that caused the trade
+    pub taker_order_id: u64,
+}
+
+impl TradeEvent {
+    /// Returns the ID and user of the resting order that was hit
+    pub fn maker(&self) -> (u64, u64) {
+        (self.trade.maker_order_id, self.trade.maker_user_id())
+    }
+}
+
+/// Aggregated price levels of one book
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepthSnapshot {
+    /// Symbol/ticker the snapshot is for
+    pub symbol: String,
+    /// Bid levels, best (highest) first
+    pub bids: Vec<(Price, Quantity)>,
+    /// Ask levels, best (lowest) first
+    pub asks: Vec<(Price, Quantity)>,
+}
+
+/// A request to the engine task with the channel its response goes back on
+enum Request {
+    AddInstrument(Instrument),
+    SetRiskLimits {
+        scope: RiskScope,
+        limits: Option<RiskLimits>,
+    },
+    Deposit {
+        user_id: u64,
+        asset: String,
+        amount: u128,
+        respond: oneshot::Sender<Result<(), EngineError>>,
+    },
+    Withdraw {
+        user_id: u64,
+        asset: String,
+        amount: u128,
+        respond: oneshot::Sender<Result<(), EngineError>>,
+    },
+    Balances {
+        user_id: u64,
+        respond: oneshot::Sender<Result<Vec<(String, Balance)>, EngineError>>,
+    },
+    SetFeeSchedule {
+        symbol: Option<String>,
+        schedule: Option<FeeSchedule>,
+    },
+    LoadFeeVolumes(Vec<Trade>),
+    SetMarketProtection {
+        symbol: Option<String>,
+        protection: Option<MarketOrderProtection>,
+    },
+    FeeRevenue {
+        respond: oneshot::Sender<Vec<(String, i128)>>,
+    },
+    Submit {
+        order: Order,
+        respond: oneshot::Sender<Result<OrderAck, EngineError>>,
+    },
+    Cancel {
+        symbol: String,
+        order_id: u64,
+        respond: oneshot::Sender<bool>,
+    },
+    Amend {
+        symbol: String,
+        order_id: u64,
+        price: Option<Price>,
+        quantity: Option<Quantity>,
+        respond: oneshot::Sender<Result<Vec<Trade>, EngineError>>,
+    },
+    AdjustTrade {
+        adjustment: Box<TradeAdjustment>,
+        respond: oneshot::Sender<Result<TradeAdjustment, EngineError>>,
+    },
+    Depth {
+        symbol: String,
+        levels: usize,
+        respond: oneshot::Sender<DepthSnapshot>,
+    },
+    Order {
+        symbol: String,
+        order_id: u64,
+        respond: oneshot::Sender<Option<Order>>,
+    },
+    OpenOrders {
+        user_id: u64,
+        respond: oneshot::Sender<Vec<Order>>,
+    },
+    Stats {
+        symbol: String,
+        respond: oneshot::Sender<Option<OrderBookStats>>,
+    },
+}
+
+/// The order books owned by the engine task
+struct EngineState {
+    books: HashMap<String, OrderBook>,
+    risk: RiskManager,
+    /// Balances checked and settled for every order, when enabled
+    ledger: Option<Ledger>,
+    fees: FeeEngine,
+    /// Latest state of each trade busted or corrected so far, by symbol and trade ID; None once busted
+    adjusted: HashMap<(String, u64), Option<Trade>>,
+    /// Collar on market orders in symbols without their own
+    market_protection: Option<MarketOrderProtection>,
+    /// Collars on market orders in individual symbols
+    symbol_protection: HashMap<String, MarketOrderProtection>,
+    trades: broadcast::Sender<TradeEvent>,
+    book_updates: broadcast::Sender<DepthSnapshot>,
+}
+
+impl EngineState {
+    fn new(trades: broadcast::Sender<TradeEvent>, book_updates: broadcast::Sender<DepthSnapshot>) -> Self {
+        Self {
+            books: HashMap::new(),
+            risk: RiskManager::new(),
+            ledger: None,
+            fees: FeeEngine::new(),
+            adjusted: HashMap::new(),
+            market_protection: None,
+            symbol_protection: HashMap::new(),
+            trades,
+            book_updates,
+        }
+    }
+
+    /// Publishes the top of a book after a change, if anyone is listening
+    fn publish_book(&self, symbol: &str) {
+        if self.book_updates.receiver_count() == 0 {
+            return;
+        }
+        if let Some(book) = self.books.get(symbol) {
+            let (bids, asks) = book.market_depth(BOOK_UPDATE_LEVELS);
+            let _ = self.book_updates.send(DepthSnapshot {
+                symbol: symbol.to_string(),
+                bids,
+                asks,
+            });
+        }
+    }
+
+    /// Publishes trades to every subscriber
+    fn publish(&self, trades: &[Trade], taker_order_id: u64) {
+        for trade in trades {
+            // Sending only fails when nobody is subscribed
+            let _ = self.trades.send(TradeEvent {
+                trade: trade.clone(),
+                taker_order_id,
+            });
+        }
+    }
+
+    /// Updates risk state and settles trades after orders in a book changed
+    ///
+    /// Resting orders whose users can no longer cover them are canceled.
+    fn track(&mut self, symbol: &str, trades: &[Trade], order_ids: impl IntoIterator<Item = u64>) {
+        let fee_rate = self.fees.max_rate(symbol);
+        let Some(book) = self.books.get_mut(symbol) else {
+            return;
+        };
+        for trade in trades {
+            self.risk.on_trade(trade);
+            if let Some(ledger) = self.ledger.as_mut() {
+                // Reservations cover every fill and the highest fee on it, so this is a bug
+                if let Err(e) = ledger.settle(trade, book.instrument()) {
+                    panic!("Trade {} could not be settled: {}", trade.id, e);
+                }
+            }
+        }
+        let makers = trades
+            .iter()
+            .flat_map(|trade| [trade.buy_order_id, trade.sell_order_id]);
+        for order_id in order_ids.into_iter().chain(makers) {
+            let Some(order) = book.get_order(order_id) else {
+                // The book refused the order, so nothing it reserved is needed
+                if let Some(ledger) = self.ledger.as_mut() {
+                    ledger.release(symbol, order_id);
+                }
+                continue;
+            };
+            let covered = match self.ledger.as_mut() {
+                Some(ledger) => ledger.track_order(order, book, fee_rate),
+                None => true,
+            };
+            if !covered {
+                warn!("Order {} was canceled: its funds no longer cover it", order_id);
+                book.cancel_order(order_id);
+                if let Some(ledger) = self.ledger.as_mut() {
+                    ledger.release(symbol, order_id);
+                }
+            }
+            if let Some(order) = book.get_order(order_id) {
+                self.risk.track_order(order);
+            }
+        }
+    }
+
+    /// Returns the collar on market orders in a symbol
+    fn protection(&self, symbol: &str) -> Option<MarketOrderProtection> {
+        self.symbol_protection.get(symbol).copied().or(self.market_protection)
+    }
+
+    /// Creates the book of an instrument with the collar of its symbol
+    fn new_book(instrument: Instrument, protection: Option<MarketOrderProtection>) -> OrderBook {
+        let mut book = OrderBook::with_instrument(instrument);
+        book.set_market_protection(protection);
+        book
+    }
+
+    fn ledger(&mut self) -> Result<&mut Ledger, EngineError> {
+        self.ledger.as_mut().ok_or(EngineError::Ledger(LedgerError::Disabled))
+    }
+
+    fn handle(&mut self, request: Request) {
+        // A caller that stopped waiting for its response is not an error
+        match request {
+            Request::AddInstrument(instrument) => {
+                let protection = self.protection(&instrument.symbol);
+                self.books
+                    .entry(instrument.symbol.clone())
+                    .or_insert_with(|| Self::new_book(instrument, protection));
+            }
+            Request::SetRiskLimits { scope, limits } => match limits {
+                Some(limits) => self.risk.set_limits(scope, limits),
+                None => self.risk.clear_limits(&scope),
+            },
+            Request::Deposit { user_id, asset, amount, respond } => {
+                let result = self.ledger().map(|ledger| ledger.deposit(user_id, &asset, amount));
+                let _ = respond.send(result);
+            }
+            Request::Withdraw { user_id, asset, amount, respond } => {
+                let result = self
+                    .ledger()
+                    .and_then(|ledger| ledger.withdraw(user_id, &asset, amount).map_err(EngineError::from));
+                let _ = respond.send(result);
+            }
+            Request::Balances { user_id, respond } => {
+                let result = self.ledger().map(|ledger| ledger.balances(user_id));
+                let _ = respond.send(result);
+            }
+            Request::SetFeeSchedule { symbol, schedule } => {
+                let symbols: Vec<String> = match &symbol {
+                    Some(symbol) => vec![symbol.clone()],
+                    None => self.books.keys().cloned().collect(),
+                };
+                match symbol {
+                    Some(symbol) => self.fees.set_symbol_schedule(&symbol, schedule),
+                    None => self.fees.set_default_schedule(schedule),
+                }
+                // Resting buys must still cover the highest fee they can now be charged
+                for symbol in symbols {
+                    let Some(book) = self.books.get(&symbol) else {
+                        continue;
+                    };
+                    let order_ids: Vec<u64> = book
+                        .all_orders()
+                        .into_iter()
+                        .filter(|order| order.is_active() && order.side == OrderSide::Buy)
+                        .map(|order| order.id)
+                        .collect();
+                    self.track(&symbol, &[], order_ids);
+                    self.publish_book(&symbol);
+                }
+            }
+            Request::LoadFeeVolumes(trades) => {
+                let books = &self.books;
+                let instrument = |symbol: &str| {
+                    books
+                        .get(symbol)
+                        .map_or_else(|| Instrument::new(symbol), |book| book.instrument().clone())
+                };
+                self.fees.load_volumes(&trades, instrument);
+            }
+            Request::SetMarketProtection { symbol, protection } => {
+                match (symbol, protection) {
+                    (Some(symbol), Some(protection)) => {
+                        self.symbol_protection.insert(symbol, protection);
+                    }
+                    (Some(symbol), None) => {
+                        self.symbol_protection.remove(&symbol);
+                    }
+                    (None, protection) => self.market_protection = protection,
+                }
+                let (default, symbols) = (self.market_protection, &self.symbol_protection);
+                for (symbol, book) in self.books.iter_mut() {
+                    book.set_market_protection(symbols.get(symbol).copied().or(default));
+                }
+            }
+            Request::FeeRevenue { respond } => {
+                let _ = respond.send(self.fees.revenue_by_symbol());
+            }
+            Request::Submit { order, respond } => {
+                let symbol = order.symbol.clone();
+                let result = self.submit(order);
+                if result.is_ok() {
+                    self.publish_book(&symbol);
+                }
+                let _ = respond.send(result);
+            }
+            Request::Cancel { symbol, order_id, respond } => {
+                let canceled = self
+                    .books
+                    .get_mut(&symbol)
+                    .is_some_and(|book| book.cancel_order(order_id));
+                if canceled {
+                    self.track(&symbol, &[], [order_id]);
+                    self.publish_book(&symbol);
+                }
+                let _ = respond.send(canceled);
+            }
+            Request::Amend { symbol, order_id, price, quantity, respond } => {
+                let result = self.amend(&symbol, order_id, price, quantity);
+                if let Ok(trades) = &result {
+                    self.track(&symbol, trades, [order_id]);
+                    self.publish(trades, order_id);
+                    self.publish_book(&symbol);
+                }
+                let _ = respond.send(result);
+            }
+            Request::AdjustTrade { adjustment, respond } => {
+                let _ = respond.send(self.adjust_trade(*adjustment));
+            }
+            Request::Depth { symbol, levels, respond } => {
+                let (bids, asks) = self
+                    .books
+                    .get(&symbol)
+                    .map(|book| book.market_depth(levels))
+                    .unwrap_or_default();
+                let _ = respond.send(DepthSnapshot { symbol, bids, asks });
+            }
+            Request::Order { symbol, order_id, respond } => {
+                let order = self
+                    .books
+                    .get(&symbol)
+                    .and_then(|book| book.get_order(order_id))
+                    .cloned();
+                let _ = respond.send(order);
+            }
+            Request::OpenOrders { user_id, respond } => {
+                let mut orders: Vec<Order> = self
+                    .books
+                    .values()
+                    .flat_map(|book| book.all_orders())
+                    .filter(|order| order.user_id == user_id && order.is_active())
+                    .cloned()
+                    .collect();
+                orders.sort_by_key(|order| order.id);
+                let _ = respond.send(orders);
+            }
+            Request::Stats { symbol, respond } => {
+                // Orders are stamped with the wall clock, so sessions end by it too
+                let stats = self.books.get(&symbol).map(|book| book.stats().as_of(time::current_timestamp_nanos()));
+                let _ = respond.send(stats);
+            }
+        }
+    }
+
+    fn submit(&mut self, order: Order) -> Result<OrderAck, EngineError> {
+        let order_id = order.id;
+        let symbol = order.symbol.clone();
+        let protection = self.protection(&symbol);
+        let book = self
+            .books
+            .entry(symbol.clone())
+            .or_insert_with(|| Self::new_book(Instrument::new(&symbol), protection));
+
+        if book.get_order(order_id).is_some() {
+            return Err(OrderError::DuplicateOrder { order_id }.into());
+        }
+        self.risk.check_order(&order, book)?;
+        if let Some(ledger) = self.ledger.as_mut() {
+            ledger.reserve(&order, book, self.fees.max_rate(&symbol))?;
+        }
+
+        let mut trades = book.process_order(order);
+        for trade in &mut trades {
+            self.fees.apply(trade, book.instrument());
+        }
+        self.track(&symbol, &trades, [order_id]);
+        self.publish(&trades, order_id);
+        let status = self
+            .books
+            .get(&symbol)
+            .and_then(|book| book.get_order(order_id))
+            .map(|order| order.status)
+            .unwrap_or(OrderStatus::Rejected);
+
+        Ok(OrderAck {
+            order_id,
+            status,
+            trades,
+        })
+    }
+
+    fn amend(
+        &mut self,
+        symbol: &str,
+        order_id: u64,
+        price: Option<Price>,
+        quantity: Option<Quantity>,
+    ) -> Result<Vec<Trade>, EngineError> {
+        let Some(book) = self.books.get_mut(symbol) else {
+            return Err(OrderError::UnknownOrder { order_id }.into());
+        };
+        let timestamp = time::current_timestamp_nanos();
+        let fee_rate = self.fees.max_rate(symbol);
+        let mut trades = match book.get_order(order_id).filter(|order| order.is_active()) {
+            Some(order) => {
+                let original = order.clone();
+                let mut amended = order.clone();
+                amended.price = price.unwrap_or(order.price);
+                amended.quantity = quantity.unwrap_or(order.quantity);
+                let filled = order.quantity.saturating_sub(order.remaining_quantity);
+                amended.remaining_quantity = amended.quantity.saturating_sub(filled);
+                self.risk.check_amend(&amended, book)?;
+                // The reservation is resized while the book still holds what the amended order would cross
+                if let Some(ledger) = self.ledger.as_mut() {
+                    ledger.check_amend(&amended, book, fee_rate)?;
+                    ledger.amend(&amended, book, fee_rate);
+                }
+                match book.amend_order(order_id, price, quantity, timestamp) {
+                    Ok(trades) => trades,
+                    Err(e) => {
+                        if let Some(ledger) = self.ledger.as_mut() {
+                            ledger.amend(&original, book, fee_rate);
+                        }
+                        return Err(e.into());
+                    }
+                }
+            }
+            None => book.amend_order(order_id, price, quantity, timestamp)?,
+        };
+        for trade in &mut trades {
+            self.fees.apply(trade, book.instrument());
+        }
+        Ok(trades)
+    }
+
+    fn adjust_trade(&mut self, mut adjustment: TradeAdjustment) -> Result<TradeAdjustment, EngineError> {
+        let trade_id = adjustment.trade_id;
+        let Some(book) = self.books.get_mut(&adjustment.symbol) else {
+            return Err(AdjustmentError::UnknownTrade { trade_id }.into());
+        };
+        if adjustment.previous.id != trade_id || adjustment.previous.symbol != adjustment.symbol {
+            return Err(AdjustmentError::UnknownTrade { trade_id }.into());
+        }
+        // An adjustment must start from the trade as the last one left it
+        let key = (adjustment.symbol.clone(), trade_id);
+        match self.adjusted.get(&key) {
+            Some(None) => return Err(AdjustmentError::Busted { trade_id }.into()),
+            Some(Some(current)) if *current != adjustment.previous => {
+                return Err(AdjustmentError::Stale { trade_id }.into());
+            }
+            _ => {}
+        }
+        if let Some(corrected) = adjustment.corrected.as_mut() {
+            (corrected.buy_fee, corrected.sell_fee) = self.fees.fees(corrected, book.instrument());
+        }
+        if let Some(ledger) = self.ledger.as_mut() {
+            ledger.check_adjustment(&adjustment, book.instrument())?;
+            ledger.adjust(&adjustment, book.instrument());
+        }
+        self.risk.on_adjustment(&adjustment);
+        self.fees.on_adjustment(&adjustment, book.instrument());
+        book.apply_adjustment(&adjustment);
+        self.adjusted.insert(key, adjustment.corrected.clone());
+        Ok(adjustment)
+    }
+}
+
+/// An async handle to a matching engine running on its own tokio task
+///
+/// Requests travel over a bounded mpsc queue, so callers wait when the
+/// engine falls behind instead of buffering without limit. Every trade is
+/// also published to trade subscribers, and the top of each book to book
+/// subscribers after it changes. Handles are cheap to clone; the engine
+/// task stops once every handle is dropped.
+#[derive(Clone)]
+pub struct EngineHandle {
+    sender: mpsc::Sender<Request>,
+    trades: broadcast::Sender<TradeEvent>,
+    book_updates: broadcast::Sender<DepthSnapshot>,
+}
+
+impl EngineHandle {
+    /// Spawns an engine task with the default queue capacity
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn spawn() -> Self {
+        Self::with_capacity(DEFAULT_QUEUE_CAPACITY)
+    }
+
+    /// Spawns an engine task whose request queue holds at most `capacity` requests
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_config(EngineConfig::new().with_capacity(capacity))
+    }
+
+    /// Spawns an engine task that rejects orders its users cannot pay for
+    ///
+    /// Each order reserves funds in `ledger` before matching and each trade
+    /// settles both legs.
+    pub fn with_ledger(ledger: Ledger) -> Self {
+        Self::with_config(EngineConfig::new().with_ledger(ledger))
+    }
+
+    /// Spawns an engine task with the given queue capacity, ledger and market order collars
+    pub fn with_config(config: EngineConfig) -> Self {
+        let (sender, mut receiver) = mpsc::channel(config.capacity.max(1));
+        let (trades, _) = broadcast::channel(TRADE_EVENT_CAPACITY);
+        let (book_updates, _) = broadcast::channel(BOOK_UPDATE_CAPACITY);
+
+        let mut state = EngineState::new(trades.clone(), book_updates.clone());
+        state.ledger = config.ledger;
+        state.market_protection = config.market_protection;
+        state.symbol_protection = config.symbol_protection;
+        tokio::spawn(async move {
+            while let Some(request) = receiver.recv().await {
+                state.handle(request);
+            }
+        });
+
+        Self { sender, trades, book_updates }
+    }
+
+    /// Subscribes to every trade executed from now on
+    pub fn subscribe_trades(&self) -> broadcast::Receiver<TradeEvent> {
+        self.trades.subscribe()
+    }
+
+    /// Subscribes to the top `BOOK_UPDATE_LEVELS` levels of every book after each change
+    pub fn subscribe_books(&self) -> broadcast::Receiver<DepthSnapshot> {
+        self.book_updates.subscribe()
+    }
+
+    /// Registers an instrument so its book uses the instrument's precision
+    pub async fn add_instrument(&self, instrument: Instrument) -> Result<(), EngineError> {
+        self.send(Request::AddInstrument(instrument)).await
+    }
+
+    /// Replaces the pre-trade risk limits of a scope for orders submitted from now on
+    pub async fn set_risk_limits(&self, scope: RiskScope, limits: RiskLimits) -> Result<(), EngineError> {
+        self.send(Request::SetRiskLimits {
+            scope,
+            limits: Some(limits),
+        })
+        .await
+    }
+
+    /// Removes the pre-trade risk limits of a scope
+    pub async fn clear_risk_limits(&self, scope: RiskScope) -> Result<(), EngineError> {
+        self.send(Request::SetRiskLimits { scope, limits: None }).await
+    }
+
+    /// Credits a user's available balance of an asset
+    /// Returns `LedgerError::Disabled` unless the engine has a ledger
+    pub async fn deposit(&self, user_id: u64, asset: &str, amount: u128) -> Result<(), EngineError> {
+        let (respond, response) = oneshot::channel();
+        self.send(Request::Deposit {
+            user_id,
+            asset: asset.to_string(),
+            amount,
+            respond,
+        })
+        .await?;
+        response.await.map_err(|_| EngineError::Closed)?
+    }
+
+    /// Debits a user's available balance of an asset
+    pub async fn withdraw(&self, user_id: u64, asset: &str, amount: u128) -> Result<(), EngineError> {
+        let (respond, response) = oneshot::channel();
+        self.send(Request::Withdraw {
+            user_id,
+            asset: asset.to_string(),
+            amount,
+            respond,
+        })
+        .await?;
+        response.await.map_err(|_| EngineError::Closed)?
+    }
+
+    /// Sets the fee schedule of a symbol, or of every symbol without one when `symbol` is `None`
+    /// A schedule of `None` removes it
+    pub async fn set_fee_schedule(&self, symbol: Option<&str>, schedule: Option<FeeSchedule>) -> Result<(), EngineError> {
+        self.send(Request::SetFeeSchedule {
+            symbol: symbol.map(str::to_string),
+            schedule,
+        })
+        .await
+    }
+
+    /// Replaces the 30-day volumes that decide each user's fee tier with those of `trades`
+    ///
+    /// Usually the trades that still stand in a `TradeStore`, loaded once at
+    /// startup; the engine counts the trades it executes itself.
+    pub async fn load_fee_volumes(&self, trades: Vec<Trade>) -> Result<(), EngineError> {
+        self.send(Request::LoadFeeVolumes(trades)).await
+    }
+
+    /// Sets the collar on market orders in a symbol, or in every symbol without one when `symbol` is `None`
+    /// A collar of `None` removes it
+    pub async fn set_market_protection(
+        &self,
+        symbol: Option<&str>,
+        protection: Option<MarketOrderProtection>,
+    ) -> Result<(), EngineError> {
+        self.send(Request::SetMarketProtection {
+            symbol: symbol.map(str::to_string),
+            protection,
+        })
+        .await
+    }
+
+    /// Returns the fees collected in each symbol, net of rebates
+    pub async fn fee_revenue(&self) -> Result<Vec<(String, i128)>, EngineError> {
+        let (respond, response) = oneshot::channel();
+        self.send(Request::FeeRevenue { respond }).await?;
+        response.await.map_err(|_| EngineError::Closed)
+    }
+
+    /// Returns a user's non-empty balances by asset
+    pub async fn balances(&self, user_id: u64) -> Result<Vec<(String, Balance)>, EngineError> {
+        let (respond, response) = oneshot::channel();
+        self.send(Request::Balances { user_id, respond }).await?;
+        response.await.map_err(|_| EngineError::Closed)?
+    }
+
+    /// Submits an order and waits for the result of matching it
+    pub async fn submit(&self, order: Order) -> Result<OrderAck, EngineError> {
+        let (respond, response) = oneshot::channel();
+        self.send(Request::Submit { order, respond }).await?;
+        response.await.map_err(|_| EngineError::Closed)?
+    }
+
+    /// Submits an order without waiting for queue space
+    /// Returns `EngineError::Busy` if the queue is full
+    pub async fn try_submit(&self, order: Order) -> Result<OrderAck, EngineError> {
+        let (respond, response) = oneshot::channel();
+        self.sender
+            .try_send(Request::Submit { order, respond })
+            .map_err(|e| match e {
+                mpsc::error::TrySendError::Full(_) => EngineError::Busy,
+                mpsc::error::TrySendError::Closed(_) => EngineError::Closed,
+            })?;
+        response.await.map_err(|_| EngineError::Closed)?
+    }
+
+    /// Cancels an order, returning true if it was active
+    pub async fn cancel(&self, symbol: &str, order_id: u64) -> Result<bool, EngineError> {
+        let (respond, response) = oneshot::channel();
+        self.send(Request::Cancel {
+            symbol: symbol.to_string(),
+            order_id,
+            respond,
+        })
+        .await?;
+        response.await.map_err(|_| EngineError::Closed)
+    }
+
+    /// Amends the price and/or total quantity of a resting order
+    /// Returns the trades caused by the amendment
+    pub async fn amend(
+        &self,
+        symbol: &str,
+        order_id: u64,
+        price: Option<Price>,
+        quantity: Option<Quantity>,
+    ) -> Result<Vec<Trade>, EngineError> {
+        let (respond, response) = oneshot::channel();
+        self.send(Request::Amend {
+            symbol: symbol.to_string(),
+            order_id,
+            price,
+            quantity,
+            respond,
+        })
+        .await?;
+        response.await.map_err(|_| EngineError::Closed)?
+    }
+
+    /// Applies a bust or correction from `TradeStore::bust_trade` or
+    /// `TradeStore::correct_trade` to the book's statistics, positions,
+    /// balances and fee revenue
+    ///
+    /// Returns the adjustment with the corrected trade's fees recomputed at
+    /// current rates, to record with `TradeStore::add_adjustment`. The engine
+    /// remembers the trades it adjusted: a trade that was busted cannot be
+    /// adjusted again, and a later adjustment must start from the trade as
+    /// the previous one left it, so replaying an adjustment is rejected.
+    pub async fn adjust_trade(&self, adjustment: TradeAdjustment) -> Result<TradeAdjustment, EngineError> {
+        let (respond, response) = oneshot::channel();
+        self.send(Request::AdjustTrade { adjustment: Box::new(adjustment), respond }).await?;
+        response.await.map_err(|_| EngineError::Closed)?
+    }
+
+    /// Returns up to `levels` aggregated price levels on each side of a book
+    pub async fn depth(&self, symbol: &str, levels: usize) -> Result<DepthSnapshot, EngineError> {
+        let (respond, response) = oneshot::channel();
+        self.send(Request::Depth {
+            symbol: symbol.to_string(),
+            levels,
+            respond,
+        })
+        .await?;
+        response.await.map_err(|_| EngineError::Closed)
+    }
+
+    /// Returns a snapshot of an order, if the book knows it
+    pub async fn order(&self, symbol: &str, order_id: u64) -> Result<Option<Order>, EngineError> {
+        let (respond, response) = oneshot::channel();
+        self.send(Request::Order {
+            symbol: symbol.to_string(),
+            order_id,
+            respond,
+        })
+        .await?;
+        response.await.map_err(|_| EngineError::Closed)
+    }
+
+    /// Returns the active orders of a user across all books, oldest first
+    pub async fn open_orders(&self, user_id: u64) -> Result<Vec<Order>, EngineError> {
+        let (respond, response) = oneshot::channel();
+        self.send(Request::OpenOrders { user_id, respond }).await?;
+        response.await.map_err(|_| EngineError::Closed)
+    }
+
+    /// Returns the statistics of a book, if it exists
+    pub async fn stats(&self, symbol: &str) -> Result<Option<OrderBookStats>, EngineError> {
+        let (respond, response) = oneshot::channel();
+        self.send(Request::Stats {
+            symbol: symbol.to_string(),
+            respond,
+        })
+        .await?;
+        response.await.map_err(|_| EngineError::Closed)
+    }
+
+    /// Queues a request, waiting for space if the queue is full
+    async fn send(&self, request: Request) -> Result<(), EngineError> {
+        self.sender.send(request).await.map_err(|_| EngineError::Closed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::order::{OrderSide, OrderType};
+    use crate::models::protection::CollarAction;
+    use crate::persistence::trade_store::TradeStore;
+
+    fn limit(id: u64, side: OrderSide, price: u64, quantity: u64) -> Order {
+        Order::new_limit(id, price, quantity, side, 1000 + id, id, None, "BTC-USD".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_query() {
+        let engine = EngineHandle::spawn();
+        engine.add_instrument(Instrument::with_precision("BTC-USD", 2, 8).unwrap()).await.unwrap();
+
+        let ack = engine.submit(limit(1, OrderSide::Sell, 10100, 5)).await.unwrap();
+        assert_eq!(ack.status, OrderStatus::New);
+        assert!(ack.trades.is_empty());
+
+        let ack = engine.submit(limit(2, OrderSide::Buy, 10100, 2)).await.unwrap();
+        assert_eq!(ack.status, OrderStatus::Filled);
+        assert_eq!(ack.trades[0].quantity, Quantity(2));
+
+        let depth = engine.depth("BTC-USD", 5).await.unwrap();
+        assert_eq!(depth.asks, vec![(Price(10100), Quantity(3))]);
+        assert!(depth.bids.is_empty());
+
+        let order = engine.order("BTC-USD", 1).await.unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::PartiallyFilled);
+        assert!(engine.order("ETH-USD", 1).await.unwrap().is_none());
+
+        assert_eq!(
+            engine.submit(limit(1, OrderSide::Sell, 10100, 5)).await,
+            Err(EngineError::Order(OrderError::DuplicateOrder { order_id: 1 }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_trades_are_published() {
+        let engine = EngineHandle::spawn();
+        let mut trades = engine.subscribe_trades();
+        engine.submit(limit(1, OrderSide::Sell, 10100, 5)).await.unwrap();
+        engine.submit(limit(2, OrderSide::Buy, 10000, 5)).await.unwrap();
+        engine.submit(limit(3, OrderSide::Buy, 10100, 2)).await.unwrap();
+        engine.amend("BTC-USD", 2, Some(Price(10100)), None).await.unwrap();
+
+        let event = trades.recv().await.unwrap();
+        assert_eq!(event.taker_order_id, 3);
+        assert_eq!(event.maker(), (1, 1001));
+        assert_eq!(event.trade.aggressor_side, OrderSide::Buy);
+        assert_eq!(event.trade.quantity, Quantity(2));
+
+        // The amended order takes the rest of the resting sell
+        let event = trades.recv().await.unwrap();
+        assert_eq!(event.taker_order_id, 2);
+        assert_eq!(event.trade.taker_order_id, 2);
+        assert_eq!(event.trade.quantity, Quantity(3));
+    }
+
+    #[tokio::test]
+    async fn test_open_orders_stats_and_book_updates() {
+        let engine = EngineHandle::spawn();
+        let mut books = engine.subscribe_books();
+        engine.submit(limit(1, OrderSide::Sell, 10100, 5)).await.unwrap();
+        engine.submit(limit(2, OrderSide::Buy, 10000, 4)).await.unwrap();
+        let mut other = limit(3, OrderSide::Buy, 9900, 1);
+        other.user_id = 1001;
+        engine.submit(other).await.unwrap();
+        engine.cancel("BTC-USD", 2).await.unwrap();
+
+        let open: Vec<u64> = engine.open_orders(1001).await.unwrap().iter().map(|o| o.id).collect();
+        assert_eq!(open, vec![1, 3]);
+        assert!(engine.open_orders(1002).await.unwrap().is_empty());
+
+        let stats = engine.stats("BTC-USD").await.unwrap().unwrap();
+        assert_eq!(stats.best_ask, Some(Price(10100)));
+        assert_eq!(stats.best_bid, Some(Price(9900)));
+        assert!(engine.stats("ETH-USD").await.unwrap().is_none());
+
+        // One update per change, the last one after the cancel
+        let mut updates = Vec::new();
+        while let Ok(update) = books.try_recv() {
+            updates.push(update);
+        }
+        assert_eq!(updates.len(), 4);
+        assert_eq!(updates[1].bids, vec![(Price(10000), Quantity(4))]);
+        assert_eq!(updates[3].bids, vec![(Price(9900), Quantity(1))]);
+        assert_eq!(updates[3].asks, vec![(Price(10100), Quantity(5))]);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_and_amend() {
+        let engine = EngineHandle::spawn();
+        engine.submit(limit(1, OrderSide::Sell, 10100, 5)).await.unwrap();
+        engine.submit(limit(2, OrderSide::Buy, 10000, 5)).await.unwrap();
+
+        let trades = engine.amend("BTC-USD", 2, Some(Price(10100)), None).await.unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(
+            engine.amend("BTC-USD", 2, None, Some(Quantity(9))).await,
+            Err(EngineError::Order(OrderError::NotAmendable { order_id: 2 }))
+        );
+
+        engine.submit(limit(3, OrderSide::Sell, 10200, 1)).await.unwrap();
+        assert!(engine.cancel("BTC-USD", 3).await.unwrap());
+        assert!(!engine.cancel("BTC-USD", 3).await.unwrap());
+        assert!(!engine.cancel("ETH-USD", 3).await.unwrap());
+        assert!(engine.depth("BTC-USD", 5).await.unwrap().asks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_market_order_collars() {
+        // 1% from the best price everywhere, and 2% in ETH-USD
+        let config = EngineConfig::new()
+            .with_market_protection(MarketOrderProtection::with_max_basis_points(100))
+            .with_symbol_protection("ETH-USD", MarketOrderProtection::with_max_basis_points(200));
+        let engine = EngineHandle::with_config(config);
+        for (id, price, quantity) in [(1, 10000, 3), (2, 10100, 2), (3, 10300, 5)] {
+            engine.submit(limit(id, OrderSide::Sell, price, quantity)).await.unwrap();
+        }
+        let market = |id, symbol: &str| Order::new_market(id, 10, OrderSide::Buy, 2000, id, None, symbol.to_string());
+
+        let ack = engine.submit(market(4, "BTC-USD")).await.unwrap();
+        assert_eq!(ack.status, OrderStatus::Canceled);
+        assert_eq!(ack.trades.iter().map(|t| t.quantity).sum::<Quantity>(), Quantity(5));
+
+        let mut eth = limit(5, OrderSide::Sell, 10150, 4);
+        eth.symbol = "ETH-USD".to_string();
+        engine.submit(eth).await.unwrap();
+        let mut eth = limit(6, OrderSide::Sell, 10000, 4);
+        eth.symbol = "ETH-USD".to_string();
+        engine.submit(eth).await.unwrap();
+        let ack = engine.submit(market(7, "ETH-USD")).await.unwrap();
+        assert_eq!(ack.trades.iter().map(|t| t.quantity).sum::<Quantity>(), Quantity(8));
+
+        // Without a collar the rest of the book is swept
+        engine.set_market_protection(None, None).await.unwrap();
+        let ack = engine.submit(market(8, "BTC-USD")).await.unwrap();
+        assert_eq!(ack.trades.iter().map(|t| t.quantity).sum::<Quantity>(), Quantity(5));
+        assert_eq!(ack.trades[0].price, Price(10300));
+    }
+
+    #[tokio::test]
+    async fn test_converted_market_orders_are_reserved() {
+        let protection = MarketOrderProtection::with_max_basis_points(100).with_action(CollarAction::ConvertToLimit);
+        let engine = EngineHandle::with_config(EngineConfig::new().with_ledger(Ledger::new()).with_market_protection(protection));
+        engine.deposit(1001, "BTC", 5).await.unwrap();
+        engine.deposit(1002, "USD", 60_000).await.unwrap();
+        engine.deposit(1003, "USD", 40_000).await.unwrap();
+        engine.submit(limit(1, OrderSide::Sell, 10000, 3)).await.unwrap();
+        let mut sell = limit(2, OrderSide::Sell, 10300, 2);
+        sell.user_id = 1001;
+        engine.submit(sell).await.unwrap();
+        let market = |id, user_id| Order::new_market(id, 5, OrderSide::Buy, user_id, id, None, "BTC-USD".to_string());
+
+        // The remainder rests at the collar with its cost reserved
+        let ack = engine.submit(market(3, 1002)).await.unwrap();
+        assert_eq!((ack.status, ack.trades.len()), (OrderStatus::PartiallyFilled, 1));
+        let order = engine.order("BTC-USD", 3).await.unwrap().unwrap();
+        assert_eq!((order.order_type, order.price), (OrderType::Limit, Price(10100)));
+        assert_eq!(
+            engine.balances(1002).await.unwrap(),
+            vec![
+                ("BTC".to_string(), Balance { available: 3, reserved: 0 }),
+                ("USD".to_string(), Balance { available: 9_800, reserved: 20_200 })
+            ]
+        );
+
+        // A remainder its user cannot pay for is canceled instead
+        let ack = engine.submit(market(4, 1003)).await.unwrap();
+        assert_eq!((ack.status, ack.trades.len()), (OrderStatus::Canceled, 1));
+        assert_eq!(
+            engine.balances(1003).await.unwrap(),
+            vec![
+                ("BTC".to_string(), Balance { available: 2, reserved: 0 }),
+                ("USD".to_string(), Balance { available: 19_400, reserved: 0 })
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_risk_limits() {
+        let engine = EngineHandle::spawn();
+        engine
+            .set_risk_limits(RiskScope::Default, RiskLimits::new().with_max_order_quantity(Quantity(10)))
+            .await
+            .unwrap();
+        engine
+            .set_risk_limits(RiskScope::User(1001), RiskLimits::new().with_max_open_orders(1))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            engine.submit(limit(1, OrderSide::Sell, 10100, 11)).await,
+            Err(EngineError::Risk(RiskReject::OrderQuantity { quantity: Quantity(11), limit: Quantity(10) }))
+        );
+        engine.submit(limit(1, OrderSide::Sell, 10100, 5)).await.unwrap();
+        let mut second = limit(2, OrderSide::Sell, 10200, 5);
+        second.user_id = 1001;
+        assert_eq!(
+            engine.submit(second.clone()).await,
+            Err(EngineError::Risk(RiskReject::OpenOrders { open: 1, limit: 1 }))
+        );
+        assert!(engine.order("BTC-USD", 2).await.unwrap().is_none());
+
+        // Filling the open order frees its slot
+        engine.submit(limit(3, OrderSide::Buy, 10100, 5)).await.unwrap();
+        engine.submit(second).await.unwrap();
+
+        // Amendments are checked too, and limits can be lifted at runtime
+        assert!(matches!(
+            engine.amend("BTC-USD", 2, None, Some(Quantity(20))).await,
+            Err(EngineError::Risk(RiskReject::OrderQuantity { .. }))
+        ));
+        engine.clear_risk_limits(RiskScope::Default).await.unwrap();
+        engine.amend("BTC-USD", 2, None, Some(Quantity(20))).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ledger_reserves_and_settles() {
+        let engine = EngineHandle::with_ledger(Ledger::new());
+        engine.deposit(1001, "BTC", 5).await.unwrap();
+        engine.deposit(1002, "USD", 30_000).await.unwrap();
+
+        engine.submit(limit(1, OrderSide::Sell, 10000, 5)).await.unwrap();
+        assert_eq!(
+            engine.submit(limit(2, OrderSide::Buy, 10000, 4)).await,
+            Err(EngineError::Ledger(LedgerError::InsufficientFunds {
+                user_id: 1002,
+                asset: "USD".to_string(),
+                required: 40_000,
+                available: 30_000,
+            }))
+        );
+        let ack = engine.submit(limit(2, OrderSide::Buy, 10000, 3)).await.unwrap();
+        assert_eq!(ack.status, OrderStatus::Filled);
+
+        assert_eq!(
+            engine.balances(1001).await.unwrap(),
+            vec![
+                ("BTC".to_string(), Balance { available: 0, reserved: 2 }),
+                ("USD".to_string(), Balance { available: 30_000, reserved: 0 }),
+            ]
+        );
+        assert_eq!(engine.balances(1002).await.unwrap(), vec![("BTC".to_string(), Balance { available: 3, reserved: 0 })]);
+
+        // Canceling releases the rest of the sell
+        assert!(engine.cancel("BTC-USD", 1).await.unwrap());
+        engine.withdraw(1001, "BTC", 2).await.unwrap();
+        assert!(engine.withdraw(1001, "BTC", 1).await.is_err());
+        assert_eq!(EngineHandle::spawn().deposit(1, "USD", 1).await, Err(EngineError::Ledger(LedgerError::Disabled)));
+    }
+
+    #[tokio::test]
+    async fn test_ledger_reserves_fees() {
+        let engine = EngineHandle::with_ledger(Ledger::new());
+        engine.set_fee_schedule(None, Some(FeeSchedule::new(0, 1_000))).await.unwrap();
+        engine.deposit(1001, "BTC", 5).await.unwrap();
+        engine.deposit(1002, "USD", 30_000).await.unwrap();
+        engine.deposit(1003, "USD", 10_010).await.unwrap();
+
+        // Three units at 10000 cost 30000 and up to 30 more in fees
+        engine.submit(limit(1, OrderSide::Sell, 10000, 3)).await.unwrap();
+        assert_eq!(
+            engine.submit(limit(2, OrderSide::Buy, 10000, 3)).await,
+            Err(EngineError::Ledger(LedgerError::InsufficientFunds {
+                user_id: 1002,
+                asset: "USD".to_string(),
+                required: 30_030,
+                available: 30_000,
+            }))
+        );
+        engine.deposit(1002, "USD", 30).await.unwrap();
+        engine.submit(limit(2, OrderSide::Buy, 10000, 3)).await.unwrap();
+        assert_eq!(engine.balances(1002).await.unwrap(), vec![("BTC".to_string(), Balance { available: 3, reserved: 0 })]);
+
+        // Raising fees cancels a resting buy that can no longer pay them
+        let ack = engine.submit(limit(3, OrderSide::Buy, 10000, 1)).await.unwrap();
+        assert_eq!(ack.status, OrderStatus::New);
+        engine.set_fee_schedule(None, Some(FeeSchedule::new(0, 2_000))).await.unwrap();
+        assert_eq!(engine.order("BTC-USD", 3).await.unwrap().unwrap().status, OrderStatus::Canceled);
+        assert_eq!(engine.balances(1003).await.unwrap(), vec![("USD".to_string(), Balance { available: 10_010, reserved: 0 })]);
+    }
+
+    #[tokio::test]
+    async fn test_trade_adjustments() {
+        let engine = EngineHandle::with_ledger(Ledger::new());
+        engine.deposit(1001, "BTC", 5).await.unwrap();
+        engine.deposit(1002, "USD", 50_000).await.unwrap();
+        engine.deposit(1003, "USD", 20_200).await.unwrap();
+        engine.submit(limit(1, OrderSide::Sell, 10000, 5)).await.unwrap();
+        let mut store = TradeStore::new();
+        store.add_trades(engine.submit(limit(2, OrderSide::Buy, 10000, 3)).await.unwrap().trades).unwrap();
+        store.add_trades(engine.submit(limit(3, OrderSide::Buy, 10100, 2)).await.unwrap().trades).unwrap();
+
+        // Correcting the first trade leaves the last trade price alone
+        let correction = store.correct_trade(1, Price(9000), Quantity(3), "Off-market price", 0).unwrap();
+        store.add_adjustment(engine.adjust_trade(correction).await.unwrap()).unwrap();
+        let stats = engine.stats("BTC-USD").await.unwrap().unwrap();
+        assert_eq!((stats.volume, stats.last_trade_price), (Quantity(5), Some(Price(10000))));
+        assert_eq!(engine.balances(1002).await.unwrap()[1].1.available, 23_000);
+
+        // Busting the last trade falls back to the corrected one
+        let bust = store.bust_trade(2, "Erroneous", 0).unwrap();
+        store.add_adjustment(engine.adjust_trade(bust).await.unwrap()).unwrap();
+        let stats = engine.stats("BTC-USD").await.unwrap().unwrap();
+        assert_eq!((stats.volume, stats.trade_count, stats.last_trade_price), (Quantity(3), 1, Some(Price(9000))));
+        assert_eq!(
+            engine.balances(1001).await.unwrap(),
+            vec![("BTC".to_string(), Balance { available: 2, reserved: 0 }), ("USD".to_string(), Balance { available: 27_000, reserved: 0 })]
+        );
+        assert_eq!(store.bust_trade(2, "Again", 0), Err(AdjustmentError::Busted { trade_id: 2 }));
+        assert_eq!(store.get_trade(2).unwrap().quantity, Quantity(2));
+
+        // The buyer no longer has the bitcoin it would have to give back
+        engine.withdraw(1002, "BTC", 3).await.unwrap();
+        let bust = store.bust_trade(1, "Erroneous", 0).unwrap();
+        assert!(matches!(engine.adjust_trade(bust).await, Err(EngineError::Ledger(_))));
+    }
+
+    #[tokio::test]
+    async fn test_adjustments_are_not_replayed() {
+        let engine = EngineHandle::with_ledger(Ledger::new());
+        engine.deposit(1001, "BTC", 5).await.unwrap();
+        engine.deposit(1002, "USD", 50_000).await.unwrap();
+        engine.submit(limit(1, OrderSide::Sell, 10000, 5)).await.unwrap();
+        let mut store = TradeStore::new();
+        store.add_trades(engine.submit(limit(2, OrderSide::Buy, 10000, 3)).await.unwrap().trades).unwrap();
+
+        // Two corrections built from the same state: only the first applies
+        let first = store.correct_trade(1, Price(9000), Quantity(3), "Off-market price", 0).unwrap();
+        let second = store.correct_trade(1, Price(9500), Quantity(3), "Off-market price", 0).unwrap();
+        engine.adjust_trade(first).await.unwrap();
+        let balances = engine.balances(1002).await.unwrap();
+        assert_eq!(engine.adjust_trade(second).await, Err(EngineError::Adjustment(AdjustmentError::Stale { trade_id: 1 })));
+
+        // A bust applies once
+        let corrected = engine.stats("BTC-USD").await.unwrap().unwrap();
+        let mut bust = store.bust_trade(1, "Erroneous", 0).unwrap();
+        bust.previous.price = Price(9000);
+        engine.adjust_trade(bust.clone()).await.unwrap();
+        assert_eq!(engine.adjust_trade(bust).await, Err(EngineError::Adjustment(AdjustmentError::Busted { trade_id: 1 })));
+        let busted = engine.stats("BTC-USD").await.unwrap().unwrap();
+        assert_eq!((corrected.volume, busted.volume), (Quantity(3), Quantity::ZERO));
+        assert_eq!(engine.balances(1002).await.unwrap()[0].1.available, balances[1].1.available + 27_000);
+    }
+
+    #[tokio::test]
+    async fn test_fees_are_recorded_on_trades() {
+        let engine = EngineHandle::spawn();
+        engine.set_fee_schedule(None, Some(FeeSchedule::new(-100, 500))).await.unwrap();
+        engine
+            .set_fee_schedule(Some("BTC-USD"), Some(FeeSchedule::new(-100, 500).with_tier(1_000_000, -200, 300)))
+            .await
+            .unwrap();
+        let history = Trade::new(1, 20_000, 100, time::current_timestamp_nanos(), 1, 2, 1002, 1005, OrderSide::Buy, "BTC-USD".to_string());
+        engine.load_fee_volumes(vec![history]).await.unwrap();
+        let mut trades = engine.subscribe_trades();
+
+        engine.submit(limit(1, OrderSide::Sell, 10000, 100)).await.unwrap();
+        let ack = engine.submit(limit(2, OrderSide::Buy, 10000, 100)).await.unwrap();
+        // The buyer took liquidity at its tier's rate; the seller earned the base rebate
+        assert_eq!((ack.trades[0].buy_fee, ack.trades[0].sell_fee), (300, -100));
+        assert_eq!(trades.recv().await.unwrap().trade, ack.trades[0]);
+
+        engine.submit(limit(3, OrderSide::Buy, 9900, 10)).await.unwrap();
+        let trades = engine.amend("BTC-USD", 3, Some(Price(10000)), None).await.unwrap();
+        assert!(trades.is_empty());
+        engine.submit(limit(4, OrderSide::Sell, 10000, 10)).await.unwrap();
+
+        assert_eq!(engine.fee_revenue().await.unwrap(), vec![("BTC-USD".to_string(), 200 + 50 - 10)]);
+    }
+
+    #[tokio::test]
+    async fn test_bounded_queue_applies_backpressure() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let (trades, _) = broadcast::channel(1);
+        let (book_updates, _) = broadcast::channel(1);
+        let engine = EngineHandle { sender, trades: trades.clone(), book_updates: book_updates.clone() };
+
+        // Nothing is draining the queue, so the first request fills it
+        let pending = tokio::spawn({
+            let engine = engine.clone();
+            async move { engine.submit(limit(1, OrderSide::Buy, 100, 1)).await }
+        });
+        while engine.sender.capacity() > 0 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(engine.try_submit(limit(2, OrderSide::Buy, 100, 1)).await, Err(EngineError::Busy));
+
+        // Serving the queued request completes the waiting caller
+        let mut state = EngineState::new(trades, book_updates);
+        state.handle(receiver.recv().await.unwrap());
+        assert_eq!(pending.await.unwrap().unwrap().status, OrderStatus::New);
+
+        drop(receiver);
+        assert_eq!(engine.depth("BTC-USD", 1).await, Err(EngineError::Closed));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_callers() {
+        let engine = EngineHandle::with_capacity(4);
+        let tasks: Vec<_> = (1..=100)
+            .map(|id| {
+                let engine = engine.clone();
+                let side = if id % 2 == 0 { OrderSide::Buy } else { OrderSide::Sell };
+                tokio::spawn(async move { engine.submit(limit(id, side, 100, 1)).await })
+            })
+            .collect();
+
+        let mut traded = 0;
+        for task in tasks {
+            traded += task.await.unwrap().unwrap().trades.len();
+        }
+
+        // 50 buys and 50 sells at the same price all cross
+        assert_eq!(traded, 50);
+        let depth = engine.depth("BTC-USD", 1).await.unwrap();
+        assert!(depth.bids.is_empty() && depth.asks.is_empty());
+    }
+}
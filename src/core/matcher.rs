@@ -1,15 +1,30 @@
 use std::collections::{BTreeMap, HashMap};
-use log::{debug, info, warn};
+use log::warn;
 
-use crate::models::order::{Order, OrderSide, OrderStatus, OrderType};
+use crate::models::order::{Order, OrderSide};
+use crate::models::price::Price;
 use crate::models::trade::Trade;
 
+/// Copies the fill state of an order to its entry in the order lookup map
+fn sync_order(orders_by_id: &mut HashMap<u64, Order>, order: &Order) {
+    if let Some(stored_order) = orders_by_id.get_mut(&order.id) {
+        stored_order.remaining_quantity = order.remaining_quantity;
+        stored_order.status = order.status;
+    }
+}
+
 /// The matching engine component that pairs buy and sell orders
 pub struct Matcher {
     /// Last generated trade ID
     last_trade_id: u64,
 }
 
+impl Default for Matcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Matcher {
     /// Creates a new matcher
     pub fn new() -> Self {
@@ -28,8 +43,8 @@ impl Matcher {
     pub fn match_market_order(
         &mut self,
         mut order: Order,
-        bids: &mut BTreeMap<u64, Vec<Order>>,
-        asks: &mut BTreeMap<u64, Vec<Order>>,
+        bids: &mut BTreeMap<Price, Vec<Order>>,
+        asks: &mut BTreeMap<Price, Vec<Order>>,
         orders_by_id: &mut HashMap<u64, Order>,
     ) -> Vec<Trade> {
         let mut trades = Vec::new();
@@ -41,7 +56,7 @@ impl Matcher {
         };
         
         // Keep matching until the order is filled or the opposite side is exhausted
-        while order.remaining_quantity > 0 && !opposite_levels.is_empty() {
+        while !order.remaining_quantity.is_zero() && !opposite_levels.is_empty() {
             let best_price = match order.side {
                 OrderSide::Buy => *opposite_levels.keys().next().unwrap(),
                 OrderSide::Sell => *opposite_levels.keys().next_back().unwrap(),
@@ -55,11 +70,24 @@ impl Matcher {
             }
             
             // Match with the first order at this price level
-            let mut opposite_order = &mut level_orders[0];
+            let opposite_order = &mut level_orders[0];
             
             // Calculate the match quantity
             let match_qty = std::cmp::min(order.remaining_quantity, opposite_order.remaining_quantity);
             
+            // Apply the fill to both orders before recording the trade
+            if let Err(e) = order
+                .fill_partial(match_qty)
+                .and_then(|_| opposite_order.fill_partial(match_qty))
+            {
+                warn!("Order {} could not be matched: {}", order.id, e);
+                break;
+            }
+            
+            // Keep the copies in orders_by_id in sync with the book
+            sync_order(orders_by_id, &order);
+            sync_order(orders_by_id, opposite_order);
+            
             // Create the trade
             let trade = Trade {
                 id: self.next_trade_id(),
@@ -70,44 +98,31 @@ impl Matcher {
                 sell_order_id: if order.is_sell() { order.id } else { opposite_order.id },
                 buy_user_id: if order.is_buy() { order.user_id } else { opposite_order.user_id },
                 sell_user_id: if order.is_sell() { order.user_id } else { opposite_order.user_id },
+                aggressor_side: order.side,
+                maker_order_id: opposite_order.id,
+                taker_order_id: order.id,
                 symbol: order.symbol.clone(),
+                buy_fee: 0,
+                sell_fee: 0,
             };
             
-            // Update the orders
-            order.fill_partial(match_qty);
-            
-            // We need to update the order in the orders_by_id map
-            if let Some(stored_order) = orders_by_id.get_mut(&order.id) {
-                stored_order.fill_partial(match_qty);
-            }
-            
-            // Update the opposite order
-            opposite_order.fill_partial(match_qty);
-            
             // Add the trade to the results
             trades.push(trade);
             
             // If the opposite order is now filled, remove it
             if opposite_order.is_filled() {
-                // We need to clone the ID because we can't mutably borrow the order
-                // and then remove it by ID in the same scope
-                let opposite_id = opposite_order.id;
-                
-                // Mark it as filled in the orders_by_id map as well
-                if let Some(stored_order) = orders_by_id.get_mut(&opposite_id) {
-                    stored_order.fill_complete();
-                }
-                
-                // Now remove the first order
                 level_orders.remove(0);
                 
-                // If the level is now empty, we'll remove it in the next iteration
+                // Remove the level once it is empty so the best price stays accurate
+                if level_orders.is_empty() {
+                    opposite_levels.remove(&best_price);
+                }
             }
         }
         
         // For market orders, we don't add any remaining quantity to the book
         // It's either filled completely or filled as much as possible
-        if order.remaining_quantity > 0 {
+        if !order.remaining_quantity.is_zero() {
             // In a real system, we might report "unable to fill completely" here
             warn!(
                 "Market order {} could not be filled completely. Remaining: {}",
@@ -123,8 +138,8 @@ impl Matcher {
     pub fn match_limit_order(
         &mut self,
         mut order: Order,
-        bids: &mut BTreeMap<u64, Vec<Order>>,
-        asks: &mut BTreeMap<u64, Vec<Order>>,
+        bids: &mut BTreeMap<Price, Vec<Order>>,
+        asks: &mut BTreeMap<Price, Vec<Order>>,
         orders_by_id: &mut HashMap<u64, Order>,
     ) -> Vec<Trade> {
         let mut trades = Vec::new();
@@ -136,7 +151,7 @@ impl Matcher {
         };
         
         // Keep matching while there's a favorable price on the opposite side
-        while order.remaining_quantity > 0 && !opposite_levels.is_empty() {
+        while !order.remaining_quantity.is_zero() && !opposite_levels.is_empty() {
             let best_opposite_price = match order.side {
                 OrderSide::Buy => {
                     if let Some(&price) = opposite_levels.keys().next() {
@@ -170,11 +185,24 @@ impl Matcher {
             }
             
             // Match with the first order at this price level
-            let mut opposite_order = &mut level_orders[0];
+            let opposite_order = &mut level_orders[0];
             
             // Calculate the match quantity
             let match_qty = std::cmp::min(order.remaining_quantity, opposite_order.remaining_quantity);
             
+            // Apply the fill to both orders before recording the trade
+            if let Err(e) = order
+                .fill_partial(match_qty)
+                .and_then(|_| opposite_order.fill_partial(match_qty))
+            {
+                warn!("Order {} could not be matched: {}", order.id, e);
+                break;
+            }
+            
+            // Keep the copies in orders_by_id in sync with the book
+            sync_order(orders_by_id, &order);
+            sync_order(orders_by_id, opposite_order);
+            
             // Create the trade
             let trade = Trade {
                 id: self.next_trade_id(),
@@ -185,38 +213,25 @@ impl Matcher {
                 sell_order_id: if order.is_sell() { order.id } else { opposite_order.id },
                 buy_user_id: if order.is_buy() { order.user_id } else { opposite_order.user_id },
                 sell_user_id: if order.is_sell() { order.user_id } else { opposite_order.user_id },
+                aggressor_side: order.side,
+                maker_order_id: opposite_order.id,
+                taker_order_id: order.id,
                 symbol: order.symbol.clone(),
+                buy_fee: 0,
+                sell_fee: 0,
             };
             
-            // Update the orders
-            order.fill_partial(match_qty);
-            
-            // We need to update the order in the orders_by_id map
-            if let Some(stored_order) = orders_by_id.get_mut(&order.id) {
-                stored_order.fill_partial(match_qty);
-            }
-            
-            // Update the opposite order
-            opposite_order.fill_partial(match_qty);
-            
             // Add the trade to the results
             trades.push(trade);
             
             // If the opposite order is now filled, remove it
             if opposite_order.is_filled() {
-                // We need to clone the ID because we can't mutably borrow the order
-                // and then remove it by ID in the same scope
-                let opposite_id = opposite_order.id;
-                
-                // Mark it as filled in the orders_by_id map as well
-                if let Some(stored_order) = orders_by_id.get_mut(&opposite_id) {
-                    stored_order.fill_complete();
-                }
-                
-                // Now remove the first order
                 level_orders.remove(0);
                 
-                // If the level is now empty, we'll remove it in the next iteration
+                // Remove the level once it is empty so the best price stays accurate
+                if level_orders.is_empty() {
+                    opposite_levels.remove(&best_opposite_price);
+                }
             }
         }
         
@@ -228,8 +243,8 @@ impl Matcher {
     pub fn simulate_order_match(
         &self,
         order: &Order,
-        bids: &BTreeMap<u64, Vec<Order>>,
-        asks: &BTreeMap<u64, Vec<Order>>,
+        bids: &BTreeMap<Price, Vec<Order>>,
+        asks: &BTreeMap<Price, Vec<Order>>,
     ) -> Vec<Trade> {
         let mut simulated_trades = Vec::new();
         let mut remaining_qty = order.remaining_quantity;
@@ -241,10 +256,12 @@ impl Matcher {
         };
         
         // Simulate matching against the opposite side
-        for (&price, level_orders) in match order.side {
-            OrderSide::Buy => opposite_levels.iter(),
-            OrderSide::Sell => opposite_levels.iter().rev(),
-        } {
+        let price_iter: Box<dyn Iterator<Item = (&Price, &Vec<Order>)>> = match order.side {
+            OrderSide::Buy => Box::new(opposite_levels.iter()),
+            OrderSide::Sell => Box::new(opposite_levels.iter().rev()),
+        };
+        
+        for (&price, level_orders) in price_iter {
             // For a buy order, only match if the ask price is <= order price
             // For a sell order, only match if the bid price is >= order price
             let price_matches = match order.side {
@@ -270,14 +287,19 @@ impl Matcher {
                     sell_order_id: if order.is_sell() { order.id } else { opposite_order.id },
                     buy_user_id: if order.is_buy() { order.user_id } else { opposite_order.user_id },
                     sell_user_id: if order.is_sell() { order.user_id } else { opposite_order.user_id },
+                    aggressor_side: order.side,
+                    maker_order_id: opposite_order.id,
+                    taker_order_id: order.id,
                     symbol: order.symbol.clone(),
+                    buy_fee: 0,
+                    sell_fee: 0,
                 };
                 
                 simulated_trades.push(trade);
                 
                 remaining_qty -= match_qty;
                 
-                if remaining_qty == 0 {
+                if remaining_qty.is_zero() {
                     return simulated_trades;
                 }
             }
@@ -1,7 +1,11 @@
 // Export core components
 pub mod order_book;
 pub mod matcher;
+pub mod engine;
+pub mod handle;
 
 // Re-export main components
 pub use order_book::OrderBook;
 pub use matcher::Matcher;
+pub use engine::{EngineCommand, EngineEvent, ShardedEngine};
+pub use handle::{DepthSnapshot, EngineConfig, EngineError, EngineHandle, OrderAck, TradeEvent};
@@ -0,0 +1,1761 @@
+use std::collections::{BTreeMap, HashMap};
+use log::warn;
+
+use crate::models::order::{Order, OrderError, OrderSide, OrderType};
+use crate::models::trade::Trade;
+use crate::models::trade_adjustment::TradeAdjustment;
+use crate::models::stats::OrderBookStats;
+use crate::models::analytics::{AnalyticsConfig, BookAnalytics};
+use crate::models::book_event::BookEvent;
+use crate::models::execution::{ExecutionEstimate, LadderLevel};
+use crate::models::instrument::Instrument;
+use crate::models::price::{Price, Quantity};
+use crate::models::protection::{CollarAction, CollarReference, MarketOrderProtection};
+use crate::core::matcher::Matcher;
+
+/// Best bid and best offer with the total quantity at each
+type TopOfBook = (Option<(Price, Quantity)>, Option<(Price, Quantity)>);
+
+/// Remaining quantity at each price level, kept up to date while analytics are enabled
+///
+/// Totals are summed in u128 so adding and removing quantity never saturates.
+#[derive(Debug, Default)]
+struct LevelTotals {
+    bids: BTreeMap<Price, u128>,
+    asks: BTreeMap<Price, u128>,
+}
+
+impl LevelTotals {
+    fn side_mut(&mut self, side: OrderSide) -> &mut BTreeMap<Price, u128> {
+        match side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        }
+    }
+    
+    fn add(&mut self, side: OrderSide, price: Price, quantity: Quantity) {
+        *self.side_mut(side).entry(price).or_default() += quantity.0 as u128;
+    }
+    
+    /// Takes quantity out of a level, dropping the level once it is empty
+    fn remove(&mut self, side: OrderSide, price: Price, quantity: Quantity) {
+        let levels = self.side_mut(side);
+        if let Some(total) = levels.get_mut(&price) {
+            *total = total.saturating_sub(quantity.0 as u128);
+            if *total == 0 {
+                levels.remove(&price);
+            }
+        }
+    }
+    
+    /// Returns the price and quantity of each level of one side, best price first
+    fn levels(&self, side: OrderSide) -> Box<dyn Iterator<Item = (Price, Quantity)> + '_> {
+        let level = |(&price, &total): (&Price, &u128)| (price, level_quantity(total));
+        match side {
+            OrderSide::Buy => Box::new(self.bids.iter().rev().map(level)),
+            OrderSide::Sell => Box::new(self.asks.iter().map(level)),
+        }
+    }
+    
+    /// Returns the bid and ask quantity priced within `bps` basis points of the midpoint
+    fn depth(&self, bid: Price, ask: Price, bps: u32) -> (Quantity, Quantity) {
+        let (lower, upper) = depth_bounds(bid, ask, bps);
+        (
+            self.bids.range(lower..).map(|(_, &total)| level_quantity(total)).sum(),
+            self.asks.range(..=upper).map(|(_, &total)| level_quantity(total)).sum(),
+        )
+    }
+}
+
+/// Returns a level total as a quantity, saturating like summing the level's orders
+fn level_quantity(total: u128) -> Quantity {
+    Quantity(u64::try_from(total).unwrap_or(u64::MAX))
+}
+
+/// The core order book data structure that maintains bid and ask orders
+pub struct OrderBook {
+    /// Symbol/ticker this order book represents
+    symbol: String,
+    
+    /// Instrument precision and currency
+    instrument: Instrument,
+    
+    /// Price-sorted buy orders (highest price first)
+    /// BTreeMap<price, Vec<Order>>
+    bids: BTreeMap<Price, Vec<Order>>,
+    
+    /// Price-sorted sell orders (lowest price first)
+    /// BTreeMap<price, Vec<Order>>
+    asks: BTreeMap<Price, Vec<Order>>,
+    
+    /// Fast lookup of orders by ID
+    orders_by_id: HashMap<u64, Order>,
+    
+    /// Current statistics
+    stats: OrderBookStats,
+    
+    /// Matching engine
+    matcher: Matcher,
+    
+    /// Optional price protection applied to market orders
+    market_protection: Option<MarketOrderProtection>,
+    
+    /// Book events not yet taken, when event recording is enabled
+    events: Option<Vec<BookEvent>>,
+    
+    /// Best bid and offer as last reported in a BBO event
+    last_bbo: TopOfBook,
+    
+    /// Configuration of the analytics returned by `analytics` and the level
+    /// totals they are computed from, when enabled
+    analytics: Option<(AnalyticsConfig, LevelTotals)>,
+}
+
+impl OrderBook {
+    /// Creates a new, empty order book for the given symbol
+    pub fn new(symbol: &str) -> Self {
+        Self::with_instrument(Instrument::new(symbol))
+    }
+    
+    /// Creates a new, empty order book for the given instrument
+    pub fn with_instrument(instrument: Instrument) -> Self {
+        Self {
+            symbol: instrument.symbol.clone(),
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            orders_by_id: HashMap::new(),
+            stats: OrderBookStats::with_instrument(instrument.clone()),
+            matcher: Matcher::new(),
+            market_protection: None,
+            events: None,
+            last_bbo: (None, None),
+            analytics: None,
+            instrument,
+        }
+    }
+    
+    /// Returns the symbol this order book represents
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+    
+    /// Returns the instrument this order book trades
+    pub fn instrument(&self) -> &Instrument {
+        &self.instrument
+    }
+    
+    /// Returns the current statistics of the order book
+    pub fn stats(&self) -> &OrderBookStats {
+        &self.stats
+    }
+    
+    /// Starts a new trading session at `timestamp`, resetting the session statistics
+    pub fn start_session(&mut self, timestamp: u64) {
+        self.stats.start_session(timestamp);
+    }
+    
+    /// Applies a bust or correction of one of the book's trades to its statistics
+    pub fn apply_adjustment(&mut self, adjustment: &TradeAdjustment) {
+        self.stats.update_with_adjustment(adjustment);
+    }
+    
+    /// Returns the price protection applied to market orders, if any
+    pub fn market_protection(&self) -> Option<&MarketOrderProtection> {
+        self.market_protection.as_ref()
+    }
+    
+    /// Sets or clears the price protection applied to market orders
+    pub fn set_market_protection(&mut self, protection: Option<MarketOrderProtection>) {
+        self.market_protection = protection;
+    }
+    
+    /// Starts or stops recording book events for `take_events`
+    ///
+    /// Recording is off by default. Starting it reports the current best bid
+    /// and offer with the first change; stopping it discards pending events.
+    pub fn set_event_recording(&mut self, enabled: bool) {
+        if enabled && self.events.is_none() {
+            self.events = Some(Vec::new());
+            self.last_bbo = (None, None);
+        } else if !enabled {
+            self.events = None;
+        }
+    }
+    
+    /// Returns true if book events are being recorded
+    pub fn is_recording_events(&self) -> bool {
+        self.events.is_some()
+    }
+    
+    /// Removes and returns the book events recorded since the last call
+    pub fn take_events(&mut self) -> Vec<BookEvent> {
+        self.events.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+    
+    /// Returns events that rebuild the current book from empty: every resting
+    /// order in priority order, then the best bid and offer
+    pub fn snapshot_events(&self) -> Vec<BookEvent> {
+        let resting = self.bids.values().rev().chain(self.asks.values()).flatten();
+        let mut events: Vec<BookEvent> = resting
+            .map(|order| BookEvent::AddOrder {
+                order_id: order.id,
+                side: order.side,
+                price: order.price,
+                quantity: order.remaining_quantity,
+            })
+            .collect();
+        let (bid, ask) = self.top_of_book();
+        events.push(BookEvent::Bbo { bid, ask });
+        events
+    }
+    
+    /// Gets the best bid price
+    pub fn best_bid(&self) -> Option<Price> {
+        self.bids.keys().next_back().copied()
+    }
+    
+    /// Gets the best ask price
+    pub fn best_ask(&self) -> Option<Price> {
+        self.asks.keys().next().copied()
+    }
+    
+    /// Gets the current spread (difference between best ask and best bid)
+    pub fn spread(&self) -> Option<Price> {
+        match (self.best_ask(), self.best_bid()) {
+            (Some(ask), Some(bid)) => Some(ask.saturating_sub(bid)),
+            _ => None,
+        }
+    }
+    
+    /// Adds a new order to the book and attempts to match it
+    /// Returns a vector of executed trades
+    pub fn process_order(&mut self, mut order: Order) -> Vec<Trade> {
+        let order_id = order.id;
+        let order_side = order.side;
+        
+        // Ensure the order is for this symbol
+        if order.symbol != self.symbol {
+            warn!("Order symbol mismatch: {} != {}", order.symbol, self.symbol);
+            return Vec::new();
+        }
+        
+        // Order IDs must be unique within the book
+        if self.orders_by_id.contains_key(&order_id) {
+            warn!("Duplicate order ID {} rejected", order_id);
+            return Vec::new();
+        }
+        
+        // Only pending orders can be accepted
+        if let Err(e) = order.accept() {
+            warn!("Order {} rejected: {}", order_id, e);
+            return Vec::new();
+        }
+        
+        // Place the order in the book
+        self.orders_by_id.insert(order_id, order.clone());
+        
+        // Update the current timestamp
+        self.stats.last_update_time = order.timestamp;
+        
+        let mut trades = Vec::new();
+        
+        // Handle different order types
+        match order.order_type {
+            OrderType::Market => {
+                // Market orders are executed immediately
+                trades = self.match_market_order(order);
+            },
+            OrderType::Limit => {
+                // Limit orders may be matched immediately or placed in the book
+                trades = self.match_limit_order(order);
+            },
+            OrderType::IOC => {
+                // IOC orders are executed immediately and any unfilled portion is canceled
+                trades = self.match_immediate(order);
+                
+                // Cancel any remaining quantity
+                self.cancel_remaining(order_id);
+            },
+            OrderType::FOK => {
+                // FOK orders must be fully executed or entirely canceled
+                let potential_trades = self.matcher.simulate_order_match(
+                    &order,
+                    &self.bids,
+                    &self.asks,
+                );
+                
+                let total_matched = potential_trades.iter().map(|t| t.quantity).sum::<Quantity>();
+                
+                if total_matched == order.quantity {
+                    // Can be fully executed
+                    trades = self.match_limit_order(order);
+                } else {
+                    // Cancel the order
+                    self.cancel_remaining(order_id);
+                }
+            },
+            OrderType::Stop(stop_price) => {
+                // Stop orders become market orders when the stop price is reached
+                let trigger_condition = match order_side {
+                    OrderSide::Buy => self.best_ask().is_some_and(|ask| ask <= stop_price),
+                    OrderSide::Sell => self.best_bid().is_some_and(|bid| bid >= stop_price),
+                };
+                
+                if trigger_condition {
+                    // Convert to market order and execute
+                    let mut market_order = order.clone();
+                    market_order.order_type = OrderType::Market;
+                    if self.trigger_order(&mut market_order) {
+                        trades = self.match_market_order(market_order);
+                    }
+                } else {
+                    // Wait for stop price to be triggered
+                    // (in a real system, we'd have a trigger watching for price changes)
+                }
+            },
+            OrderType::StopLimit(stop_price, limit_price) => {
+                // Stop-limit orders become limit orders when the stop price is reached
+                let trigger_condition = match order_side {
+                    OrderSide::Buy => self.best_ask().is_some_and(|ask| ask <= stop_price),
+                    OrderSide::Sell => self.best_bid().is_some_and(|bid| bid >= stop_price),
+                };
+                
+                if trigger_condition {
+                    // Convert to limit order and execute
+                    let mut limit_order = order.clone();
+                    limit_order.order_type = OrderType::Limit;
+                    limit_order.price = limit_price;
+                    if self.trigger_order(&mut limit_order) {
+                        trades = self.match_limit_order(limit_order);
+                    }
+                } else {
+                    // Wait for stop price to be triggered
+                    // (in a real system, we'd have a trigger watching for price changes)
+                }
+            },
+        }
+        
+        // Update stats
+        self.update_stats();
+        
+        // Update statistics with trade information
+        for trade in &trades {
+            self.stats.update_with_trade(trade);
+        }
+        self.record_bbo();
+        
+        trades
+    }
+    
+    /// Cancels an order by ID
+    /// Returns true if the order was found and was still active
+    pub fn cancel_order(&mut self, order_id: u64) -> bool {
+        let canceled = self.cancel_remaining(order_id);
+        if canceled {
+            self.update_stats();
+            self.record_bbo();
+        }
+        canceled
+    }
+    
+    /// Expires an order by ID at the end of its time in force
+    /// Returns true if the order was found and was still active
+    pub fn expire_order(&mut self, order_id: u64) -> bool {
+        let expired = match self.orders_by_id.get_mut(&order_id) {
+            Some(order) => order.expire().is_ok(),
+            None => false,
+        };
+        
+        if expired {
+            self.remove_from_book(order_id);
+            self.update_stats();
+            self.record_bbo();
+        }
+        expired
+    }
+    
+    /// Amends the price and/or total quantity of a resting order
+    ///
+    /// Reducing the quantity at the same price keeps time priority. Any other
+    /// change re-queues the order at `timestamp` and may trade immediately.
+    /// Returns the trades caused by the amendment.
+    pub fn amend_order(
+        &mut self,
+        order_id: u64,
+        new_price: Option<Price>,
+        new_quantity: Option<Quantity>,
+        timestamp: u64,
+    ) -> Result<Vec<Trade>, OrderError> {
+        let order = self.resting_order(order_id)?;
+        
+        let price = new_price.unwrap_or(order.price);
+        let quantity = new_quantity.unwrap_or(order.quantity);
+        let filled = order.quantity - order.remaining_quantity;
+        if quantity <= filled {
+            return Err(OrderError::InvalidQuantity { order_id, quantity });
+        }
+        let remaining_quantity = quantity - filled;
+        
+        if price == order.price && remaining_quantity <= order.remaining_quantity {
+            // Update both copies in place so the order keeps its queue position
+            let side = order.side;
+            let canceled = order.remaining_quantity - remaining_quantity;
+            for resting_order in self.level_mut(side, price).into_iter().flatten() {
+                if resting_order.id == order_id {
+                    resting_order.quantity = quantity;
+                    resting_order.remaining_quantity = remaining_quantity;
+                }
+            }
+            if let Some(stored_order) = self.orders_by_id.get_mut(&order_id) {
+                stored_order.quantity = quantity;
+                stored_order.remaining_quantity = remaining_quantity;
+            }
+            if let Some((_, totals)) = self.analytics.as_mut() {
+                totals.remove(side, price, canceled);
+            }
+            if !canceled.is_zero() {
+                self.record(BookEvent::Cancel { order_id, quantity: canceled });
+            }
+            self.update_stats();
+            self.record_bbo();
+            return Ok(Vec::new());
+        }
+        
+        // Re-queue the order behind everything already at the new price
+        self.remove_from_book(order_id);
+        let amended = match self.orders_by_id.get_mut(&order_id) {
+            Some(stored_order) => {
+                stored_order.price = price;
+                stored_order.quantity = quantity;
+                stored_order.remaining_quantity = remaining_quantity;
+                stored_order.timestamp = timestamp;
+                stored_order.clone()
+            }
+            None => return Err(OrderError::UnknownOrder { order_id }),
+        };
+        
+        let trades = self.match_limit_order(amended);
+        self.stats.last_update_time = timestamp;
+        self.update_stats();
+        for trade in &trades {
+            self.stats.update_with_trade(trade);
+        }
+        self.record_bbo();
+        
+        Ok(trades)
+    }
+    
+    /// Places an order in the book without matching it
+    ///
+    /// For rebuilding books from an external feed whose venue has already
+    /// matched, so the order rests even if it crosses the book.
+    pub fn insert_order(&mut self, mut order: Order) -> Result<(), OrderError> {
+        if self.orders_by_id.contains_key(&order.id) {
+            return Err(OrderError::DuplicateOrder { order_id: order.id });
+        }
+        order.accept()?;
+        self.place_accepted_order(order);
+        Ok(())
+    }
+    
+    /// Fills a resting order against a counterparty outside the book
+    ///
+    /// Returns the trade, whose counterparty order and user IDs are zero. The
+    /// resting order is the maker.
+    pub fn execute_order(
+        &mut self,
+        order_id: u64,
+        quantity: Quantity,
+        price: Price,
+        timestamp: u64,
+    ) -> Result<Trade, OrderError> {
+        let mut order = self.resting_order(order_id)?.clone();
+        order.fill_partial(quantity)?;
+        if let Some((_, totals)) = self.analytics.as_mut() {
+            totals.remove(order.side, order.price, quantity);
+        }
+        
+        if order.remaining_quantity.is_zero() {
+            if let Some(orders) = self.level_mut(order.side, order.price) {
+                orders.retain(|o| o.id != order_id);
+                if orders.is_empty() {
+                    match order.side {
+                        OrderSide::Buy => self.bids.remove(&order.price),
+                        OrderSide::Sell => self.asks.remove(&order.price),
+                    };
+                }
+            }
+        } else {
+            for resting_order in self.level_mut(order.side, order.price).into_iter().flatten() {
+                if resting_order.id == order_id {
+                    resting_order.remaining_quantity = order.remaining_quantity;
+                    resting_order.status = order.status;
+                }
+            }
+        }
+        
+        let (buy, sell) = match order.side {
+            OrderSide::Buy => ((order.id, order.user_id), (0, 0)),
+            OrderSide::Sell => ((0, 0), (order.id, order.user_id)),
+        };
+        let trade = Trade::new(
+            self.matcher.next_trade_id(),
+            price,
+            quantity,
+            timestamp,
+            buy.0,
+            sell.0,
+            buy.1,
+            sell.1,
+            order.side.opposite(),
+            self.symbol.clone(),
+        );
+        self.orders_by_id.insert(order_id, order);
+        
+        self.record_executions(std::slice::from_ref(&trade), 0);
+        self.stats.last_update_time = timestamp;
+        self.update_stats();
+        self.stats.update_with_trade(&trade);
+        self.record_bbo();
+        Ok(trade)
+    }
+    
+    /// Replaces a resting order with a new order, which joins the back of its level
+    ///
+    /// Like `insert_order`, the new order is placed without matching.
+    pub fn replace_order(&mut self, order_id: u64, mut new_order: Order) -> Result<(), OrderError> {
+        self.resting_order(order_id)?;
+        if self.orders_by_id.contains_key(&new_order.id) {
+            return Err(OrderError::DuplicateOrder { order_id: new_order.id });
+        }
+        new_order.accept()?;
+        
+        if let Some(order) = self.orders_by_id.get_mut(&order_id) {
+            order.replace()?;
+        }
+        self.remove_from_book(order_id);
+        self.place_accepted_order(new_order);
+        Ok(())
+    }
+    
+    /// Rests an accepted order in the book without matching
+    fn place_accepted_order(&mut self, order: Order) {
+        self.stats.last_update_time = order.timestamp;
+        self.orders_by_id.insert(order.id, order.clone());
+        self.add_to_book(order);
+        self.update_stats();
+        self.record_bbo();
+    }
+    
+    /// Returns an active order that is resting in the book
+    fn resting_order(&self, order_id: u64) -> Result<&Order, OrderError> {
+        let order = self
+            .orders_by_id
+            .get(&order_id)
+            .ok_or(OrderError::UnknownOrder { order_id })?;
+        let resting = order.is_active()
+            && self.level(order.side, order.price).is_some_and(|orders| orders.iter().any(|o| o.id == order_id));
+        if !resting {
+            return Err(OrderError::NotAmendable { order_id });
+        }
+        Ok(order)
+    }
+    
+    /// Returns the orders resting at a price level
+    fn level(&self, side: OrderSide, price: Price) -> Option<&Vec<Order>> {
+        match side {
+            OrderSide::Buy => self.bids.get(&price),
+            OrderSide::Sell => self.asks.get(&price),
+        }
+    }
+    
+    /// Returns the orders resting at a price level for modification
+    fn level_mut(&mut self, side: OrderSide, price: Price) -> Option<&mut Vec<Order>> {
+        match side {
+            OrderSide::Buy => self.bids.get_mut(&price),
+            OrderSide::Sell => self.asks.get_mut(&price),
+        }
+    }
+    
+    /// Cancels any remaining quantity of an active order and takes it out of the book
+    fn cancel_remaining(&mut self, order_id: u64) -> bool {
+        let canceled = match self.orders_by_id.get_mut(&order_id) {
+            Some(order) => order.cancel().is_ok(),
+            None => false,
+        };
+        
+        if canceled {
+            self.remove_from_book(order_id);
+        }
+        canceled
+    }
+    
+    /// Marks a stop order as triggered, keeping the stored copy in sync
+    fn trigger_order(&mut self, order: &mut Order) -> bool {
+        if let Err(e) = order.trigger() {
+            warn!("Stop order {} could not be triggered: {}", order.id, e);
+            return false;
+        }
+        
+        if let Some(stored_order) = self.orders_by_id.get_mut(&order.id) {
+            stored_order.status = order.status;
+            stored_order.price = order.price;
+        }
+        true
+    }
+    
+    /// Removes an order from its price level
+    /// The order stays in orders_by_id so its final status can still be queried
+    fn remove_from_book(&mut self, order_id: u64) -> bool {
+        let Some(order) = self.orders_by_id.get(&order_id) else {
+            return false;
+        };
+        let price = order.price;
+        let level_map = match order.side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        };
+        
+        let Some(orders) = level_map.get_mut(&price) else {
+            return false;
+        };
+        // Find and remove the order
+        let Some(pos) = orders.iter().position(|o| o.id == order_id) else {
+            return false;
+        };
+        let removed = orders.remove(pos);
+        
+        // If the price level is now empty, remove it
+        if orders.is_empty() {
+            level_map.remove(&price);
+        }
+        
+        if let Some((_, totals)) = self.analytics.as_mut() {
+            totals.remove(removed.side, price, removed.remaining_quantity);
+        }
+        self.record(BookEvent::Delete { order_id });
+        true
+    }
+    
+    /// Matches a market order, applying the price protection collar if configured
+    fn match_market_order(&mut self, mut order: Order) -> Vec<Trade> {
+        let collar = self.market_protection.and_then(|protection| {
+            self.collar_reference_price(&protection, order.side)
+                .map(|reference| (protection, protection.collar_price(order.side, reference)))
+        });
+        
+        let order_id = order.id;
+        let Some((protection, collar_price)) = collar else {
+            let trades = self.matcher.match_market_order(
+                order,
+                &mut self.bids,
+                &mut self.asks,
+                &mut self.orders_by_id,
+            );
+            self.remove_fills(&trades);
+            self.record_executions(&trades, order_id);
+            
+            // Market orders never rest, so whatever is left is canceled
+            self.cancel_remaining(order_id);
+            return trades;
+        };
+        
+        // Match as a limit order at the collar so the order cannot sweep past it
+        order.price = collar_price;
+        if protection.action == CollarAction::ConvertToLimit {
+            order.order_type = OrderType::Limit;
+        }
+        if let Some(stored_order) = self.orders_by_id.get_mut(&order.id) {
+            stored_order.price = order.price;
+            stored_order.order_type = order.order_type;
+        }
+        
+        let trades = match protection.action {
+            // Any remainder rests in the book at the collar price
+            CollarAction::ConvertToLimit => self.match_limit_order(order),
+            CollarAction::Cancel => self.match_immediate(order),
+        };
+        
+        if let Some(remaining_order) = self.orders_by_id.get(&order_id) {
+            if remaining_order.is_active() {
+                warn!(
+                    "Market order {} reached its collar at {}. Remaining: {}",
+                    order_id, collar_price, remaining_order.remaining_quantity
+                );
+                
+                if protection.action == CollarAction::Cancel {
+                    self.cancel_remaining(order_id);
+                }
+            }
+        }
+        
+        trades
+    }
+    
+    /// Returns the price a market order collar is measured from
+    fn collar_reference_price(&self, protection: &MarketOrderProtection, side: OrderSide) -> Option<Price> {
+        let best_opposite = match side {
+            OrderSide::Buy => self.best_ask(),
+            OrderSide::Sell => self.best_bid(),
+        };
+        
+        match protection.reference {
+            CollarReference::BestPrice => best_opposite,
+            CollarReference::LastTrade => self.stats.last_trade_price.or(best_opposite),
+        }
+    }
+    
+    /// Matches a limit order without resting any remainder
+    fn match_immediate(&mut self, order: Order) -> Vec<Trade> {
+        let order_id = order.id;
+        let trades = self.matcher.match_limit_order(
+            order,
+            &mut self.bids,
+            &mut self.asks,
+            &mut self.orders_by_id,
+        );
+        self.remove_fills(&trades);
+        self.record_executions(&trades, order_id);
+        trades
+    }
+    
+    /// Takes the makers' fills out of the level totals; the matcher fills
+    /// each maker at the price of its level
+    fn remove_fills(&mut self, trades: &[Trade]) {
+        if let Some((_, totals)) = self.analytics.as_mut() {
+            for trade in trades {
+                totals.remove(trade.aggressor_side.opposite(), trade.price, trade.quantity);
+            }
+        }
+    }
+    
+    /// Matches a limit order (wrapper around the matcher method)
+    fn match_limit_order(&mut self, order: Order) -> Vec<Trade> {
+        let trades = self.match_immediate(order.clone());
+        
+        // If the order is not completely filled, add it to the book
+        if let Some(updated_order) = self.orders_by_id.get(&order.id) {
+            if !updated_order.remaining_quantity.is_zero() {
+                // We need to clone because we can't mutably borrow from orders_by_id
+                // while it's being used by add_to_book
+                let order_to_add = updated_order.clone();
+                self.add_to_book(order_to_add);
+            }
+        }
+        
+        trades
+    }
+    
+    /// Adds an order to the appropriate side of the book
+    fn add_to_book(&mut self, order: Order) {
+        // Select the right side of the book
+        let level_map = match order.side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        };
+        
+        // Get or create the price level
+        let orders = level_map.entry(order.price).or_default();
+        
+        let event = BookEvent::AddOrder {
+            order_id: order.id,
+            side: order.side,
+            price: order.price,
+            quantity: order.remaining_quantity,
+        };
+        
+        // Add the order to this price level
+        orders.push(order);
+        
+        // Orders at the same price level are sorted by timestamp (time priority)
+        orders.sort_by_key(|o| o.timestamp);
+        
+        if let (Some((_, totals)), BookEvent::AddOrder { side, price, quantity, .. }) = (self.analytics.as_mut(), &event) {
+            totals.add(*side, *price, *quantity);
+        }
+        self.record(event);
+    }
+    
+    /// Appends an event if recording is enabled
+    fn record(&mut self, event: BookEvent) {
+        if let Some(events) = self.events.as_mut() {
+            events.push(event);
+        }
+    }
+    
+    /// Records the resting side of each trade followed by the trade itself
+    fn record_executions(&mut self, trades: &[Trade], taker_order_id: u64) {
+        let Some(events) = self.events.as_mut() else {
+            return;
+        };
+        for trade in trades {
+            let maker_order_id = if trade.buy_order_id == taker_order_id {
+                trade.sell_order_id
+            } else {
+                trade.buy_order_id
+            };
+            events.push(BookEvent::Execute {
+                order_id: maker_order_id,
+                quantity: trade.quantity,
+                price: trade.price,
+                trade_id: trade.id,
+            });
+            events.push(BookEvent::Trade(trade.clone()));
+        }
+    }
+    
+    /// Records a BBO event if the best bid or offer changed since the last one
+    fn record_bbo(&mut self) {
+        if self.events.is_none() {
+            return;
+        }
+        let top = self.top_of_book();
+        if top != self.last_bbo {
+            self.last_bbo = top;
+            self.record(BookEvent::Bbo { bid: top.0, ask: top.1 });
+        }
+    }
+    
+    /// Returns the best bid and offer with the total quantity at each
+    fn top_of_book(&self) -> TopOfBook {
+        let level_total = |(&price, orders): (&Price, &Vec<Order>)| {
+            (price, orders.iter().map(|o| o.remaining_quantity).sum())
+        };
+        (
+            self.bids.iter().next_back().map(level_total),
+            self.asks.iter().next().map(level_total),
+        )
+    }
+    
+    /// Updates the order book statistics
+    fn update_stats(&mut self) {
+        self.stats.best_bid = self.best_bid();
+        self.stats.best_ask = self.best_ask();
+        
+        // Count orders
+        let bid_count = self.bids.values().map(|orders| orders.len()).sum();
+        let ask_count = self.asks.values().map(|orders| orders.len()).sum();
+        self.stats.update_order_counts(bid_count, ask_count);
+    }
+    
+    /// Returns the current market depth up to the specified number of levels
+    #[allow(clippy::type_complexity)]
+    pub fn market_depth(&self, levels: usize) -> (Vec<(Price, Quantity)>, Vec<(Price, Quantity)>) {
+        let mut bids = Vec::new();
+        let mut asks = Vec::new();
+        
+        // Collect bid levels (highest price first)
+        for (&price, orders) in self.bids.iter().rev().take(levels) {
+            let total_quantity: Quantity = orders.iter().map(|o| o.remaining_quantity).sum();
+            bids.push((price, total_quantity));
+        }
+        
+        // Collect ask levels (lowest price first)
+        for (&price, orders) in self.asks.iter().take(levels) {
+            let total_quantity: Quantity = orders.iter().map(|o| o.remaining_quantity).sum();
+            asks.push((price, total_quantity));
+        }
+        
+        (bids, asks)
+    }
+    
+    /// Returns the price and total quantity of each level of one side, best price first
+    fn side_levels(&self, side: OrderSide) -> Box<dyn Iterator<Item = (Price, Quantity)> + '_> {
+        let level_total = |(&price, orders): (&Price, &Vec<Order>)| {
+            (price, orders.iter().map(|o| o.remaining_quantity).sum())
+        };
+        match side {
+            OrderSide::Buy => Box::new(self.bids.iter().rev().map(level_total)),
+            OrderSide::Sell => Box::new(self.asks.iter().map(level_total)),
+        }
+    }
+    
+    /// Returns the top `levels` levels of each side, and at least the best level
+    #[allow(clippy::type_complexity)]
+    fn top_levels<'a>(
+        levels: impl Fn(OrderSide) -> Box<dyn Iterator<Item = (Price, Quantity)> + 'a>,
+        count: usize,
+    ) -> (Vec<(Price, Quantity)>, Vec<(Price, Quantity)>) {
+        (
+            levels(OrderSide::Buy).take(count.max(1)).collect(),
+            levels(OrderSide::Sell).take(count.max(1)).collect(),
+        )
+    }
+    
+    /// Returns the best bid and ask weighted by the quantity on the opposite side
+    ///
+    /// Unlike the midpoint, it leans toward the side with less quantity,
+    /// which is the one more likely to be traded through.
+    pub fn microprice(&self) -> Option<f64> {
+        let (bids, asks) = Self::top_levels(|side| self.side_levels(side), 1);
+        microprice(&bids, &asks)
+    }
+    
+    /// Returns the order imbalance over the top `levels` levels of each side,
+    /// from -1 when there are only asks to 1 when there are only bids
+    pub fn imbalance(&self, levels: usize) -> Option<f64> {
+        let (bids, asks) = Self::top_levels(|side| self.side_levels(side), levels);
+        imbalance(&bids[..levels.min(bids.len())], &asks[..levels.min(asks.len())])
+    }
+    
+    /// Returns the bid and ask quantity priced within `bps` basis points of the midpoint
+    pub fn depth_within_bps(&self, bps: u32) -> Option<(Quantity, Quantity)> {
+        let (lower, upper) = depth_bounds(self.best_bid()?, self.best_ask()?, bps);
+        let level_quantity = |(_, orders): (&Price, &Vec<Order>)| -> Quantity {
+            orders.iter().map(|o| o.remaining_quantity).sum()
+        };
+        Some((
+            self.bids.range(lower..).map(level_quantity).sum(),
+            self.asks.range(..=upper).map(level_quantity).sum(),
+        ))
+    }
+    
+    /// Returns the quantity-weighted average price of the top `levels` levels of one side,
+    /// rounded to the nearest unit
+    pub fn weighted_depth_price(&self, side: OrderSide, levels: usize) -> Option<Price> {
+        let side_levels: Vec<(Price, Quantity)> = self.side_levels(side).take(levels).collect();
+        weighted_price(&side_levels)
+    }
+    
+    /// Returns the slope of one side over its top `levels` levels: the quantity
+    /// resting per raw price unit between the best and the furthest level
+    ///
+    /// A steep book absorbs large orders with little price movement. Returns
+    /// None with fewer than two levels.
+    pub fn book_slope(&self, side: OrderSide, levels: usize) -> Option<f64> {
+        let side_levels: Vec<(Price, Quantity)> = self.side_levels(side).take(levels).collect();
+        slope(&side_levels)
+    }
+    
+    /// Computes every analytic over the levels and distance in `config`
+    pub fn compute_analytics(&self, config: AnalyticsConfig) -> BookAnalytics {
+        let (bids, asks) = Self::top_levels(|side| self.side_levels(side), config.levels);
+        let depth = self.depth_within_bps(config.depth_bps).unwrap_or_default();
+        analytics_from_levels(&bids, &asks, config.levels, depth)
+    }
+    
+    /// Enables analytics with `config`, or disables them with `None`
+    ///
+    /// While enabled, the book keeps the total quantity of each price level
+    /// up to date as orders rest, fill, shrink and leave, so `analytics` reads
+    /// only the levels it needs instead of every resting order.
+    pub fn set_analytics(&mut self, config: Option<AnalyticsConfig>) {
+        self.analytics = config.map(|config| {
+            let mut totals = LevelTotals::default();
+            for (side, levels) in [(OrderSide::Buy, &self.bids), (OrderSide::Sell, &self.asks)] {
+                for order in levels.values().flatten() {
+                    totals.add(side, order.price, order.remaining_quantity);
+                }
+            }
+            (config, totals)
+        });
+    }
+    
+    /// Returns the current analytics from the level totals, if enabled with `set_analytics`
+    pub fn analytics(&self) -> Option<BookAnalytics> {
+        let (config, totals) = self.analytics.as_ref()?;
+        let (bids, asks) = Self::top_levels(|side| totals.levels(side), config.levels);
+        let depth = match (bids.first(), asks.first()) {
+            (Some(&(bid, _)), Some(&(ask, _))) => totals.depth(bid, ask, config.depth_bps),
+            _ => (Quantity::ZERO, Quantity::ZERO),
+        };
+        Some(analytics_from_levels(&bids, &asks, config.levels, depth))
+    }
+    
+    /// Returns all orders in the book
+    pub fn all_orders(&self) -> Vec<&Order> {
+        self.orders_by_id.values().collect()
+    }
+    
+    /// Returns an order by ID
+    pub fn get_order(&self, order_id: u64) -> Option<&Order> {
+        self.orders_by_id.get(&order_id)
+    }
+    
+    /// Calculate the theoretical slippage for a market order of the given size
+    /// Returns the average execution price (rounded to the nearest unit) and the
+    /// slippage from the best price in percent, or None if liquidity is insufficient
+    pub fn calculate_slippage(&self, side: OrderSide, quantity: Quantity) -> Option<(Price, f64)> {
+        let estimate = self.estimate_execution(side, quantity);
+        
+        if estimate.filled_quantity.is_zero() || !estimate.is_fully_filled() {
+            return None;
+        }
+        
+        let filled = estimate.filled_quantity.0 as u128;
+        let avg_price = (estimate.notional + filled / 2) / filled;
+        
+        Some((Price(avg_price as u64), estimate.slippage_percent().unwrap_or(0.0)))
+    }
+    
+    /// Estimates how a market order of the given size would execute against the book
+    pub fn estimate_execution(&self, side: OrderSide, quantity: Quantity) -> ExecutionEstimate {
+        self.build_execution_estimate(side, quantity, None)
+    }
+    
+    /// Estimates how a limit order would execute immediately against the book,
+    /// consuming only levels at or better than the limit price
+    pub fn estimate_execution_with_limit(
+        &self,
+        side: OrderSide,
+        quantity: Quantity,
+        limit_price: Price,
+    ) -> ExecutionEstimate {
+        self.build_execution_estimate(side, quantity, Some(limit_price))
+    }
+    
+    /// Returns the price and quantity of each fill an incoming order would get from
+    /// the resting orders it crosses, in matching order
+    ///
+    /// Each resting order fills separately, so this is finer than an execution
+    /// estimate, which adds up whole levels. Without a limit price the whole
+    /// opposite side is walked.
+    pub fn maker_fills(
+        &self,
+        side: OrderSide,
+        quantity: Quantity,
+        limit_price: Option<Price>,
+    ) -> Vec<(Price, Quantity)> {
+        let price_time_iter: Box<dyn Iterator<Item = (&Price, &Vec<Order>)>> = match side {
+            OrderSide::Buy => Box::new(self.asks.iter()),
+            OrderSide::Sell => Box::new(self.bids.iter().rev()),
+        };
+        
+        let mut fills = Vec::new();
+        let mut remaining = quantity;
+        for (&price, orders) in price_time_iter {
+            let crosses = limit_price.is_none_or(|limit| match side {
+                OrderSide::Buy => price <= limit,
+                OrderSide::Sell => price >= limit,
+            });
+            if !crosses {
+                break;
+            }
+            for order in orders {
+                if remaining.is_zero() {
+                    return fills;
+                }
+                let fill = remaining.min(order.remaining_quantity);
+                fills.push((price, fill));
+                remaining = remaining.saturating_sub(fill);
+            }
+        }
+        fills
+    }
+    
+    /// Walks the opposite side of the book level by level to build an execution ladder
+    fn build_execution_estimate(
+        &self,
+        side: OrderSide,
+        quantity: Quantity,
+        limit_price: Option<Price>,
+    ) -> ExecutionEstimate {
+        // For buy orders: start from lowest ask
+        // For sell orders: start from highest bid
+        let price_time_iter: Box<dyn Iterator<Item = (&Price, &Vec<Order>)>> = match side {
+            OrderSide::Buy => Box::new(self.asks.iter()),
+            OrderSide::Sell => Box::new(self.bids.iter().rev()),
+        };
+        
+        let mut levels = Vec::new();
+        let mut remaining = quantity;
+        let mut cumulative_quantity = Quantity::ZERO;
+        let mut cumulative_notional = 0u128;
+        
+        for (&price, orders) in price_time_iter {
+            if remaining.is_zero() {
+                break;
+            }
+            
+            let within_limit = match (side, limit_price) {
+                (_, None) => true,
+                (OrderSide::Buy, Some(limit)) => price <= limit,
+                (OrderSide::Sell, Some(limit)) => price >= limit,
+            };
+            if !within_limit {
+                break;
+            }
+            
+            let available: Quantity = orders.iter().map(|o| o.remaining_quantity).sum();
+            let fill_qty = std::cmp::min(remaining, available);
+            if fill_qty.is_zero() {
+                continue;
+            }
+            
+            remaining -= fill_qty;
+            cumulative_quantity += fill_qty;
+            // Total filled quantity is bounded by a u64, so the notional sum fits in a u128
+            cumulative_notional += price.notional(fill_qty);
+            
+            levels.push(LadderLevel {
+                price,
+                quantity: fill_qty,
+                available,
+                cumulative_quantity,
+                cumulative_notional,
+            });
+        }
+        
+        ExecutionEstimate {
+            side,
+            requested_quantity: quantity,
+            filled_quantity: cumulative_quantity,
+            unfilled_quantity: remaining,
+            notional: cumulative_notional,
+            limit_price,
+            best_price: match side {
+                OrderSide::Buy => self.best_ask(),
+                OrderSide::Sell => self.best_bid(),
+            },
+            midpoint: match (self.best_bid(), self.best_ask()) {
+                (Some(bid), Some(ask)) => Some((bid.as_f64() + ask.as_f64()) / 2.0),
+                _ => None,
+            },
+            levels,
+        }
+    }
+    
+    /// Prints a formatted representation of the order book
+    pub fn print_book(&self, depth: usize) {
+        println!("\nOrder Book for {}", self.symbol);
+        println!("{:-^40}", "");
+        
+        let (bids, asks) = self.market_depth(depth);
+        
+        // Print asks (sell orders) in reverse order (highest to lowest)
+        for (i, (price, quantity)) in asks.iter().rev().enumerate() {
+            println!("{:2} | {:>11} | {:>10} |", 
+                     asks.len() - i,
+                     self.instrument.format_price(*price), 
+                     self.instrument.format_quantity(*quantity));
+        }
+        
+        // Print the spread
+        if let Some(spread) = self.spread() {
+            println!("{:-^40}", format!(" Spread: {} ", self.instrument.format_price(spread)));
+        } else {
+            println!("{:-^40}", " No Spread ");
+        }
+        
+        // Print bids (buy orders)
+        for (i, (price, quantity)) in bids.iter().enumerate() {
+            println!("{:2} | {:>11} | {:>10} |", 
+                     i + 1,
+                     self.instrument.format_price(*price), 
+                     self.instrument.format_quantity(*quantity));
+        }
+        
+        // Print statistics
+        println!("\nStatistics:");
+        println!("Best Bid: {}", self.stats.formatted_best_bid());
+        println!("Best Ask: {}", self.stats.formatted_best_ask());
+        println!("Spread: {}", self.stats.formatted_spread());
+        println!("Bid Orders: {}", self.stats.bid_order_count);
+        println!("Ask Orders: {}", self.stats.ask_order_count);
+        println!("Trade Count: {}", self.stats.trade_count);
+    }
+}
+
+/// Returns the best bid and ask weighted by the quantity on the opposite side,
+/// from levels ordered best price first
+fn microprice(bids: &[(Price, Quantity)], asks: &[(Price, Quantity)]) -> Option<f64> {
+    let (&(bid, bid_quantity), &(ask, ask_quantity)) = (bids.first()?, asks.first()?);
+    let (bid_quantity, ask_quantity) = (bid_quantity.0 as f64, ask_quantity.0 as f64);
+    let total = bid_quantity + ask_quantity;
+    (total > 0.0).then(|| (bid.as_f64() * ask_quantity + ask.as_f64() * bid_quantity) / total)
+}
+
+/// Returns the imbalance between the quantity of the given bid and ask levels
+fn imbalance(bids: &[(Price, Quantity)], asks: &[(Price, Quantity)]) -> Option<f64> {
+    let side_total = |levels: &[(Price, Quantity)]| levels.iter().map(|(_, quantity)| quantity.0 as f64).sum::<f64>();
+    let (bid, ask) = (side_total(bids), side_total(asks));
+    let total = bid + ask;
+    (total > 0.0).then(|| (bid - ask) / total)
+}
+
+/// Returns the lowest bid and highest ask price within `bps` basis points of the midpoint
+fn depth_bounds(bid: Price, ask: Price, bps: u32) -> (Price, Price) {
+    // Bounds are computed from twice the midpoint to stay in integers
+    let double_mid = bid.0 as u128 + ask.0 as u128;
+    let lower = (double_mid * 10_000u128.saturating_sub(bps as u128)).div_ceil(20_000);
+    let upper = double_mid * (10_000 + bps as u128) / 20_000;
+    (Price(lower as u64), Price(u64::try_from(upper).unwrap_or(u64::MAX)))
+}
+
+/// Returns the quantity-weighted average price of the given levels, rounded to the nearest unit
+fn weighted_price(levels: &[(Price, Quantity)]) -> Option<Price> {
+    let (quantity, notional) = levels
+        .iter()
+        .fold((0u128, 0u128), |(quantity, notional), &(price, level_quantity)| {
+            (quantity + level_quantity.0 as u128, notional.saturating_add(price.notional(level_quantity)))
+        });
+    (quantity > 0).then(|| Price((notional.saturating_add(quantity / 2) / quantity) as u64))
+}
+
+/// Returns the quantity per raw price unit between the first and last of the given levels
+fn slope(levels: &[(Price, Quantity)]) -> Option<f64> {
+    let (&(best, _), &(furthest, _)) = (levels.first()?, levels.last()?);
+    let quantity: f64 = levels.iter().map(|(_, quantity)| quantity.0 as f64).sum();
+    let distance = best.0.abs_diff(furthest.0);
+    (distance > 0).then(|| quantity / distance as f64)
+}
+
+/// Computes every analytic from the top levels of each side, best price first,
+/// and the depth within the configured distance of the midpoint
+fn analytics_from_levels(
+    bids: &[(Price, Quantity)],
+    asks: &[(Price, Quantity)],
+    levels: usize,
+    (bid_depth, ask_depth): (Quantity, Quantity),
+) -> BookAnalytics {
+    let (top_bids, top_asks) = (&bids[..levels.min(bids.len())], &asks[..levels.min(asks.len())]);
+    BookAnalytics {
+        microprice: microprice(bids, asks),
+        imbalance: imbalance(top_bids, top_asks),
+        bid_depth,
+        ask_depth,
+        bid_weighted_price: weighted_price(top_bids),
+        ask_weighted_price: weighted_price(top_asks),
+        bid_slope: slope(top_bids),
+        ask_slope: slope(top_asks),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::order::OrderStatus;
+    
+    #[test]
+    fn test_order_book_creation() {
+        let book = OrderBook::new("BTC-USD");
+        assert_eq!(book.symbol(), "BTC-USD");
+        assert!(book.best_bid().is_none());
+        assert!(book.best_ask().is_none());
+    }
+    
+    #[test]
+    fn test_limit_order_placement() {
+        let mut book = OrderBook::new("BTC-USD");
+        
+        // Add a buy limit order
+        let buy_order = Order::new_limit(
+            1, 100, 10, OrderSide::Buy, 1001, 100, None, "BTC-USD".to_string()
+        );
+        let trades = book.process_order(buy_order);
+        
+        assert!(trades.is_empty()); // No trades executed yet
+        assert_eq!(book.best_bid(), Some(Price(100))); // Best bid is now 100
+        
+        // Add a sell limit order above the bid
+        let sell_order = Order::new_limit(
+            2, 110, 5, OrderSide::Sell, 1002, 200, None, "BTC-USD".to_string()
+        );
+        let trades = book.process_order(sell_order);
+        
+        assert!(trades.is_empty()); // No trades (prices don't cross)
+        assert_eq!(book.best_ask(), Some(Price(110))); // Best ask is now 110
+        
+        // Add a sell limit order that crosses with the buy
+        let matching_sell = Order::new_limit(
+            3, 90, 5, OrderSide::Sell, 1003, 300, None, "BTC-USD".to_string()
+        );
+        let trades = book.process_order(matching_sell);
+        
+        assert_eq!(trades.len(), 1); // One trade executed
+        assert_eq!(trades[0].price, Price(100)); // Execute at the resting price (100)
+        assert_eq!(trades[0].quantity, Quantity(5)); // Trade for 5 units
+    }
+    
+    #[test]
+    fn test_filled_level_is_removed() {
+        let mut book = OrderBook::new("BTC-USD");
+        
+        book.process_order(Order::new_limit(
+            1, 100, 5, OrderSide::Buy, 1001, 100, None, "BTC-USD".to_string()
+        ));
+        book.process_order(Order::new_market(
+            2, 5, OrderSide::Sell, 1002, 200, None, "BTC-USD".to_string()
+        ));
+        
+        // The emptied level must not linger as the best bid
+        assert!(book.best_bid().is_none());
+        assert!(book.market_depth(5).0.is_empty());
+    }
+    
+    fn book_with_asks() -> OrderBook {
+        let mut book = OrderBook::new("BTC-USD");
+        let asks = [(1, 10000, 2), (2, 10000, 1), (3, 10100, 2), (4, 10300, 5)];
+        for (id, price, quantity) in asks {
+            book.process_order(Order::new_limit(
+                id, price, quantity, OrderSide::Sell, 2001, id * 100, None, "BTC-USD".to_string()
+            ));
+        }
+        book.process_order(Order::new_limit(
+            5, 9900, 4, OrderSide::Buy, 1001, 500, None, "BTC-USD".to_string()
+        ));
+        book
+    }
+    
+    #[test]
+    fn test_microprice() {
+        let book = book_with_asks();
+        assert!((book.microprice().unwrap() - 69_700.0 / 7.0).abs() < 1e-9);
+        assert!(OrderBook::new("BTC-USD").microprice().is_none());
+    }
+    
+    #[test]
+    fn test_imbalance() {
+        let book = book_with_asks();
+        assert!((book.imbalance(1).unwrap() - 1.0 / 7.0).abs() < 1e-9);
+        assert!((book.imbalance(3).unwrap() + 6.0 / 14.0).abs() < 1e-9);
+        assert!(OrderBook::new("BTC-USD").imbalance(5).is_none());
+    }
+    
+    #[test]
+    fn test_depth_within_bps() {
+        let book = book_with_asks();
+        assert_eq!(book.depth_within_bps(100), Some((Quantity(4), Quantity(3))));
+        assert_eq!(book.depth_within_bps(400), Some((Quantity(4), Quantity(10))));
+        assert!(OrderBook::new("BTC-USD").depth_within_bps(10).is_none());
+    }
+    
+    #[test]
+    fn test_weighted_depth_price() {
+        let book = book_with_asks();
+        assert_eq!(book.weighted_depth_price(OrderSide::Sell, 3), Some(Price(10170)));
+        assert_eq!(book.weighted_depth_price(OrderSide::Buy, 3), Some(Price(9900)));
+        assert!(OrderBook::new("BTC-USD").weighted_depth_price(OrderSide::Buy, 3).is_none());
+    }
+    
+    #[test]
+    fn test_book_slope() {
+        let book = book_with_asks();
+        assert_eq!(book.book_slope(OrderSide::Sell, 2), Some(0.05));
+        // A single level has no slope
+        assert!(book.book_slope(OrderSide::Buy, 3).is_none());
+    }
+    
+    #[test]
+    fn test_book_analytics() {
+        let mut book = book_with_asks();
+        
+        // Analytics follow every change to the book from the level totals
+        let config = AnalyticsConfig::new(3, 100);
+        assert!(book.analytics().is_none());
+        book.set_analytics(Some(config));
+        assert_eq!(book.analytics().unwrap().ask_weighted_price, Some(Price(10170)));
+        book.cancel_order(4);
+        let analytics = book.analytics().unwrap();
+        assert_eq!(analytics, book.compute_analytics(config));
+        assert_eq!((analytics.ask_slope, analytics.bid_depth, analytics.ask_depth), (Some(0.05), Quantity(4), Quantity(3)));
+        
+        // Fills, amendments, outside executions, inserts, replacements and expiries
+        book.process_order(Order::new_limit(6, 10000, 2, OrderSide::Buy, 1002, 600, None, "BTC-USD".to_string()));
+        assert_eq!(book.analytics().unwrap(), book.compute_analytics(config));
+        book.amend_order(5, None, Some(Quantity(3)), 700).unwrap();
+        book.amend_order(3, Some(Price(10050)), None, 700).unwrap();
+        assert_eq!(book.analytics().unwrap(), book.compute_analytics(config));
+        book.execute_order(2, Quantity(1), Price(10010), 800).unwrap();
+        book.insert_order(Order::new_limit(7, 9950, 6, OrderSide::Buy, 1003, 900, None, "BTC-USD".to_string())).unwrap();
+        book.replace_order(7, Order::new_limit(8, 9960, 1, OrderSide::Buy, 1003, 900, None, "BTC-USD".to_string())).unwrap();
+        book.process_order(Order::new_market(9, 2, OrderSide::Sell, 1004, 1000, None, "BTC-USD".to_string()));
+        book.expire_order(3);
+        let analytics = book.analytics().unwrap();
+        assert_eq!(analytics, book.compute_analytics(config));
+        assert_eq!((analytics.bid_weighted_price, analytics.ask_weighted_price, analytics.microprice), (Some(Price(9900)), None, None));
+        book.set_analytics(None);
+        assert!(book.analytics().is_none());
+        
+        let empty = OrderBook::new("BTC-USD");
+        assert_eq!(empty.compute_analytics(AnalyticsConfig::default()), BookAnalytics::default());
+    }
+    
+    #[test]
+    fn test_execution_ladder() {
+        let book = book_with_asks();
+        let estimate = book.estimate_execution(OrderSide::Buy, Quantity(6));
+        
+        assert!(estimate.is_fully_filled());
+        assert_eq!(estimate.levels_consumed(), 3);
+        assert_eq!(estimate.levels[0].quantity, Quantity(3));
+        assert_eq!(estimate.levels[0].available, Quantity(3));
+        assert_eq!(estimate.levels[2].quantity, Quantity(1));
+        assert_eq!(estimate.levels[2].available, Quantity(5));
+        assert_eq!(estimate.worst_price(), Some(Price(10300)));
+        
+        // 3 @ 10000 + 2 @ 10100 + 1 @ 10300 = 60500
+        assert_eq!(estimate.notional, 60500);
+        assert_eq!(estimate.levels[2].cumulative_notional, 60500);
+        assert!((estimate.average_price().unwrap() - 60500.0 / 6.0).abs() < 1e-9);
+        assert_eq!(estimate.midpoint, Some(9950.0));
+    }
+    
+    #[test]
+    fn test_execution_partial_liquidity() {
+        let book = book_with_asks();
+        let estimate = book.estimate_execution(OrderSide::Buy, Quantity(15));
+        
+        assert!(!estimate.is_fully_filled());
+        assert_eq!(estimate.filled_quantity, Quantity(10));
+        assert_eq!(estimate.unfilled_quantity, Quantity(5));
+        
+        // calculate_slippage still requires full liquidity
+        assert!(book.calculate_slippage(OrderSide::Buy, Quantity(15)).is_none());
+    }
+    
+    #[test]
+    fn test_execution_with_limit_price() {
+        let book = book_with_asks();
+        let estimate = book.estimate_execution_with_limit(OrderSide::Buy, Quantity(8), Price(10100));
+        
+        assert_eq!(estimate.filled_quantity, Quantity(5));
+        assert_eq!(estimate.unfilled_quantity, Quantity(3));
+        assert_eq!(estimate.worst_price(), Some(Price(10100)));
+        assert_eq!(estimate.limit_price, Some(Price(10100)));
+        
+        let sell = book.estimate_execution_with_limit(OrderSide::Sell, Quantity(2), Price(9950));
+        assert_eq!(sell.filled_quantity, Quantity(0));
+        assert_eq!(sell.unfilled_quantity, Quantity(2));
+    }
+    
+    #[test]
+    fn test_maker_fills() {
+        let book = book_with_asks();
+        
+        // Two resting orders at 10000 fill separately
+        assert_eq!(
+            book.maker_fills(OrderSide::Buy, Quantity(4), Some(Price(10100))),
+            vec![(Price(10000), Quantity(2)), (Price(10000), Quantity(1)), (Price(10100), Quantity(1))]
+        );
+        assert_eq!(book.maker_fills(OrderSide::Buy, Quantity(20), None).len(), 4);
+        assert!(book.maker_fills(OrderSide::Sell, Quantity(2), Some(Price(9950))).is_empty());
+    }
+    
+    #[test]
+    fn test_slippage_average_price_is_rounded() {
+        let book = book_with_asks();
+        
+        // 3 @ 10000 + 1 @ 10100 = 10025 exactly
+        let (avg_price, slippage) = book.calculate_slippage(OrderSide::Buy, Quantity(4)).unwrap();
+        assert_eq!(avg_price, Price(10025));
+        assert!((slippage - 0.25).abs() < 1e-9);
+        
+        // 3 @ 10000 + 2 @ 10100 + 1 @ 10300 = 10083.33, previously truncated
+        let (avg_price, _) = book.calculate_slippage(OrderSide::Buy, Quantity(6)).unwrap();
+        assert_eq!(avg_price, Price(10083));
+        
+        // 3 @ 10000 + 2 @ 10100 + 3 @ 10300 = 10137.5 rounds up
+        let (avg_price, _) = book.calculate_slippage(OrderSide::Buy, Quantity(8)).unwrap();
+        assert_eq!(avg_price, Price(10138));
+    }
+    
+    fn market_buy(id: u64, quantity: u64) -> Order {
+        Order::new_market(id, quantity, OrderSide::Buy, 1002, id * 100, None, "BTC-USD".to_string())
+    }
+    
+    #[test]
+    fn test_market_collar_cancels_remainder() {
+        let mut book = book_with_asks();
+        // 1% above the best ask of 10000
+        book.set_market_protection(Some(MarketOrderProtection::with_max_basis_points(100)));
+        
+        let trades = book.process_order(market_buy(10, 10));
+        let filled: Quantity = trades.iter().map(|t| t.quantity).sum();
+        assert_eq!(filled, Quantity(5));
+        assert!(trades.iter().all(|t| t.price <= Price(10100)));
+        
+        // The remainder is canceled and the level beyond the collar is untouched
+        let order = book.get_order(10).unwrap();
+        assert_eq!(order.status, OrderStatus::Canceled);
+        assert_eq!(order.remaining_quantity, Quantity(5));
+        assert_eq!(book.best_ask(), Some(Price(10300)));
+        assert_eq!(book.best_bid(), Some(Price(9900)));
+    }
+    
+    #[test]
+    fn test_market_collar_converts_to_limit() {
+        let mut book = book_with_asks();
+        book.set_market_protection(Some(
+            MarketOrderProtection::with_max_ticks(1, Price(100)).with_action(CollarAction::ConvertToLimit),
+        ));
+        
+        let trades = book.process_order(market_buy(10, 10));
+        assert_eq!(trades.len(), 3);
+        
+        // The remaining 5 rest as a limit bid at the collar
+        let order = book.get_order(10).unwrap();
+        assert_eq!(order.order_type, OrderType::Limit);
+        assert_eq!(order.price, Price(10100));
+        assert_eq!(order.remaining_quantity, Quantity(5));
+        assert_eq!(book.best_bid(), Some(Price(10100)));
+        assert_eq!(book.best_ask(), Some(Price(10300)));
+    }
+    
+    #[test]
+    fn test_market_collar_from_last_trade() {
+        let mut book = book_with_asks();
+        book.process_order(market_buy(10, 1));
+        assert_eq!(book.stats().last_trade_price, Some(Price(10000)));
+        
+        book.set_market_protection(Some(
+            MarketOrderProtection::with_max_ticks(0, Price(1)).with_reference(CollarReference::LastTrade),
+        ));
+        let trades = book.process_order(market_buy(11, 5));
+        
+        // Only the liquidity at the last trade price is taken
+        assert_eq!(trades.iter().map(|t| t.quantity).sum::<Quantity>(), Quantity(2));
+        assert_eq!(book.best_ask(), Some(Price(10100)));
+        
+        // Without protection the order sweeps through the collar
+        book.set_market_protection(None);
+        let trades = book.process_order(market_buy(12, 5));
+        assert_eq!(trades.last().unwrap().price, Price(10300));
+    }
+    
+    fn order_of_type(id: u64, order_type: OrderType, side: OrderSide, price: u64, quantity: u64) -> Order {
+        let mut order = match order_type {
+            OrderType::Market | OrderType::Stop(_) => {
+                Order::new_market(id, quantity, side, 1003, id * 100, None, "BTC-USD".to_string())
+            }
+            _ => Order::new_limit(id, price, quantity, side, 1003, id * 100, None, "BTC-USD".to_string()),
+        };
+        order.order_type = order_type;
+        order
+    }
+    
+    #[test]
+    fn test_order_lifecycle_by_type() {
+        let mut book = book_with_asks();
+        let status = |book: &OrderBook, id: u64| book.get_order(id).unwrap().status;
+        
+        // Resting limit orders are accepted, then fill
+        assert_eq!(status(&book, 1), OrderStatus::New);
+        book.process_order(order_of_type(10, OrderType::Limit, OrderSide::Buy, 10000, 1));
+        assert_eq!(status(&book, 1), OrderStatus::PartiallyFilled);
+        assert_eq!(status(&book, 10), OrderStatus::Filled);
+        
+        // IOC remainder is canceled and never rests
+        book.process_order(order_of_type(11, OrderType::IOC, OrderSide::Buy, 10000, 5));
+        assert_eq!(status(&book, 1), OrderStatus::Filled);
+        assert_eq!(status(&book, 11), OrderStatus::Canceled);
+        assert_eq!(book.get_order(11).unwrap().remaining_quantity, Quantity(3));
+        assert_eq!(book.best_bid(), Some(Price(9900)));
+        
+        // FOK is either filled entirely or canceled untouched
+        assert!(book.process_order(order_of_type(12, OrderType::FOK, OrderSide::Buy, 10100, 3)).is_empty());
+        assert_eq!(status(&book, 12), OrderStatus::Canceled);
+        assert_eq!(book.process_order(order_of_type(13, OrderType::FOK, OrderSide::Buy, 10100, 1)).len(), 1);
+        assert_eq!(status(&book, 13), OrderStatus::Filled);
+        
+        // Stop orders wait as New until triggered, then trade
+        book.process_order(order_of_type(14, OrderType::Stop(Price(9000)), OrderSide::Buy, 0, 1));
+        assert_eq!(status(&book, 14), OrderStatus::New);
+        book.process_order(order_of_type(15, OrderType::Stop(Price(10100)), OrderSide::Buy, 0, 1));
+        assert_eq!(status(&book, 15), OrderStatus::Filled);
+        
+        // A triggered stop-limit that cannot fill rests as Triggered
+        book.process_order(order_of_type(16, OrderType::StopLimit(Price(10300), Price(10200)), OrderSide::Buy, 10200, 2));
+        assert_eq!(status(&book, 16), OrderStatus::Triggered);
+        assert_eq!(book.best_bid(), Some(Price(10200)));
+        
+        // Market orders that run out of liquidity are canceled
+        book.process_order(order_of_type(17, OrderType::Market, OrderSide::Sell, 0, 20));
+        assert_eq!(status(&book, 16), OrderStatus::Filled);
+        assert_eq!(status(&book, 17), OrderStatus::Canceled);
+        assert_eq!(book.get_order(17).unwrap().remaining_quantity, Quantity(14));
+        assert!(book.best_bid().is_none());
+    }
+    
+    #[test]
+    fn test_cancel_and_expire_terminal_orders() {
+        let mut book = book_with_asks();
+        
+        assert!(book.expire_order(3));
+        assert_eq!(book.get_order(3).unwrap().status, OrderStatus::Expired);
+        assert!(!book.cancel_order(3));
+        
+        // Filled orders can no longer be canceled
+        book.process_order(order_of_type(10, OrderType::Limit, OrderSide::Buy, 10000, 2));
+        assert!(!book.cancel_order(1));
+        assert!(book.cancel_order(2));
+        assert!(!book.cancel_order(2));
+        assert!(!book.cancel_order(99));
+        assert_eq!(book.best_ask(), Some(Price(10300)));
+        
+        // Reusing an order ID is rejected
+        assert!(book.process_order(order_of_type(1, OrderType::Limit, OrderSide::Buy, 10300, 1)).is_empty());
+        assert_eq!(book.best_ask(), Some(Price(10300)));
+    }
+    
+    #[test]
+    fn test_amend_order() {
+        let mut book = book_with_asks();
+        
+        // Reducing size keeps priority at the level
+        assert_eq!(book.amend_order(1, None, Some(Quantity(1)), 900), Ok(Vec::new()));
+        let trades = book.process_order(order_of_type(10, OrderType::Limit, OrderSide::Buy, 10000, 1));
+        assert_eq!(trades[0].sell_order_id, 1);
+        assert_eq!(book.get_order(1).unwrap().status, OrderStatus::Filled);
+        
+        // Increasing size loses priority
+        book.process_order(order_of_type(11, OrderType::Limit, OrderSide::Sell, 10000, 1));
+        book.amend_order(2, None, Some(Quantity(3)), 1100).unwrap();
+        let trades = book.process_order(order_of_type(12, OrderType::Limit, OrderSide::Buy, 10000, 1));
+        assert_eq!(trades[0].sell_order_id, 11);
+        
+        // Repricing through the book trades immediately
+        let trades = book.amend_order(5, Some(Price(10000)), Some(Quantity(5)), 1300).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].quantity, Quantity(3));
+        assert_eq!(book.best_bid(), Some(Price(10000)));
+        assert_eq!(book.get_order(5).unwrap().remaining_quantity, Quantity(2));
+        assert_eq!(book.stats().last_trade_price, Some(Price(10000)));
+    }
+    
+    #[test]
+    fn test_amend_order_errors() {
+        let mut book = book_with_asks();
+        book.process_order(order_of_type(10, OrderType::Limit, OrderSide::Buy, 10000, 1));
+        
+        assert_eq!(
+            book.amend_order(99, None, Some(Quantity(1)), 0),
+            Err(OrderError::UnknownOrder { order_id: 99 })
+        );
+        assert_eq!(
+            book.amend_order(10, None, Some(Quantity(2)), 0),
+            Err(OrderError::NotAmendable { order_id: 10 })
+        );
+        // Order 1 has 1 of 2 filled
+        assert_eq!(
+            book.amend_order(1, None, Some(Quantity(1)), 0),
+            Err(OrderError::InvalidQuantity { order_id: 1, quantity: Quantity(1) })
+        );
+        assert!(book.amend_order(1, None, Some(Quantity(4)), 0).is_ok());
+        assert_eq!(book.get_order(1).unwrap().remaining_quantity, Quantity(3));
+    }
+    
+    #[test]
+    fn test_book_events() {
+        let mut book = book_with_asks();
+        assert!(book.take_events().is_empty());
+        book.set_event_recording(true);
+        
+        // An IOC that sweeps a level never rests, so it adds nothing to the book
+        book.process_order(order_of_type(10, OrderType::IOC, OrderSide::Buy, 10000, 5));
+        let events = book.take_events();
+        assert_eq!(events.len(), 5);
+        assert_eq!(
+            events[0],
+            BookEvent::Execute { order_id: 1, quantity: Quantity(2), price: Price(10000), trade_id: 1 }
+        );
+        assert!(matches!(&events[1], BookEvent::Trade(trade) if trade.buy_order_id == 10));
+        assert!(matches!(events[2], BookEvent::Execute { order_id: 2, .. }));
+        assert_eq!(
+            events[4],
+            BookEvent::Bbo { bid: Some((Price(9900), Quantity(4))), ask: Some((Price(10100), Quantity(2))) }
+        );
+        
+        // Reducing in place is a partial cancel; repricing deletes and re-adds
+        book.amend_order(5, None, Some(Quantity(3)), 600).unwrap();
+        book.amend_order(5, Some(Price(9950)), None, 700).unwrap();
+        book.cancel_order(4);
+        assert_eq!(
+            book.take_events(),
+            vec![
+                BookEvent::Cancel { order_id: 5, quantity: Quantity(1) },
+                BookEvent::Bbo { bid: Some((Price(9900), Quantity(3))), ask: Some((Price(10100), Quantity(2))) },
+                BookEvent::Delete { order_id: 5 },
+                BookEvent::AddOrder { order_id: 5, side: OrderSide::Buy, price: Price(9950), quantity: Quantity(3) },
+                BookEvent::Bbo { bid: Some((Price(9950), Quantity(3))), ask: Some((Price(10100), Quantity(2))) },
+                BookEvent::Delete { order_id: 4 },
+            ]
+        );
+        
+        // A snapshot lists resting orders best first, then the BBO
+        let snapshot = book.snapshot_events();
+        assert_eq!(snapshot.len(), 3);
+        assert!(matches!(snapshot[0], BookEvent::AddOrder { order_id: 5, .. }));
+        assert!(matches!(snapshot[1], BookEvent::AddOrder { order_id: 3, .. }));
+        
+        book.set_event_recording(false);
+        book.cancel_order(3);
+        assert!(book.take_events().is_empty());
+    }
+    
+    mod overflow {
+        use super::*;
+        use crate::models::order::OrderType;
+        use proptest::prelude::*;
+        
+        /// Prices and quantities biased towards the edges of the u64 range
+        fn extreme_u64() -> impl Strategy<Value = u64> {
+            prop_oneof![
+                1u64..1_000,
+                Just(u64::MAX),
+                (u64::MAX - 1_000)..=u64::MAX,
+                1u64..=u64::MAX,
+            ]
+        }
+        
+        fn order_type() -> impl Strategy<Value = OrderType> {
+            prop_oneof![
+                Just(OrderType::Limit),
+                Just(OrderType::Market),
+                Just(OrderType::IOC),
+                Just(OrderType::FOK),
+            ]
+        }
+        
+        proptest! {
+            #[test]
+            fn matching_and_analytics_never_overflow(
+                orders in prop::collection::vec(
+                    (any::<bool>(), order_type(), extreme_u64(), extreme_u64()),
+                    1..40,
+                ),
+                probe in extreme_u64(),
+            ) {
+                let mut book = OrderBook::new("BTC-USD");
+                let config = AnalyticsConfig::new(10, probe as u32);
+                book.set_analytics(Some(config));
+                
+                for (i, (is_buy, order_type, price, quantity)) in orders.into_iter().enumerate() {
+                    let id = i as u64 + 1;
+                    let side = if is_buy { OrderSide::Buy } else { OrderSide::Sell };
+                    let mut order = match order_type {
+                        // Market buys carry a price of u64::MAX
+                        OrderType::Market => Order::new_market(id, quantity, side, 1, id, None, "BTC-USD".to_string()),
+                        _ => Order::new_limit(id, price, quantity, side, 1, id, None, "BTC-USD".to_string()),
+                    };
+                    order.order_type = order_type;
+                    
+                    for trade in book.process_order(order) {
+                        prop_assert_eq!(trade.value(), trade.price.0 as u128 * trade.quantity.0 as u128);
+                    }
+                    
+                    // The book never stays crossed after matching
+                    if let (Some(bid), Some(ask)) = (book.best_bid(), book.best_ask()) {
+                        prop_assert!(bid < ask);
+                    }
+                }
+                
+                let _ = book.market_depth(10);
+                // The level totals kept through matching agree with the resting orders
+                prop_assert_eq!(book.analytics(), Some(book.compute_analytics(config)));
+                let _ = book.stats().summary();
+                for side in [OrderSide::Buy, OrderSide::Sell] {
+                    let estimate = book.estimate_execution(side, Quantity(probe));
+                    prop_assert_eq!(estimate.filled_quantity + estimate.unfilled_quantity, Quantity(probe));
+                    let _ = estimate.impact_bps();
+                    let _ = estimate.slippage_percent();
+                    
+                    if let Some((avg_price, _)) = book.calculate_slippage(side, Quantity(probe)) {
+                        prop_assert!(Some(avg_price) >= estimate.levels.first().map(|l| l.price).min(estimate.worst_price()));
+                        prop_assert!(Some(avg_price) <= estimate.levels.first().map(|l| l.price).max(estimate.worst_price()));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::fees::schedule::{fee_amount, FeeSchedule};
+use crate::models::instrument::Instrument;
+use crate::models::order::OrderSide;
+use crate::models::trade::Trade;
+use crate::models::trade_adjustment::TradeAdjustment;
+
+/// Days of traded volume that decide a user's tier
+pub const VOLUME_WINDOW_DAYS: u64 = 30;
+
+/// Nanoseconds in a day, the granularity of tier volumes
+const NANOS_PER_DAY: u64 = 86_400_000_000_000;
+
+/// Computes maker and taker fees on trades and totals the revenue per symbol
+///
+/// Symbols use their own schedule if one is set and the default schedule
+/// otherwise; without either, trades are free. A user's tier comes from the
+/// notional they traded in the symbol's quote asset over the UTC day of the
+/// latest trade and the 29 days before, counted in the quote asset's
+/// smallest unit as trades are applied and adjusted. `load_volumes` seeds it
+/// from earlier trades. Fees are in the quote asset's smallest unit.
+#[derive(Debug, Default)]
+pub struct FeeEngine {
+    default_schedule: Option<FeeSchedule>,
+    symbol_schedules: HashMap<String, FeeSchedule>,
+    /// Notional each user traded by quote asset and UTC day, in the quote asset's smallest unit
+    volumes: HashMap<(u64, String), BTreeMap<u64, u128>>,
+    /// UTC day of the latest trade, on which the volume window ends
+    day: u64,
+    /// Fees collected net of rebates, by symbol
+    revenue: HashMap<String, i128>,
+}
+
+impl FeeEngine {
+    /// Creates an engine that charges no fees
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an engine applying `schedule` to every symbol
+    pub fn with_schedule(schedule: FeeSchedule) -> Self {
+        Self {
+            default_schedule: Some(schedule),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the schedule of symbols without their own, or removes it with `None`
+    pub fn set_default_schedule(&mut self, schedule: Option<FeeSchedule>) {
+        self.default_schedule = schedule;
+    }
+
+    /// Sets the schedule of one symbol, or removes it with `None`
+    pub fn set_symbol_schedule(&mut self, symbol: &str, schedule: Option<FeeSchedule>) {
+        match schedule {
+            Some(schedule) => {
+                self.symbol_schedules.insert(symbol.to_string(), schedule);
+            }
+            None => {
+                self.symbol_schedules.remove(symbol);
+            }
+        }
+    }
+
+    /// Returns the schedule that applies to a symbol
+    pub fn schedule(&self, symbol: &str) -> Option<&FeeSchedule> {
+        self.symbol_schedules
+            .get(symbol)
+            .or(self.default_schedule.as_ref())
+    }
+
+    /// Replaces the volumes that decide each user's tier with those of `trades`
+    ///
+    /// `instrument` gives the precision of each trade's symbol. Usually the
+    /// trades that still stand in a `TradeStore`.
+    pub fn load_volumes<'a>(&mut self, trades: impl IntoIterator<Item = &'a Trade>, instrument: impl Fn(&str) -> Instrument) {
+        self.volumes.clear();
+        self.day = 0;
+        for trade in trades {
+            self.record_volume(trade, &instrument(&trade.symbol), 1);
+        }
+    }
+
+    /// Returns the notional a user traded in a quote asset over the 30-day window
+    pub fn volume(&self, user_id: u64, quote: &str) -> u128 {
+        let first_day = (self.day + 1).saturating_sub(VOLUME_WINDOW_DAYS);
+        self.volumes
+            .get(&(user_id, quote.to_string()))
+            .map_or(0, |days| days.range(first_day..).map(|(_, volume)| volume).sum())
+    }
+
+    /// Returns the rate a user pays in a symbol as maker or taker
+    pub fn rate(&self, user_id: u64, symbol: &str, maker: bool) -> i64 {
+        let (_, quote) = Instrument::split_symbol(symbol);
+        self.schedule(symbol)
+            .map_or(0, |schedule| schedule.rate(self.volume(user_id, &quote), maker))
+    }
+
+    /// Returns the highest rate any user can pay in a symbol, which buy orders reserve
+    pub fn max_rate(&self, symbol: &str) -> i64 {
+        self.schedule(symbol).map_or(0, FeeSchedule::max_rate)
+    }
+
+    /// Records the fees of both sides on a trade and adds them to the symbol's revenue
+    ///
+    /// The trade is then counted in both users' volumes, so it does not
+    /// change the tier its own fees are charged at.
+    pub fn apply(&mut self, trade: &mut Trade, instrument: &Instrument) {
+        self.day = self.day.max(trade.timestamp / NANOS_PER_DAY);
+        (trade.buy_fee, trade.sell_fee) = self.fees(trade, instrument);
+        *self.revenue.entry(trade.symbol.clone()).or_default() += trade.fee_revenue();
+        self.record_volume(trade, instrument, 1);
+    }
+
+    /// Returns the buyer's and seller's fees on a trade at their current rates
+    pub fn fees(&self, trade: &Trade, instrument: &Instrument) -> (i64, i64) {
+        let amount = instrument.quote_units(trade.value());
+        let buyer_is_maker = trade.aggressor_side == OrderSide::Sell;
+        (
+            fee_amount(amount, self.rate(trade.buy_user_id, &trade.symbol, buyer_is_maker)),
+            fee_amount(amount, self.rate(trade.sell_user_id, &trade.symbol, !buyer_is_maker)),
+        )
+    }
+
+    /// Takes the fees and volume of a busted or corrected trade back out and adds those of its correction
+    pub fn on_adjustment(&mut self, adjustment: &TradeAdjustment, instrument: &Instrument) {
+        let corrected = adjustment.corrected.as_ref().map_or(0, Trade::fee_revenue);
+        *self.revenue.entry(adjustment.symbol.clone()).or_default() +=
+            corrected - adjustment.previous.fee_revenue();
+        self.record_volume(&adjustment.previous, instrument, -1);
+        if let Some(corrected) = &adjustment.corrected {
+            self.record_volume(corrected, instrument, 1);
+        }
+    }
+
+    /// Adds a trade's notional to the volumes of its buyer and seller, or takes it out when `sign` is negative
+    ///
+    /// Days that left the window are dropped as trades are added.
+    fn record_volume(&mut self, trade: &Trade, instrument: &Instrument, sign: i8) {
+        let day = trade.timestamp / NANOS_PER_DAY;
+        if sign > 0 {
+            self.day = self.day.max(day);
+        }
+        let first_day = (self.day + 1).saturating_sub(VOLUME_WINDOW_DAYS);
+        let amount = instrument.quote_units(trade.value());
+        let (_, quote) = Instrument::split_symbol(&trade.symbol);
+        let mut users = vec![trade.buy_user_id];
+        if trade.sell_user_id != trade.buy_user_id {
+            users.push(trade.sell_user_id);
+        }
+        for user_id in users {
+            let days = self.volumes.entry((user_id, quote.clone())).or_default();
+            let volume = days.entry(day).or_default();
+            *volume = if sign > 0 {
+                volume.saturating_add(amount)
+            } else {
+                volume.saturating_sub(amount)
+            };
+            *days = days.split_off(&first_day);
+            days.retain(|_, volume| *volume > 0);
+        }
+    }
+
+    /// Returns the fees collected in a symbol, net of rebates
+    pub fn revenue(&self, symbol: &str) -> i128 {
+        self.revenue.get(symbol).copied().unwrap_or(0)
+    }
+
+    /// Returns the fees collected in each symbol, net of rebates, by symbol
+    pub fn revenue_by_symbol(&self) -> Vec<(String, i128)> {
+        let mut revenue: Vec<(String, i128)> = self
+            .revenue
+            .iter()
+            .map(|(symbol, revenue)| (symbol.clone(), *revenue))
+            .collect();
+        revenue.sort();
+        revenue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 86_400_000_000_000;
+
+    fn trade(id: u64, price: u64, quantity: u64, timestamp: u64, buyer: u64, seller: u64, symbol: &str) -> Trade {
+        Trade::new(id, price, quantity, timestamp, 10 + id, 20 + id, buyer, seller, OrderSide::Buy, symbol.to_string())
+    }
+
+    #[test]
+    fn test_maker_rebate_and_taker_fee() {
+        let mut fees = FeeEngine::with_schedule(FeeSchedule::new(-100, 500));
+        fees.set_symbol_schedule("ETH-USD", Some(FeeSchedule::new(0, 1_000)));
+        let instrument = Instrument::new("BTC-USD");
+
+        // The buy order took liquidity
+        let mut btc = trade(1, 50_000, 20, DAY, 1, 2, "BTC-USD");
+        fees.apply(&mut btc, &instrument);
+        assert_eq!((btc.buy_fee, btc.sell_fee), (500, -100));
+
+        let mut eth = trade(2, 3_000, 10, DAY, 1, 2, "ETH-USD");
+        eth.aggressor_side = OrderSide::Sell;
+        fees.apply(&mut eth, &Instrument::new("ETH-USD"));
+        assert_eq!((eth.buy_fee, eth.sell_fee), (0, 30));
+
+        assert_eq!(fees.revenue_by_symbol(), vec![("BTC-USD".to_string(), 400), ("ETH-USD".to_string(), 30)]);
+        fees.set_default_schedule(None);
+        assert_eq!(fees.rate(1, "BTC-USD", false), 0);
+    }
+
+    #[test]
+    fn test_clamped_fees_add_up() {
+        let mut fees = FeeEngine::with_schedule(FeeSchedule::new(100, 500));
+        let mut trade = trade(1, u64::MAX, u64::MAX, DAY, 1, 2, "BTC-USD");
+        fees.apply(&mut trade, &Instrument::new("BTC-USD"));
+
+        assert_eq!((trade.buy_fee, trade.sell_fee), (i64::MAX, i64::MAX));
+        assert_eq!(trade.fee_revenue(), 2 * i64::MAX as i128);
+        assert_eq!(fees.revenue("BTC-USD"), 2 * i64::MAX as i128);
+    }
+
+    #[test]
+    fn test_tiers_follow_30_day_volume() {
+        let trades = [
+            trade(1, 1_000, 2_000, DAY, 1, 2, "BTC-USD"),
+            trade(2, 1_000, 1_000, 20 * DAY, 1, 3, "ETH-USD"),
+            trade(3, 1_000, 5_000, 40 * DAY, 2, 1, "BTC-USD"),
+            trade(4, 1_000, 9_000, 40 * DAY, 3, 1, "BTC-EUR"),
+        ];
+        // ETH quantities have a decimal place, so its notional is a tenth in quote units
+        let instrument = |symbol: &str| match symbol {
+            "ETH-USD" => Instrument::with_precision(symbol, 0, 1).unwrap(),
+            _ => Instrument::new(symbol),
+        };
+
+        let mut fees = FeeEngine::with_schedule(FeeSchedule::new(0, 500).with_tier(5_000_000, -50, 300));
+        fees.load_volumes(&trades, instrument);
+        // The first trade is more than 30 days old, and euros count apart from dollars
+        assert_eq!((fees.volume(1, "USD"), fees.volume(2, "USD"), fees.volume(3, "USD")), (5_100_000, 5_000_000, 100_000));
+        assert_eq!((fees.volume(1, "EUR"), fees.volume(3, "EUR")), (9_000_000, 9_000_000));
+        assert_eq!(fees.rate(1, "BTC-USD", true), -50);
+        assert_eq!((fees.rate(3, "BTC-USD", false), fees.rate(3, "BTC-EUR", false)), (500, 300));
+
+        // Applied trades count once their own fees are charged, and the window moves with them
+        let mut next = trade(5, 1_000, 1_000, 51 * DAY, 2, 3, "BTC-USD");
+        fees.apply(&mut next, &instrument("BTC-USD"));
+        assert_eq!(next.buy_fee, 300);
+        assert_eq!((fees.volume(1, "USD"), fees.volume(2, "USD"), fees.volume(3, "USD")), (5_000_000, 6_000_000, 1_000_000));
+
+        let bust = TradeAdjustment {
+            id: 1,
+            trade_id: 5,
+            symbol: "BTC-USD".to_string(),
+            reason: String::new(),
+            timestamp: 51 * DAY,
+            previous: next,
+            corrected: None,
+            last_trade: None,
+        };
+        fees.on_adjustment(&bust, &instrument("BTC-USD"));
+        assert_eq!((fees.volume(2, "USD"), fees.volume(3, "USD")), (5_000_000, 0));
+    }
+}
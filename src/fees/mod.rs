@@ -0,0 +1,7 @@
+// Export fee components
+pub mod schedule;
+pub mod fee_engine;
+
+// Re-export main components
+pub use schedule::{FeeSchedule, FeeTier};
+pub use fee_engine::{FeeEngine, VOLUME_WINDOW_DAYS};
@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+
+/// Denominator of fee rates: a rate of 100 is one basis point (0.01%)
+pub const RATE_SCALE: i64 = 1_000_000;
+
+/// Fee rates for users above a 30-day volume threshold
+///
+/// Rates are in parts per million of the trade's notional. A negative maker
+/// rate is a rebate paid to the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeTier {
+    /// Smallest 30-day traded notional, in the quote asset's smallest unit, that qualifies for this tier
+    pub min_volume: u128,
+    /// Rate charged when the user's order was resting in the book
+    pub maker_rate: i64,
+    /// Rate charged when the user's order took liquidity
+    pub taker_rate: i64,
+}
+
+/// Maker and taker rates by volume tier
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    /// Tiers by ascending minimum volume, the first starting at zero
+    tiers: Vec<FeeTier>,
+}
+
+impl FeeSchedule {
+    /// Creates a schedule charging every user the same rates
+    pub fn new(maker_rate: i64, taker_rate: i64) -> Self {
+        Self {
+            tiers: vec![FeeTier {
+                min_volume: 0,
+                maker_rate,
+                taker_rate,
+            }],
+        }
+    }
+
+    /// Adds or replaces the tier starting at `min_volume`
+    pub fn with_tier(mut self, min_volume: u128, maker_rate: i64, taker_rate: i64) -> Self {
+        let tier = FeeTier {
+            min_volume,
+            maker_rate,
+            taker_rate,
+        };
+        match self.tiers.binary_search_by_key(&min_volume, |tier| tier.min_volume) {
+            Ok(index) => self.tiers[index] = tier,
+            Err(index) => self.tiers.insert(index, tier),
+        }
+        self
+    }
+
+    /// Returns the tiers by ascending minimum volume
+    pub fn tiers(&self) -> &[FeeTier] {
+        &self.tiers
+    }
+
+    /// Returns the tier for a user with the given 30-day volume
+    pub fn tier(&self, volume: u128) -> &FeeTier {
+        let index = self.tiers.partition_point(|tier| tier.min_volume <= volume);
+        &self.tiers[index.saturating_sub(1)]
+    }
+
+    /// Returns the highest rate any tier charges, or zero if every tier pays a rebate
+    pub fn max_rate(&self) -> i64 {
+        self.tiers
+            .iter()
+            .flat_map(|tier| [tier.maker_rate, tier.taker_rate])
+            .max()
+            .unwrap_or(0)
+            .max(0)
+    }
+
+    /// Returns the rate for a maker or taker with the given 30-day volume
+    pub fn rate(&self, volume: u128, maker: bool) -> i64 {
+        let tier = self.tier(volume);
+        if maker {
+            tier.maker_rate
+        } else {
+            tier.taker_rate
+        }
+    }
+}
+
+/// Returns the fee at `rate` on an amount, rounding charges up and rebates toward zero
+///
+/// A charge is never more than the amount itself, so a seller can always pay
+/// it out of what the trade earns.
+pub fn fee_amount(amount: u128, rate: i64) -> i64 {
+    let scale = RATE_SCALE as u128;
+    let fee = if rate >= 0 {
+        amount.saturating_mul(rate as u128).div_ceil(scale).min(amount)
+    } else {
+        amount.saturating_mul(rate.unsigned_abs() as u128) / scale
+    };
+    let fee = i64::try_from(fee).unwrap_or(i64::MAX);
+    if rate >= 0 {
+        fee
+    } else {
+        -fee
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tiers_by_volume() {
+        let schedule = FeeSchedule::new(-50, 500)
+            .with_tier(10_000_000, -100, 400)
+            .with_tier(1_000_000, -75, 450)
+            .with_tier(1_000_000, -80, 450);
+
+        let minimums: Vec<u128> = schedule.tiers().iter().map(|tier| tier.min_volume).collect();
+        assert_eq!(minimums, vec![0, 1_000_000, 10_000_000]);
+        assert_eq!(schedule.rate(0, true), -50);
+        assert_eq!(schedule.rate(999_999, false), 500);
+        assert_eq!(schedule.rate(1_000_000, true), -80);
+        assert_eq!(schedule.rate(u128::MAX, false), 400);
+        assert_eq!(schedule.max_rate(), 500);
+        assert_eq!(FeeSchedule::new(-50, -10).max_rate(), 0);
+
+        assert_eq!(fee_amount(10_001, 500), 6);
+        assert_eq!(fee_amount(10_001, -50), 0);
+        assert_eq!(fee_amount(1_000_000, -50), -50);
+        assert_eq!(fee_amount(1_000_000, 0), 0);
+        assert_eq!(fee_amount(7, 2 * RATE_SCALE), 7);
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{self, Instant};
+
+use crate::core::handle::{EngineHandle, TradeEvent};
+use crate::fix::message::{self, frame_length, msg_type, tags, FixMessage};
+use crate::fix::session::{Session, Shared};
+use crate::models::instrument::Instrument;
+
+/// Default time allowed between connecting and sending Logon
+pub const DEFAULT_LOGON_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Session identities, instruments and persistence for a FIX acceptor
+#[derive(Debug, Clone)]
+pub struct FixConfig {
+    /// Our SenderCompID, which counterparties send as TargetCompID
+    comp_id: String,
+    /// User ID of each accepted counterparty SenderCompID
+    counterparties: HashMap<String, u64>,
+    /// Decimal precision of each symbol; unlisted symbols use the defaults
+    instruments: HashMap<String, Instrument>,
+    /// Directory for sequence numbers and sent messages; None keeps them in memory
+    store_dir: Option<String>,
+    /// Time allowed between connecting and sending Logon
+    logon_timeout: Duration,
+}
+
+impl FixConfig {
+    /// Creates a config for an acceptor with the given comp ID and no counterparties
+    pub fn new(comp_id: &str) -> Self {
+        Self {
+            comp_id: comp_id.to_string(),
+            counterparties: HashMap::new(),
+            instruments: HashMap::new(),
+            store_dir: None,
+            logon_timeout: DEFAULT_LOGON_TIMEOUT,
+        }
+    }
+
+    /// Accepts logons from a counterparty comp ID, trading as the given user
+    pub fn with_counterparty(mut self, comp_id: &str, user_id: u64) -> Self {
+        self.counterparties.insert(comp_id.to_string(), user_id);
+        self
+    }
+
+    /// Sets the decimal precision used to parse and format a symbol's prices and quantities
+    pub fn with_instrument(mut self, instrument: Instrument) -> Self {
+        self.instruments.insert(instrument.symbol.clone(), instrument);
+        self
+    }
+
+    /// Persists session sequence numbers and sent messages in a directory
+    pub fn with_store_dir(mut self, dir: &str) -> Self {
+        self.store_dir = Some(dir.to_string());
+        self
+    }
+
+    /// Sets the time allowed between connecting and sending Logon
+    pub fn with_logon_timeout(mut self, logon_timeout: Duration) -> Self {
+        self.logon_timeout = logon_timeout;
+        self
+    }
+
+    /// Returns our comp ID
+    pub fn comp_id(&self) -> &str {
+        &self.comp_id
+    }
+
+    /// Returns the user ID of a counterparty, if it may log on
+    pub fn user_id(&self, comp_id: &str) -> Option<u64> {
+        self.counterparties.get(comp_id).copied()
+    }
+
+    /// Returns the instrument for a symbol, falling back to default precision
+    pub fn instrument(&self, symbol: &str) -> Instrument {
+        self.instruments
+            .get(symbol)
+            .cloned()
+            .unwrap_or_else(|| Instrument::new(symbol))
+    }
+
+    /// Returns the directory session stores are kept in, if any
+    pub fn store_dir(&self) -> Option<&str> {
+        self.store_dir.as_deref()
+    }
+}
+
+/// FIX 4.4 order entry acceptor
+///
+/// Each counterparty logs on with its own SenderCompID and trades as the
+/// user configured for it. NewOrderSingle, OrderCancelRequest and
+/// OrderCancelReplaceRequest are forwarded to the engine, and fills,
+/// cancels and rejects come back as ExecutionReports. Reports for a
+/// counterparty that is not connected are stored and delivered by resend
+/// once it logs on again.
+#[derive(Clone)]
+pub struct FixAcceptor {
+    shared: Arc<Shared>,
+}
+
+impl FixAcceptor {
+    /// Creates an acceptor that forwards orders to the given engine
+    pub fn new(engine: EngineHandle, config: FixConfig) -> Self {
+        Self {
+            shared: Arc::new(Shared::new(engine, config)),
+        }
+    }
+
+    /// Accepts connections until the listener fails, running each session on its own task
+    pub async fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        for instrument in self.shared.config.instruments.values() {
+            self.shared
+                .engine
+                .add_instrument(instrument.clone())
+                .await
+                .map_err(|e| io::Error::new(io::ErrorKind::BrokenPipe, e.to_string()))?;
+        }
+
+        let trades = self.shared.engine.subscribe_trades();
+        tokio::spawn(route_trades(Arc::clone(&self.shared), trades));
+
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            info!("FIX connection from {}", peer);
+
+            let shared = Arc::clone(&self.shared);
+            tokio::spawn(async move {
+                match run_connection(shared, stream).await {
+                    Ok(()) => info!("FIX connection from {} closed", peer),
+                    Err(e) => warn!("FIX connection from {} failed: {}", peer, e),
+                }
+            });
+        }
+    }
+}
+
+/// Reports maker fills caused by orders that did not come through this acceptor
+///
+/// Trades whose taker is a FIX order are reported by the taker's session.
+async fn route_trades(shared: Arc<Shared>, mut trades: broadcast::Receiver<TradeEvent>) {
+    loop {
+        match trades.recv().await {
+            Ok(event) => {
+                if !shared.is_fix_order(event.taker_order_id) {
+                    let (maker_order_id, _) = event.maker();
+                    shared.report_fill(&event.trade, maker_order_id);
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!("FIX acceptor missed {} trade events", missed);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Reads complete FIX messages from a byte stream
+///
+/// Messages that fail their checksum or length check are logged and
+/// skipped, as FIX requires; a stream that no longer starts with
+/// BeginString is an error.
+pub struct FixReader<R> {
+    reader: R,
+    buffer: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> FixReader<R> {
+    /// Creates a reader over a byte stream
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Returns the next valid message, or None once the stream ends
+    ///
+    /// Cancel safe: a partially read message stays buffered.
+    pub async fn next_message(&mut self) -> io::Result<Option<FixMessage>> {
+        loop {
+            if let Some(length) = frame_length(&self.buffer)? {
+                let frame: Vec<u8> = self.buffer.drain(..length).collect();
+                match FixMessage::decode(&frame) {
+                    Ok(message) => return Ok(Some(message)),
+                    Err(e) => {
+                        warn!("Ignoring garbled FIX message: {}", e);
+                        continue;
+                    }
+                }
+            }
+
+            let mut chunk = [0u8; 4096];
+            let read = self.reader.read(&mut chunk).await?;
+            if read == 0 {
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                }
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Connection closed mid-message"));
+            }
+            self.buffer.extend_from_slice(&chunk[..read]);
+        }
+    }
+}
+
+async fn run_connection(shared: Arc<Shared>, stream: TcpStream) -> io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = FixReader::new(reader);
+
+    let logon = match time::timeout(shared.config.logon_timeout, reader.next_message()).await {
+        Ok(message) => message?,
+        Err(_) => return Err(io::Error::new(io::ErrorKind::TimedOut, "No Logon received")),
+    };
+    let Some(logon) = logon else {
+        return Ok(());
+    };
+    if logon.msg_type() != msg_type::LOGON {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("First message must be Logon, got {}", logon),
+        ));
+    }
+
+    // Unidentified counterparties are disconnected without a reply
+    let (outbound, mut inbox) = mpsc::unbounded_channel();
+    let mut session = Session::logon(&shared, &logon, outbound)
+        .map_err(|reason| io::Error::new(io::ErrorKind::PermissionDenied, reason))?;
+    let result = run_session(&shared, &mut session, &mut reader, &mut writer, &mut inbox).await;
+    session.close(&shared);
+
+    result?;
+    writer.shutdown().await
+}
+
+async fn run_session<R: AsyncRead + Unpin>(
+    shared: &Shared,
+    session: &mut Session,
+    reader: &mut FixReader<R>,
+    writer: &mut tokio::net::tcp::OwnedWriteHalf,
+    inbox: &mut mpsc::UnboundedReceiver<FixMessage>,
+) -> io::Result<()> {
+    let heartbeat = Duration::from_secs(session.heart_bt_int());
+    let mut ticker = time::interval(Duration::from_millis(250));
+    let mut last_sent = Instant::now();
+    let mut last_received = Instant::now();
+    let mut test_request_sent: Option<Instant> = None;
+
+    loop {
+        write_outbox(session, writer, &mut last_sent).await?;
+        if session.is_closing() {
+            return Ok(());
+        }
+
+        tokio::select! {
+            message = reader.next_message() => {
+                let Some(message) = message? else {
+                    info!("FIX session {} disconnected", session.counterparty());
+                    return Ok(());
+                };
+                debug!("FIX in: {}", message);
+                last_received = Instant::now();
+                test_request_sent = None;
+                session.on_message(shared, message).await?;
+            }
+            Some(message) = inbox.recv() => {
+                session.send(message)?;
+            }
+            _ = ticker.tick(), if !heartbeat.is_zero() => {
+                let now = Instant::now();
+                match test_request_sent {
+                    Some(sent_at) if now - sent_at > heartbeat => {
+                        warn!("FIX session {} did not answer a TestRequest", session.counterparty());
+                        return Ok(());
+                    }
+                    None if now - last_received > heartbeat + heartbeat / 5 => {
+                        let test_req_id = message::sending_time();
+                        session.send(FixMessage::new(msg_type::TEST_REQUEST).with(tags::TEST_REQ_ID, test_req_id))?;
+                        test_request_sent = Some(now);
+                    }
+                    _ if now - last_sent >= heartbeat => {
+                        session.send(FixMessage::new(msg_type::HEARTBEAT))?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+async fn write_outbox(
+    session: &mut Session,
+    writer: &mut tokio::net::tcp::OwnedWriteHalf,
+    last_sent: &mut Instant,
+) -> io::Result<()> {
+    let outbox = session.take_outbox();
+    if outbox.is_empty() {
+        return Ok(());
+    }
+
+    for message in &outbox {
+        debug!("FIX out: {}", message);
+        writer.write_all(&message.encode()).await?;
+    }
+    *last_sent = Instant::now();
+    Ok(())
+}
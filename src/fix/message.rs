@@ -0,0 +1,391 @@
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+
+/// Field separator of the FIX tag=value encoding
+pub const SOH: u8 = 0x01;
+
+/// The only protocol version the acceptor speaks
+pub const BEGIN_STRING: &str = "FIX.4.4";
+
+/// Tag numbers used by the acceptor
+pub mod tags {
+    pub const ACCOUNT: u32 = 1;
+    pub const AVG_PX: u32 = 6;
+    pub const BEGIN_SEQ_NO: u32 = 7;
+    pub const BEGIN_STRING: u32 = 8;
+    pub const BODY_LENGTH: u32 = 9;
+    pub const CHECKSUM: u32 = 10;
+    pub const CL_ORD_ID: u32 = 11;
+    pub const CUM_QTY: u32 = 14;
+    pub const END_SEQ_NO: u32 = 16;
+    pub const EXEC_ID: u32 = 17;
+    pub const LAST_PX: u32 = 31;
+    pub const LAST_QTY: u32 = 32;
+    pub const MSG_SEQ_NUM: u32 = 34;
+    pub const MSG_TYPE: u32 = 35;
+    pub const NEW_SEQ_NO: u32 = 36;
+    pub const ORDER_ID: u32 = 37;
+    pub const ORDER_QTY: u32 = 38;
+    pub const ORD_STATUS: u32 = 39;
+    pub const ORD_TYPE: u32 = 40;
+    pub const ORIG_CL_ORD_ID: u32 = 41;
+    pub const POSS_DUP_FLAG: u32 = 43;
+    pub const PRICE: u32 = 44;
+    pub const REF_SEQ_NUM: u32 = 45;
+    pub const SENDER_COMP_ID: u32 = 49;
+    pub const SENDING_TIME: u32 = 52;
+    pub const SIDE: u32 = 54;
+    pub const SYMBOL: u32 = 55;
+    pub const TARGET_COMP_ID: u32 = 56;
+    pub const TEXT: u32 = 58;
+    pub const TIME_IN_FORCE: u32 = 59;
+    pub const TRANSACT_TIME: u32 = 60;
+    pub const ENCRYPT_METHOD: u32 = 98;
+    pub const STOP_PX: u32 = 99;
+    pub const CXL_REJ_REASON: u32 = 102;
+    pub const ORD_REJ_REASON: u32 = 103;
+    pub const HEART_BT_INT: u32 = 108;
+    pub const TEST_REQ_ID: u32 = 112;
+    pub const ORIG_SENDING_TIME: u32 = 122;
+    pub const GAP_FILL_FLAG: u32 = 123;
+    pub const RESET_SEQ_NUM_FLAG: u32 = 141;
+    pub const EXEC_TYPE: u32 = 150;
+    pub const LEAVES_QTY: u32 = 151;
+    pub const REF_TAG_ID: u32 = 371;
+    pub const REF_MSG_TYPE: u32 = 372;
+    pub const SESSION_REJECT_REASON: u32 = 373;
+    pub const CXL_REJ_RESPONSE_TO: u32 = 434;
+}
+
+/// MsgType (35) values used by the acceptor
+pub mod msg_type {
+    pub const HEARTBEAT: &str = "0";
+    pub const TEST_REQUEST: &str = "1";
+    pub const RESEND_REQUEST: &str = "2";
+    pub const REJECT: &str = "3";
+    pub const SEQUENCE_RESET: &str = "4";
+    pub const LOGOUT: &str = "5";
+    pub const EXECUTION_REPORT: &str = "8";
+    pub const ORDER_CANCEL_REJECT: &str = "9";
+    pub const LOGON: &str = "A";
+    pub const NEW_ORDER_SINGLE: &str = "D";
+    pub const ORDER_CANCEL_REQUEST: &str = "F";
+    pub const ORDER_CANCEL_REPLACE_REQUEST: &str = "G";
+}
+
+/// A FIX message as an ordered list of tag=value fields
+///
+/// BeginString, BodyLength and CheckSum are not stored; they are written by
+/// `encode` and verified by `decode`. MsgType is always the first field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixMessage {
+    fields: Vec<(u32, String)>,
+}
+
+impl FixMessage {
+    /// Creates an empty message of the given type
+    pub fn new(msg_type: &str) -> Self {
+        Self {
+            fields: vec![(tags::MSG_TYPE, msg_type.to_string())],
+        }
+    }
+
+    /// Appends a field and returns the message
+    pub fn with(mut self, tag: u32, value: impl ToString) -> Self {
+        self.fields.push((tag, value.to_string()));
+        self
+    }
+
+    /// Sets a field, replacing the first existing value or appending it
+    pub fn set(&mut self, tag: u32, value: impl ToString) {
+        match self.fields.iter_mut().find(|(t, _)| *t == tag) {
+            Some(field) => field.1 = value.to_string(),
+            None => self.fields.push((tag, value.to_string())),
+        }
+    }
+
+    /// Removes every occurrence of a field
+    pub fn remove(&mut self, tag: u32) {
+        self.fields.retain(|(t, _)| *t != tag);
+    }
+
+    /// Returns the first value of a field
+    pub fn get(&self, tag: u32) -> Option<&str> {
+        self.fields.iter().find(|(t, _)| *t == tag).map(|(_, value)| value.as_str())
+    }
+
+    /// Parses the first value of a field, returning None if it is missing or malformed
+    pub fn get_parsed<T: FromStr>(&self, tag: u32) -> Option<T> {
+        self.get(tag).and_then(|value| value.parse().ok())
+    }
+
+    /// Returns true if a Y/N field is set to Y
+    pub fn get_flag(&self, tag: u32) -> bool {
+        self.get(tag) == Some("Y")
+    }
+
+    /// Returns the message type
+    pub fn msg_type(&self) -> &str {
+        self.get(tags::MSG_TYPE).unwrap_or_default()
+    }
+
+    /// Returns the message sequence number, if present and numeric
+    pub fn seq_num(&self) -> Option<u64> {
+        self.get_parsed(tags::MSG_SEQ_NUM)
+    }
+
+    /// Returns all fields in order, excluding BeginString, BodyLength and CheckSum
+    pub fn fields(&self) -> &[(u32, String)] {
+        &self.fields
+    }
+
+    /// Returns true for session-level (administrative) messages
+    pub fn is_admin(&self) -> bool {
+        matches!(
+            self.msg_type(),
+            msg_type::HEARTBEAT
+                | msg_type::TEST_REQUEST
+                | msg_type::RESEND_REQUEST
+                | msg_type::REJECT
+                | msg_type::SEQUENCE_RESET
+                | msg_type::LOGOUT
+                | msg_type::LOGON
+        )
+    }
+
+    /// Sets the standard header fields, placing them directly after MsgType
+    pub fn set_header(&mut self, sender_comp_id: &str, target_comp_id: &str, seq_num: u64, sending_time: &str) {
+        for tag in [tags::SENDER_COMP_ID, tags::TARGET_COMP_ID, tags::MSG_SEQ_NUM, tags::SENDING_TIME] {
+            self.remove(tag);
+        }
+        let header = [
+            (tags::SENDER_COMP_ID, sender_comp_id.to_string()),
+            (tags::TARGET_COMP_ID, target_comp_id.to_string()),
+            (tags::MSG_SEQ_NUM, seq_num.to_string()),
+            (tags::SENDING_TIME, sending_time.to_string()),
+        ];
+        self.fields.splice(1..1, header);
+    }
+
+    /// Encodes the message with BeginString, BodyLength and CheckSum
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (tag, value) in &self.fields {
+            body.extend_from_slice(format!("{}={}", tag, value).as_bytes());
+            body.push(SOH);
+        }
+
+        let mut bytes = format!("8={}\x019={}\x01", BEGIN_STRING, body.len()).into_bytes();
+        bytes.extend_from_slice(&body);
+        let checksum = checksum(&bytes);
+        bytes.extend_from_slice(format!("10={:03}\x01", checksum).as_bytes());
+        bytes
+    }
+
+    /// Decodes one complete message, verifying BeginString, BodyLength and CheckSum
+    pub fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let text = std::str::from_utf8(bytes).map_err(|_| invalid("Message is not valid UTF-8"))?;
+        let text = text.strip_suffix('\x01').ok_or_else(|| invalid("Message does not end with SOH"))?;
+
+        let mut fields = Vec::new();
+        for field in text.split('\x01') {
+            let (tag, value) = field
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("Malformed field '{}'", field)))?;
+            let tag = tag
+                .parse::<u32>()
+                .map_err(|_| invalid(format!("Malformed tag '{}'", tag)))?;
+            fields.push((tag, value.to_string()));
+        }
+
+        match fields.first() {
+            Some((tags::BEGIN_STRING, version)) if version == BEGIN_STRING => {}
+            Some((tags::BEGIN_STRING, version)) => return Err(invalid(format!("Unsupported BeginString {}", version))),
+            _ => return Err(invalid("BeginString must be the first field")),
+        }
+        let body_length = match fields.get(1) {
+            Some((tags::BODY_LENGTH, length)) => length
+                .parse::<usize>()
+                .map_err(|_| invalid(format!("Malformed BodyLength {}", length)))?,
+            _ => return Err(invalid("BodyLength must be the second field")),
+        };
+        let expected_checksum = match fields.last() {
+            Some((tags::CHECKSUM, checksum)) => checksum
+                .parse::<u8>()
+                .map_err(|_| invalid(format!("Malformed CheckSum {}", checksum)))?,
+            _ => return Err(invalid("CheckSum must be the last field")),
+        };
+
+        // The body runs from after BodyLength up to the CheckSum field
+        let body_start = text.find("\x019=").map(|i| i + 1).and_then(|i| text[i..].find('\x01').map(|j| i + j + 1));
+        let checksum_start = text.rfind("\x0110=").map(|i| i + 1);
+        let (Some(body_start), Some(checksum_start)) = (body_start, checksum_start) else {
+            return Err(invalid("Message is missing its body"));
+        };
+        if checksum_start - body_start != body_length {
+            return Err(invalid(format!(
+                "BodyLength is {} but the body is {} bytes",
+                body_length,
+                checksum_start - body_start
+            )));
+        }
+        let actual_checksum = checksum(&bytes[..checksum_start]);
+        if actual_checksum != expected_checksum {
+            return Err(invalid(format!(
+                "CheckSum is {:03} but the message sums to {:03}",
+                expected_checksum, actual_checksum
+            )));
+        }
+
+        fields.truncate(fields.len() - 1);
+        fields.drain(..2);
+        if fields.first().map(|(tag, _)| *tag) != Some(tags::MSG_TYPE) {
+            return Err(invalid("MsgType must be the third field"));
+        }
+
+        Ok(Self { fields })
+    }
+}
+
+impl fmt::Display for FixMessage {
+    /// Formats the encoded message with `|` in place of SOH, for logs
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let encoded = self.encode();
+        write!(f, "{}", String::from_utf8_lossy(&encoded).replace('\x01', "|"))
+    }
+}
+
+/// Returns the length of the first complete message in `buffer`, if any
+///
+/// Fails if the buffer does not start with a BeginString and BodyLength,
+/// in which case the stream cannot be resynchronised.
+pub fn frame_length(buffer: &[u8]) -> io::Result<Option<usize>> {
+    const PREFIX: &[u8] = b"8=";
+
+    let prefix_len = PREFIX.len().min(buffer.len());
+    if buffer[..prefix_len] != PREFIX[..prefix_len] {
+        return Err(invalid("Message does not start with BeginString"));
+    }
+
+    // BeginString and BodyLength fields
+    let Some(begin_end) = buffer.iter().position(|&b| b == SOH) else {
+        return Ok(None);
+    };
+    let length_start = begin_end + 1;
+    let Some(length_len) = buffer[length_start..].iter().position(|&b| b == SOH) else {
+        return Ok(None);
+    };
+    let length_field = std::str::from_utf8(&buffer[length_start..length_start + length_len])
+        .ok()
+        .and_then(|field| field.strip_prefix("9="))
+        .ok_or_else(|| invalid("BodyLength must follow BeginString"))?;
+    let body_length = length_field
+        .parse::<usize>()
+        .map_err(|_| invalid(format!("Malformed BodyLength {}", length_field)))?;
+
+    // Body followed by the fixed-size "10=NNN<SOH>" trailer
+    let total = length_start + length_len + 1 + body_length + 7;
+    Ok((buffer.len() >= total).then_some(total))
+}
+
+/// Returns the current time in the UTCTimestamp format used by SendingTime
+pub fn sending_time() -> String {
+    format_utc_timestamp(Utc::now())
+}
+
+/// Formats a time as a FIX UTCTimestamp with milliseconds
+pub fn format_utc_timestamp(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%d-%H:%M:%S%.3f").to_string()
+}
+
+/// Sums the bytes of a message modulo 256
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, &b| sum.wrapping_add(b))
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logon() -> FixMessage {
+        let mut message = FixMessage::new(msg_type::LOGON)
+            .with(tags::ENCRYPT_METHOD, 0)
+            .with(tags::HEART_BT_INT, 30);
+        message.set_header("CLIENT", "RUSTFLOW", 1, "20240102-03:04:05.678");
+        message
+    }
+
+    #[test]
+    fn test_encode_layout_and_checksum() {
+        let encoded = logon().encode();
+        let text = String::from_utf8(encoded.clone()).unwrap().replace('\x01', "|");
+        let body = "35=A|49=CLIENT|56=RUSTFLOW|34=1|52=20240102-03:04:05.678|98=0|108=30|";
+        assert_eq!(&text[..text.len() - 7], format!("8=FIX.4.4|9={}|{}", body.len(), body));
+
+        let expected = encoded[..encoded.len() - 7].iter().map(|&b| b as u32).sum::<u32>() % 256;
+        assert_eq!(&text[text.len() - 7..], format!("10={:03}|", expected));
+        assert_eq!(logon().to_string(), text);
+    }
+
+    #[test]
+    fn test_decode_round_trip() {
+        let message = logon();
+        let decoded = FixMessage::decode(&message.encode()).unwrap();
+        assert_eq!(decoded, message);
+        assert_eq!(decoded.msg_type(), msg_type::LOGON);
+        assert_eq!(decoded.seq_num(), Some(1));
+        assert_eq!(decoded.get_parsed::<u64>(tags::HEART_BT_INT), Some(30));
+        assert!(decoded.is_admin());
+        assert!(!FixMessage::new(msg_type::NEW_ORDER_SINGLE).is_admin());
+    }
+
+    #[test]
+    fn test_decode_rejects_corruption() {
+        let encoded = logon().encode();
+
+        let mut bad_checksum = encoded.clone();
+        let len = bad_checksum.len();
+        bad_checksum[len - 2] = if bad_checksum[len - 2] == b'0' { b'1' } else { b'0' };
+        assert!(FixMessage::decode(&bad_checksum).unwrap_err().to_string().starts_with("CheckSum"));
+
+        let text = String::from_utf8(encoded).unwrap();
+        let bad_version = text.replace("FIX.4.4", "FIX.4.2");
+        assert!(FixMessage::decode(bad_version.as_bytes()).is_err());
+
+        let bad_length = text.replacen("9=", "9=1", 1);
+        assert!(FixMessage::decode(bad_length.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_frame_length() {
+        let first = logon().encode();
+        let mut stream = first.clone();
+        stream.extend_from_slice(&FixMessage::new(msg_type::HEARTBEAT).encode());
+
+        assert_eq!(frame_length(&stream).unwrap(), Some(first.len()));
+        assert_eq!(frame_length(&first[..first.len() - 1]).unwrap(), None);
+        assert_eq!(frame_length(b"8").unwrap(), None);
+        assert_eq!(frame_length(b"").unwrap(), None);
+        assert!(frame_length(b"garbage").is_err());
+    }
+
+    #[test]
+    fn test_set_replaces_fields() {
+        let mut message = logon();
+        message.set(tags::HEART_BT_INT, 10);
+        message.set(tags::TEST_REQ_ID, "T1");
+        message.set_header("CLIENT", "RUSTFLOW", 2, "20240102-03:04:06.000");
+        assert_eq!(message.get(tags::HEART_BT_INT), Some("10"));
+        assert_eq!(message.get(tags::TEST_REQ_ID), Some("T1"));
+        assert_eq!(message.seq_num(), Some(2));
+        assert_eq!(message.fields()[3], (tags::MSG_SEQ_NUM, "2".to_string()));
+        assert_eq!(message.fields().iter().filter(|(tag, _)| *tag == tags::MSG_SEQ_NUM).count(), 1);
+    }
+}
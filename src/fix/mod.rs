@@ -0,0 +1,10 @@
+// Export FIX components
+pub mod message;
+pub mod store;
+pub mod session;
+pub mod acceptor;
+
+// Re-export main components
+pub use acceptor::{FixAcceptor, FixConfig, FixReader};
+pub use message::FixMessage;
+pub use store::SessionStore;
@@ -0,0 +1,969 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use log::{debug, info, warn};
+use tokio::sync::mpsc;
+
+use crate::core::handle::EngineHandle;
+use crate::fix::acceptor::FixConfig;
+use crate::fix::message::{self, msg_type, tags, FixMessage};
+use crate::fix::store::SessionStore;
+use crate::models::instrument::Instrument;
+use crate::models::order::{Order, OrderSide, OrderStatus, OrderType};
+use crate::models::price::{Price, Quantity};
+use crate::models::trade::Trade;
+use crate::utils::time;
+
+/// SessionRejectReason (373) values
+mod reject_reason {
+    pub const REQUIRED_TAG_MISSING: u32 = 1;
+    pub const VALUE_INCORRECT: u32 = 5;
+    pub const COMP_ID_PROBLEM: u32 = 9;
+    pub const INVALID_MSG_TYPE: u32 = 11;
+}
+
+/// OrdRejReason (103) values
+mod ord_rej_reason {
+    pub const DUPLICATE_ORDER: u32 = 6;
+    pub const INCORRECT_QUANTITY: u32 = 13;
+    pub const OTHER: u32 = 99;
+}
+
+/// CxlRejReason (102) values
+mod cxl_rej_reason {
+    pub const TOO_LATE_TO_CANCEL: u32 = 0;
+    pub const UNKNOWN_ORDER: u32 = 1;
+    pub const DUPLICATE_CL_ORD_ID: u32 = 6;
+    pub const OTHER: u32 = 99;
+}
+
+/// ExecType (150) values
+mod exec_type {
+    pub const NEW: &str = "0";
+    pub const CANCELED: &str = "4";
+    pub const REPLACED: &str = "5";
+    pub const REJECTED: &str = "8";
+    pub const EXPIRED: &str = "C";
+    pub const TRADE: &str = "F";
+    pub const TRIGGERED: &str = "L";
+}
+
+/// Why an application message could not be handled
+enum MessageError {
+    /// A required tag is missing; answered by a session-level Reject
+    Missing(u32),
+    /// A tag has a malformed value; answered by a session-level Reject
+    Value(u32),
+    /// Writing to the session store failed
+    Io(io::Error),
+}
+
+impl From<io::Error> for MessageError {
+    fn from(e: io::Error) -> Self {
+        MessageError::Io(e)
+    }
+}
+
+/// What the acceptor knows about an order entered over FIX
+#[derive(Debug, Clone)]
+pub(crate) struct OrderInfo {
+    /// Comp ID of the counterparty that owns the order
+    counterparty: String,
+    /// Current ClOrdID
+    cl_ord_id: String,
+    /// ClOrdID the current one replaced, if any
+    orig_cl_ord_id: Option<String>,
+    /// OrdType (40) as sent by the counterparty
+    ord_type: String,
+    symbol: String,
+    side: OrderSide,
+    /// Limit price, if the order has one
+    price: Option<Price>,
+    order_qty: Quantity,
+    cum_qty: Quantity,
+    /// Sum of price * quantity over all fills, for AvgPx
+    cum_notional: u128,
+    status: OrderStatus,
+}
+
+impl OrderInfo {
+    fn leaves_qty(&self) -> Quantity {
+        if self.status.is_terminal() {
+            Quantity::ZERO
+        } else {
+            self.order_qty - self.cum_qty
+        }
+    }
+
+    fn avg_px(&self) -> Price {
+        if self.cum_qty.is_zero() {
+            return Price::ZERO;
+        }
+        let cum_qty = self.cum_qty.0 as u128;
+        Price(((self.cum_notional + cum_qty / 2) / cum_qty) as u64)
+    }
+
+    fn apply_fill(&mut self, price: Price, quantity: Quantity) {
+        self.cum_qty += quantity;
+        self.cum_notional = self.cum_notional.saturating_add(price.notional(quantity));
+        self.status = if self.cum_qty >= self.order_qty {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+    }
+}
+
+/// FIX orders by engine order ID and by counterparty and ClOrdID
+#[derive(Default)]
+struct Orders {
+    by_id: HashMap<u64, OrderInfo>,
+    by_cl_ord_id: HashMap<(String, String), u64>,
+}
+
+/// State shared by all FIX sessions of an acceptor
+pub(crate) struct Shared {
+    pub(crate) engine: EngineHandle,
+    pub(crate) config: FixConfig,
+    next_order_id: AtomicU64,
+    next_exec_id: AtomicU64,
+    /// Session stores by counterparty, kept across reconnects
+    stores: Mutex<HashMap<String, Arc<Mutex<SessionStore>>>>,
+    /// Outbound queues of the connected sessions by counterparty
+    sessions: Mutex<HashMap<String, mpsc::UnboundedSender<FixMessage>>>,
+    orders: Mutex<Orders>,
+}
+
+impl Shared {
+    pub(crate) fn new(engine: EngineHandle, config: FixConfig) -> Self {
+        Self {
+            engine,
+            config,
+            next_order_id: AtomicU64::new(1),
+            next_exec_id: AtomicU64::new(1),
+            stores: Mutex::new(HashMap::new()),
+            sessions: Mutex::new(HashMap::new()),
+            orders: Mutex::new(Orders::default()),
+        }
+    }
+
+    /// Returns true if an engine order was entered over FIX
+    pub(crate) fn is_fix_order(&self, order_id: u64) -> bool {
+        self.orders.lock().unwrap().by_id.contains_key(&order_id)
+    }
+
+    /// Applies one side of a trade to a FIX order and reports it to the owner
+    pub(crate) fn report_fill(&self, trade: &Trade, order_id: u64) {
+        if let Some((counterparty, report)) = self.apply_fill(trade, order_id) {
+            self.deliver(&counterparty, report);
+        }
+    }
+
+    /// Applies one side of a trade to a FIX order, returning its owner and the report
+    fn apply_fill(&self, trade: &Trade, order_id: u64) -> Option<(String, FixMessage)> {
+        let mut orders = self.orders.lock().unwrap();
+        let info = orders.by_id.get_mut(&order_id)?;
+        info.apply_fill(trade.price, trade.quantity);
+
+        let report = self
+            .execution_report(order_id, info, exec_type::TRADE)
+            .with(tags::LAST_PX, self.config.instrument(&info.symbol).price_to_string(trade.price))
+            .with(tags::LAST_QTY, self.config.instrument(&info.symbol).format_quantity(trade.quantity));
+        Some((info.counterparty.clone(), report))
+    }
+
+    /// Sends a message to a counterparty, storing it for resend if it is not connected
+    fn deliver(&self, counterparty: &str, message: FixMessage) {
+        let message = match self.sessions.lock().unwrap().get(counterparty) {
+            Some(sender) => match sender.send(message) {
+                Ok(()) => return,
+                Err(mpsc::error::SendError(message)) => message,
+            },
+            None => message,
+        };
+
+        debug!("Storing FIX message for disconnected session {}", counterparty);
+        let stored = self
+            .store(counterparty)
+            .and_then(|store| stamp(&mut store.lock().unwrap(), self.config.comp_id(), counterparty, message).map(|_| ()));
+        if let Err(e) = stored {
+            warn!("Failed to store FIX message for {}: {}", counterparty, e);
+        }
+    }
+
+    /// Returns the store for a counterparty, opening it on first use
+    fn store(&self, counterparty: &str) -> io::Result<Arc<Mutex<SessionStore>>> {
+        let mut stores = self.stores.lock().unwrap();
+        if let Some(store) = stores.get(counterparty) {
+            return Ok(Arc::clone(store));
+        }
+
+        let store = match self.config.store_dir() {
+            Some(dir) => SessionStore::with_dir(dir, counterparty)?,
+            None => SessionStore::new(),
+        };
+        let store = Arc::new(Mutex::new(store));
+        stores.insert(counterparty.to_string(), Arc::clone(&store));
+        Ok(store)
+    }
+
+    /// Builds an ExecutionReport from the acceptor's view of an order
+    fn execution_report(&self, order_id: u64, info: &OrderInfo, exec_type: &str) -> FixMessage {
+        let instrument = self.config.instrument(&info.symbol);
+        let exec_id = self.next_exec_id.fetch_add(1, Ordering::Relaxed);
+
+        let mut report = FixMessage::new(msg_type::EXECUTION_REPORT)
+            .with(tags::ORDER_ID, order_id)
+            .with(tags::CL_ORD_ID, &info.cl_ord_id);
+        if let Some(orig_cl_ord_id) = &info.orig_cl_ord_id {
+            report = report.with(tags::ORIG_CL_ORD_ID, orig_cl_ord_id);
+        }
+        report = report
+            .with(tags::EXEC_ID, exec_id)
+            .with(tags::EXEC_TYPE, exec_type)
+            .with(tags::ORD_STATUS, ord_status(info.status))
+            .with(tags::SYMBOL, &info.symbol)
+            .with(tags::SIDE, side_code(info.side))
+            .with(tags::ORDER_QTY, instrument.format_quantity(info.order_qty));
+        if let Some(price) = info.price {
+            report = report.with(tags::PRICE, instrument.price_to_string(price));
+        }
+        report
+            .with(tags::LEAVES_QTY, instrument.format_quantity(info.leaves_qty()))
+            .with(tags::CUM_QTY, instrument.format_quantity(info.cum_qty))
+            .with(tags::AVG_PX, instrument.price_to_string(info.avg_px()))
+            .with(tags::TRANSACT_TIME, message::sending_time())
+    }
+}
+
+/// Stamps a message with the next outgoing sequence number and records it
+fn stamp(store: &mut SessionStore, sender: &str, target: &str, mut message: FixMessage) -> io::Result<FixMessage> {
+    message.set_header(sender, target, store.next_sender_seq_num(), &message::sending_time());
+    store.record_sent(&message)?;
+    Ok(message)
+}
+
+/// One logged-on FIX session
+///
+/// Handles sequence numbers, gap detection and administrative messages,
+/// and turns application messages into engine requests. Outgoing messages
+/// are stamped and recorded as they are produced and collected in an
+/// outbox for the connection to write.
+pub(crate) struct Session {
+    counterparty: String,
+    user_id: u64,
+    heart_bt_int: u64,
+    store: Arc<Mutex<SessionStore>>,
+    sender_comp_id: String,
+    /// Messages received ahead of a sequence gap, by sequence number
+    queued: BTreeMap<u64, FixMessage>,
+    /// Whether a ResendRequest for the current gap is outstanding
+    resend_requested: bool,
+    outbox: Vec<FixMessage>,
+    closing: bool,
+}
+
+impl Session {
+    /// Validates a Logon and starts the session, queueing the Logon reply
+    ///
+    /// Fails if the counterparty cannot be identified or is already logged
+    /// on; such connections are dropped without a reply.
+    pub(crate) fn logon(
+        shared: &Shared,
+        logon: &FixMessage,
+        outbound: mpsc::UnboundedSender<FixMessage>,
+    ) -> Result<Self, String> {
+        let counterparty = logon.get(tags::SENDER_COMP_ID).unwrap_or_default().to_string();
+        if logon.get(tags::TARGET_COMP_ID) != Some(shared.config.comp_id()) {
+            return Err(format!("Logon from {} has the wrong TargetCompID", counterparty));
+        }
+        let user_id = shared
+            .config
+            .user_id(&counterparty)
+            .ok_or_else(|| format!("Unknown SenderCompID '{}'", counterparty))?;
+        let store = shared.store(&counterparty).map_err(|e| e.to_string())?;
+
+        {
+            let mut sessions = shared.sessions.lock().unwrap();
+            if sessions.get(&counterparty).is_some_and(|sender| !sender.is_closed()) {
+                return Err(format!("Session {} is already logged on", counterparty));
+            }
+            sessions.insert(counterparty.clone(), outbound);
+        }
+
+        let mut session = Self {
+            counterparty,
+            user_id,
+            heart_bt_int: 0,
+            store,
+            sender_comp_id: shared.config.comp_id().to_string(),
+            queued: BTreeMap::new(),
+            resend_requested: false,
+            outbox: Vec::new(),
+            closing: false,
+        };
+        if let Err(e) = session.on_logon(logon) {
+            session.close(shared);
+            return Err(e.to_string());
+        }
+        Ok(session)
+    }
+
+    fn on_logon(&mut self, logon: &FixMessage) -> io::Result<()> {
+        let Some(heart_bt_int) = logon.get_parsed::<u64>(tags::HEART_BT_INT) else {
+            return self.logout("Logon requires a valid HeartBtInt");
+        };
+        let Some(seq_num) = logon.seq_num() else {
+            return self.logout("MsgSeqNum missing");
+        };
+        self.heart_bt_int = heart_bt_int;
+
+        let reset = logon.get_flag(tags::RESET_SEQ_NUM_FLAG);
+        if reset {
+            self.store.lock().unwrap().reset()?;
+        }
+
+        let expected = self.next_target_seq_num();
+        if seq_num < expected {
+            return self.logout(&format!(
+                "MsgSeqNum too low, expecting {} but received {}",
+                expected, seq_num
+            ));
+        }
+
+        let mut reply = FixMessage::new(msg_type::LOGON)
+            .with(tags::ENCRYPT_METHOD, 0)
+            .with(tags::HEART_BT_INT, heart_bt_int);
+        if reset {
+            reply = reply.with(tags::RESET_SEQ_NUM_FLAG, "Y");
+        }
+        self.send(reply)?;
+        info!("FIX session {} logged on as user {}", self.counterparty, self.user_id);
+
+        if seq_num > expected {
+            self.request_resend(expected)
+        } else {
+            self.store.lock().unwrap().incr_target_seq_num()
+        }
+    }
+
+    /// Returns the heartbeat interval agreed at logon, in seconds
+    pub(crate) fn heart_bt_int(&self) -> u64 {
+        self.heart_bt_int
+    }
+
+    /// Returns the counterparty comp ID
+    pub(crate) fn counterparty(&self) -> &str {
+        &self.counterparty
+    }
+
+    /// Returns true once the session has logged out or must disconnect
+    pub(crate) fn is_closing(&self) -> bool {
+        self.closing
+    }
+
+    /// Returns the messages produced since the last call
+    pub(crate) fn take_outbox(&mut self) -> Vec<FixMessage> {
+        std::mem::take(&mut self.outbox)
+    }
+
+    /// Stamps and records a message and adds it to the outbox
+    pub(crate) fn send(&mut self, message: FixMessage) -> io::Result<()> {
+        let message = stamp(
+            &mut self.store.lock().unwrap(),
+            &self.sender_comp_id,
+            &self.counterparty,
+            message,
+        )?;
+        self.outbox.push(message);
+        Ok(())
+    }
+
+    /// Stops routing reports to this connection
+    pub(crate) fn close(&self, shared: &Shared) {
+        shared.sessions.lock().unwrap().remove(&self.counterparty);
+    }
+
+    fn next_target_seq_num(&self) -> u64 {
+        self.store.lock().unwrap().next_target_seq_num()
+    }
+
+    /// Handles a message received after logon
+    pub(crate) async fn on_message(&mut self, shared: &Shared, message: FixMessage) -> io::Result<()> {
+        if message.get(tags::SENDER_COMP_ID) != Some(self.counterparty.as_str())
+            || message.get(tags::TARGET_COMP_ID) != Some(self.sender_comp_id.as_str())
+        {
+            self.reject(&message, reject_reason::COMP_ID_PROBLEM, None, "CompID problem")?;
+            return self.logout("Incorrect SenderCompID or TargetCompID");
+        }
+        let Some(seq_num) = message.seq_num() else {
+            return self.logout("MsgSeqNum missing");
+        };
+
+        // SequenceReset-Reset ignores sequence numbers altogether
+        if message.msg_type() == msg_type::SEQUENCE_RESET && !message.get_flag(tags::GAP_FILL_FLAG) {
+            return self.sequence_reset(&message);
+        }
+
+        let expected = self.next_target_seq_num();
+        if seq_num > expected {
+            debug!(
+                "FIX session {} sequence gap: expected {} but received {}",
+                self.counterparty, expected, seq_num
+            );
+            match message.msg_type() {
+                // Answered straight away; the counterparty gap fills them later
+                msg_type::RESEND_REQUEST | msg_type::LOGOUT => self.dispatch(shared, message).await?,
+                _ => {
+                    self.queued.insert(seq_num, message);
+                }
+            }
+            if !self.resend_requested {
+                self.request_resend(expected)?;
+            }
+            return Ok(());
+        }
+        if seq_num < expected {
+            if message.get_flag(tags::POSS_DUP_FLAG) {
+                debug!("Ignoring possible duplicate {} from {}", seq_num, self.counterparty);
+                return Ok(());
+            }
+            return self.logout(&format!(
+                "MsgSeqNum too low, expecting {} but received {}",
+                expected, seq_num
+            ));
+        }
+
+        self.store.lock().unwrap().incr_target_seq_num()?;
+        self.dispatch(shared, message).await?;
+
+        // Process anything that was waiting for the gap to close
+        while !self.closing {
+            let expected = self.next_target_seq_num();
+            self.queued.retain(|&seq_num, _| seq_num >= expected);
+            let Some(next) = self.queued.remove(&expected) else {
+                break;
+            };
+            self.store.lock().unwrap().incr_target_seq_num()?;
+            self.dispatch(shared, next).await?;
+        }
+        if self.queued.is_empty() {
+            self.resend_requested = false;
+        }
+        Ok(())
+    }
+
+    async fn dispatch(&mut self, shared: &Shared, message: FixMessage) -> io::Result<()> {
+        let result = match message.msg_type() {
+            msg_type::HEARTBEAT => Ok(()),
+            msg_type::TEST_REQUEST => {
+                let mut heartbeat = FixMessage::new(msg_type::HEARTBEAT);
+                if let Some(test_req_id) = message.get(tags::TEST_REQ_ID) {
+                    heartbeat = heartbeat.with(tags::TEST_REQ_ID, test_req_id);
+                }
+                self.send(heartbeat).map_err(MessageError::from)
+            }
+            msg_type::RESEND_REQUEST => self.resend(&message),
+            msg_type::SEQUENCE_RESET => self.sequence_reset(&message).map_err(MessageError::from),
+            msg_type::REJECT => {
+                warn!("FIX session {} rejected our message: {}", self.counterparty, message);
+                Ok(())
+            }
+            msg_type::LOGOUT => {
+                info!("FIX session {} logged out", self.counterparty);
+                self.closing = true;
+                self.send(FixMessage::new(msg_type::LOGOUT)).map_err(MessageError::from)
+            }
+            msg_type::LOGON => self
+                .reject(&message, reject_reason::VALUE_INCORRECT, None, "Already logged on")
+                .map_err(MessageError::from),
+            msg_type::NEW_ORDER_SINGLE => self.new_order_single(shared, &message).await,
+            msg_type::ORDER_CANCEL_REQUEST => self.cancel_request(shared, &message).await,
+            msg_type::ORDER_CANCEL_REPLACE_REQUEST => self.cancel_replace_request(shared, &message).await,
+            _ => self
+                .reject(&message, reject_reason::INVALID_MSG_TYPE, None, "Unsupported MsgType")
+                .map_err(MessageError::from),
+        };
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(MessageError::Missing(tag)) => {
+                self.reject(&message, reject_reason::REQUIRED_TAG_MISSING, Some(tag), "Required tag missing")
+            }
+            Err(MessageError::Value(tag)) => self.reject(
+                &message,
+                reject_reason::VALUE_INCORRECT,
+                Some(tag),
+                "Value is incorrect for this tag",
+            ),
+            Err(MessageError::Io(e)) => Err(e),
+        }
+    }
+
+    /// Asks the counterparty to resend everything from `begin_seq_no`
+    fn request_resend(&mut self, begin_seq_no: u64) -> io::Result<()> {
+        self.resend_requested = true;
+        self.send(
+            FixMessage::new(msg_type::RESEND_REQUEST)
+                .with(tags::BEGIN_SEQ_NO, begin_seq_no)
+                .with(tags::END_SEQ_NO, 0),
+        )
+    }
+
+    /// Answers a ResendRequest
+    ///
+    /// Stored application messages are resent with PossDupFlag; admin
+    /// messages and anything no longer stored are skipped with
+    /// SequenceReset-GapFill. Resent messages keep their sequence numbers.
+    fn resend(&mut self, request: &FixMessage) -> Result<(), MessageError> {
+        let begin = request
+            .get_parsed::<u64>(tags::BEGIN_SEQ_NO)
+            .ok_or(MessageError::Missing(tags::BEGIN_SEQ_NO))?;
+        let end = request
+            .get_parsed::<u64>(tags::END_SEQ_NO)
+            .ok_or(MessageError::Missing(tags::END_SEQ_NO))?;
+
+        let (end, messages) = {
+            let store = self.store.lock().unwrap();
+            let last_sent = store.next_sender_seq_num() - 1;
+            // EndSeqNo 0 means "everything"
+            let end = if end == 0 { last_sent } else { end.min(last_sent) };
+            (end, store.messages(begin.max(1), end))
+        };
+        info!(
+            "FIX session {} resending {}..={} ({} application messages)",
+            self.counterparty,
+            begin,
+            end,
+            messages.len()
+        );
+
+        let mut next = begin.max(1);
+        for (seq_num, mut resent) in messages {
+            if seq_num > next {
+                self.outbox.push(self.gap_fill(next, seq_num));
+            }
+            if let Some(original) = resent.get(tags::SENDING_TIME).map(str::to_string) {
+                resent.set(tags::ORIG_SENDING_TIME, original);
+            }
+            resent.set(tags::POSS_DUP_FLAG, "Y");
+            resent.set_header(&self.sender_comp_id, &self.counterparty, seq_num, &message::sending_time());
+            self.outbox.push(resent);
+            next = seq_num + 1;
+        }
+        if next <= end {
+            self.outbox.push(self.gap_fill(next, end + 1));
+        }
+        Ok(())
+    }
+
+    /// Builds a SequenceReset-GapFill covering `seq_num..new_seq_no`
+    fn gap_fill(&self, seq_num: u64, new_seq_no: u64) -> FixMessage {
+        let mut gap_fill = FixMessage::new(msg_type::SEQUENCE_RESET)
+            .with(tags::POSS_DUP_FLAG, "Y")
+            .with(tags::GAP_FILL_FLAG, "Y")
+            .with(tags::NEW_SEQ_NO, new_seq_no);
+        gap_fill.set_header(&self.sender_comp_id, &self.counterparty, seq_num, &message::sending_time());
+        gap_fill
+    }
+
+    /// Moves the expected incoming sequence number forward
+    fn sequence_reset(&mut self, message: &FixMessage) -> io::Result<()> {
+        let Some(new_seq_no) = message.get_parsed::<u64>(tags::NEW_SEQ_NO) else {
+            return self.reject(message, reject_reason::REQUIRED_TAG_MISSING, Some(tags::NEW_SEQ_NO), "NewSeqNo missing");
+        };
+
+        let mut store = self.store.lock().unwrap();
+        if new_seq_no < store.next_target_seq_num() {
+            drop(store);
+            return self.reject(
+                message,
+                reject_reason::VALUE_INCORRECT,
+                Some(tags::NEW_SEQ_NO),
+                "NewSeqNo may not decrease the sequence number",
+            );
+        }
+        store.set_next_target_seq_num(new_seq_no)
+    }
+
+    /// Sends Logout with a reason and closes the session
+    fn logout(&mut self, text: &str) -> io::Result<()> {
+        warn!("Logging out FIX session {}: {}", self.counterparty, text);
+        self.closing = true;
+        self.send(FixMessage::new(msg_type::LOGOUT).with(tags::TEXT, text))
+    }
+
+    /// Sends a session-level Reject for a received message
+    fn reject(&mut self, message: &FixMessage, reason: u32, ref_tag: Option<u32>, text: &str) -> io::Result<()> {
+        let mut reject = FixMessage::new(msg_type::REJECT).with(tags::REF_SEQ_NUM, message.seq_num().unwrap_or(0));
+        if let Some(ref_tag) = ref_tag {
+            reject = reject.with(tags::REF_TAG_ID, ref_tag);
+        }
+        self.send(
+            reject
+                .with(tags::REF_MSG_TYPE, message.msg_type())
+                .with(tags::SESSION_REJECT_REASON, reason)
+                .with(tags::TEXT, text),
+        )
+    }
+
+    /// Handles NewOrderSingle (D)
+    async fn new_order_single(&mut self, shared: &Shared, message: &FixMessage) -> Result<(), MessageError> {
+        let cl_ord_id = required(message, tags::CL_ORD_ID)?.to_string();
+        let symbol = required(message, tags::SYMBOL)?.to_string();
+        let side = parse_side(required(message, tags::SIDE)?).ok_or(MessageError::Value(tags::SIDE))?;
+        let instrument = shared.config.instrument(&symbol);
+        let quantity = parse_quantity(&instrument, message, tags::ORDER_QTY)?;
+        let ord_type = required(message, tags::ORD_TYPE)?.to_string();
+        let time_in_force = message.get(tags::TIME_IN_FORCE).unwrap_or("0");
+
+        let order_type = match ord_type.as_str() {
+            "1" => OrderType::Market,
+            "2" => OrderType::Limit,
+            "3" => OrderType::Stop(parse_price(&instrument, message, tags::STOP_PX)?),
+            "4" => OrderType::StopLimit(
+                parse_price(&instrument, message, tags::STOP_PX)?,
+                parse_price(&instrument, message, tags::PRICE)?,
+            ),
+            _ => return Err(MessageError::Value(tags::ORD_TYPE)),
+        };
+        let price = match order_type {
+            OrderType::Limit => Some(parse_price(&instrument, message, tags::PRICE)?),
+            OrderType::StopLimit(_, limit_price) => Some(limit_price),
+            _ => None,
+        };
+
+        let mut info = OrderInfo {
+            counterparty: self.counterparty.clone(),
+            cl_ord_id: cl_ord_id.clone(),
+            orig_cl_ord_id: None,
+            ord_type,
+            symbol: symbol.clone(),
+            side,
+            price,
+            order_qty: quantity,
+            cum_qty: Quantity::ZERO,
+            cum_notional: 0,
+            status: OrderStatus::PendingNew,
+        };
+
+        // Day and good-till-cancel orders rest until canceled; limits may also be IOC or FOK
+        let order_type = match (order_type, time_in_force) {
+            (order_type, "0" | "1") => order_type,
+            (OrderType::Market, "3") => OrderType::Market,
+            (OrderType::Limit, "3") => OrderType::IOC,
+            (OrderType::Limit, "4") => OrderType::FOK,
+            _ => {
+                return self.reject_order(shared, info, ord_rej_reason::OTHER, "Unsupported TimeInForce for OrdType");
+            }
+        };
+        if quantity.is_zero() {
+            return self.reject_order(shared, info, ord_rej_reason::INCORRECT_QUANTITY, "OrderQty must be positive");
+        }
+
+        let order_id = {
+            let mut orders = shared.orders.lock().unwrap();
+            let key = (self.counterparty.clone(), cl_ord_id.clone());
+            if orders.by_cl_ord_id.contains_key(&key) {
+                drop(orders);
+                return self.reject_order(shared, info, ord_rej_reason::DUPLICATE_ORDER, "Duplicate ClOrdID");
+            }
+            let order_id = shared.next_order_id.fetch_add(1, Ordering::Relaxed);
+            info.status = OrderStatus::New;
+            orders.by_cl_ord_id.insert(key, order_id);
+            orders.by_id.insert(order_id, info.clone());
+            order_id
+        };
+
+        let order = Order::new(
+            order_id,
+            order_type,
+            price.unwrap_or_default(),
+            quantity,
+            side,
+            self.user_id,
+            time::current_timestamp_nanos(),
+            Some(cl_ord_id),
+            symbol,
+        );
+        let ack = match shared.engine.submit(order).await {
+            Ok(ack) if ack.status != OrderStatus::Rejected => ack,
+            Ok(_) => return self.reject_accepted(shared, order_id, "Order rejected by the engine"),
+            Err(e) => return self.reject_accepted(shared, order_id, &e.to_string()),
+        };
+        debug!("FIX order {} from {} is {}", order_id, self.counterparty, ack.status);
+
+        self.send(shared.execution_report(order_id, &info, exec_type::NEW))?;
+        self.report_trades(shared, order_id, &ack.trades)?;
+        self.report_status(shared, order_id, ack.status)?;
+        Ok(())
+    }
+
+    /// Handles OrderCancelRequest (F)
+    async fn cancel_request(&mut self, shared: &Shared, message: &FixMessage) -> Result<(), MessageError> {
+        let orig_cl_ord_id = required(message, tags::ORIG_CL_ORD_ID)?.to_string();
+        let cl_ord_id = required(message, tags::CL_ORD_ID)?.to_string();
+        required(message, tags::SYMBOL)?;
+        required(message, tags::SIDE)?;
+
+        let reject = CancelReject::new(msg_type::ORDER_CANCEL_REQUEST, &orig_cl_ord_id, &cl_ord_id);
+        let (order_id, info) = match self.lookup(shared, &orig_cl_ord_id, &cl_ord_id) {
+            Ok(found) => found,
+            Err(reason) => return self.cancel_reject(reject, None, reason.0, reason.1),
+        };
+
+        match shared.engine.cancel(&info.symbol, order_id).await {
+            Ok(true) => {
+                let info = self.rename(shared, order_id, orig_cl_ord_id, cl_ord_id, |info| {
+                    info.status = OrderStatus::Canceled;
+                });
+                self.send(shared.execution_report(order_id, &info, exec_type::CANCELED))?;
+                Ok(())
+            }
+            Ok(false) => self.cancel_reject(
+                reject,
+                Some((order_id, &info)),
+                cxl_rej_reason::TOO_LATE_TO_CANCEL,
+                "Order is not active",
+            ),
+            Err(e) => self.cancel_reject(reject, Some((order_id, &info)), cxl_rej_reason::OTHER, &e.to_string()),
+        }
+    }
+
+    /// Handles OrderCancelReplaceRequest (G), changing price and/or total quantity
+    async fn cancel_replace_request(&mut self, shared: &Shared, message: &FixMessage) -> Result<(), MessageError> {
+        let orig_cl_ord_id = required(message, tags::ORIG_CL_ORD_ID)?.to_string();
+        let cl_ord_id = required(message, tags::CL_ORD_ID)?.to_string();
+        let symbol = required(message, tags::SYMBOL)?;
+        required(message, tags::SIDE)?;
+        let ord_type = required(message, tags::ORD_TYPE)?;
+        let instrument = shared.config.instrument(symbol);
+        let quantity = parse_quantity(&instrument, message, tags::ORDER_QTY)?;
+        let price = match message.get(tags::PRICE) {
+            Some(_) => Some(parse_price(&instrument, message, tags::PRICE)?),
+            None => None,
+        };
+
+        let reject = CancelReject::new(msg_type::ORDER_CANCEL_REPLACE_REQUEST, &orig_cl_ord_id, &cl_ord_id);
+        let (order_id, info) = match self.lookup(shared, &orig_cl_ord_id, &cl_ord_id) {
+            Ok(found) => found,
+            Err(reason) => return self.cancel_reject(reject, None, reason.0, reason.1),
+        };
+        if ord_type != info.ord_type {
+            return self.cancel_reject(reject, Some((order_id, &info)), cxl_rej_reason::OTHER, "OrdType cannot be changed");
+        }
+
+        let trades = match shared.engine.amend(&info.symbol, order_id, price, Some(quantity)).await {
+            Ok(trades) => trades,
+            Err(e) => {
+                return self.cancel_reject(reject, Some((order_id, &info)), cxl_rej_reason::OTHER, &e.to_string());
+            }
+        };
+
+        let info = self.rename(shared, order_id, orig_cl_ord_id, cl_ord_id, |info| {
+            info.order_qty = quantity;
+            info.price = price.or(info.price);
+        });
+        self.send(shared.execution_report(order_id, &info, exec_type::REPLACED))?;
+        self.report_trades(shared, order_id, &trades)?;
+        Ok(())
+    }
+
+    /// Finds one of this session's orders by OrigClOrdID, checking the new ClOrdID is unused
+    fn lookup(
+        &self,
+        shared: &Shared,
+        orig_cl_ord_id: &str,
+        cl_ord_id: &str,
+    ) -> Result<(u64, OrderInfo), (u32, &'static str)> {
+        let orders = shared.orders.lock().unwrap();
+        if orders
+            .by_cl_ord_id
+            .contains_key(&(self.counterparty.clone(), cl_ord_id.to_string()))
+        {
+            return Err((cxl_rej_reason::DUPLICATE_CL_ORD_ID, "Duplicate ClOrdID"));
+        }
+        orders
+            .by_cl_ord_id
+            .get(&(self.counterparty.clone(), orig_cl_ord_id.to_string()))
+            .and_then(|order_id| orders.by_id.get(order_id).map(|info| (*order_id, info.clone())))
+            .ok_or((cxl_rej_reason::UNKNOWN_ORDER, "Unknown order"))
+    }
+
+    /// Gives an order a new ClOrdID after a cancel or replace and applies a change
+    fn rename(
+        &self,
+        shared: &Shared,
+        order_id: u64,
+        orig_cl_ord_id: String,
+        cl_ord_id: String,
+        change: impl FnOnce(&mut OrderInfo),
+    ) -> OrderInfo {
+        let mut orders = shared.orders.lock().unwrap();
+        orders
+            .by_cl_ord_id
+            .insert((self.counterparty.clone(), cl_ord_id.clone()), order_id);
+        let info = orders.by_id.get_mut(&order_id).expect("Renamed order is known");
+        info.cl_ord_id = cl_ord_id;
+        info.orig_cl_ord_id = Some(orig_cl_ord_id);
+        change(info);
+        info.clone()
+    }
+
+    /// Reports the taker side of trades to this session and the maker side to its owner
+    fn report_trades(&mut self, shared: &Shared, order_id: u64, trades: &[Trade]) -> io::Result<()> {
+        for trade in trades {
+            let maker_order_id = if trade.buy_order_id == order_id {
+                trade.sell_order_id
+            } else {
+                trade.buy_order_id
+            };
+            if let Some((_, report)) = shared.apply_fill(trade, order_id) {
+                self.send(report)?;
+            }
+            shared.report_fill(trade, maker_order_id);
+        }
+        Ok(())
+    }
+
+    /// Reports a new order that was canceled, expired or triggered without filling completely
+    fn report_status(&mut self, shared: &Shared, order_id: u64, status: OrderStatus) -> io::Result<()> {
+        let exec_type = match status {
+            OrderStatus::Canceled => exec_type::CANCELED,
+            OrderStatus::Expired => exec_type::EXPIRED,
+            OrderStatus::Triggered => exec_type::TRIGGERED,
+            _ => return Ok(()),
+        };
+
+        let info = {
+            let mut orders = shared.orders.lock().unwrap();
+            let Some(info) = orders.by_id.get_mut(&order_id) else {
+                return Ok(());
+            };
+            info.status = status;
+            info.clone()
+        };
+        self.send(shared.execution_report(order_id, &info, exec_type))
+    }
+
+    /// Rejects an order before it reaches the engine
+    fn reject_order(&mut self, shared: &Shared, mut info: OrderInfo, reason: u32, text: &str) -> Result<(), MessageError> {
+        info.status = OrderStatus::Rejected;
+        let mut report = shared
+            .execution_report(0, &info, exec_type::REJECTED)
+            .with(tags::ORD_REJ_REASON, reason)
+            .with(tags::TEXT, text);
+        // The order never reached the engine, so it has no order ID
+        report.set(tags::ORDER_ID, "NONE");
+        self.send(report)?;
+        Ok(())
+    }
+
+    /// Rejects an order the acceptor had already registered
+    fn reject_accepted(&mut self, shared: &Shared, order_id: u64, text: &str) -> Result<(), MessageError> {
+        let info = {
+            let mut orders = shared.orders.lock().unwrap();
+            let info = orders.by_id.get_mut(&order_id).expect("Rejected order is known");
+            info.status = OrderStatus::Rejected;
+            info.clone()
+        };
+        self.send(
+            shared
+                .execution_report(order_id, &info, exec_type::REJECTED)
+                .with(tags::ORD_REJ_REASON, ord_rej_reason::OTHER)
+                .with(tags::TEXT, text),
+        )?;
+        Ok(())
+    }
+
+    /// Sends OrderCancelReject (9)
+    fn cancel_reject(
+        &mut self,
+        reject: CancelReject,
+        order: Option<(u64, &OrderInfo)>,
+        reason: u32,
+        text: &str,
+    ) -> Result<(), MessageError> {
+        let (order_id, ord_status) = match order {
+            Some((order_id, info)) => (order_id.to_string(), ord_status(info.status)),
+            None => ("NONE".to_string(), ord_status(OrderStatus::Rejected)),
+        };
+        self.send(
+            FixMessage::new(msg_type::ORDER_CANCEL_REJECT)
+                .with(tags::ORDER_ID, order_id)
+                .with(tags::CL_ORD_ID, reject.cl_ord_id)
+                .with(tags::ORIG_CL_ORD_ID, reject.orig_cl_ord_id)
+                .with(tags::ORD_STATUS, ord_status)
+                .with(tags::CXL_REJ_RESPONSE_TO, reject.response_to)
+                .with(tags::CXL_REJ_REASON, reason)
+                .with(tags::TEXT, text),
+        )?;
+        Ok(())
+    }
+}
+
+/// The request an OrderCancelReject answers
+struct CancelReject {
+    orig_cl_ord_id: String,
+    cl_ord_id: String,
+    /// CxlRejResponseTo (434): 1 for cancel, 2 for cancel/replace
+    response_to: &'static str,
+}
+
+impl CancelReject {
+    fn new(msg_type: &str, orig_cl_ord_id: &str, cl_ord_id: &str) -> Self {
+        Self {
+            orig_cl_ord_id: orig_cl_ord_id.to_string(),
+            cl_ord_id: cl_ord_id.to_string(),
+            response_to: if msg_type == msg_type::ORDER_CANCEL_REQUEST { "1" } else { "2" },
+        }
+    }
+}
+
+fn required(message: &FixMessage, tag: u32) -> Result<&str, MessageError> {
+    message.get(tag).filter(|value| !value.is_empty()).ok_or(MessageError::Missing(tag))
+}
+
+fn parse_price(instrument: &Instrument, message: &FixMessage, tag: u32) -> Result<Price, MessageError> {
+    instrument
+        .parse_price(required(message, tag)?)
+        .map_err(|_| MessageError::Value(tag))
+}
+
+fn parse_quantity(instrument: &Instrument, message: &FixMessage, tag: u32) -> Result<Quantity, MessageError> {
+    instrument
+        .parse_quantity(required(message, tag)?)
+        .map_err(|_| MessageError::Value(tag))
+}
+
+fn parse_side(side: &str) -> Option<OrderSide> {
+    match side {
+        "1" => Some(OrderSide::Buy),
+        "2" => Some(OrderSide::Sell),
+        _ => None,
+    }
+}
+
+/// Returns the Side (54) code of an order side
+fn side_code(side: OrderSide) -> &'static str {
+    match side {
+        OrderSide::Buy => "1",
+        OrderSide::Sell => "2",
+    }
+}
+
+/// Returns the OrdStatus (39) code of an order status
+fn ord_status(status: OrderStatus) -> &'static str {
+    match status {
+        OrderStatus::PendingNew => "A",
+        OrderStatus::New | OrderStatus::Triggered => "0",
+        OrderStatus::PartiallyFilled => "1",
+        OrderStatus::Filled => "2",
+        OrderStatus::Canceled => "4",
+        OrderStatus::Replaced => "5",
+        OrderStatus::Rejected => "8",
+        OrderStatus::Expired => "C",
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use log::{debug, info};
+
+use crate::fix::message::FixMessage;
+
+/// Sequence numbers and sent application messages of one FIX session
+///
+/// File-backed stores keep the next sequence numbers in `<session>.seqnums`
+/// and append every sent application message to `<session>.messages`, so a
+/// restarted acceptor continues the session and can answer resend requests.
+pub struct SessionStore {
+    /// Sequence number of the next message we send
+    next_sender_seq_num: u64,
+    /// Sequence number expected on the next message we receive
+    next_target_seq_num: u64,
+    /// Sent application messages by sequence number, encoded
+    messages: BTreeMap<u64, Vec<u8>>,
+    /// Optional path prefix for persistence
+    file_path: Option<PathBuf>,
+}
+
+impl SessionStore {
+    /// Creates a new in-memory session store
+    pub fn new() -> Self {
+        Self {
+            next_sender_seq_num: 1,
+            next_target_seq_num: 1,
+            messages: BTreeMap::new(),
+            file_path: None,
+        }
+    }
+
+    /// Opens the store for a session in a directory, loading any saved state
+    pub fn with_dir(dir: &str, session_id: &str) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let mut store = Self {
+            file_path: Some(Path::new(dir).join(session_id)),
+            ..Self::new()
+        };
+        store.load()?;
+        Ok(store)
+    }
+
+    /// Returns the sequence number of the next message we send
+    pub fn next_sender_seq_num(&self) -> u64 {
+        self.next_sender_seq_num
+    }
+
+    /// Returns the sequence number expected on the next message we receive
+    pub fn next_target_seq_num(&self) -> u64 {
+        self.next_target_seq_num
+    }
+
+    /// Sets the sequence number expected on the next message we receive
+    pub fn set_next_target_seq_num(&mut self, seq_num: u64) -> io::Result<()> {
+        self.next_target_seq_num = seq_num;
+        self.save_seq_nums()
+    }
+
+    /// Records that the expected message was received
+    pub fn incr_target_seq_num(&mut self) -> io::Result<()> {
+        self.set_next_target_seq_num(self.next_target_seq_num + 1)
+    }
+
+    /// Records a sent message, which must carry the next sender sequence number
+    ///
+    /// Application messages are kept for resending; admin messages only
+    /// advance the sequence number.
+    pub fn record_sent(&mut self, message: &FixMessage) -> io::Result<()> {
+        let seq_num = self.next_sender_seq_num;
+        debug_assert_eq!(message.seq_num(), Some(seq_num));
+        self.next_sender_seq_num += 1;
+
+        if !message.is_admin() {
+            let encoded = message.encode();
+            if let Some(path) = self.messages_path() {
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                file.write_all(&encoded)?;
+                file.write_all(b"\n")?;
+            }
+            self.messages.insert(seq_num, encoded);
+        }
+
+        self.save_seq_nums()
+    }
+
+    /// Returns the sent application messages with sequence numbers in `begin..=end`
+    pub fn messages(&self, begin: u64, end: u64) -> Vec<(u64, FixMessage)> {
+        self.messages
+            .range(begin..=end)
+            .filter_map(|(&seq_num, encoded)| FixMessage::decode(encoded).ok().map(|message| (seq_num, message)))
+            .collect()
+    }
+
+    /// Resets both sequence numbers to 1 and forgets sent messages
+    pub fn reset(&mut self) -> io::Result<()> {
+        info!("Resetting FIX session sequence numbers");
+        self.next_sender_seq_num = 1;
+        self.next_target_seq_num = 1;
+        self.messages.clear();
+
+        if let Some(path) = self.messages_path() {
+            File::create(path)?;
+        }
+        self.save_seq_nums()
+    }
+
+    fn seq_nums_path(&self) -> Option<PathBuf> {
+        self.path_with_suffix(".seqnums")
+    }
+
+    fn messages_path(&self) -> Option<PathBuf> {
+        self.path_with_suffix(".messages")
+    }
+
+    /// Appends a suffix to the path prefix; comp IDs may contain dots
+    fn path_with_suffix(&self, suffix: &str) -> Option<PathBuf> {
+        self.file_path.as_ref().map(|path| {
+            let mut path = path.clone().into_os_string();
+            path.push(suffix);
+            PathBuf::from(path)
+        })
+    }
+
+    fn save_seq_nums(&self) -> io::Result<()> {
+        match self.seq_nums_path() {
+            Some(path) => fs::write(path, format!("{} {}\n", self.next_sender_seq_num, self.next_target_seq_num)),
+            None => Ok(()),
+        }
+    }
+
+    fn load(&mut self) -> io::Result<()> {
+        if let Some(path) = self.seq_nums_path().filter(|path| path.exists()) {
+            let contents = fs::read_to_string(path)?;
+            let seq_nums: Vec<u64> = contents.split_whitespace().filter_map(|n| n.parse().ok()).collect();
+            let [sender, target] = seq_nums[..] else {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Malformed sequence number file"));
+            };
+            self.next_sender_seq_num = sender;
+            self.next_target_seq_num = target;
+        }
+
+        if let Some(path) = self.messages_path().filter(|path| path.exists()) {
+            // Encoded messages never contain a newline, so each line is one message
+            for line in BufReader::new(File::open(path)?).split(b'\n') {
+                let encoded = line?;
+                if let Some(seq_num) = FixMessage::decode(&encoded)?.seq_num() {
+                    self.messages.insert(seq_num, encoded);
+                }
+            }
+        }
+
+        debug!(
+            "Loaded FIX session store: next sender {}, next target {}, {} messages",
+            self.next_sender_seq_num,
+            self.next_target_seq_num,
+            self.messages.len()
+        );
+        Ok(())
+    }
+}
+
+impl Default for SessionStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::message::{msg_type, tags};
+
+    fn sent(store: &SessionStore, msg_type: &str) -> FixMessage {
+        let mut message = FixMessage::new(msg_type).with(tags::TEXT, "hello");
+        message.set_header("RUSTFLOW", "CLIENT", store.next_sender_seq_num(), "20240102-03:04:05.678");
+        message
+    }
+
+    #[test]
+    fn test_in_memory_store() {
+        let mut store = SessionStore::new();
+        let heartbeat = sent(&store, msg_type::HEARTBEAT);
+        store.record_sent(&heartbeat).unwrap();
+        let report = sent(&store, msg_type::EXECUTION_REPORT);
+        store.record_sent(&report).unwrap();
+        store.incr_target_seq_num().unwrap();
+
+        assert_eq!(store.next_sender_seq_num(), 3);
+        assert_eq!(store.next_target_seq_num(), 2);
+        // Only the application message is kept for resending
+        assert_eq!(store.messages(1, 10), vec![(2, report)]);
+
+        store.reset().unwrap();
+        assert_eq!(store.next_sender_seq_num(), 1);
+        assert!(store.messages(1, 10).is_empty());
+    }
+
+    #[test]
+    fn test_file_store_survives_reopen() {
+        let dir = std::env::temp_dir().join(format!("rustflow_fix_store_{}", std::process::id()));
+        let dir = dir.to_str().unwrap();
+
+        let report = {
+            let mut store = SessionStore::with_dir(dir, "CLIENT").unwrap();
+            store.record_sent(&sent(&store, msg_type::LOGON)).unwrap();
+            let report = sent(&store, msg_type::EXECUTION_REPORT);
+            store.record_sent(&report).unwrap();
+            store.set_next_target_seq_num(5).unwrap();
+            report
+        };
+
+        let mut store = SessionStore::with_dir(dir, "CLIENT").unwrap();
+        assert_eq!(store.next_sender_seq_num(), 3);
+        assert_eq!(store.next_target_seq_num(), 5);
+        assert_eq!(store.messages(2, 2), vec![(2, report)]);
+
+        store.reset().unwrap();
+        let store = SessionStore::with_dir(dir, "CLIENT").unwrap();
+        assert_eq!(store.next_sender_seq_num(), 1);
+        assert!(store.messages(1, 10).is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,7 @@
+// Export gateway components
+pub mod protocol;
+pub mod server;
+
+// Re-export main components
+pub use protocol::{ClientMessage, ExecutionReport, ServerMessage};
+pub use server::{load_credentials, parse_credentials, Gateway, GatewayConfig};
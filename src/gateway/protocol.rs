@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::order::{Order, OrderSide, OrderStatus, OrderType};
+use crate::models::price::{Price, Quantity};
+use crate::models::trade::Trade;
+
+/// A command sent by a client, one JSON object per line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Authenticates the session; must be the first message
+    Logon {
+        user_id: u64,
+        #[serde(default)]
+        token: String,
+    },
+    /// Submits a new order; the gateway assigns the order ID
+    NewOrder {
+        symbol: String,
+        side: OrderSide,
+        order_type: OrderType,
+        /// Limit price (ignored for market and stop orders)
+        #[serde(default)]
+        price: Price,
+        quantity: Quantity,
+        #[serde(default)]
+        client_order_id: Option<String>,
+    },
+    /// Cancels one of the session user's orders
+    Cancel { symbol: String, order_id: u64 },
+    /// Changes the price and/or total quantity of one of the session user's orders
+    Amend {
+        symbol: String,
+        order_id: u64,
+        #[serde(default)]
+        price: Option<Price>,
+        #[serde(default)]
+        quantity: Option<Quantity>,
+    },
+    /// Streams every trade on a symbol to this session
+    Subscribe { symbol: String },
+    /// Ends the session
+    Logout,
+}
+
+/// The state of an order after an event, sent to the order's owner
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionReport {
+    /// Gateway-assigned order identifier
+    pub order_id: u64,
+    /// Client-provided order identifier, if any
+    pub client_order_id: Option<String>,
+    /// Symbol/ticker of the order
+    pub symbol: String,
+    /// Order side
+    pub side: OrderSide,
+    /// Status after the event
+    pub status: OrderStatus,
+    /// Order price
+    pub price: Price,
+    /// Total order quantity
+    pub quantity: Quantity,
+    /// Quantity still open
+    pub leaves_quantity: Quantity,
+    /// Price of the fill that caused this report, if any
+    pub last_price: Option<Price>,
+    /// Quantity of the fill that caused this report, if any
+    pub last_quantity: Option<Quantity>,
+}
+
+impl ExecutionReport {
+    /// Builds a report from the current state of an order
+    pub fn from_order(order: &Order) -> Self {
+        Self {
+            order_id: order.id,
+            client_order_id: order.client_order_id.clone(),
+            symbol: order.symbol.clone(),
+            side: order.side,
+            status: order.status,
+            price: order.price,
+            quantity: order.quantity,
+            leaves_quantity: order.remaining_quantity,
+            last_price: None,
+            last_quantity: None,
+        }
+    }
+
+    /// Adds the fill that caused this report
+    pub fn with_fill(mut self, price: Price, quantity: Quantity) -> Self {
+        self.last_price = Some(price);
+        self.last_quantity = Some(quantity);
+        self
+    }
+}
+
+/// A message sent by the gateway, one JSON object per line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// The session is authenticated
+    LogonAck { user_id: u64 },
+    /// A change in the state of one of the session user's orders
+    ExecutionReport(ExecutionReport),
+    /// A trade on a subscribed symbol
+    Trade(Trade),
+    /// The session is now receiving trades for a symbol
+    Subscribed { symbol: String },
+    /// A command was rejected
+    Reject {
+        reason: String,
+        #[serde(default)]
+        order_id: Option<u64>,
+        #[serde(default)]
+        client_order_id: Option<String>,
+    },
+    /// The session is closing
+    LogoutAck,
+}
+
+impl ServerMessage {
+    /// Creates a reject that does not refer to a specific order
+    pub fn reject(reason: impl Into<String>) -> Self {
+        ServerMessage::Reject {
+            reason: reason.into(),
+            order_id: None,
+            client_order_id: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_messages_parse() {
+        let message: ClientMessage = serde_json::from_str(
+            r#"{"type":"new_order","symbol":"BTC-USD","side":"Buy","order_type":"Limit","price":10100,"quantity":5,"client_order_id":"c-1"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            message,
+            ClientMessage::NewOrder {
+                symbol: "BTC-USD".to_string(),
+                side: OrderSide::Buy,
+                order_type: OrderType::Limit,
+                price: Price(10100),
+                quantity: Quantity(5),
+                client_order_id: Some("c-1".to_string()),
+            }
+        );
+
+        let amend: ClientMessage =
+            serde_json::from_str(r#"{"type":"amend","symbol":"BTC-USD","order_id":7,"quantity":3}"#).unwrap();
+        assert!(matches!(amend, ClientMessage::Amend { price: None, quantity: Some(Quantity(3)), .. }));
+        assert_eq!(serde_json::from_str::<ClientMessage>(r#"{"type":"logout"}"#).unwrap(), ClientMessage::Logout);
+    }
+
+    #[test]
+    fn test_server_messages_are_tagged() {
+        let json = serde_json::to_string(&ServerMessage::LogonAck { user_id: 7 }).unwrap();
+        assert_eq!(json, r#"{"type":"logon_ack","user_id":7}"#);
+
+        let order = Order::new_limit(3, 100, 5, OrderSide::Sell, 7, 0, None, "BTC-USD".to_string());
+        let report = ExecutionReport::from_order(&order).with_fill(Price(100), Quantity(2));
+        let json = serde_json::to_string(&ServerMessage::ExecutionReport(report.clone())).unwrap();
+        assert!(json.starts_with(r#"{"type":"execution_report","order_id":3"#));
+        assert_eq!(
+            serde_json::from_str::<ServerMessage>(&json).unwrap(),
+            ServerMessage::ExecutionReport(report)
+        );
+    }
+}
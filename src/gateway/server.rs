@@ -0,0 +1,533 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use log::{debug, info, warn};
+use tokio::io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader as AsyncBufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+
+use crate::core::handle::{EngineHandle, TradeEvent};
+use crate::gateway::protocol::{ClientMessage, ExecutionReport, ServerMessage};
+use crate::models::order::{Order, OrderStatus};
+use crate::utils::time;
+
+/// Session authentication settings
+#[derive(Debug, Clone, Default)]
+pub struct GatewayConfig {
+    /// Token required for each user; None accepts any logon
+    credentials: Option<HashMap<u64, String>>,
+}
+
+impl GatewayConfig {
+    /// Creates a config that accepts any user without a token
+    pub fn new() -> Self {
+        Self { credentials: None }
+    }
+
+    /// Creates a config that only accepts the given users and tokens
+    pub fn with_credentials(credentials: HashMap<u64, String>) -> Self {
+        Self {
+            credentials: Some(credentials),
+        }
+    }
+
+    /// Returns true if the user may log on with the token
+    pub fn authenticate(&self, user_id: u64, token: &str) -> bool {
+        match &self.credentials {
+            Some(credentials) => credentials.get(&user_id).is_some_and(|expected| expected == token),
+            None => true,
+        }
+    }
+}
+
+/// Parses `user_id:token` lines; blank lines and `#` comments are skipped
+pub fn parse_credentials<R: BufRead>(reader: R) -> io::Result<HashMap<u64, String>> {
+    let mut credentials = HashMap::new();
+
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: expected user_id:token", line_number + 1),
+            )
+        };
+        let (user_id, token) = trimmed.split_once(':').ok_or_else(invalid)?;
+        let user_id = user_id.trim().parse::<u64>().map_err(|_| invalid())?;
+        credentials.insert(user_id, token.trim().to_string());
+    }
+
+    Ok(credentials)
+}
+
+/// Loads `user_id:token` credentials from a file
+pub fn load_credentials(file_path: &str) -> io::Result<HashMap<u64, String>> {
+    parse_credentials(BufReader::new(File::open(file_path)?))
+}
+
+/// State shared by all sessions
+struct Shared {
+    engine: EngineHandle,
+    config: GatewayConfig,
+    next_order_id: AtomicU64,
+}
+
+/// TCP order entry gateway speaking newline-delimited JSON
+///
+/// Each connection is a session that logs on as a user, submits commands
+/// to the engine and receives execution reports for that user's orders
+/// plus trades on the symbols it subscribes to.
+#[derive(Clone)]
+pub struct Gateway {
+    shared: Arc<Shared>,
+}
+
+impl Gateway {
+    /// Creates a gateway that forwards commands to the given engine
+    pub fn new(engine: EngineHandle, config: GatewayConfig) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                engine,
+                config,
+                next_order_id: AtomicU64::new(1),
+            }),
+        }
+    }
+
+    /// Accepts connections until the listener fails, running each session on its own task
+    pub async fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            info!("Session connected from {}", peer);
+
+            let shared = Arc::clone(&self.shared);
+            tokio::spawn(async move {
+                match run_session(shared, stream).await {
+                    Ok(()) => info!("Session from {} closed", peer),
+                    Err(e) => warn!("Session from {} failed: {}", peer, e),
+                }
+            });
+        }
+    }
+}
+
+/// The state of one client connection
+#[derive(Default)]
+struct Session {
+    user_id: Option<u64>,
+    subscriptions: HashSet<String>,
+}
+
+async fn run_session(shared: Arc<Shared>, stream: TcpStream) -> io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = AsyncBufReader::new(reader).lines();
+    let mut trades = shared.engine.subscribe_trades();
+    let mut session = Session::default();
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Some(line) = line? else {
+                    break;
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let (replies, close) = match serde_json::from_str::<ClientMessage>(&line) {
+                    Ok(message) => session.handle(&shared, message).await,
+                    Err(e) => (vec![ServerMessage::reject(format!("Invalid message: {}", e))], false),
+                };
+                for reply in &replies {
+                    write_message(&mut writer, reply).await?;
+                }
+                if close {
+                    break;
+                }
+            }
+            event = trades.recv() => match event {
+                Ok(event) => {
+                    for message in session.on_trade(&shared, &event).await {
+                        write_message(&mut writer, &message).await?;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    let reject = ServerMessage::reject(format!("Missed {} trade events", missed));
+                    write_message(&mut writer, &reject).await?;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+
+    writer.shutdown().await
+}
+
+async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &ServerMessage) -> io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line).await
+}
+
+impl Session {
+    /// Handles a client command, returning the replies and whether to close the session
+    async fn handle(&mut self, shared: &Shared, message: ClientMessage) -> (Vec<ServerMessage>, bool) {
+        let user_id = match (&message, self.user_id) {
+            (ClientMessage::Logon { user_id, token }, None) => {
+                if shared.config.authenticate(*user_id, token) {
+                    self.user_id = Some(*user_id);
+                    return (vec![ServerMessage::LogonAck { user_id: *user_id }], false);
+                }
+                warn!("Authentication failed for user {}", user_id);
+                return (vec![ServerMessage::reject("Authentication failed")], true);
+            }
+            (ClientMessage::Logon { .. }, Some(_)) => {
+                return (vec![ServerMessage::reject("Already logged on")], false);
+            }
+            (_, None) => return (vec![ServerMessage::reject("Not logged on")], false),
+            (_, Some(user_id)) => user_id,
+        };
+
+        let replies = match message {
+            ClientMessage::NewOrder {
+                symbol,
+                side,
+                order_type,
+                price,
+                quantity,
+                client_order_id,
+            } => {
+                if quantity.is_zero() {
+                    return (vec![reject_order("Quantity must be positive", None, client_order_id)], false);
+                }
+
+                let order_id = shared.next_order_id.fetch_add(1, Ordering::Relaxed);
+                let order = Order::new(
+                    order_id,
+                    order_type,
+                    price,
+                    quantity,
+                    side,
+                    user_id,
+                    time::current_timestamp_nanos(),
+                    client_order_id,
+                    symbol,
+                );
+                self.submit(shared, order).await
+            }
+            ClientMessage::Cancel { symbol, order_id } => match self.owned_order(shared, &symbol, order_id).await {
+                Err(reject) => vec![reject],
+                Ok(_) => match shared.engine.cancel(&symbol, order_id).await {
+                    Ok(true) => self.report(shared, &symbol, order_id).await.into_iter().collect(),
+                    Ok(false) => vec![reject_order("Order is not active", Some(order_id), None)],
+                    Err(e) => vec![reject_order(e.to_string(), Some(order_id), None)],
+                },
+            },
+            ClientMessage::Amend {
+                symbol,
+                order_id,
+                price,
+                quantity,
+            } => match self.owned_order(shared, &symbol, order_id).await {
+                Err(reject) => vec![reject],
+                Ok(order) => match shared.engine.amend(&symbol, order_id, price, quantity).await {
+                    Ok(_) => self.report(shared, &symbol, order_id).await.into_iter().collect(),
+                    Err(e) => vec![reject_order(e.to_string(), Some(order_id), order.client_order_id)],
+                },
+            },
+            ClientMessage::Subscribe { symbol } => {
+                self.subscriptions.insert(symbol.clone());
+                vec![ServerMessage::Subscribed { symbol }]
+            }
+            ClientMessage::Logout => return (vec![ServerMessage::LogoutAck], true),
+            ClientMessage::Logon { .. } => unreachable!("Logon is handled above"),
+        };
+
+        (replies, false)
+    }
+
+    /// Submits an order and reports each fill followed by its resting status
+    async fn submit(&self, shared: &Shared, order: Order) -> Vec<ServerMessage> {
+        let ack = match shared.engine.submit(order.clone()).await {
+            Ok(ack) => ack,
+            Err(e) => return vec![reject_order(e.to_string(), Some(order.id), order.client_order_id)],
+        };
+        debug!("Order {} for user {} is {}", order.id, order.user_id, ack.status);
+
+        let mut state = order.clone();
+        let mut replies = Vec::new();
+        for trade in &ack.trades {
+            state.remaining_quantity -= trade.quantity;
+            state.status = if state.remaining_quantity.is_zero() {
+                OrderStatus::Filled
+            } else {
+                OrderStatus::PartiallyFilled
+            };
+            replies.push(ServerMessage::ExecutionReport(
+                ExecutionReport::from_order(&state).with_fill(trade.price, trade.quantity),
+            ));
+        }
+
+        // Report acceptance, resting or cancellation of whatever did not fill
+        if ack.trades.is_empty() || !matches!(ack.status, OrderStatus::Filled | OrderStatus::PartiallyFilled) {
+            state.status = ack.status;
+            replies.push(ServerMessage::ExecutionReport(ExecutionReport::from_order(&state)));
+        }
+
+        replies
+    }
+
+    /// Builds the messages a trade produces for this session
+    async fn on_trade(&self, shared: &Shared, event: &TradeEvent) -> Vec<ServerMessage> {
+        let Some(user_id) = self.user_id else {
+            return Vec::new();
+        };
+        let trade = &event.trade;
+        let mut messages = Vec::new();
+
+        if self.subscriptions.contains(&trade.symbol) {
+            messages.push(ServerMessage::Trade(trade.clone()));
+        }
+
+        // The taker already received its fills; report the resting side to its owner
+        let (maker_order_id, maker_user_id) = event.maker();
+        if maker_user_id == user_id {
+            if let Ok(Some(order)) = shared.engine.order(&trade.symbol, maker_order_id).await {
+                messages.push(ServerMessage::ExecutionReport(
+                    ExecutionReport::from_order(&order).with_fill(trade.price, trade.quantity),
+                ));
+            }
+        }
+
+        messages
+    }
+
+    /// Looks up an order, rejecting it unless it belongs to the session user
+    async fn owned_order(&self, shared: &Shared, symbol: &str, order_id: u64) -> Result<Order, ServerMessage> {
+        match shared.engine.order(symbol, order_id).await {
+            // Other users' orders are reported as unknown so their IDs are not revealed
+            Ok(Some(order)) if Some(order.user_id) == self.user_id => Ok(order),
+            Ok(_) => Err(reject_order(format!("Unknown order {}", order_id), Some(order_id), None)),
+            Err(e) => Err(reject_order(e.to_string(), Some(order_id), None)),
+        }
+    }
+
+    /// Reports the current state of an order
+    async fn report(&self, shared: &Shared, symbol: &str, order_id: u64) -> Option<ServerMessage> {
+        match shared.engine.order(symbol, order_id).await {
+            Ok(Some(order)) => Some(ServerMessage::ExecutionReport(ExecutionReport::from_order(&order))),
+            _ => None,
+        }
+    }
+}
+
+fn reject_order(reason: impl Into<String>, order_id: Option<u64>, client_order_id: Option<String>) -> ServerMessage {
+    ServerMessage::Reject {
+        reason: reason.into(),
+        order_id,
+        client_order_id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    use tokio::io::{BufReader as AsyncBufReader, Lines};
+    use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+
+    use crate::models::price::{Price, Quantity};
+
+    struct Client {
+        lines: Lines<AsyncBufReader<OwnedReadHalf>>,
+        writer: OwnedWriteHalf,
+    }
+
+    impl Client {
+        async fn connect(addr: SocketAddr) -> Self {
+            let (reader, writer) = TcpStream::connect(addr).await.unwrap().into_split();
+            Self {
+                lines: AsyncBufReader::new(reader).lines(),
+                writer,
+            }
+        }
+
+        async fn logon(addr: SocketAddr, user_id: u64) -> Self {
+            let mut client = Self::connect(addr).await;
+            client.send(&format!(r#"{{"type":"logon","user_id":{},"token":"secret-{}"}}"#, user_id, user_id)).await;
+            assert_eq!(client.recv().await, ServerMessage::LogonAck { user_id });
+            client
+        }
+
+        async fn send(&mut self, line: &str) {
+            self.writer.write_all(line.as_bytes()).await.unwrap();
+            self.writer.write_all(b"\n").await.unwrap();
+        }
+
+        async fn recv(&mut self) -> ServerMessage {
+            let line = tokio::time::timeout(Duration::from_secs(5), self.lines.next_line())
+                .await
+                .expect("Timed out waiting for the gateway")
+                .unwrap()
+                .expect("Gateway closed the session");
+            serde_json::from_str(&line).unwrap()
+        }
+
+        async fn recv_report(&mut self) -> ExecutionReport {
+            match self.recv().await {
+                ServerMessage::ExecutionReport(report) => report,
+                other => panic!("Expected an execution report, got {:?}", other),
+            }
+        }
+
+        async fn new_order(&mut self, side: &str, order_type: &str, price: u64, quantity: u64) {
+            self.send(&format!(
+                r#"{{"type":"new_order","symbol":"BTC-USD","side":"{}","order_type":"{}","price":{},"quantity":{}}}"#,
+                side, order_type, price, quantity
+            ))
+            .await;
+        }
+    }
+
+    async fn start_gateway() -> SocketAddr {
+        let credentials = (1..=50).map(|user_id| (user_id, format!("secret-{}", user_id))).collect();
+        let gateway = Gateway::new(EngineHandle::spawn(), GatewayConfig::with_credentials(credentials));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { gateway.serve(listener).await });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_logon_is_required() {
+        let addr = start_gateway().await;
+
+        let mut client = Client::connect(addr).await;
+        client.new_order("Buy", "Limit", 100, 1).await;
+        assert!(matches!(client.recv().await, ServerMessage::Reject { reason, .. } if reason == "Not logged on"));
+
+        client.send("not json").await;
+        assert!(matches!(client.recv().await, ServerMessage::Reject { reason, .. } if reason.starts_with("Invalid message")));
+
+        client.send(r#"{"type":"logon","user_id":1,"token":"wrong"}"#).await;
+        assert_eq!(client.recv().await, ServerMessage::reject("Authentication failed"));
+        assert!(client.lines.next_line().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fills_reach_both_sides_and_subscribers() {
+        let addr = start_gateway().await;
+        let mut maker = Client::logon(addr, 1).await;
+        let mut taker = Client::logon(addr, 2).await;
+        let mut watcher = Client::logon(addr, 3).await;
+        watcher.send(r#"{"type":"subscribe","symbol":"BTC-USD"}"#).await;
+        assert_eq!(watcher.recv().await, ServerMessage::Subscribed { symbol: "BTC-USD".to_string() });
+
+        maker.new_order("Sell", "Limit", 10100, 5).await;
+        let resting = maker.recv_report().await;
+        assert_eq!(resting.status, OrderStatus::New);
+        assert_eq!(resting.leaves_quantity, Quantity(5));
+
+        taker.new_order("Buy", "IOC", 10100, 7).await;
+        let fill = taker.recv_report().await;
+        assert_eq!(fill.status, OrderStatus::PartiallyFilled);
+        assert_eq!(fill.last_price, Some(Price(10100)));
+        assert_eq!(fill.last_quantity, Some(Quantity(5)));
+        assert_eq!(fill.leaves_quantity, Quantity(2));
+        assert_eq!(taker.recv_report().await.status, OrderStatus::Canceled);
+
+        let maker_fill = maker.recv_report().await;
+        assert_eq!(maker_fill.order_id, resting.order_id);
+        assert_eq!(maker_fill.status, OrderStatus::Filled);
+        assert_eq!(maker_fill.last_quantity, Some(Quantity(5)));
+
+        match watcher.recv().await {
+            ServerMessage::Trade(trade) => {
+                assert_eq!(trade.buy_user_id, 2);
+                assert_eq!(trade.sell_order_id, resting.order_id);
+            }
+            other => panic!("Expected a trade, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_and_amend_own_orders_only() {
+        let addr = start_gateway().await;
+        let mut owner = Client::logon(addr, 1).await;
+        let mut other = Client::logon(addr, 2).await;
+
+        owner.new_order("Buy", "Limit", 9900, 4).await;
+        let order_id = owner.recv_report().await.order_id;
+
+        other.send(&format!(r#"{{"type":"cancel","symbol":"BTC-USD","order_id":{}}}"#, order_id)).await;
+        assert!(matches!(other.recv().await, ServerMessage::Reject { reason, .. } if reason.starts_with("Unknown order")));
+
+        owner.send(&format!(r#"{{"type":"amend","symbol":"BTC-USD","order_id":{},"price":9950,"quantity":6}}"#, order_id)).await;
+        let amended = owner.recv_report().await;
+        assert_eq!(amended.price, Price(9950));
+        assert_eq!(amended.leaves_quantity, Quantity(6));
+
+        owner.send(&format!(r#"{{"type":"cancel","symbol":"BTC-USD","order_id":{}}}"#, order_id)).await;
+        assert_eq!(owner.recv_report().await.status, OrderStatus::Canceled);
+        owner.send(&format!(r#"{{"type":"cancel","symbol":"BTC-USD","order_id":{}}}"#, order_id)).await;
+        assert!(matches!(owner.recv().await, ServerMessage::Reject { reason, .. } if reason == "Order is not active"));
+
+        owner.send(r#"{"type":"logout"}"#).await;
+        assert_eq!(owner.recv().await, ServerMessage::LogoutAck);
+    }
+
+    #[tokio::test]
+    async fn test_many_concurrent_sessions() {
+        let addr = start_gateway().await;
+
+        let sessions: Vec<_> = (1..=40u64)
+            .map(|user_id| {
+                tokio::spawn(async move {
+                    let mut client = Client::logon(addr, user_id).await;
+                    let side = if user_id % 2 == 0 { "Buy" } else { "Sell" };
+                    for _ in 0..10 {
+                        client.new_order(side, "Limit", 100, 1).await;
+                        // Every order gets at least one report of its own
+                        loop {
+                            if let ServerMessage::ExecutionReport(report) = client.recv().await {
+                                if report.last_quantity.is_some() || report.status == OrderStatus::New {
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for session in sessions {
+            tokio::time::timeout(Duration::from_secs(10), session).await.unwrap().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_parse_credentials() {
+        let credentials = parse_credentials("# users\n1001:alpha\n\n1002: beta \n".as_bytes()).unwrap();
+        assert_eq!(credentials[&1001], "alpha");
+        assert_eq!(credentials[&1002], "beta");
+
+        let config = GatewayConfig::with_credentials(credentials);
+        assert!(config.authenticate(1001, "alpha"));
+        assert!(!config.authenticate(1001, "beta"));
+        assert!(!config.authenticate(1003, ""));
+        assert!(GatewayConfig::new().authenticate(1003, ""));
+
+        let err = parse_credentials("1001\n".as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,201 @@
+//! Conversions between engine types and their protobuf messages
+
+use tonic::Status;
+
+use crate::core::handle::{DepthSnapshot, EngineError};
+use crate::grpc::proto;
+use crate::models::order::{Order, OrderError, OrderSide, OrderStatus, OrderType};
+use crate::models::price::{Price, Quantity};
+use crate::models::trade::Trade;
+use crate::models::trade_adjustment::AdjustmentError;
+
+impl From<OrderSide> for proto::Side {
+    fn from(side: OrderSide) -> Self {
+        match side {
+            OrderSide::Buy => proto::Side::Buy,
+            OrderSide::Sell => proto::Side::Sell,
+        }
+    }
+}
+
+impl From<OrderStatus> for proto::OrderStatus {
+    fn from(status: OrderStatus) -> Self {
+        match status {
+            OrderStatus::PendingNew => proto::OrderStatus::PendingNew,
+            OrderStatus::New => proto::OrderStatus::New,
+            OrderStatus::Triggered => proto::OrderStatus::Triggered,
+            OrderStatus::PartiallyFilled => proto::OrderStatus::PartiallyFilled,
+            OrderStatus::Filled => proto::OrderStatus::Filled,
+            OrderStatus::Canceled => proto::OrderStatus::Canceled,
+            OrderStatus::Rejected => proto::OrderStatus::Rejected,
+            OrderStatus::Expired => proto::OrderStatus::Expired,
+            OrderStatus::Replaced => proto::OrderStatus::Replaced,
+        }
+    }
+}
+
+impl From<&Order> for proto::Order {
+    fn from(order: &Order) -> Self {
+        let (order_type, stop_price) = match order.order_type {
+            OrderType::Limit => (proto::OrderType::Limit, Price::ZERO),
+            OrderType::Market => (proto::OrderType::Market, Price::ZERO),
+            OrderType::Stop(stop) => (proto::OrderType::Stop, stop),
+            OrderType::StopLimit(stop, _) => (proto::OrderType::StopLimit, stop),
+            OrderType::IOC => (proto::OrderType::Ioc, Price::ZERO),
+            OrderType::FOK => (proto::OrderType::Fok, Price::ZERO),
+        };
+        Self {
+            id: order.id,
+            symbol: order.symbol.clone(),
+            user_id: order.user_id,
+            side: proto::Side::from(order.side).into(),
+            order_type: order_type.into(),
+            price: order.price.0,
+            stop_price: stop_price.0,
+            quantity: order.quantity.0,
+            remaining_quantity: order.remaining_quantity.0,
+            status: proto::OrderStatus::from(order.status).into(),
+            timestamp: order.timestamp,
+            client_order_id: order.client_order_id.clone(),
+        }
+    }
+}
+
+impl From<&Trade> for proto::Trade {
+    fn from(trade: &Trade) -> Self {
+        Self {
+            id: trade.id,
+            symbol: trade.symbol.clone(),
+            price: trade.price.0,
+            quantity: trade.quantity.0,
+            timestamp: trade.timestamp,
+            buy_order_id: trade.buy_order_id,
+            sell_order_id: trade.sell_order_id,
+            buy_user_id: trade.buy_user_id,
+            sell_user_id: trade.sell_user_id,
+            buy_fee: trade.buy_fee,
+            sell_fee: trade.sell_fee,
+            aggressor_side: proto::Side::from(trade.aggressor_side).into(),
+            maker_order_id: trade.maker_order_id,
+            taker_order_id: trade.taker_order_id,
+        }
+    }
+}
+
+impl From<&DepthSnapshot> for proto::BookSnapshot {
+    fn from(depth: &DepthSnapshot) -> Self {
+        let levels = |levels: &[(Price, Quantity)]| {
+            levels
+                .iter()
+                .map(|(price, quantity)| proto::PriceLevel {
+                    price: price.0,
+                    quantity: quantity.0,
+                })
+                .collect()
+        };
+        Self {
+            symbol: depth.symbol.clone(),
+            bids: levels(&depth.bids),
+            asks: levels(&depth.asks),
+        }
+    }
+}
+
+impl proto::ExecutionReport {
+    /// Builds a report from the current state of an order
+    pub fn from_order(order: &Order) -> Self {
+        Self {
+            order_id: order.id,
+            client_order_id: order.client_order_id.clone(),
+            symbol: order.symbol.clone(),
+            user_id: order.user_id,
+            side: proto::Side::from(order.side).into(),
+            status: proto::OrderStatus::from(order.status).into(),
+            price: order.price.0,
+            quantity: order.quantity.0,
+            leaves_quantity: order.remaining_quantity.0,
+            last_price: None,
+            last_quantity: None,
+            trade_id: None,
+        }
+    }
+
+    /// Marks the report as caused by a trade
+    pub fn with_fill(mut self, trade: &Trade) -> Self {
+        self.last_price = Some(trade.price.0);
+        self.last_quantity = Some(trade.quantity.0);
+        self.trade_id = Some(trade.id);
+        self
+    }
+}
+
+impl From<EngineError> for Status {
+    fn from(e: EngineError) -> Self {
+        let message = e.to_string();
+        match e {
+            EngineError::Closed | EngineError::Busy => Status::unavailable(message),
+            EngineError::Order(OrderError::UnknownOrder { .. }) => Status::not_found(message),
+            EngineError::Order(OrderError::DuplicateOrder { .. }) => Status::already_exists(message),
+            EngineError::Order(_) => Status::invalid_argument(message),
+            EngineError::Risk(_) | EngineError::Ledger(_) => Status::failed_precondition(message),
+            EngineError::Adjustment(AdjustmentError::UnknownTrade { .. }) => Status::not_found(message),
+            EngineError::Adjustment(AdjustmentError::Busted { .. }) => Status::failed_precondition(message),
+            EngineError::Adjustment(AdjustmentError::Stale { .. }) => Status::aborted(message),
+            EngineError::Adjustment(AdjustmentError::InvalidCorrection { .. }) => Status::invalid_argument(message),
+        }
+    }
+}
+
+/// Decodes a protobuf side
+pub fn side_from_proto(side: i32) -> Result<OrderSide, Status> {
+    match proto::Side::try_from(side) {
+        Ok(proto::Side::Buy) => Ok(OrderSide::Buy),
+        Ok(proto::Side::Sell) => Ok(OrderSide::Sell),
+        _ => Err(Status::invalid_argument(format!("Invalid side {}", side))),
+    }
+}
+
+/// Decodes a protobuf order type and its prices
+pub fn order_type_from_proto(order_type: i32, price: Price, stop_price: Price) -> Result<OrderType, Status> {
+    match proto::OrderType::try_from(order_type) {
+        Ok(proto::OrderType::Limit) => Ok(OrderType::Limit),
+        Ok(proto::OrderType::Market) => Ok(OrderType::Market),
+        Ok(proto::OrderType::Stop) => Ok(OrderType::Stop(stop_price)),
+        Ok(proto::OrderType::StopLimit) => Ok(OrderType::StopLimit(stop_price, price)),
+        Ok(proto::OrderType::Ioc) => Ok(OrderType::IOC),
+        Ok(proto::OrderType::Fok) => Ok(OrderType::FOK),
+        _ => Err(Status::invalid_argument(format!("Invalid order type {}", order_type))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_round_trip_fields() {
+        let order_type = order_type_from_proto(proto::OrderType::StopLimit.into(), Price(10100), Price(10000)).unwrap();
+        assert_eq!(order_type, OrderType::StopLimit(Price(10000), Price(10100)));
+        let order = Order::new(
+            7,
+            order_type,
+            10100,
+            5,
+            side_from_proto(proto::Side::Sell.into()).unwrap(),
+            1001,
+            42,
+            Some("c-7".to_string()),
+            "BTC-USD".to_string(),
+        );
+
+        let message = proto::Order::from(&order);
+        assert_eq!(message.order_type(), proto::OrderType::StopLimit);
+        assert_eq!(message.side(), proto::Side::Sell);
+        assert_eq!(message.status(), proto::OrderStatus::PendingNew);
+        assert_eq!((message.price, message.stop_price, message.quantity), (10100, 10000, 5));
+        assert_eq!(message.client_order_id.as_deref(), Some("c-7"));
+
+        assert!(side_from_proto(proto::Side::Unspecified.into()).is_err());
+        assert!(order_type_from_proto(99, Price::ZERO, Price::ZERO).is_err());
+    }
+}
@@ -0,0 +1,12 @@
+// Export gRPC components
+pub mod convert;
+pub mod server;
+
+/// Messages and service stubs generated from `proto/rustflow.proto`
+pub mod proto {
+    tonic::include_proto!("rustflow.v1");
+}
+
+// Re-export main components
+pub use proto::matching_engine_client::MatchingEngineClient;
+pub use server::GrpcServer;
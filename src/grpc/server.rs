@@ -0,0 +1,327 @@
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+
+use log::{debug, info};
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tokio_stream::Stream;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+
+use crate::api::rest::{DEFAULT_DEPTH_LEVELS, MAX_PAGE_SIZE};
+use crate::core::handle::{EngineHandle, TradeEvent, BOOK_UPDATE_LEVELS};
+use crate::grpc::convert::{order_type_from_proto, side_from_proto};
+use crate::grpc::proto;
+use crate::grpc::proto::matching_engine_server::{MatchingEngine, MatchingEngineServer};
+use crate::models::order::{Order, OrderStatus};
+use crate::models::price::{Price, Quantity};
+use crate::utils::time;
+
+/// Number of execution reports buffered for slow subscribers before they miss reports
+pub const EXECUTION_REPORT_CAPACITY: usize = 4096;
+
+/// Number of messages buffered per streaming call
+const STREAM_BUFFER: usize = 256;
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// State shared by all calls
+struct GrpcState {
+    engine: EngineHandle,
+    next_order_id: AtomicU64,
+    executions: broadcast::Sender<proto::ExecutionReport>,
+}
+
+impl GrpcState {
+    fn publish(&self, report: proto::ExecutionReport) {
+        let _ = self.executions.send(report);
+    }
+
+    /// Looks up an order that must exist
+    async fn order(&self, symbol: &str, order_id: u64) -> Result<Order, Status> {
+        self.engine
+            .order(symbol, order_id)
+            .await?
+            .ok_or_else(|| Status::not_found(format!("Unknown order {}", order_id)))
+    }
+}
+
+/// gRPC server for the `rustflow.v1.MatchingEngine` service
+///
+/// Execution report subscriptions cover orders entered through this server,
+/// plus any resting order of the user that trades against an incoming order.
+#[derive(Clone)]
+pub struct GrpcServer {
+    state: Arc<GrpcState>,
+}
+
+impl GrpcServer {
+    /// Creates a server for the given engine and starts reporting fills on resting orders
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn new(engine: EngineHandle) -> Self {
+        let trades = engine.subscribe_trades();
+        let (executions, _) = broadcast::channel(EXECUTION_REPORT_CAPACITY);
+        let state = Arc::new(GrpcState {
+            engine,
+            next_order_id: AtomicU64::new(1),
+            executions,
+        });
+        tokio::spawn(report_maker_fills(Arc::downgrade(&state), trades));
+
+        Self { state }
+    }
+
+    /// Returns the tonic service, for serving alongside other services
+    pub fn service(&self) -> MatchingEngineServer<GrpcServer> {
+        MatchingEngineServer::new(self.clone())
+    }
+
+    /// Serves gRPC until the listener fails
+    pub async fn serve(&self, listener: TcpListener) -> Result<(), tonic::transport::Error> {
+        if let Ok(addr) = listener.local_addr() {
+            info!("gRPC listening on {}", addr);
+        }
+        Server::builder()
+            .add_service(self.service())
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+    }
+}
+
+/// Reports the resting side of every trade; incoming orders are reported by their call
+async fn report_maker_fills(state: Weak<GrpcState>, mut trades: broadcast::Receiver<TradeEvent>) {
+    loop {
+        let event = trades.recv().await;
+        let Some(state) = state.upgrade() else {
+            break;
+        };
+        match event {
+            Ok(event) => {
+                if state.executions.receiver_count() == 0 {
+                    continue;
+                }
+                let (maker_order_id, _) = event.maker();
+                if let Ok(Some(order)) = state.engine.order(&event.trade.symbol, maker_order_id).await {
+                    state.publish(proto::ExecutionReport::from_order(&order).with_fill(&event.trade));
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                debug!("Execution reports missed {} trades", missed);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl MatchingEngine for GrpcServer {
+    async fn submit_order(
+        &self,
+        request: Request<proto::SubmitOrderRequest>,
+    ) -> Result<Response<proto::OrderResponse>, Status> {
+        let request = request.into_inner();
+        if request.quantity == 0 {
+            return Err(Status::invalid_argument("Quantity must be positive"));
+        }
+        let side = side_from_proto(request.side)?;
+        let order_type = order_type_from_proto(request.order_type, Price(request.price), Price(request.stop_price))?;
+
+        let order_id = self.state.next_order_id.fetch_add(1, Ordering::Relaxed);
+        let order = Order::new(
+            order_id,
+            order_type,
+            request.price,
+            request.quantity,
+            side,
+            request.user_id,
+            time::current_timestamp_nanos(),
+            request.client_order_id,
+            request.symbol,
+        );
+        let ack = self.state.engine.submit(order.clone()).await?;
+
+        // Report each fill of the incoming order, then whatever happened to the rest
+        let mut report = order;
+        for trade in &ack.trades {
+            report.remaining_quantity -= trade.quantity;
+            report.status = if report.remaining_quantity.is_zero() {
+                OrderStatus::Filled
+            } else {
+                OrderStatus::PartiallyFilled
+            };
+            self.state.publish(proto::ExecutionReport::from_order(&report).with_fill(trade));
+        }
+        if ack.trades.is_empty() || !matches!(ack.status, OrderStatus::Filled | OrderStatus::PartiallyFilled) {
+            report.status = ack.status;
+            self.state.publish(proto::ExecutionReport::from_order(&report));
+        }
+
+        let order = self.state.order(&report.symbol, order_id).await?;
+        Ok(Response::new(proto::OrderResponse {
+            order: Some(proto::Order::from(&order)),
+            trades: ack.trades.iter().map(proto::Trade::from).collect(),
+        }))
+    }
+
+    async fn cancel_order(&self, request: Request<proto::OrderRequest>) -> Result<Response<proto::Order>, Status> {
+        let proto::OrderRequest { symbol, order_id } = request.into_inner();
+        let canceled = self.state.engine.cancel(&symbol, order_id).await?;
+        let order = self.state.order(&symbol, order_id).await?;
+        if !canceled {
+            return Err(Status::failed_precondition(format!(
+                "Order {} is {} and cannot be canceled",
+                order_id, order.status
+            )));
+        }
+
+        self.state.publish(proto::ExecutionReport::from_order(&order));
+        Ok(Response::new(proto::Order::from(&order)))
+    }
+
+    async fn amend_order(
+        &self,
+        request: Request<proto::AmendOrderRequest>,
+    ) -> Result<Response<proto::OrderResponse>, Status> {
+        let request = request.into_inner();
+        let trades = self
+            .state
+            .engine
+            .amend(
+                &request.symbol,
+                request.order_id,
+                request.price.map(Price),
+                request.quantity.map(Quantity),
+            )
+            .await?;
+
+        let order = self.state.order(&request.symbol, request.order_id).await?;
+        let mut report = proto::ExecutionReport::from_order(&order);
+        if let Some(trade) = trades.last() {
+            report = report.with_fill(trade);
+        }
+        self.state.publish(report);
+
+        Ok(Response::new(proto::OrderResponse {
+            order: Some(proto::Order::from(&order)),
+            trades: trades.iter().map(proto::Trade::from).collect(),
+        }))
+    }
+
+    async fn get_order(&self, request: Request<proto::OrderRequest>) -> Result<Response<proto::Order>, Status> {
+        let request = request.into_inner();
+        let order = self.state.order(&request.symbol, request.order_id).await?;
+        Ok(Response::new(proto::Order::from(&order)))
+    }
+
+    async fn get_book(&self, request: Request<proto::BookRequest>) -> Result<Response<proto::BookSnapshot>, Status> {
+        let request = request.into_inner();
+        let levels = match request.levels as usize {
+            0 => DEFAULT_DEPTH_LEVELS,
+            levels => levels.min(MAX_PAGE_SIZE),
+        };
+        let depth = self.state.engine.depth(&request.symbol, levels).await?;
+        Ok(Response::new(proto::BookSnapshot::from(&depth)))
+    }
+
+    type SubscribeMarketDataStream = ResponseStream<proto::MarketDataUpdate>;
+
+    async fn subscribe_market_data(
+        &self,
+        request: Request<proto::MarketDataRequest>,
+    ) -> Result<Response<Self::SubscribeMarketDataStream>, Status> {
+        let symbol = request.into_inner().symbol;
+
+        // Subscribe before the snapshot so no change in between is lost
+        let mut trades = self.state.engine.subscribe_trades();
+        let mut books = self.state.engine.subscribe_books();
+        let depth = self.state.engine.depth(&symbol, BOOK_UPDATE_LEVELS).await?;
+
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        tokio::spawn(async move {
+            let snapshot = proto::MarketDataUpdate {
+                update: Some(proto::market_data_update::Update::Book(proto::BookSnapshot::from(&depth))),
+            };
+            if sender.send(Ok(snapshot)).await.is_err() {
+                return;
+            }
+            loop {
+                // Trades are published before the book update they cause, so poll them first
+                let update = tokio::select! {
+                    biased;
+                    event = trades.recv() => match event {
+                        Ok(event) if event.trade.symbol == symbol => {
+                            proto::market_data_update::Update::Trade(proto::Trade::from(&event.trade))
+                        }
+                        Ok(_) => continue,
+                        Err(e) => {
+                            let _ = sender.send(Err(lagged(e))).await;
+                            return;
+                        }
+                    },
+                    update = books.recv() => match update {
+                        Ok(depth) if depth.symbol == symbol => {
+                            proto::market_data_update::Update::Book(proto::BookSnapshot::from(&depth))
+                        }
+                        Ok(_) => continue,
+                        Err(e) => {
+                            let _ = sender.send(Err(lagged(e))).await;
+                            return;
+                        }
+                    },
+                    _ = sender.closed() => return,
+                };
+                let update = proto::MarketDataUpdate { update: Some(update) };
+                if sender.send(Ok(update)).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+
+    type SubscribeExecutionsStream = ResponseStream<proto::ExecutionReport>;
+
+    async fn subscribe_executions(
+        &self,
+        request: Request<proto::ExecutionsRequest>,
+    ) -> Result<Response<Self::SubscribeExecutionsStream>, Status> {
+        let user_id = request.into_inner().user_id;
+        let mut executions = self.state.executions.subscribe();
+
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        tokio::spawn(async move {
+            loop {
+                let report = tokio::select! {
+                    report = executions.recv() => match report {
+                        Ok(report) if report.user_id == user_id => report,
+                        Ok(_) => continue,
+                        Err(e) => {
+                            let _ = sender.send(Err(lagged(e))).await;
+                            return;
+                        }
+                    },
+                    _ = sender.closed() => return,
+                };
+                if sender.send(Ok(report)).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+}
+
+/// Ends a stream that fell behind; the client resubscribes for a fresh snapshot
+fn lagged(e: broadcast::error::RecvError) -> Status {
+    match e {
+        broadcast::error::RecvError::Lagged(missed) => {
+            Status::data_loss(format!("Subscriber fell behind and missed {} messages", missed))
+        }
+        broadcast::error::RecvError::Closed => Status::unavailable("Engine stopped"),
+    }
+}
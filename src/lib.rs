@@ -8,12 +8,36 @@ pub mod models;
 pub mod core;
 pub mod persistence;
 pub mod utils;
+pub mod backtest;
+pub mod gateway;
+pub mod fix;
+pub mod api;
+pub mod grpc;
+pub mod marketdata;
+pub mod cli;
+pub mod loadgen;
+pub mod risk;
+pub mod account;
+pub mod fees;
 
 // Re-export commonly used types
 pub use models::order::{Order, OrderSide, OrderType, OrderStatus};
 pub use models::trade::Trade;
+pub use models::trade_adjustment::{AdjustmentError, LastTrade, TradeAdjustment};
 pub use models::stats::OrderBookStats;
+pub use models::execution::{ExecutionEstimate, LadderLevel};
+pub use models::analytics::{AnalyticsConfig, BookAnalytics};
+pub use models::price::{Price, Quantity};
+pub use models::instrument::Instrument;
+pub use models::protection::{CollarAction, CollarLimit, CollarReference, MarketOrderProtection};
+pub use models::book_event::BookEvent;
 pub use core::order_book::OrderBook;
 pub use core::matcher::Matcher;
+pub use core::engine::{EngineCommand, EngineEvent, ShardedEngine};
+pub use core::handle::{DepthSnapshot, EngineConfig, EngineError, EngineHandle, OrderAck, TradeEvent};
 pub use persistence::trade_store::TradeStore;
 pub use persistence::order_store::OrderStore;
+pub use backtest::{Backtest, BacktestReport};
+pub use risk::{RiskLimits, RiskManager, RiskReject, RiskScope};
+pub use account::{Balance, Ledger, LedgerError};
+pub use fees::{FeeEngine, FeeSchedule, FeeTier};
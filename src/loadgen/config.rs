@@ -0,0 +1,177 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Relative weights of the messages a load test sends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderMix {
+    /// Passive limit orders that rest in the book
+    pub limit: u32,
+    /// Market orders
+    pub market: u32,
+    /// Immediate-or-cancel orders priced through the touch
+    pub ioc: u32,
+    /// Fill-or-kill orders priced through the touch
+    pub fok: u32,
+    /// Cancels of orders resting from earlier limit orders
+    pub cancel: u32,
+}
+
+impl Default for OrderMix {
+    fn default() -> Self {
+        Self {
+            limit: 60,
+            market: 5,
+            ioc: 7,
+            fok: 3,
+            cancel: 25,
+        }
+    }
+}
+
+impl OrderMix {
+    /// Returns the sum of all weights
+    pub fn total(&self) -> u32 {
+        self.limit + self.market + self.ioc + self.fok + self.cancel
+    }
+}
+
+/// Parses weights like `limit=60,market=5,cancel=35`; missing kinds get no weight
+impl FromStr for OrderMix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut mix = OrderMix {
+            limit: 0,
+            market: 0,
+            ioc: 0,
+            fok: 0,
+            cancel: 0,
+        };
+        for entry in s.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (kind, weight) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("Expected <kind>=<weight>, got '{}'", entry))?;
+            let weight = weight
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid weight '{}' for {}", weight, kind))?;
+            match kind.trim().to_ascii_lowercase().as_str() {
+                "limit" => mix.limit = weight,
+                "market" => mix.market = weight,
+                "ioc" => mix.ioc = weight,
+                "fok" => mix.fok = weight,
+                "cancel" => mix.cancel = weight,
+                other => return Err(format!("Unknown message kind '{}'", other)),
+            }
+        }
+        if mix.total() == 0 {
+            return Err("Order mix needs at least one positive weight".to_string());
+        }
+        Ok(mix)
+    }
+}
+
+/// Rate, duration and content of a load test
+#[derive(Debug, Clone)]
+pub struct LoadConfig {
+    /// Target messages per second
+    pub rate: u64,
+    /// How long to send for
+    pub duration: Duration,
+    /// Symbols that orders are spread across
+    pub symbols: Vec<String>,
+    /// Relative weights of each kind of message
+    pub mix: OrderMix,
+    /// Seed of the message generator, so runs are repeatable
+    pub seed: u64,
+    /// How long to wait for outstanding responses once sending has finished
+    pub drain_timeout: Duration,
+}
+
+impl LoadConfig {
+    /// Creates a config sending the default mix on `BTC-USD` at `rate` messages per second
+    pub fn new(rate: u64, duration: Duration) -> Self {
+        Self {
+            rate: rate.max(1),
+            duration,
+            symbols: vec!["BTC-USD".to_string()],
+            mix: OrderMix::default(),
+            seed: 1,
+            drain_timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Spreads orders across the given symbols
+    pub fn with_symbols(mut self, symbols: Vec<String>) -> Self {
+        if !symbols.is_empty() {
+            self.symbols = symbols;
+        }
+        self
+    }
+
+    /// Uses the given message mix
+    pub fn with_mix(mut self, mix: OrderMix) -> Self {
+        self.mix = mix;
+        self
+    }
+
+    /// Seeds the message generator
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Returns the number of messages the test sends
+    pub fn message_count(&self) -> u64 {
+        (self.rate as f64 * self.duration.as_secs_f64()).round() as u64
+    }
+
+    /// Returns the time between two scheduled messages
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.rate as f64)
+    }
+}
+
+/// A gateway to send load to instead of an in-process engine
+#[derive(Debug, Clone)]
+pub struct GatewayTarget {
+    /// Address of the gateway
+    pub address: String,
+    /// User of the first connection; connection `n` logs on as `user_id + n`
+    pub user_id: u64,
+    /// Token sent with each logon
+    pub token: String,
+    /// Number of sessions the messages are spread across
+    pub connections: usize,
+}
+
+impl GatewayTarget {
+    /// Creates a target using one session logged on as user 1 without a token
+    pub fn new(address: &str) -> Self {
+        Self {
+            address: address.to_string(),
+            user_id: 1,
+            token: String::new(),
+            connections: 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mix() {
+        let mix: OrderMix = "limit=70, cancel=30".parse().unwrap();
+        assert_eq!((mix.limit, mix.market, mix.cancel, mix.total()), (70, 0, 30, 100));
+        assert!("limit".parse::<OrderMix>().is_err());
+        assert!("limit=x".parse::<OrderMix>().is_err());
+        assert!("amend=5".parse::<OrderMix>().is_err());
+        assert!("limit=0".parse::<OrderMix>().is_err());
+
+        let config = LoadConfig::new(4_000, Duration::from_millis(250));
+        assert_eq!(config.message_count(), 1_000);
+        assert_eq!(config.interval(), Duration::from_micros(250));
+    }
+}
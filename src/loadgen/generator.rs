@@ -0,0 +1,160 @@
+use crate::loadgen::config::{LoadConfig, OrderMix};
+use crate::models::order::{Order, OrderSide, OrderType};
+use crate::utils::time;
+
+/// Raw price that generated orders are placed around
+pub const MID_PRICE: u64 = 10_000;
+
+/// Number of ticks from the mid price that passive orders spread over
+const PASSIVE_TICKS: u64 = 20;
+
+/// Number of ticks past the mid price that aggressive orders may reach
+const AGGRESSIVE_TICKS: u64 = 5;
+
+/// Number of distinct users generated orders belong to
+const USER_COUNT: u64 = 100;
+
+/// A message of a load test
+#[derive(Debug, Clone)]
+pub enum LoadMessage {
+    /// A new order
+    New(Order),
+    /// A cancel of any order still resting from an earlier limit order
+    Cancel,
+}
+
+/// Generates a repeatable stream of messages following an order mix
+///
+/// Passive limit orders rest within `PASSIVE_TICKS` of `MID_PRICE`, while
+/// immediate-or-cancel and fill-or-kill orders reach up to `AGGRESSIVE_TICKS`
+/// through it, so books stay populated and trade regularly.
+pub struct OrderGenerator {
+    mix: OrderMix,
+    symbols: Vec<String>,
+    state: u64,
+    next_order_id: u64,
+}
+
+impl OrderGenerator {
+    /// Creates a generator for the config's symbols, mix and seed
+    pub fn new(config: &LoadConfig) -> Self {
+        Self {
+            mix: config.mix,
+            symbols: config.symbols.clone(),
+            // Xorshift state must not be zero
+            state: config.seed.max(1),
+            next_order_id: 1,
+        }
+    }
+
+    /// Returns the next message
+    pub fn next_message(&mut self) -> LoadMessage {
+        let mut pick = self.random(self.mix.total() as u64) as u32;
+        for (weight, order_type) in [
+            (self.mix.limit, OrderType::Limit),
+            (self.mix.market, OrderType::Market),
+            (self.mix.ioc, OrderType::IOC),
+            (self.mix.fok, OrderType::FOK),
+        ] {
+            if pick < weight {
+                return LoadMessage::New(self.order(order_type));
+            }
+            pick -= weight;
+        }
+        LoadMessage::Cancel
+    }
+
+    /// Returns a passive limit order, sent in place of a cancel when nothing is resting
+    pub fn next_limit(&mut self) -> Order {
+        self.order(OrderType::Limit)
+    }
+
+    fn order(&mut self, order_type: OrderType) -> Order {
+        let id = self.next_order_id;
+        self.next_order_id += 1;
+
+        let side = if self.random(2) == 0 { OrderSide::Buy } else { OrderSide::Sell };
+        let price = match order_type {
+            OrderType::Limit => {
+                let ticks = 1 + self.random(PASSIVE_TICKS);
+                match side {
+                    OrderSide::Buy => MID_PRICE - ticks,
+                    OrderSide::Sell => MID_PRICE + ticks,
+                }
+            }
+            _ => {
+                let ticks = self.random(AGGRESSIVE_TICKS + 1);
+                match side {
+                    OrderSide::Buy => MID_PRICE + ticks,
+                    OrderSide::Sell => MID_PRICE - ticks,
+                }
+            }
+        };
+        let quantity = 1 + self.random(10);
+        let user_id = 1 + self.random(USER_COUNT);
+        let symbol = self.random(self.symbols.len() as u64) as usize;
+        let symbol = self.symbols[symbol].clone();
+
+        Order::new(
+            id,
+            order_type,
+            price,
+            quantity,
+            side,
+            user_id,
+            time::current_timestamp_nanos(),
+            Some(id.to_string()),
+            symbol,
+        )
+    }
+
+    /// Returns a pseudo-random number below `bound` (xorshift64)
+    fn random(&mut self, bound: u64) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state % bound.max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_messages_follow_mix() {
+        let config = LoadConfig::new(1_000, Duration::from_secs(1))
+            .with_symbols(vec!["AAA".to_string(), "BBB".to_string()])
+            .with_mix("limit=50,ioc=25,cancel=25".parse().unwrap())
+            .with_seed(42);
+        let mut generator = OrderGenerator::new(&config);
+
+        let (mut limits, mut iocs, mut cancels) = (0, 0, 0);
+        for _ in 0..10_000 {
+            match generator.next_message() {
+                LoadMessage::New(order) => match order.order_type {
+                    OrderType::Limit => {
+                        limits += 1;
+                        assert!(order.price.0.abs_diff(MID_PRICE) <= PASSIVE_TICKS);
+                        match order.side {
+                            OrderSide::Buy => assert!(order.price.0 < MID_PRICE),
+                            OrderSide::Sell => assert!(order.price.0 > MID_PRICE),
+                        }
+                    }
+                    OrderType::IOC => iocs += 1,
+                    other => panic!("Unexpected order type {}", other),
+                },
+                LoadMessage::Cancel => cancels += 1,
+            }
+        }
+        assert!((4_500..5_500).contains(&limits), "{} limits", limits);
+        assert!((2_000..3_000).contains(&iocs), "{} IOC orders", iocs);
+        assert!((2_000..3_000).contains(&cancels), "{} cancels", cancels);
+
+        // The same seed gives the same stream
+        let first = OrderGenerator::new(&config).next_limit();
+        let again = OrderGenerator::new(&config).next_limit();
+        assert_eq!((first.id, first.price, first.side, first.symbol), (again.id, again.price, again.side, again.symbol));
+    }
+}
@@ -0,0 +1,11 @@
+// Export load generator components
+pub mod config;
+pub mod generator;
+pub mod report;
+pub mod runner;
+
+// Re-export main components
+pub use config::{GatewayTarget, LoadConfig, OrderMix};
+pub use generator::{LoadMessage, OrderGenerator};
+pub use report::LoadReport;
+pub use runner::{run_gateway, run_in_process};
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use crate::utils::metrics::Histogram;
+
+/// Results of a load test
+///
+/// Latencies are in microseconds, measured from when each message was
+/// scheduled to be sent rather than when it was sent. A stall in the system
+/// under test therefore shows up in every message it delays, instead of only
+/// the one in flight (coordinated omission).
+#[derive(Debug, Clone)]
+pub struct LoadReport {
+    /// Target messages per second
+    pub target_rate: u64,
+    /// Messages sent
+    pub sent: u64,
+    /// Messages that got a response
+    pub completed: u64,
+    /// Responses that rejected the message, or cancels of orders no longer active
+    pub rejected: u64,
+    /// Time from the first scheduled message to the last response
+    pub elapsed: Duration,
+    /// Response latencies in microseconds
+    pub latency: Histogram,
+}
+
+impl LoadReport {
+    /// Creates an empty report for a test at the given rate
+    pub fn new(target_rate: u64) -> Self {
+        Self {
+            target_rate,
+            sent: 0,
+            completed: 0,
+            rejected: 0,
+            elapsed: Duration::ZERO,
+            latency: Histogram::new(),
+        }
+    }
+
+    /// Records a response received `latency` after its message was scheduled
+    pub fn record(&mut self, latency: Duration, rejected: bool) {
+        self.completed += 1;
+        if rejected {
+            self.rejected += 1;
+        }
+        self.latency.observe(latency.as_micros() as u64);
+    }
+
+    /// Returns the number of messages that never got a response
+    pub fn unanswered(&self) -> u64 {
+        self.sent.saturating_sub(self.completed)
+    }
+
+    /// Returns completed messages per second
+    pub fn throughput(&self) -> f64 {
+        if self.elapsed.is_zero() {
+            return 0.0;
+        }
+        self.completed as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// Returns a human-readable summary of throughput and latency percentiles
+    pub fn summary(&self) -> String {
+        let percentile = |p: f64| self.latency.percentile(p).unwrap_or(0);
+        format!(
+            "Sent {} messages at a target of {} msg/s\n\
+             Completed {} in {:.3} s ({:.0} msg/s), {} rejected, {} unanswered\n\
+             Latency (µs, power-of-two buckets): p50 {}, p99 {}, p99.9 {}, max {}",
+            self.sent,
+            self.target_rate,
+            self.completed,
+            self.elapsed.as_secs_f64(),
+            self.throughput(),
+            self.rejected,
+            self.unanswered(),
+            percentile(50.0),
+            percentile(99.0),
+            percentile(99.9),
+            self.latency.max().unwrap_or(0)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let mut report = LoadReport::new(1_000);
+        report.sent = 1_001;
+        for i in 0..1_000 {
+            report.record(Duration::from_micros(if i >= 995 { 5_000 } else { 20 }), i % 100 == 0);
+        }
+        report.elapsed = Duration::from_secs(2);
+
+        assert_eq!((report.completed, report.rejected, report.unanswered()), (1_000, 10, 1));
+        assert_eq!(report.throughput(), 500.0);
+        assert_eq!(
+            report.summary(),
+            "Sent 1001 messages at a target of 1000 msg/s\n\
+             Completed 1000 in 2.000 s (500 msg/s), 10 rejected, 1 unanswered\n\
+             Latency (µs, power-of-two buckets): p50 16, p99 16, p99.9 4096, max 5000"
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader as AsyncBufReader, BufWriter};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::core::handle::EngineHandle;
+use crate::gateway::protocol::{ClientMessage, ServerMessage};
+use crate::loadgen::config::{GatewayTarget, LoadConfig};
+use crate::loadgen::generator::{LoadMessage, OrderGenerator};
+use crate::loadgen::report::LoadReport;
+use crate::models::order::{Order, OrderStatus, OrderType};
+
+/// A response: when its message was scheduled, when it arrived and whether it was rejected
+type Sample = (Instant, Instant, bool);
+
+/// Orders known to rest in a book, with the connection that owns them
+type Resting = Arc<Mutex<Vec<(usize, String, u64)>>>;
+
+/// Drives an in-process engine at the configured rate
+///
+/// Messages are sent on schedule whether or not earlier ones have been
+/// answered, each from its own task.
+pub async fn run_in_process(engine: EngineHandle, config: &LoadConfig) -> LoadReport {
+    let resting: Resting = Arc::new(Mutex::new(Vec::new()));
+    let (samples, receiver) = mpsc::unbounded_channel();
+    let mut generator = OrderGenerator::new(config);
+
+    let start = Instant::now();
+    let mut sent = 0;
+    for index in 0..config.message_count() {
+        let message = next_message(&mut generator, &resting, 1);
+        let intended = start + config.interval().mul_f64(index as f64);
+        wait_until(intended).await;
+
+        let engine = engine.clone();
+        let resting = Arc::clone(&resting);
+        let samples = samples.clone();
+        tokio::spawn(async move {
+            let rejected = match message {
+                Message::New(order) => {
+                    let symbol = order.symbol.clone();
+                    match engine.submit(order).await {
+                        Ok(ack) => {
+                            if rests(ack.status) {
+                                lock(&resting).push((0, symbol, ack.order_id));
+                            }
+                            ack.status == OrderStatus::Rejected
+                        }
+                        Err(_) => true,
+                    }
+                }
+                Message::Cancel { symbol, order_id, .. } => !matches!(engine.cancel(&symbol, order_id).await, Ok(true)),
+            };
+            let _ = samples.send((intended, Instant::now(), rejected));
+        });
+        sent += 1;
+    }
+    drop(samples);
+
+    collect(config, start, sent, receiver).await
+}
+
+/// Drives a gateway at the configured rate over `target.connections` sessions
+///
+/// New orders are spread round-robin across sessions and each cancel goes to
+/// the session that owns the order. A message counts as answered by the first
+/// execution report or reject that refers to it.
+pub async fn run_gateway(target: &GatewayTarget, config: &LoadConfig) -> io::Result<LoadReport> {
+    let resting: Resting = Arc::new(Mutex::new(Vec::new()));
+    let (samples, receiver) = mpsc::unbounded_channel();
+
+    let mut writers = Vec::new();
+    let mut pending = Vec::new();
+    let mut readers = Vec::new();
+    for connection in 0..target.connections.max(1) {
+        let user_id = target.user_id + connection as u64;
+        let (lines, writer) = logon(&target.address, user_id, &target.token).await?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+        readers.push(tokio::spawn(read_replies(
+            connection,
+            lines,
+            receiver,
+            Arc::clone(&resting),
+            samples.clone(),
+        )));
+        writers.push(writer);
+        pending.push(sender);
+    }
+    drop(samples);
+
+    let mut generator = OrderGenerator::new(config);
+    let start = Instant::now();
+    let mut sent = 0;
+    for index in 0..config.message_count() {
+        let message = next_message(&mut generator, &resting, writers.len());
+        let intended = start + config.interval().mul_f64(index as f64);
+        if intended > Instant::now() {
+            // Caught up: send what is buffered before waiting
+            for writer in &mut writers {
+                writer.flush().await?;
+            }
+            wait_until(intended).await;
+        }
+
+        let (connection, key, limit, request) = match message {
+            Message::New(order) => (
+                index as usize % writers.len(),
+                Key::Order(order.id.to_string()),
+                order.order_type == OrderType::Limit,
+                ClientMessage::NewOrder {
+                    symbol: order.symbol,
+                    side: order.side,
+                    order_type: order.order_type,
+                    price: order.price,
+                    quantity: order.quantity,
+                    client_order_id: Some(order.id.to_string()),
+                },
+            ),
+            Message::Cancel { connection, symbol, order_id } => {
+                (connection, Key::Cancel(order_id), false, ClientMessage::Cancel { symbol, order_id })
+            }
+        };
+        // Register the message before it can be answered
+        let _ = pending[connection].send((key, (intended, limit)));
+        write_message(&mut writers[connection], &request).await?;
+        sent += 1;
+    }
+    for writer in &mut writers {
+        writer.flush().await?;
+    }
+    drop(pending);
+
+    let report = collect(config, start, sent, receiver).await;
+    for reader in readers {
+        reader.abort();
+    }
+    Ok(report)
+}
+
+/// A message with any cancel resolved to a resting order
+enum Message {
+    New(Order),
+    Cancel {
+        connection: usize,
+        symbol: String,
+        order_id: u64,
+    },
+}
+
+/// A message awaiting its response
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    /// A new order, by client order ID
+    Order(String),
+    /// A cancel, by order ID
+    Cancel(u64),
+}
+
+/// When a message was scheduled, and for new orders whether an open remainder rests
+type Registration = (Instant, bool);
+
+/// Returns the next message, sending a limit order in place of a cancel when nothing rests
+fn next_message(generator: &mut OrderGenerator, resting: &Resting, connections: usize) -> Message {
+    match generator.next_message() {
+        LoadMessage::New(order) => Message::New(order),
+        LoadMessage::Cancel => {
+            let mut resting = lock(resting);
+            // The most recent order is the most likely to still be open
+            let Some((connection, symbol, order_id)) = resting.pop() else {
+                return Message::New(generator.next_limit());
+            };
+            Message::Cancel {
+                connection: connection.min(connections - 1),
+                symbol,
+                order_id,
+            }
+        }
+    }
+}
+
+/// Waits until `intended`, returning at once when the schedule has fallen behind
+async fn wait_until(intended: Instant) {
+    if intended > Instant::now() {
+        tokio::time::sleep_until(intended).await;
+    }
+}
+
+/// Gathers responses until every message is answered or the drain timeout passes
+async fn collect(
+    config: &LoadConfig,
+    start: Instant,
+    sent: u64,
+    mut receiver: mpsc::UnboundedReceiver<Sample>,
+) -> LoadReport {
+    let mut report = LoadReport::new(config.rate);
+    report.sent = sent;
+    let mut last = start;
+
+    let deadline = Instant::now() + config.drain_timeout;
+    while report.completed < sent {
+        match tokio::time::timeout_at(deadline, receiver.recv()).await {
+            Ok(Some((intended, received, rejected))) => {
+                report.record(received.saturating_duration_since(intended), rejected);
+                last = last.max(received);
+            }
+            Ok(None) | Err(_) => break,
+        }
+    }
+    report.elapsed = last.saturating_duration_since(start);
+    report
+}
+
+fn rests(status: OrderStatus) -> bool {
+    matches!(status, OrderStatus::New | OrderStatus::PartiallyFilled)
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+type Lines = tokio::io::Lines<AsyncBufReader<OwnedReadHalf>>;
+
+async fn logon(address: &str, user_id: u64, token: &str) -> io::Result<(Lines, BufWriter<OwnedWriteHalf>)> {
+    let stream = TcpStream::connect(address).await?;
+    stream.set_nodelay(true)?;
+    let (reader, writer) = stream.into_split();
+    let mut lines = AsyncBufReader::new(reader).lines();
+    let mut writer = BufWriter::new(writer);
+
+    let logon = ClientMessage::Logon {
+        user_id,
+        token: token.to_string(),
+    };
+    write_message(&mut writer, &logon).await?;
+    writer.flush().await?;
+    match lines.next_line().await? {
+        Some(line) => match serde_json::from_str(&line)? {
+            ServerMessage::LogonAck { .. } => Ok((lines, writer)),
+            reply => Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("Logon as user {} failed: {:?}", user_id, reply),
+            )),
+        },
+        None => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Gateway closed during logon")),
+    }
+}
+
+async fn write_message(writer: &mut BufWriter<OwnedWriteHalf>, message: &ClientMessage) -> io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line).await
+}
+
+/// Matches replies on one session to the messages awaiting them
+async fn read_replies(
+    connection: usize,
+    mut lines: Lines,
+    mut registrations: mpsc::UnboundedReceiver<(Key, Registration)>,
+    resting: Resting,
+    samples: mpsc::UnboundedSender<Sample>,
+) -> io::Result<()> {
+    let mut pending: HashMap<Key, Registration> = HashMap::new();
+    let mut sending = true;
+
+    while sending || !pending.is_empty() {
+        let line = tokio::select! {
+            registration = registrations.recv(), if sending => {
+                match registration {
+                    Some((key, registration)) => {
+                        pending.insert(key, registration);
+                    }
+                    None => sending = false,
+                }
+                continue;
+            }
+            line = lines.next_line() => match line? {
+                Some(line) => line,
+                None => break,
+            },
+        };
+        // A reply may overtake the registration of its message in this task
+        while let Ok((key, registration)) = registrations.try_recv() {
+            pending.insert(key, registration);
+        }
+
+        let received = Instant::now();
+        let Ok(message) = serde_json::from_str::<ServerMessage>(&line) else {
+            continue;
+        };
+        match message {
+            ServerMessage::ExecutionReport(report) => {
+                let order = report.client_order_id.clone().map(Key::Order);
+                // The first report for a new order answers it
+                if let Some((intended, limit)) = order.and_then(|key| pending.remove(&key)) {
+                    if limit && rests(report.status) {
+                        lock(&resting).push((connection, report.symbol.clone(), report.order_id));
+                    }
+                    let _ = samples.send((intended, received, report.status == OrderStatus::Rejected));
+                } else if report.status == OrderStatus::Canceled {
+                    if let Some((intended, _)) = pending.remove(&Key::Cancel(report.order_id)) {
+                        let _ = samples.send((intended, received, false));
+                    }
+                }
+            }
+            ServerMessage::Reject { order_id, client_order_id, .. } => {
+                let order = client_order_id.map(Key::Order);
+                let intended = order
+                    .and_then(|key| pending.remove(&key))
+                    .or_else(|| order_id.and_then(|order_id| pending.remove(&Key::Cancel(order_id))));
+                if let Some((intended, _)) = intended {
+                    let _ = samples.send((intended, received, true));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::{Gateway, GatewayConfig};
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_in_process_run_answers_every_message() {
+        let config = LoadConfig::new(4_000, Duration::from_millis(250));
+        let report = run_in_process(EngineHandle::spawn(), &config).await;
+
+        assert_eq!(report.sent, 1_000);
+        assert_eq!(report.completed, 1_000);
+        assert_eq!(report.latency.count(), 1_000);
+        // The schedule alone takes a quarter of a second
+        assert!(report.elapsed >= Duration::from_millis(240), "{:?}", report.elapsed);
+        assert!(report.summary().contains("0 unanswered"));
+    }
+
+    #[tokio::test]
+    async fn test_gateway_run_answers_every_message() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let gateway = Gateway::new(EngineHandle::spawn(), GatewayConfig::new());
+        tokio::spawn(async move { gateway.serve(listener).await });
+
+        let mut target = GatewayTarget::new(&address);
+        target.connections = 2;
+        let config = LoadConfig::new(2_000, Duration::from_millis(200)).with_symbols(vec!["AAA".to_string(), "BBB".to_string()]);
+        let report = run_gateway(&target, &config).await.unwrap();
+
+        assert_eq!(report.sent, 400);
+        assert_eq!(report.completed, 400, "{}", report.summary());
+        assert_eq!(report.unanswered(), 0);
+    }
+}
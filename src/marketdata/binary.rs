@@ -0,0 +1,515 @@
+//! Compact fixed-layout binary encoding of book events
+//!
+//! Every message starts with a header, followed by a body whose layout
+//! depends on the message type. All integers are big-endian.
+//!
+//! | Field     | Bytes | Notes                                   |
+//! |-----------|-------|-----------------------------------------|
+//! | length    | 2     | Bytes following the length field        |
+//! | type      | 1     | One of the `msg_type` constants         |
+//! | sequence  | 8     | Increments by one per message           |
+//! | timestamp | 8     | Nanoseconds since the Unix epoch        |
+//! | symbol    | 8     | ASCII, right-padded with zero bytes     |
+//!
+//! Bodies:
+//! - `A` add order: order ID, side (`B`/`S`), quantity, price
+//! - `E` execute: order ID, quantity, price, trade ID
+//! - `X` cancel: order ID, canceled quantity
+//! - `D` delete: order ID
+//! - `P` trade: trade ID, price, quantity, buy order ID, sell order ID, buy user ID, sell user ID,
+//!   aggressor side (`B`/`S`)
+//! - `Q` BBO: bid price, bid quantity, ask price, ask quantity (zero for an empty side)
+
+use std::io;
+
+use crate::models::book_event::BookEvent;
+use crate::models::order::OrderSide;
+use crate::models::price::{Price, Quantity};
+use crate::models::trade::Trade;
+
+/// Message type codes
+pub mod msg_type {
+    pub const ADD_ORDER: u8 = b'A';
+    pub const EXECUTE: u8 = b'E';
+    pub const CANCEL: u8 = b'X';
+    pub const DELETE: u8 = b'D';
+    pub const TRADE: u8 = b'P';
+    pub const BBO: u8 = b'Q';
+}
+
+/// Size of the length field that prefixes every message
+pub const LENGTH_SIZE: usize = 2;
+
+/// Size of the header after the length field: type, sequence, timestamp and symbol
+pub const HEADER_SIZE: usize = 1 + 8 + 8 + SYMBOL_SIZE;
+
+/// Size of the fixed symbol field
+pub const SYMBOL_SIZE: usize = 8;
+
+/// Returns the body size of a message type, or None if the type is unknown
+pub fn body_size(message_type: u8) -> Option<usize> {
+    match message_type {
+        msg_type::ADD_ORDER => Some(8 + 1 + 8 + 8),
+        msg_type::EXECUTE => Some(8 * 4),
+        msg_type::CANCEL => Some(8 * 2),
+        msg_type::DELETE => Some(8),
+        msg_type::TRADE => Some(8 * 7 + 1),
+        msg_type::BBO => Some(8 * 4),
+        _ => None,
+    }
+}
+
+/// A decoded market data message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedMessage {
+    /// Sequence number assigned by the encoder
+    pub sequence: u64,
+    /// Nanoseconds since the Unix epoch
+    pub timestamp: u64,
+    /// Symbol/ticker the event belongs to
+    pub symbol: String,
+    /// The book event
+    pub event: BookEvent,
+}
+
+/// Encodes book events into binary messages with consecutive sequence numbers
+#[derive(Debug, Clone)]
+pub struct FeedEncoder {
+    next_sequence: u64,
+}
+
+impl FeedEncoder {
+    /// Creates an encoder whose first message has sequence number 1
+    pub fn new() -> Self {
+        Self::with_sequence(1)
+    }
+
+    /// Creates an encoder continuing from the given sequence number
+    pub fn with_sequence(next_sequence: u64) -> Self {
+        Self { next_sequence }
+    }
+
+    /// Returns the sequence number of the next message
+    pub fn next_sequence(&self) -> u64 {
+        self.next_sequence
+    }
+
+    /// Appends one message for a book event to `buf`, returning its sequence number
+    ///
+    /// Trades are stamped with `timestamp` rather than their own timestamp.
+    /// Fails without writing anything if the symbol does not fit the symbol field.
+    pub fn encode(&mut self, symbol: &str, timestamp: u64, event: &BookEvent, buf: &mut Vec<u8>) -> io::Result<u64> {
+        let message_type = match event {
+            BookEvent::AddOrder { .. } => msg_type::ADD_ORDER,
+            BookEvent::Execute { .. } => msg_type::EXECUTE,
+            BookEvent::Cancel { .. } => msg_type::CANCEL,
+            BookEvent::Delete { .. } => msg_type::DELETE,
+            BookEvent::Trade(_) => msg_type::TRADE,
+            BookEvent::Bbo { .. } => msg_type::BBO,
+        };
+        let sequence = self.write_header(message_type, timestamp, symbol, buf)?;
+
+        match event {
+            BookEvent::AddOrder { order_id, side, price, quantity } => {
+                put(buf, *order_id);
+                buf.push(side_byte(*side));
+                put(buf, quantity.0);
+                put(buf, price.0);
+            }
+            BookEvent::Execute { order_id, quantity, price, trade_id } => {
+                put(buf, *order_id);
+                put(buf, quantity.0);
+                put(buf, price.0);
+                put(buf, *trade_id);
+            }
+            BookEvent::Cancel { order_id, quantity } => {
+                put(buf, *order_id);
+                put(buf, quantity.0);
+            }
+            BookEvent::Delete { order_id } => put(buf, *order_id),
+            BookEvent::Trade(trade) => write_trade_body(trade, buf),
+            BookEvent::Bbo { bid, ask } => {
+                for (price, quantity) in [bid, ask].map(|level| level.unwrap_or((Price::ZERO, Quantity::ZERO))) {
+                    put(buf, price.0);
+                    put(buf, quantity.0);
+                }
+            }
+        }
+        Ok(sequence)
+    }
+
+    /// Appends a trade message stamped with the trade's own timestamp, returning its sequence number
+    pub fn encode_trade(&mut self, trade: &Trade, buf: &mut Vec<u8>) -> io::Result<u64> {
+        let sequence = self.write_header(msg_type::TRADE, trade.timestamp, &trade.symbol, buf)?;
+        write_trade_body(trade, buf);
+        Ok(sequence)
+    }
+
+    /// Writes the length and header, assigning the next sequence number
+    fn write_header(&mut self, message_type: u8, timestamp: u64, symbol: &str, buf: &mut Vec<u8>) -> io::Result<u64> {
+        if symbol.len() > SYMBOL_SIZE || !symbol.is_ascii() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Symbol {} does not fit in {} ASCII bytes", symbol, SYMBOL_SIZE),
+            ));
+        }
+        let body_size = body_size(message_type).unwrap_or_default();
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+
+        buf.extend_from_slice(&((HEADER_SIZE + body_size) as u16).to_be_bytes());
+        buf.push(message_type);
+        put(buf, sequence);
+        put(buf, timestamp);
+        buf.extend_from_slice(symbol.as_bytes());
+        buf.resize(buf.len() + SYMBOL_SIZE - symbol.len(), 0);
+        Ok(sequence)
+    }
+}
+
+impl Default for FeedEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decodes binary messages and checks that sequence numbers have no gaps
+#[derive(Debug, Clone, Default)]
+pub struct FeedDecoder {
+    next_sequence: Option<u64>,
+}
+
+impl FeedDecoder {
+    /// Creates a decoder that accepts any first sequence number
+    pub fn new() -> Self {
+        Self { next_sequence: None }
+    }
+
+    /// Returns the sequence number expected next, once a message has been decoded
+    pub fn next_sequence(&self) -> Option<u64> {
+        self.next_sequence
+    }
+
+    /// Decodes the message at the start of `buf`, returning it and the bytes it used,
+    /// or None if `buf` does not yet hold a whole message
+    ///
+    /// A sequence gap is reported as an `InvalidData` error; the decoder then
+    /// continues from the message after the gap.
+    pub fn decode(&mut self, buf: &[u8]) -> io::Result<Option<(FeedMessage, usize)>> {
+        let Some((message, length)) = decode(buf)? else {
+            return Ok(None);
+        };
+
+        let expected = self.next_sequence.replace(message.sequence + 1);
+        if let Some(expected) = expected {
+            if message.sequence != expected {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Sequence gap: expected {}, received {}", expected, message.sequence),
+                ));
+            }
+        }
+        Ok(Some((message, length)))
+    }
+}
+
+/// Returns the total length of the message at the start of `buf`, or None if
+/// the length field is incomplete
+pub fn frame_length(buf: &[u8]) -> Option<usize> {
+    let length = buf.get(..LENGTH_SIZE)?;
+    Some(LENGTH_SIZE + u16::from_be_bytes([length[0], length[1]]) as usize)
+}
+
+/// Decodes the message at the start of `buf` without sequence checks,
+/// returning it and the bytes it used, or None if the message is incomplete
+pub fn decode(buf: &[u8]) -> io::Result<Option<(FeedMessage, usize)>> {
+    let Some(length) = frame_length(buf) else {
+        return Ok(None);
+    };
+    if buf.len() < length {
+        return Ok(None);
+    }
+    let message = &buf[LENGTH_SIZE..length];
+    if message.len() < HEADER_SIZE {
+        return Err(invalid(format!("Message length {} is shorter than the header", message.len())));
+    }
+
+    let message_type = message[0];
+    let expected = body_size(message_type).ok_or_else(|| invalid(format!("Unknown message type {}", message_type)))?;
+    if message.len() != HEADER_SIZE + expected {
+        return Err(invalid(format!(
+            "Message type {} has length {}, expected {}",
+            message_type as char,
+            message.len(),
+            HEADER_SIZE + expected
+        )));
+    }
+
+    let mut fields = Fields(&message[1..]);
+    let sequence = fields.next_u64();
+    let timestamp = fields.next_u64();
+    let symbol_field = fields.take(SYMBOL_SIZE);
+    let symbol_end = symbol_field.iter().position(|&b| b == 0).unwrap_or(SYMBOL_SIZE);
+    let symbol = std::str::from_utf8(&symbol_field[..symbol_end])
+        .map_err(|_| invalid("Symbol is not ASCII".to_string()))?
+        .to_string();
+
+    let event = match message_type {
+        msg_type::ADD_ORDER => {
+            let order_id = fields.next_u64();
+            let side = parse_side(fields.take(1)[0])?;
+            BookEvent::AddOrder {
+                order_id,
+                side,
+                quantity: Quantity(fields.next_u64()),
+                price: Price(fields.next_u64()),
+            }
+        }
+        msg_type::EXECUTE => BookEvent::Execute {
+            order_id: fields.next_u64(),
+            quantity: Quantity(fields.next_u64()),
+            price: Price(fields.next_u64()),
+            trade_id: fields.next_u64(),
+        },
+        msg_type::CANCEL => BookEvent::Cancel {
+            order_id: fields.next_u64(),
+            quantity: Quantity(fields.next_u64()),
+        },
+        msg_type::DELETE => BookEvent::Delete {
+            order_id: fields.next_u64(),
+        },
+        msg_type::TRADE => {
+            let values: [u64; 7] = std::array::from_fn(|_| fields.next_u64());
+            let aggressor_side = parse_side(fields.take(1)[0])?;
+            // Fees are private to the two sides and not part of the feed
+            BookEvent::Trade(Trade::new(
+                values[0],
+                values[1],
+                values[2],
+                timestamp,
+                values[3],
+                values[4],
+                values[5],
+                values[6],
+                aggressor_side,
+                symbol.clone(),
+            ))
+        }
+        _ => {
+            let mut level = || {
+                let (price, quantity) = (Price(fields.next_u64()), Quantity(fields.next_u64()));
+                (!quantity.is_zero()).then_some((price, quantity))
+            };
+            let bid = level();
+            let ask = level();
+            BookEvent::Bbo { bid, ask }
+        }
+    };
+
+    let message = FeedMessage {
+        sequence,
+        timestamp,
+        symbol,
+        event,
+    };
+    Ok(Some((message, length)))
+}
+
+fn write_trade_body(trade: &Trade, buf: &mut Vec<u8>) {
+    for value in [
+        trade.id,
+        trade.price.0,
+        trade.quantity.0,
+        trade.buy_order_id,
+        trade.sell_order_id,
+        trade.buy_user_id,
+        trade.sell_user_id,
+    ] {
+        put(buf, value);
+    }
+    buf.push(side_byte(trade.aggressor_side));
+}
+
+fn side_byte(side: OrderSide) -> u8 {
+    match side {
+        OrderSide::Buy => b'B',
+        OrderSide::Sell => b'S',
+    }
+}
+
+fn parse_side(byte: u8) -> io::Result<OrderSide> {
+    match byte {
+        b'B' => Ok(OrderSide::Buy),
+        b'S' => Ok(OrderSide::Sell),
+        other => Err(invalid(format!("Unknown side {}", other))),
+    }
+}
+
+fn put(buf: &mut Vec<u8>, value: u64) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Reads fixed-size fields from a message whose length has been checked
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Fields<'a> {
+    fn take(&mut self, size: usize) -> &'a [u8] {
+        let (field, rest) = self.0.split_at(size);
+        self.0 = rest;
+        field
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8));
+        u64::from_be_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::order_book::OrderBook;
+    use crate::models::order::Order;
+
+    fn trade() -> Trade {
+        Trade::new(7, 10100, 3, 1_700_000_000_000_000_000, 11, 12, 1001, 1002, OrderSide::Sell, "BTC-USD".to_string())
+    }
+
+    #[test]
+    fn test_every_message_type_round_trips() {
+        let events = vec![
+            BookEvent::AddOrder {
+                order_id: 1,
+                side: OrderSide::Sell,
+                price: Price(10100),
+                quantity: Quantity(5),
+            },
+            BookEvent::Execute {
+                order_id: 1,
+                quantity: Quantity(2),
+                price: Price(10100),
+                trade_id: 9,
+            },
+            BookEvent::Cancel {
+                order_id: 1,
+                quantity: Quantity(1),
+            },
+            BookEvent::Delete { order_id: 1 },
+            BookEvent::Trade(trade()),
+            BookEvent::Bbo {
+                bid: None,
+                ask: Some((Price(10100), Quantity(2))),
+            },
+        ];
+
+        let mut encoder = FeedEncoder::new();
+        let mut buf = Vec::new();
+        for event in &events {
+            encoder.encode("BTC-USD", trade().timestamp, event, &mut buf).unwrap();
+        }
+        assert_eq!(encoder.next_sequence(), 7);
+
+        let mut decoder = FeedDecoder::new();
+        let mut offset = 0;
+        for (i, event) in events.iter().enumerate() {
+            let (message, length) = decoder.decode(&buf[offset..]).unwrap().unwrap();
+            assert_eq!(message.sequence, i as u64 + 1);
+            assert_eq!(message.symbol, "BTC-USD");
+            assert_eq!(message.timestamp, trade().timestamp);
+            assert_eq!(&message.event, event);
+            assert_eq!(length, LENGTH_SIZE + HEADER_SIZE + body_size(buf[offset + 2]).unwrap());
+            offset += length;
+        }
+        assert_eq!(offset, buf.len());
+    }
+
+    #[test]
+    fn test_partial_invalid_and_gapped_input() {
+        let mut encoder = FeedEncoder::with_sequence(41);
+        let mut buf = Vec::new();
+        assert_eq!(encoder.encode_trade(&trade(), &mut buf).unwrap(), 41);
+        assert_eq!(buf.len(), 2 + 25 + 57);
+
+        // Incomplete messages wait for more bytes
+        assert!(decode(&buf[..1]).unwrap().is_none());
+        assert!(decode(&buf[..buf.len() - 1]).unwrap().is_none());
+        let (message, _) = decode(&buf).unwrap().unwrap();
+        assert_eq!(message.event, BookEvent::Trade(trade()));
+
+        // Unknown types and oversized symbols are rejected
+        let mut bad = buf.clone();
+        bad[2] = b'Z';
+        assert_eq!(decode(&bad).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let long = BookEvent::Delete { order_id: 1 };
+        assert!(encoder.encode("VERYLONGSYMBOL", 0, &long, &mut Vec::new()).is_err());
+        assert_eq!(encoder.next_sequence(), 42);
+
+        // Skipping a message is reported once, then decoding continues
+        let mut decoder = FeedDecoder::new();
+        decoder.decode(&buf).unwrap();
+        encoder.encode("BTC-USD", 0, &long, &mut Vec::new()).unwrap();
+        let mut next = Vec::new();
+        encoder.encode("BTC-USD", 0, &long, &mut next).unwrap();
+        assert!(decoder.decode(&next).unwrap_err().to_string().contains("expected 42, received 43"));
+        assert_eq!(decoder.next_sequence(), Some(44));
+    }
+
+    #[test]
+    fn test_order_book_events_rebuild_the_book() {
+        let mut book = OrderBook::new("BTC-USD");
+        book.set_event_recording(true);
+        let limit = |id, side, price, quantity| {
+            Order::new_limit(id, price, quantity, side, 1000 + id, id, None, "BTC-USD".to_string())
+        };
+        book.process_order(limit(1, OrderSide::Sell, 10100, 5));
+        book.process_order(limit(2, OrderSide::Sell, 10200, 5));
+        book.process_order(limit(3, OrderSide::Buy, 10100, 7));
+        book.process_order(limit(4, OrderSide::Buy, 9900, 3));
+        book.amend_order(4, None, Some(Quantity(2)), 5).unwrap();
+        book.cancel_order(3);
+
+        let mut encoder = FeedEncoder::new();
+        let mut buf = Vec::new();
+        for event in book.take_events() {
+            encoder.encode(book.symbol(), 0, &event, &mut buf).unwrap();
+        }
+
+        // Replaying the decoded adds, executions, cancels and deletes gives the same depth
+        let mut levels: Vec<(u64, OrderSide, Price, Quantity)> = Vec::new();
+        let mut last_bbo = None;
+        let mut offset = 0;
+        let mut decoder = FeedDecoder::new();
+        while let Some((message, length)) = decoder.decode(&buf[offset..]).unwrap() {
+            offset += length;
+            match message.event {
+                BookEvent::AddOrder { order_id, side, price, quantity } => {
+                    levels.push((order_id, side, price, quantity))
+                }
+                BookEvent::Execute { order_id, quantity, .. } | BookEvent::Cancel { order_id, quantity } => {
+                    let order = levels.iter_mut().find(|o| o.0 == order_id).unwrap();
+                    order.3 -= quantity;
+                }
+                BookEvent::Delete { order_id } => levels.retain(|o| o.0 != order_id),
+                BookEvent::Trade(trade) => assert_eq!((trade.buy_order_id, trade.sell_order_id), (3, 1)),
+                BookEvent::Bbo { bid, ask } => last_bbo = Some((bid, ask)),
+            }
+        }
+        levels.retain(|o| !o.3.is_zero());
+        let (bids, asks) = book.market_depth(10);
+        let side = |side| {
+            levels
+                .iter()
+                .filter(|o| o.1 == side)
+                .map(|o| (o.2, o.3))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(side(OrderSide::Buy), bids);
+        assert_eq!(side(OrderSide::Sell), asks);
+        assert_eq!(last_bbo, Some((bids.first().copied(), asks.first().copied())));
+    }
+}
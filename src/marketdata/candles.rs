@@ -0,0 +1,460 @@
+//! OHLCV candles aggregated from trades
+//!
+//! A `CandleAggregator` buckets trades into candles per symbol for each of its
+//! intervals. Trades may arrive out of order: each lands in the candle of its
+//! own timestamp, and the open and close follow the earliest and latest
+//! trade in the candle rather than arrival order.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::core::handle::TradeEvent;
+use crate::models::price::{Price, Quantity};
+use crate::models::trade::Trade;
+use crate::persistence::trade_store::TradeStore;
+
+/// Nanoseconds in a second
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// Length of the period a candle covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Interval {
+    #[serde(rename = "1s")]
+    OneSecond,
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+impl Interval {
+    /// Every supported interval, shortest first
+    pub const ALL: [Interval; 5] = [
+        Interval::OneSecond,
+        Interval::OneMinute,
+        Interval::FiveMinutes,
+        Interval::OneHour,
+        Interval::OneDay,
+    ];
+
+    /// Returns the length of the interval in nanoseconds
+    pub fn nanos(self) -> u64 {
+        let seconds = match self {
+            Interval::OneSecond => 1,
+            Interval::OneMinute => 60,
+            Interval::FiveMinutes => 300,
+            Interval::OneHour => 3_600,
+            Interval::OneDay => 86_400,
+        };
+        seconds * NANOS_PER_SECOND
+    }
+
+    /// Returns the start of the interval containing `timestamp`, aligned to the Unix epoch
+    pub fn start_of(self, timestamp: u64) -> u64 {
+        timestamp - timestamp % self.nanos()
+    }
+
+    /// Returns the short name of the interval, such as `5m`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Interval::OneSecond => "1s",
+            Interval::OneMinute => "1m",
+            Interval::FiveMinutes => "5m",
+            Interval::OneHour => "1h",
+            Interval::OneDay => "1d",
+        }
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Interval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        Interval::ALL
+            .into_iter()
+            .find(|interval| interval.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown interval: {}", s))
+    }
+}
+
+/// Open, high, low and close prices, volume and trade count of one symbol over one interval
+///
+/// A candle without trades repeats the previous close in all four prices.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Candle {
+    /// Symbol/ticker of the trades
+    pub symbol: String,
+    /// Length of the period covered
+    pub interval: Interval,
+    /// Start of the period in nanoseconds since the Unix epoch
+    pub start: u64,
+    /// Price of the earliest trade
+    pub open: Price,
+    /// Highest trade price
+    pub high: Price,
+    /// Lowest trade price
+    pub low: Price,
+    /// Price of the latest trade
+    pub close: Price,
+    /// Total quantity traded
+    pub volume: Quantity,
+    /// Sum of price times quantity over the trades, in raw units
+    pub notional: u128,
+    /// Number of trades
+    pub trade_count: u64,
+    /// Timestamp and ID of the trades that set the open and close
+    #[serde(skip)]
+    first: (u64, u64),
+    #[serde(skip)]
+    last: (u64, u64),
+}
+
+impl Candle {
+    fn from_trade(trade: &Trade, interval: Interval) -> Self {
+        let key = (trade.timestamp, trade.id);
+        Self {
+            symbol: trade.symbol.clone(),
+            interval,
+            start: interval.start_of(trade.timestamp),
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            volume: trade.quantity,
+            notional: trade.value(),
+            trade_count: 1,
+            first: key,
+            last: key,
+        }
+    }
+
+    fn flat(symbol: &str, interval: Interval, start: u64, price: Price) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            interval,
+            start,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: Quantity::ZERO,
+            notional: 0,
+            trade_count: 0,
+            first: (start, 0),
+            last: (start, 0),
+        }
+    }
+
+    fn add(&mut self, trade: &Trade) {
+        let key = (trade.timestamp, trade.id);
+        if key < self.first {
+            self.first = key;
+            self.open = trade.price;
+        }
+        if key >= self.last {
+            self.last = key;
+            self.close = trade.price;
+        }
+        self.high = self.high.max(trade.price);
+        self.low = self.low.min(trade.price);
+        self.volume += trade.quantity;
+        self.notional = self.notional.saturating_add(trade.value());
+        self.trade_count += 1;
+    }
+
+    /// Returns the end of the period, exclusive
+    pub fn end(&self) -> u64 {
+        self.start + self.interval.nanos()
+    }
+
+    /// Returns true if nothing traded in the period
+    pub fn is_empty(&self) -> bool {
+        self.trade_count == 0
+    }
+
+    /// Returns the volume-weighted average price, rounded to the nearest raw unit
+    pub fn vwap(&self) -> Option<Price> {
+        let volume = self.volume.0 as u128;
+        (volume > 0).then(|| Price((self.notional.saturating_add(volume / 2) / volume) as u64))
+    }
+}
+
+/// Builds candles at several intervals from trades of any number of symbols
+#[derive(Debug, Clone)]
+pub struct CandleAggregator {
+    intervals: Vec<Interval>,
+    /// How far behind the latest trade of its symbol a trade may be and still count
+    allowed_lateness: Option<u64>,
+    /// Candles by symbol and interval, keyed by start
+    candles: HashMap<(String, Interval), BTreeMap<u64, Candle>>,
+    /// Timestamp of the latest trade of each symbol
+    latest: HashMap<String, u64>,
+    /// Number of trades dropped for arriving too late
+    dropped: u64,
+}
+
+impl CandleAggregator {
+    /// Creates an aggregator building candles at the given intervals
+    pub fn new(intervals: &[Interval]) -> Self {
+        let mut intervals = intervals.to_vec();
+        intervals.sort();
+        intervals.dedup();
+        Self {
+            intervals,
+            allowed_lateness: None,
+            candles: HashMap::new(),
+            latest: HashMap::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Creates an aggregator from the trades in `store`, as adjusted by any busts and corrections
+    pub fn from_store(store: &TradeStore, intervals: &[Interval]) -> Self {
+        let mut aggregator = Self::new(intervals);
+        for trade in store.effective_trades() {
+            aggregator.add_trade(trade);
+        }
+        aggregator
+    }
+
+    /// Drops trades more than `nanos` older than the latest trade of their symbol
+    ///
+    /// Without a limit every late trade updates the candle it belongs to.
+    pub fn with_allowed_lateness(mut self, nanos: u64) -> Self {
+        self.allowed_lateness = Some(nanos);
+        self
+    }
+
+    /// Returns the intervals candles are built at, shortest first
+    pub fn intervals(&self) -> &[Interval] {
+        &self.intervals
+    }
+
+    /// Adds a trade to its candle at every interval
+    /// Returns false if the trade was dropped for arriving too late
+    pub fn add_trade(&mut self, trade: &Trade) -> bool {
+        let latest = self.latest.entry(trade.symbol.clone()).or_insert(trade.timestamp);
+        if let Some(lateness) = self.allowed_lateness {
+            if trade.timestamp.saturating_add(lateness) < *latest {
+                debug!("Dropped trade {} of {}, {} ns late", trade.id, trade.symbol, *latest - trade.timestamp);
+                self.dropped += 1;
+                return false;
+            }
+        }
+        *latest = (*latest).max(trade.timestamp);
+
+        for &interval in &self.intervals {
+            let start = interval.start_of(trade.timestamp);
+            self.candles
+                .entry((trade.symbol.clone(), interval))
+                .or_default()
+                .entry(start)
+                .and_modify(|candle| candle.add(trade))
+                .or_insert_with(|| Candle::from_trade(trade, interval));
+        }
+        true
+    }
+
+    /// Returns the number of trades dropped for arriving too late
+    pub fn dropped_trades(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Returns the candle of the latest trade of a symbol
+    pub fn current(&self, symbol: &str, interval: Interval) -> Option<&Candle> {
+        self.candles
+            .get(&(symbol.to_string(), interval))
+            .and_then(|candles| candles.values().next_back())
+    }
+
+    /// Returns the candles of a symbol starting in `[from, to)`, oldest first
+    ///
+    /// Periods without trades between the symbol's first and latest trade
+    /// are filled with flat candles at the previous close.
+    pub fn candles(&self, symbol: &str, interval: Interval, from: u64, to: u64) -> Vec<Candle> {
+        let (Some(series), Some(&latest)) = (self.candles.get(&(symbol.to_string(), interval)), self.latest.get(symbol)) else {
+            return Vec::new();
+        };
+        let step = interval.nanos();
+        let from = interval.start_of(from.saturating_add(step - 1));
+        let to = to.min(interval.start_of(latest) + step);
+        if from >= to {
+            return Vec::new();
+        }
+
+        let mut previous = series.range(..from).next_back().map(|(_, candle)| candle.close);
+        let mut start = from;
+        let mut candles = Vec::new();
+        for (&candle_start, candle) in series.range(from..to) {
+            if let Some(close) = previous {
+                candles.extend((start..candle_start).step_by(step as usize).map(|start| Candle::flat(symbol, interval, start, close)));
+            }
+            candles.push(candle.clone());
+            previous = Some(candle.close);
+            start = candle_start + step;
+        }
+        if let Some(close) = previous {
+            candles.extend((start..to).step_by(step as usize).map(|start| Candle::flat(symbol, interval, start, close)));
+        }
+        candles
+    }
+}
+
+/// Writes candles as CSV with a header row
+///
+/// Prices and quantities are raw fixed-point integers; the VWAP is empty for
+/// candles without trades.
+pub fn write_csv<W: Write>(candles: &[Candle], mut writer: W) -> io::Result<()> {
+    writeln!(writer, "symbol,interval,start,open,high,low,close,volume,vwap,trade_count")?;
+    for candle in candles {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{},{}",
+            candle.symbol,
+            candle.interval,
+            candle.start,
+            candle.open,
+            candle.high,
+            candle.low,
+            candle.close,
+            candle.volume,
+            candle.vwap().map(|vwap| vwap.to_string()).unwrap_or_default(),
+            candle.trade_count
+        )?;
+    }
+    writer.flush()
+}
+
+/// Adds every trade an engine publishes to `aggregator` until the engine stops
+///
+/// Spawn it with a receiver from `EngineHandle::subscribe_trades`.
+pub async fn aggregate_trades(aggregator: Arc<Mutex<CandleAggregator>>, mut trades: broadcast::Receiver<TradeEvent>) {
+    loop {
+        match trades.recv().await {
+            Ok(event) => {
+                aggregator.lock().unwrap().add_trade(&event.trade);
+            }
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!("Candle aggregation missed {} trades", missed);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::handle::EngineHandle;
+    use crate::models::order::{Order, OrderSide};
+
+    const SECOND: u64 = NANOS_PER_SECOND;
+
+    fn trade(id: u64, price: u64, quantity: u64, timestamp: u64) -> Trade {
+        Trade::new(id, price, quantity, timestamp, 10 + id, 20 + id, 1001, 1002, OrderSide::Buy, "BTC-USD".to_string())
+    }
+
+    #[test]
+    fn test_intervals() {
+        assert_eq!("5m".parse::<Interval>(), Ok(Interval::FiveMinutes));
+        assert_eq!("1D".parse::<Interval>(), Ok(Interval::OneDay));
+        assert!("2m".parse::<Interval>().is_err());
+        assert_eq!(Interval::OneHour.to_string(), "1h");
+        assert_eq!(Interval::OneMinute.start_of(125 * SECOND + 7), 120 * SECOND);
+        assert_eq!(serde_json::to_string(&Interval::OneSecond).unwrap(), "\"1s\"");
+    }
+
+    #[test]
+    fn test_ohlcv_with_gaps_and_late_trades() {
+        let mut aggregator = CandleAggregator::new(&[Interval::OneMinute, Interval::OneSecond]);
+        aggregator.add_trade(&trade(1, 10000, 2, 60 * SECOND + 5));
+        aggregator.add_trade(&trade(2, 10200, 1, 60 * SECOND + 30 * SECOND));
+        aggregator.add_trade(&trade(3, 10100, 3, 240 * SECOND));
+        // Arrives last but traded first in its minute, so it sets the open
+        aggregator.add_trade(&trade(4, 9900, 2, 60 * SECOND));
+
+        let candles = aggregator.candles("BTC-USD", Interval::OneMinute, 0, u64::MAX);
+        let starts: Vec<u64> = candles.iter().map(|candle| candle.start / SECOND).collect();
+        assert_eq!(starts, vec![60, 120, 180, 240]);
+        let first = &candles[0];
+        assert_eq!((first.open, first.high, first.low, first.close), (Price(9900), Price(10200), Price(9900), Price(10200)));
+        assert_eq!((first.volume, first.trade_count), (Quantity(5), 3));
+        assert_eq!(first.vwap(), Some(Price(10000)));
+        assert!(candles[1].is_empty());
+        assert_eq!((candles[1].open, candles[2].close), (Price(10200), Price(10200)));
+        assert_eq!(candles[3].close, Price(10100));
+        assert_eq!(aggregator.current("BTC-USD", Interval::OneMinute), candles.last());
+
+        let range = aggregator.candles("BTC-USD", Interval::OneMinute, 150 * SECOND, 240 * SECOND);
+        assert_eq!(range.len(), 1);
+        assert_eq!((range[0].start, range[0].close), (180 * SECOND, Price(10200)));
+        assert_eq!(aggregator.candles("BTC-USD", Interval::OneSecond, 0, u64::MAX).len(), 181);
+        assert!(aggregator.candles("BTC-USD", Interval::OneHour, 0, u64::MAX).is_empty());
+
+        let mut strict = CandleAggregator::new(&[Interval::OneMinute]).with_allowed_lateness(10 * SECOND);
+        strict.add_trade(&trade(1, 10000, 2, 100 * SECOND));
+        assert!(strict.add_trade(&trade(2, 10000, 2, 95 * SECOND)));
+        assert!(!strict.add_trade(&trade(3, 10000, 2, 80 * SECOND)));
+        assert_eq!(strict.dropped_trades(), 1);
+        assert_eq!(strict.current("BTC-USD", Interval::OneMinute).unwrap().volume, Quantity(4));
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let mut aggregator = CandleAggregator::new(&[Interval::OneSecond]);
+        aggregator.add_trade(&trade(1, 10000, 1, SECOND));
+        aggregator.add_trade(&trade(2, 10001, 2, 3 * SECOND));
+
+        let mut csv = Vec::new();
+        write_csv(&aggregator.candles("BTC-USD", Interval::OneSecond, 0, u64::MAX), &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "symbol,interval,start,open,high,low,close,volume,vwap,trade_count");
+        assert_eq!(lines[1], "BTC-USD,1s,1000000000,10000,10000,10000,10000,1,10000,1");
+        assert_eq!(lines[2], "BTC-USD,1s,2000000000,10000,10000,10000,10000,0,,0");
+        assert_eq!(lines[3], "BTC-USD,1s,3000000000,10001,10001,10001,10001,2,10001,1");
+    }
+
+    #[tokio::test]
+    async fn test_candles_from_store_and_engine() {
+        let mut store = TradeStore::new();
+        store.add_trade(trade(1, 10000, 2, SECOND)).unwrap();
+        store.add_trade(trade(2, 10100, 2, 2 * SECOND)).unwrap();
+        store.add_adjustment(store.bust_trade(2, "Erroneous", 3 * SECOND).unwrap()).unwrap();
+        let aggregator = CandleAggregator::from_store(&store, &[Interval::OneDay]);
+        let candle = aggregator.current("BTC-USD", Interval::OneDay).unwrap();
+        assert_eq!((candle.trade_count, candle.close), (1, Price(10000)));
+
+        let engine = EngineHandle::spawn();
+        let aggregator = Arc::new(Mutex::new(CandleAggregator::new(&Interval::ALL)));
+        let task = tokio::spawn(aggregate_trades(Arc::clone(&aggregator), engine.subscribe_trades()));
+        let symbol = "ETH-USD".to_string();
+        engine.submit(Order::new_limit(1, 2000, 5, OrderSide::Sell, 1, 1, None, symbol.clone())).await.unwrap();
+        engine.submit(Order::new_limit(2, 2000, 3, OrderSide::Buy, 2, 2, None, symbol.clone())).await.unwrap();
+        drop(engine);
+        task.await.unwrap();
+
+        let aggregator = aggregator.lock().unwrap();
+        for interval in Interval::ALL {
+            assert_eq!(aggregator.current("ETH-USD", interval).unwrap().volume, Quantity(3));
+        }
+    }
+}
@@ -0,0 +1,761 @@
+//! NASDAQ TotalView-ITCH 5.0 file parsing and historical book reconstruction
+//!
+//! ITCH files are a sequence of messages, each preceded by a two-byte
+//! big-endian length. Every message starts with its type, stock locate,
+//! tracking number and a six-byte timestamp in nanoseconds since midnight.
+//! Prices have four implied decimal places.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+
+use log::debug;
+
+use crate::core::order_book::OrderBook;
+use crate::models::instrument::Instrument;
+use crate::models::order::{Order, OrderError, OrderSide};
+use crate::models::price::{Price, Quantity};
+
+/// Decimal places of ITCH prices
+pub const PRICE_DECIMALS: u32 = 4;
+
+/// Size of the header shared by every message
+pub const HEADER_SIZE: usize = 11;
+
+/// Message type codes handled by the parser
+pub mod msg_type {
+    pub const SYSTEM_EVENT: u8 = b'S';
+    pub const STOCK_DIRECTORY: u8 = b'R';
+    pub const ADD_ORDER: u8 = b'A';
+    pub const ADD_ORDER_MPID: u8 = b'F';
+    pub const ORDER_EXECUTED: u8 = b'E';
+    pub const ORDER_EXECUTED_WITH_PRICE: u8 = b'C';
+    pub const ORDER_CANCEL: u8 = b'X';
+    pub const ORDER_DELETE: u8 = b'D';
+    pub const ORDER_REPLACE: u8 = b'U';
+    pub const TRADE: u8 = b'P';
+}
+
+/// A parsed ITCH 5.0 message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItchMessage {
+    /// Start or end of a market session, identified by its event code
+    SystemEvent { timestamp: u64, event_code: u8 },
+    /// Assigns a stock locate code to a stock for the day
+    StockDirectory {
+        stock_locate: u16,
+        timestamp: u64,
+        stock: String,
+    },
+    /// A new displayed order, with the market participant for `F` messages
+    AddOrder {
+        stock_locate: u16,
+        timestamp: u64,
+        order_ref: u64,
+        side: OrderSide,
+        shares: Quantity,
+        stock: String,
+        price: Price,
+        attribution: Option<String>,
+    },
+    /// A resting order was executed at its own price
+    OrderExecuted {
+        stock_locate: u16,
+        timestamp: u64,
+        order_ref: u64,
+        shares: Quantity,
+        match_number: u64,
+    },
+    /// A resting order was executed at a different price
+    OrderExecutedWithPrice {
+        stock_locate: u16,
+        timestamp: u64,
+        order_ref: u64,
+        shares: Quantity,
+        match_number: u64,
+        printable: bool,
+        price: Price,
+    },
+    /// Part of a resting order was canceled
+    OrderCancel {
+        stock_locate: u16,
+        timestamp: u64,
+        order_ref: u64,
+        canceled_shares: Quantity,
+    },
+    /// A resting order was removed
+    OrderDelete {
+        stock_locate: u16,
+        timestamp: u64,
+        order_ref: u64,
+    },
+    /// A resting order was replaced by a new order that loses priority
+    OrderReplace {
+        stock_locate: u16,
+        timestamp: u64,
+        original_order_ref: u64,
+        new_order_ref: u64,
+        shares: Quantity,
+        price: Price,
+    },
+    /// An execution against a non-displayed order
+    Trade {
+        stock_locate: u16,
+        timestamp: u64,
+        order_ref: u64,
+        side: OrderSide,
+        shares: Quantity,
+        stock: String,
+        price: Price,
+        match_number: u64,
+    },
+    /// Any other message type, which does not affect the books
+    Other {
+        message_type: u8,
+        stock_locate: u16,
+        timestamp: u64,
+    },
+}
+
+impl ItchMessage {
+    /// Returns the message timestamp in nanoseconds since midnight
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            ItchMessage::SystemEvent { timestamp, .. }
+            | ItchMessage::StockDirectory { timestamp, .. }
+            | ItchMessage::AddOrder { timestamp, .. }
+            | ItchMessage::OrderExecuted { timestamp, .. }
+            | ItchMessage::OrderExecutedWithPrice { timestamp, .. }
+            | ItchMessage::OrderCancel { timestamp, .. }
+            | ItchMessage::OrderDelete { timestamp, .. }
+            | ItchMessage::OrderReplace { timestamp, .. }
+            | ItchMessage::Trade { timestamp, .. }
+            | ItchMessage::Other { timestamp, .. } => *timestamp,
+        }
+    }
+
+    /// Parses one message without its length prefix
+    pub fn parse(message: &[u8]) -> io::Result<Self> {
+        if message.len() < HEADER_SIZE {
+            return Err(invalid(format!("Message of {} bytes is shorter than the header", message.len())));
+        }
+        let message_type = message[0];
+        let expected = match message_type {
+            msg_type::SYSTEM_EVENT => Some(12),
+            msg_type::STOCK_DIRECTORY => Some(39),
+            msg_type::ADD_ORDER => Some(36),
+            msg_type::ADD_ORDER_MPID => Some(40),
+            msg_type::ORDER_EXECUTED => Some(31),
+            msg_type::ORDER_EXECUTED_WITH_PRICE => Some(36),
+            msg_type::ORDER_CANCEL => Some(23),
+            msg_type::ORDER_DELETE => Some(19),
+            msg_type::ORDER_REPLACE => Some(35),
+            msg_type::TRADE => Some(44),
+            _ => None,
+        };
+        if let Some(expected) = expected {
+            if message.len() != expected {
+                return Err(invalid(format!(
+                    "Message type {} has {} bytes, expected {}",
+                    message_type as char,
+                    message.len(),
+                    expected
+                )));
+            }
+        }
+
+        let mut fields = Fields(&message[1..]);
+        let stock_locate = fields.u16();
+        let _tracking_number = fields.u16();
+        let timestamp = fields.uint(6);
+
+        let message = match message_type {
+            msg_type::SYSTEM_EVENT => ItchMessage::SystemEvent {
+                timestamp,
+                event_code: fields.take(1)[0],
+            },
+            msg_type::STOCK_DIRECTORY => ItchMessage::StockDirectory {
+                stock_locate,
+                timestamp,
+                stock: fields.text(8),
+            },
+            msg_type::ADD_ORDER | msg_type::ADD_ORDER_MPID => ItchMessage::AddOrder {
+                stock_locate,
+                timestamp,
+                order_ref: fields.uint(8),
+                side: fields.side()?,
+                shares: Quantity(fields.uint(4)),
+                stock: fields.text(8),
+                price: Price(fields.uint(4)),
+                attribution: (message_type == msg_type::ADD_ORDER_MPID).then(|| fields.text(4)),
+            },
+            msg_type::ORDER_EXECUTED => ItchMessage::OrderExecuted {
+                stock_locate,
+                timestamp,
+                order_ref: fields.uint(8),
+                shares: Quantity(fields.uint(4)),
+                match_number: fields.uint(8),
+            },
+            msg_type::ORDER_EXECUTED_WITH_PRICE => ItchMessage::OrderExecutedWithPrice {
+                stock_locate,
+                timestamp,
+                order_ref: fields.uint(8),
+                shares: Quantity(fields.uint(4)),
+                match_number: fields.uint(8),
+                printable: fields.take(1)[0] == b'Y',
+                price: Price(fields.uint(4)),
+            },
+            msg_type::ORDER_CANCEL => ItchMessage::OrderCancel {
+                stock_locate,
+                timestamp,
+                order_ref: fields.uint(8),
+                canceled_shares: Quantity(fields.uint(4)),
+            },
+            msg_type::ORDER_DELETE => ItchMessage::OrderDelete {
+                stock_locate,
+                timestamp,
+                order_ref: fields.uint(8),
+            },
+            msg_type::ORDER_REPLACE => ItchMessage::OrderReplace {
+                stock_locate,
+                timestamp,
+                original_order_ref: fields.uint(8),
+                new_order_ref: fields.uint(8),
+                shares: Quantity(fields.uint(4)),
+                price: Price(fields.uint(4)),
+            },
+            msg_type::TRADE => ItchMessage::Trade {
+                stock_locate,
+                timestamp,
+                order_ref: fields.uint(8),
+                side: fields.side()?,
+                shares: Quantity(fields.uint(4)),
+                stock: fields.text(8),
+                price: Price(fields.uint(4)),
+                match_number: fields.uint(8),
+            },
+            _ => ItchMessage::Other {
+                message_type,
+                stock_locate,
+                timestamp,
+            },
+        };
+        Ok(message)
+    }
+}
+
+/// Reads length-prefixed ITCH messages from a byte stream
+pub struct ItchReader<R> {
+    reader: R,
+    buffer: Vec<u8>,
+}
+
+impl<R: Read> ItchReader<R> {
+    /// Creates a reader over a byte stream; wrap files in a `BufReader`
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Returns the next message, or None at the end of the stream
+    pub fn next_message(&mut self) -> io::Result<Option<ItchMessage>> {
+        let mut length = [0u8; 2];
+        match self.reader.read_exact(&mut length[..1]) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        self.reader.read_exact(&mut length[1..])?;
+
+        self.buffer.resize(u16::from_be_bytes(length) as usize, 0);
+        self.reader.read_exact(&mut self.buffer)?;
+        ItchMessage::parse(&self.buffer).map(Some)
+    }
+}
+
+/// Rebuilds per-stock order books from an ITCH file without re-matching
+///
+/// Books are keyed by stock symbol and use the order reference numbers as
+/// order IDs, with a user ID of zero. Executions are applied to the resting
+/// order they name; trades against non-displayed orders (`P` messages) do not
+/// touch the books. Messages that do not apply, such as executions of orders
+/// added before the file starts, are skipped and counted.
+pub struct ItchReplay<R> {
+    reader: ItchReader<R>,
+    /// A message read ahead while advancing to a timestamp
+    pending: Option<ItchMessage>,
+    /// Stocks by locate code
+    stocks: HashMap<u16, String>,
+    /// Stocks to rebuild, or None for all
+    filter: Option<HashSet<String>>,
+    books: HashMap<String, OrderBook>,
+    timestamp: u64,
+    applied: u64,
+    skipped: u64,
+}
+
+impl ItchReplay<BufReader<File>> {
+    /// Opens an ITCH file for replay
+    pub fn open(file_path: &str) -> io::Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(file_path)?)))
+    }
+}
+
+impl<R: Read> ItchReplay<R> {
+    /// Creates a replay over an ITCH byte stream
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: ItchReader::new(reader),
+            pending: None,
+            stocks: HashMap::new(),
+            filter: None,
+            books: HashMap::new(),
+            timestamp: 0,
+            applied: 0,
+            skipped: 0,
+        }
+    }
+
+    /// Only rebuilds the books of the given stocks
+    pub fn with_symbols<I, S>(mut self, symbols: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.filter = Some(symbols.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Applies every message up to and including `timestamp` (nanoseconds since midnight)
+    /// Returns the number of messages read
+    pub fn advance_to(&mut self, timestamp: u64) -> io::Result<u64> {
+        let mut read = 0;
+        loop {
+            let message = match self.pending.take() {
+                Some(message) => message,
+                None => match self.reader.next_message()? {
+                    Some(message) => message,
+                    None => return Ok(read),
+                },
+            };
+            if message.timestamp() > timestamp {
+                self.pending = Some(message);
+                return Ok(read);
+            }
+            self.apply(&message);
+            read += 1;
+        }
+    }
+
+    /// Applies every remaining message, returning the number read
+    pub fn run_to_end(&mut self) -> io::Result<u64> {
+        self.advance_to(u64::MAX)
+    }
+
+    /// Returns the book of a stock, if it has had any orders
+    pub fn book(&self, symbol: &str) -> Option<&OrderBook> {
+        self.books.get(symbol)
+    }
+
+    /// Returns every rebuilt book by stock
+    pub fn books(&self) -> &HashMap<String, OrderBook> {
+        &self.books
+    }
+
+    /// Returns the timestamp of the last applied message
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Returns the number of messages that changed a book
+    pub fn applied_count(&self) -> u64 {
+        self.applied
+    }
+
+    /// Returns the number of book messages that could not be applied
+    pub fn skipped_count(&self) -> u64 {
+        self.skipped
+    }
+
+    /// Applies one message to the books
+    pub fn apply(&mut self, message: &ItchMessage) {
+        self.timestamp = message.timestamp();
+        let result = match message {
+            ItchMessage::StockDirectory { stock_locate, stock, .. } => {
+                self.stocks.insert(*stock_locate, stock.clone());
+                return;
+            }
+            ItchMessage::AddOrder {
+                stock_locate,
+                timestamp,
+                order_ref,
+                side,
+                shares,
+                stock,
+                price,
+                ..
+            } => {
+                self.stocks.entry(*stock_locate).or_insert_with(|| stock.clone());
+                let Some(book) = self.book_mut(*stock_locate) else {
+                    return;
+                };
+                let order = Order::new_limit(*order_ref, *price, *shares, *side, 0, *timestamp, None, stock.clone());
+                book.insert_order(order)
+            }
+            ItchMessage::OrderExecuted {
+                stock_locate,
+                timestamp,
+                order_ref,
+                shares,
+                ..
+            } => {
+                let Some(book) = self.book_mut(*stock_locate) else {
+                    return;
+                };
+                match book.get_order(*order_ref) {
+                    Some(order) => {
+                        let price = order.price;
+                        book.execute_order(*order_ref, *shares, price, *timestamp).map(|_| ())
+                    }
+                    None => Err(OrderError::UnknownOrder { order_id: *order_ref }),
+                }
+            }
+            ItchMessage::OrderExecutedWithPrice {
+                stock_locate,
+                timestamp,
+                order_ref,
+                shares,
+                price,
+                ..
+            } => {
+                let Some(book) = self.book_mut(*stock_locate) else {
+                    return;
+                };
+                book.execute_order(*order_ref, *shares, *price, *timestamp).map(|_| ())
+            }
+            ItchMessage::OrderCancel {
+                stock_locate,
+                timestamp,
+                order_ref,
+                canceled_shares,
+            } => {
+                let Some(book) = self.book_mut(*stock_locate) else {
+                    return;
+                };
+                match book.get_order(*order_ref) {
+                    Some(order) if *canceled_shares >= order.remaining_quantity => {
+                        cancel(book, *order_ref)
+                    }
+                    Some(order) => {
+                        let quantity = order.quantity - *canceled_shares;
+                        book.amend_order(*order_ref, None, Some(quantity), *timestamp).map(|_| ())
+                    }
+                    None => Err(OrderError::UnknownOrder { order_id: *order_ref }),
+                }
+            }
+            ItchMessage::OrderDelete { stock_locate, order_ref, .. } => {
+                let Some(book) = self.book_mut(*stock_locate) else {
+                    return;
+                };
+                cancel(book, *order_ref)
+            }
+            ItchMessage::OrderReplace {
+                stock_locate,
+                timestamp,
+                original_order_ref,
+                new_order_ref,
+                shares,
+                price,
+            } => {
+                let Some(book) = self.book_mut(*stock_locate) else {
+                    return;
+                };
+                match book.get_order(*original_order_ref) {
+                    Some(order) => {
+                        let side = order.side;
+                        let symbol = order.symbol.clone();
+                        let new_order =
+                            Order::new_limit(*new_order_ref, *price, *shares, side, 0, *timestamp, None, symbol);
+                        book.replace_order(*original_order_ref, new_order)
+                    }
+                    None => Err(OrderError::UnknownOrder { order_id: *original_order_ref }),
+                }
+            }
+            ItchMessage::SystemEvent { .. } | ItchMessage::Trade { .. } | ItchMessage::Other { .. } => return,
+        };
+
+        match result {
+            Ok(()) => self.applied += 1,
+            Err(e) => {
+                debug!("Skipping ITCH message at {}: {}", message.timestamp(), e);
+                self.skipped += 1;
+            }
+        }
+    }
+
+    /// Returns the book for a stock locate, creating it on first use, or None
+    /// if the stock is unknown or filtered out
+    fn book_mut(&mut self, stock_locate: u16) -> Option<&mut OrderBook> {
+        let stock = self.stocks.get(&stock_locate)?;
+        if self.filter.as_ref().is_some_and(|filter| !filter.contains(stock)) {
+            return None;
+        }
+        let book = self
+            .books
+            .entry(stock.clone())
+            .or_insert_with(|| OrderBook::with_instrument(Instrument::with_precision(stock, PRICE_DECIMALS, 0).expect("ITCH precision is supported")));
+        Some(book)
+    }
+}
+
+/// Removes a resting order, reporting unknown or inactive orders
+fn cancel(book: &mut OrderBook, order_ref: u64) -> Result<(), OrderError> {
+    if book.cancel_order(order_ref) {
+        Ok(())
+    } else {
+        Err(OrderError::UnknownOrder { order_id: order_ref })
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Reads big-endian fields from a message whose length has been checked
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Fields<'a> {
+    fn take(&mut self, size: usize) -> &'a [u8] {
+        let (field, rest) = self.0.split_at(size.min(self.0.len()));
+        self.0 = rest;
+        field
+    }
+
+    fn uint(&mut self, size: usize) -> u64 {
+        self.take(size).iter().fold(0, |value, &byte| value << 8 | byte as u64)
+    }
+
+    fn u16(&mut self) -> u16 {
+        self.uint(2) as u16
+    }
+
+    /// Reads a space-padded alphanumeric field
+    fn text(&mut self, size: usize) -> String {
+        String::from_utf8_lossy(self.take(size)).trim_end().to_string()
+    }
+
+    fn side(&mut self) -> io::Result<OrderSide> {
+        match self.take(1) {
+            [b'B'] => Ok(OrderSide::Buy),
+            [b'S'] => Ok(OrderSide::Sell),
+            other => Err(invalid(format!("Unknown side {:?}", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    use crate::models::order::OrderStatus;
+
+    const AAPL: u16 = 1;
+    const MSFT: u16 = 2;
+
+    /// Builds synthetic ITCH messages with length prefixes
+    #[derive(Default)]
+    struct ItchWriter {
+        bytes: Vec<u8>,
+    }
+
+    impl ItchWriter {
+        fn message(&mut self, message_type: u8, locate: u16, timestamp: u64, body: &[&[u8]]) -> &mut Self {
+            let mut message = vec![message_type];
+            message.extend_from_slice(&locate.to_be_bytes());
+            message.extend_from_slice(&0u16.to_be_bytes());
+            message.extend_from_slice(&timestamp.to_be_bytes()[2..]);
+            for field in body {
+                message.extend_from_slice(field);
+            }
+            self.bytes.extend_from_slice(&(message.len() as u16).to_be_bytes());
+            self.bytes.extend_from_slice(&message);
+            self
+        }
+
+        fn system_event(&mut self, timestamp: u64, code: u8) -> &mut Self {
+            self.message(msg_type::SYSTEM_EVENT, 0, timestamp, &[&[code]])
+        }
+
+        fn directory(&mut self, locate: u16, stock: &str) -> &mut Self {
+            // Everything after the stock is reference data the books do not use
+            self.message(msg_type::STOCK_DIRECTORY, locate, 0, &[&text(stock, 8), &[b' '; 20]])
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        fn add(&mut self, locate: u16, ts: u64, order_ref: u64, side: u8, shares: u32, stock: &str, price: u32) -> &mut Self {
+            let body: [&[u8]; 5] = [&order_ref.to_be_bytes(), &[side], &shares.to_be_bytes(), &text(stock, 8), &price.to_be_bytes()];
+            self.message(msg_type::ADD_ORDER, locate, ts, &body)
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        fn add_mpid(&mut self, locate: u16, ts: u64, order_ref: u64, side: u8, shares: u32, stock: &str, price: u32) -> &mut Self {
+            let body: [&[u8]; 6] = [
+                &order_ref.to_be_bytes(),
+                &[side],
+                &shares.to_be_bytes(),
+                &text(stock, 8),
+                &price.to_be_bytes(),
+                b"GSCO",
+            ];
+            self.message(msg_type::ADD_ORDER_MPID, locate, ts, &body)
+        }
+
+        fn executed(&mut self, locate: u16, ts: u64, order_ref: u64, shares: u32, match_number: u64) -> &mut Self {
+            let body: [&[u8]; 3] = [&order_ref.to_be_bytes(), &shares.to_be_bytes(), &match_number.to_be_bytes()];
+            self.message(msg_type::ORDER_EXECUTED, locate, ts, &body)
+        }
+
+        fn executed_with_price(&mut self, locate: u16, ts: u64, order_ref: u64, shares: u32, price: u32) -> &mut Self {
+            let body: [&[u8]; 5] = [&order_ref.to_be_bytes(), &shares.to_be_bytes(), &7u64.to_be_bytes(), b"Y", &price.to_be_bytes()];
+            self.message(msg_type::ORDER_EXECUTED_WITH_PRICE, locate, ts, &body)
+        }
+
+        fn cancel(&mut self, locate: u16, ts: u64, order_ref: u64, shares: u32) -> &mut Self {
+            self.message(msg_type::ORDER_CANCEL, locate, ts, &[&order_ref.to_be_bytes(), &shares.to_be_bytes()])
+        }
+
+        fn delete(&mut self, locate: u16, ts: u64, order_ref: u64) -> &mut Self {
+            self.message(msg_type::ORDER_DELETE, locate, ts, &[&order_ref.to_be_bytes()])
+        }
+
+        fn replace(&mut self, locate: u16, ts: u64, original: u64, new: u64, shares: u32, price: u32) -> &mut Self {
+            let body: [&[u8]; 4] = [&original.to_be_bytes(), &new.to_be_bytes(), &shares.to_be_bytes(), &price.to_be_bytes()];
+            self.message(msg_type::ORDER_REPLACE, locate, ts, &body)
+        }
+
+        fn trade(&mut self, locate: u16, ts: u64, stock: &str, shares: u32, price: u32) -> &mut Self {
+            let body: [&[u8]; 6] = [&0u64.to_be_bytes(), b"B", &shares.to_be_bytes(), &text(stock, 8), &price.to_be_bytes(), &9u64.to_be_bytes()];
+            self.message(msg_type::TRADE, locate, ts, &body)
+        }
+    }
+
+    fn text(value: &str, size: usize) -> Vec<u8> {
+        format!("{:<width$}", value, width = size).into_bytes()
+    }
+
+    /// A short synthetic trading day for two stocks
+    fn synthetic_day() -> Vec<u8> {
+        let mut itch = ItchWriter::default();
+        itch.system_event(1_000, b'O')
+            .directory(AAPL, "AAPL")
+            .directory(MSFT, "MSFT")
+            // 09:30 book build
+            .add(AAPL, 10_000, 1, b'B', 100, "AAPL", 1_500_000)
+            .add(AAPL, 10_001, 2, b'B', 200, "AAPL", 1_499_900)
+            .add(AAPL, 10_002, 3, b'S', 150, "AAPL", 1_500_100)
+            .add_mpid(AAPL, 10_003, 4, b'S', 300, "AAPL", 1_500_200)
+            .add(MSFT, 10_004, 5, b'B', 50, "MSFT", 3_000_000)
+            // Executions, cancels and replaces
+            .executed(AAPL, 20_000, 3, 50, 1)
+            .executed_with_price(AAPL, 20_001, 1, 100, 1_500_050)
+            .cancel(AAPL, 20_002, 2, 50)
+            .replace(AAPL, 20_003, 4, 6, 250, 1_500_150)
+            .trade(AAPL, 20_004, "AAPL", 500, 1_500_000)
+            .delete(MSFT, 20_005, 5)
+            // Unknown order references are skipped
+            .delete(AAPL, 20_006, 99)
+            .system_event(30_000, b'C');
+        itch.bytes
+    }
+
+    #[test]
+    fn test_parse_messages() {
+        let bytes = synthetic_day();
+        let mut reader = ItchReader::new(bytes.as_slice());
+        let mut messages = Vec::new();
+        while let Some(message) = reader.next_message().unwrap() {
+            messages.push(message);
+        }
+        assert_eq!(messages.len(), 16);
+        assert_eq!(
+            messages[1],
+            ItchMessage::StockDirectory { stock_locate: AAPL, timestamp: 0, stock: "AAPL".to_string() }
+        );
+        assert_eq!(
+            messages[6],
+            ItchMessage::AddOrder {
+                stock_locate: AAPL,
+                timestamp: 10_003,
+                order_ref: 4,
+                side: OrderSide::Sell,
+                shares: Quantity(300),
+                stock: "AAPL".to_string(),
+                price: Price(1_500_200),
+                attribution: Some("GSCO".to_string()),
+            }
+        );
+        assert!(matches!(
+            messages[9],
+            ItchMessage::OrderExecutedWithPrice { printable: true, price: Price(1_500_050), .. }
+        ));
+        assert_eq!(messages[15].timestamp(), 30_000);
+
+        // Wrong lengths and truncated files are errors
+        assert!(ItchMessage::parse(&[msg_type::ORDER_DELETE; 18]).is_err());
+        let mut truncated = ItchReader::new(&bytes[..bytes.len() - 1]);
+        let result = (0..16).try_for_each(|_| truncated.next_message().map(|_| ()));
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_books_at_points_in_the_day() {
+        let bytes = synthetic_day();
+        let mut replay = ItchReplay::new(bytes.as_slice());
+
+        // After the book build
+        assert_eq!(replay.advance_to(10_004).unwrap(), 8);
+        let aapl = replay.book("AAPL").unwrap();
+        let (bids, asks) = aapl.market_depth(5);
+        assert_eq!(bids, vec![(Price(1_500_000), Quantity(100)), (Price(1_499_900), Quantity(200))]);
+        assert_eq!(asks, vec![(Price(1_500_100), Quantity(150)), (Price(1_500_200), Quantity(300))]);
+        assert_eq!(aapl.instrument().price_to_string(Price(1_500_000)), "150.0000");
+        let (average, _) = aapl.calculate_slippage(OrderSide::Buy, Quantity(300)).unwrap();
+        assert_eq!(average, Price(1_500_150));
+        assert_eq!(replay.book("MSFT").unwrap().best_bid(), Some(Price(3_000_000)));
+
+        // After executions, a partial cancel, a replace and a delete
+        replay.run_to_end().unwrap();
+        let aapl = replay.book("AAPL").unwrap();
+        let (bids, asks) = aapl.market_depth(5);
+        assert_eq!(bids, vec![(Price(1_499_900), Quantity(150))]);
+        assert_eq!(asks, vec![(Price(1_500_100), Quantity(100)), (Price(1_500_150), Quantity(250))]);
+        assert_eq!(aapl.get_order(1).unwrap().status, OrderStatus::Filled);
+        assert_eq!(aapl.get_order(4).unwrap().status, OrderStatus::Replaced);
+        assert_eq!(aapl.stats().trade_count, 2);
+        assert_eq!(aapl.stats().volume, Quantity(150));
+        assert_eq!(aapl.stats().last_trade_price, Some(Price(1_500_050)));
+        assert!(replay.book("MSFT").unwrap().market_depth(5).0.is_empty());
+        assert_eq!(replay.timestamp(), 30_000);
+        assert_eq!(replay.applied_count(), 10);
+        assert_eq!(replay.skipped_count(), 1);
+    }
+
+    #[test]
+    fn test_replay_file_with_symbol_filter() {
+        let path = std::env::temp_dir().join(format!("rustflow_itch_{}.bin", std::process::id()));
+        File::create(&path).unwrap().write_all(&synthetic_day()).unwrap();
+
+        let mut replay = ItchReplay::open(path.to_str().unwrap()).unwrap().with_symbols(["MSFT"]);
+        replay.advance_to(10_010).unwrap();
+        assert!(replay.book("AAPL").is_none());
+        assert_eq!(replay.books().len(), 1);
+        assert_eq!(replay.book("MSFT").unwrap().best_bid(), Some(Price(3_000_000)));
+        replay.run_to_end().unwrap();
+        assert_eq!(replay.skipped_count(), 0);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
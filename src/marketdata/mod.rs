@@ -0,0 +1,9 @@
+// Export market data components
+pub mod binary;
+pub mod candles;
+pub mod itch;
+
+// Re-export main components
+pub use binary::{FeedDecoder, FeedEncoder, FeedMessage};
+pub use candles::{Candle, CandleAggregator, Interval};
+pub use itch::{ItchMessage, ItchReader, ItchReplay};
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::price::{Price, Quantity};
+
+/// Parameters of the analytics computed from an order book
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnalyticsConfig {
+    /// Number of levels per side used for imbalance, weighted price and slope
+    pub levels: usize,
+    /// Distance from the midpoint, in basis points, that depth is summed within
+    pub depth_bps: u32,
+}
+
+impl AnalyticsConfig {
+    /// Creates a configuration over `levels` levels and depth within `depth_bps` of the midpoint
+    pub fn new(levels: usize, depth_bps: u32) -> Self {
+        Self { levels, depth_bps }
+    }
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self::new(5, 10)
+    }
+}
+
+/// Signals derived from the resting orders of a book at one point in time
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BookAnalytics {
+    /// Best bid and ask weighted by the size on the opposite side
+    pub microprice: Option<f64>,
+    /// Bid volume less ask volume over the top levels, divided by their sum
+    pub imbalance: Option<f64>,
+    /// Bid quantity within the configured distance of the midpoint
+    pub bid_depth: Quantity,
+    /// Ask quantity within the configured distance of the midpoint
+    pub ask_depth: Quantity,
+    /// Quantity-weighted average price of the top bid levels
+    pub bid_weighted_price: Option<Price>,
+    /// Quantity-weighted average price of the top ask levels
+    pub ask_weighted_price: Option<Price>,
+    /// Bid quantity per raw price unit across the top levels
+    pub bid_slope: Option<f64>,
+    /// Ask quantity per raw price unit across the top levels
+    pub ask_slope: Option<f64>,
+}
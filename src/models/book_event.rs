@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::order::OrderSide;
+use crate::models::price::{Price, Quantity};
+use crate::models::trade::Trade;
+
+/// A change to the visible state of an order book, in the order it happened
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BookEvent {
+    /// An order started resting in the book
+    AddOrder {
+        order_id: u64,
+        side: OrderSide,
+        price: Price,
+        quantity: Quantity,
+    },
+    /// A resting order was filled; it leaves the book once nothing remains
+    Execute {
+        order_id: u64,
+        quantity: Quantity,
+        price: Price,
+        trade_id: u64,
+    },
+    /// Part of a resting order was canceled
+    Cancel { order_id: u64, quantity: Quantity },
+    /// A resting order was removed from the book
+    Delete { order_id: u64 },
+    /// A trade between an incoming and a resting order
+    Trade(Trade),
+    /// The best bid and offer changed
+    Bbo {
+        bid: Option<(Price, Quantity)>,
+        ask: Option<(Price, Quantity)>,
+    },
+}
@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::order::OrderSide;
+use crate::models::price::{Price, Quantity};
+
+/// A single price level consumed by a hypothetical order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LadderLevel {
+    /// Price of the level
+    pub price: Price,
+    /// Quantity that would be filled at this level
+    pub quantity: Quantity,
+    /// Total quantity resting at this level
+    pub available: Quantity,
+    /// Quantity filled up to and including this level
+    pub cumulative_quantity: Quantity,
+    /// Notional filled up to and including this level
+    pub cumulative_notional: u128,
+}
+
+/// Pre-trade estimate of how an order would execute against the current book
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionEstimate {
+    /// Side of the hypothetical order
+    pub side: OrderSide,
+    /// Quantity requested
+    pub requested_quantity: Quantity,
+    /// Quantity that could be filled
+    pub filled_quantity: Quantity,
+    /// Quantity left unfilled due to insufficient liquidity or the limit price
+    pub unfilled_quantity: Quantity,
+    /// Total notional of the filled quantity (price * quantity)
+    pub notional: u128,
+    /// Optional limit price capping the levels consumed
+    pub limit_price: Option<Price>,
+    /// Best opposite price before the order executes
+    pub best_price: Option<Price>,
+    /// Midpoint before the order executes, if both sides are present
+    pub midpoint: Option<f64>,
+    /// Level-by-level fill ladder, best price first
+    pub levels: Vec<LadderLevel>,
+}
+
+impl ExecutionEstimate {
+    /// Returns true if the whole requested quantity could be filled
+    pub fn is_fully_filled(&self) -> bool {
+        self.unfilled_quantity.is_zero()
+    }
+
+    /// Returns the exact average execution price of the filled quantity
+    pub fn average_price(&self) -> Option<f64> {
+        if !self.filled_quantity.is_zero() {
+            Some(self.notional as f64 / self.filled_quantity.0 as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the worst (last) price level reached
+    pub fn worst_price(&self) -> Option<Price> {
+        self.levels.last().map(|level| level.price)
+    }
+
+    /// Returns the number of price levels consumed
+    pub fn levels_consumed(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Returns the adverse move of the average price from the best price, in basis points
+    pub fn impact_bps(&self) -> Option<f64> {
+        let best = self.best_price?.as_f64();
+        let average = self.average_price()?;
+        Some(self.adverse_bps(average, best))
+    }
+
+    /// Returns the adverse move of the average price from the midpoint, in basis points
+    pub fn mid_impact_bps(&self) -> Option<f64> {
+        let mid = self.midpoint?;
+        let average = self.average_price()?;
+        Some(self.adverse_bps(average, mid))
+    }
+
+    /// Returns the adverse move of the worst price from the best price, in basis points
+    pub fn worst_price_impact_bps(&self) -> Option<f64> {
+        let best = self.best_price?.as_f64();
+        let worst = self.worst_price()?.as_f64();
+        Some(self.adverse_bps(worst, best))
+    }
+
+    /// Returns the slippage of the average price from the best price, in percent
+    pub fn slippage_percent(&self) -> Option<f64> {
+        self.impact_bps().map(|bps| bps / 100.0)
+    }
+
+    /// Price difference relative to a reference, positive when it costs the order
+    fn adverse_bps(&self, price: f64, reference: f64) -> f64 {
+        if reference == 0.0 {
+            return 0.0;
+        }
+
+        let difference = match self.side {
+            OrderSide::Buy => price - reference,
+            OrderSide::Sell => reference - price,
+        };
+        difference / reference * 10_000.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn estimate(side: OrderSide, levels: &[(u64, u64)], requested: u64) -> ExecutionEstimate {
+        let mut ladder = Vec::new();
+        let mut cumulative_quantity = Quantity::ZERO;
+        let mut cumulative_notional = 0u128;
+
+        for &(price, quantity) in levels {
+            cumulative_quantity += Quantity(quantity);
+            cumulative_notional += Price(price).notional(Quantity(quantity));
+            ladder.push(LadderLevel {
+                price: Price(price),
+                quantity: Quantity(quantity),
+                available: Quantity(quantity),
+                cumulative_quantity,
+                cumulative_notional,
+            });
+        }
+
+        ExecutionEstimate {
+            side,
+            requested_quantity: Quantity(requested),
+            filled_quantity: cumulative_quantity,
+            unfilled_quantity: Quantity(requested) - cumulative_quantity,
+            notional: cumulative_notional,
+            limit_price: None,
+            best_price: levels.first().map(|&(price, _)| Price(price)),
+            midpoint: None,
+            levels: ladder,
+        }
+    }
+
+    #[test]
+    fn test_buy_impact() {
+        let estimate = estimate(OrderSide::Buy, &[(10000, 1), (10100, 1)], 2);
+
+        assert!(estimate.is_fully_filled());
+        assert_eq!(estimate.average_price(), Some(10050.0));
+        assert_eq!(estimate.worst_price(), Some(Price(10100)));
+        assert_eq!(estimate.impact_bps(), Some(50.0));
+        assert_eq!(estimate.worst_price_impact_bps(), Some(100.0));
+        assert_eq!(estimate.slippage_percent(), Some(0.5));
+    }
+
+    #[test]
+    fn test_sell_impact_is_positive_when_adverse() {
+        let estimate = estimate(OrderSide::Sell, &[(10000, 1), (9900, 3)], 6);
+
+        assert!(!estimate.is_fully_filled());
+        assert_eq!(estimate.unfilled_quantity, Quantity(2));
+        assert_eq!(estimate.average_price(), Some(9925.0));
+        assert_eq!(estimate.impact_bps(), Some(75.0));
+    }
+
+    #[test]
+    fn test_empty_estimate() {
+        let estimate = estimate(OrderSide::Buy, &[], 5);
+
+        assert_eq!(estimate.average_price(), None);
+        assert_eq!(estimate.worst_price(), None);
+        assert_eq!(estimate.impact_bps(), None);
+        assert_eq!(estimate.levels_consumed(), 0);
+    }
+}
@@ -0,0 +1,219 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::price::{Price, Quantity};
+
+/// Default number of decimal places for prices (e.g. cents)
+pub const DEFAULT_PRICE_DECIMALS: u32 = 2;
+
+/// Default number of decimal places for quantities (whole units)
+pub const DEFAULT_QUANTITY_DECIMALS: u32 = 0;
+
+/// Largest number of decimal places in a price or quantity, so that one whole unit fits in a u64
+pub const MAX_DECIMALS: u32 = 19;
+
+/// Describes a tradable instrument and the precision of its prices and quantities
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "InstrumentFields")]
+pub struct Instrument {
+    /// Symbol/ticker (e.g., "BTC-USD")
+    pub symbol: String,
+    /// Asset being bought or sold (e.g., "BTC")
+    pub base_asset: String,
+    /// Asset prices are quoted in (e.g., "USD")
+    pub quote_asset: String,
+    /// Number of decimal places in a price
+    pub price_decimals: u32,
+    /// Number of decimal places in a quantity
+    pub quantity_decimals: u32,
+}
+
+/// Fields of a serialized instrument, whose precision is checked before it is built
+#[derive(Deserialize)]
+struct InstrumentFields {
+    symbol: String,
+    base_asset: String,
+    quote_asset: String,
+    price_decimals: u32,
+    quantity_decimals: u32,
+}
+
+impl TryFrom<InstrumentFields> for Instrument {
+    type Error = String;
+
+    fn try_from(fields: InstrumentFields) -> Result<Self, Self::Error> {
+        check_precision(&fields.symbol, fields.price_decimals, fields.quantity_decimals)?;
+        Ok(Self {
+            symbol: fields.symbol,
+            base_asset: fields.base_asset,
+            quote_asset: fields.quote_asset,
+            price_decimals: fields.price_decimals,
+            quantity_decimals: fields.quantity_decimals,
+        })
+    }
+}
+
+impl Instrument {
+    /// Creates an instrument with the default precision (2 price decimals, whole quantities)
+    pub fn new(symbol: &str) -> Self {
+        Self::build(symbol, DEFAULT_PRICE_DECIMALS, DEFAULT_QUANTITY_DECIMALS)
+    }
+
+    /// Creates an instrument with the given price and quantity precision
+    /// Returns an error if either has more than `MAX_DECIMALS` decimal places
+    pub fn with_precision(symbol: &str, price_decimals: u32, quantity_decimals: u32) -> Result<Self, String> {
+        check_precision(symbol, price_decimals, quantity_decimals)?;
+        Ok(Self::build(symbol, price_decimals, quantity_decimals))
+    }
+
+    fn build(symbol: &str, price_decimals: u32, quantity_decimals: u32) -> Self {
+        let (base_asset, quote_asset) = Self::split_symbol(symbol);
+
+        Self {
+            symbol: symbol.to_string(),
+            base_asset,
+            quote_asset,
+            price_decimals,
+            quantity_decimals,
+        }
+    }
+
+    /// Splits a symbol like "BTC-USD", "BTC/USD" or "BTC_USD" into base and quote assets
+    /// Symbols without a separator (e.g. equities) have an empty quote asset
+    pub fn split_symbol(symbol: &str) -> (String, String) {
+        match symbol.split_once(['-', '/', '_']) {
+            Some((base, quote)) => (base.to_ascii_uppercase(), quote.to_ascii_uppercase()),
+            None => (symbol.to_ascii_uppercase(), String::new()),
+        }
+    }
+
+    /// Returns the currency sign for the quote asset, if it has a well-known one
+    pub fn currency_symbol(&self) -> Option<&'static str> {
+        match self.quote_asset.as_str() {
+            "USD" | "USDT" | "USDC" => Some("$"),
+            "EUR" => Some("€"),
+            "GBP" => Some("£"),
+            "JPY" => Some("¥"),
+            _ => None,
+        }
+    }
+
+    /// Parses a decimal price string (e.g. "101.25") into a price
+    pub fn parse_price(&self, s: &str) -> Result<Price, String> {
+        Price::from_decimal_str(s, self.price_decimals)
+    }
+
+    /// Parses a decimal quantity string (e.g. "0.5") into a quantity
+    pub fn parse_quantity(&self, s: &str) -> Result<Quantity, String> {
+        Quantity::from_decimal_str(s, self.quantity_decimals)
+    }
+
+    /// Formats a price as a plain decimal string
+    pub fn price_to_string(&self, price: Price) -> String {
+        price.to_decimal_string(self.price_decimals)
+    }
+
+    /// Formats a price for display with its currency (e.g. "$101.25" or "0.00012345 BTC")
+    pub fn format_price(&self, price: Price) -> String {
+        let amount = self.price_to_string(price);
+
+        match (self.currency_symbol(), self.quote_asset.is_empty()) {
+            (Some(sign), _) => format!("{}{}", sign, amount),
+            (None, true) => amount,
+            (None, false) => format!("{} {}", amount, self.quote_asset),
+        }
+    }
+
+    /// Formats a quantity as a decimal string
+    pub fn format_quantity(&self, quantity: Quantity) -> String {
+        quantity.to_decimal_string(self.quantity_decimals)
+    }
+
+    /// Converts a raw price to a floating point number in quote currency units
+    pub fn price_to_f64(&self, price: Price) -> f64 {
+        price.as_f64() / 10f64.powi(self.price_decimals as i32)
+    }
+
+    /// Converts a raw quantity to a floating point number in base asset units
+    pub fn quantity_to_f64(&self, quantity: Quantity) -> f64 {
+        quantity.0 as f64 / 10f64.powi(self.quantity_decimals as i32)
+    }
+
+    /// Converts a raw notional (raw price times raw quantity) to the quote asset's smallest unit, rounding up
+    pub fn quote_units(&self, notional: u128) -> u128 {
+        notional.div_ceil(10u128.pow(self.quantity_decimals))
+    }
+}
+
+/// Checks that prices and quantities of an instrument fit the supported precision
+fn check_precision(symbol: &str, price_decimals: u32, quantity_decimals: u32) -> Result<(), String> {
+    for (name, decimals) in [("price", price_decimals), ("quantity", quantity_decimals)] {
+        if decimals > MAX_DECIMALS {
+            return Err(format!(
+                "{} has {} {} decimal places, more than the {} supported",
+                symbol, decimals, name, MAX_DECIMALS
+            ));
+        }
+    }
+    Ok(())
+}
+
+impl Default for Instrument {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_assets() {
+        let instrument = Instrument::new("btc-usd");
+        assert_eq!(instrument.base_asset, "BTC");
+        assert_eq!(instrument.quote_asset, "USD");
+
+        let equity = Instrument::new("AAPL");
+        assert_eq!(equity.base_asset, "AAPL");
+        assert_eq!(equity.quote_asset, "");
+    }
+
+    #[test]
+    fn test_currency_aware_formatting() {
+        let usd = Instrument::new("BTC-USD");
+        assert_eq!(usd.format_price(Price(10125)), "$101.25");
+        assert_eq!(usd.format_quantity(Quantity(3)), "3");
+
+        let crypto = Instrument::with_precision("ETH-BTC", 8, 4).unwrap();
+        assert_eq!(crypto.format_price(Price(12345)), "0.00012345 BTC");
+        assert_eq!(crypto.format_quantity(Quantity(15)), "0.0015");
+
+        let equity = Instrument::new("AAPL");
+        assert_eq!(equity.format_price(Price(17550)), "175.50");
+    }
+
+    #[test]
+    fn test_parse_with_instrument_precision() {
+        let instrument = Instrument::with_precision("BTC-USD", 2, 8).unwrap();
+        assert_eq!(instrument.parse_price("65000.5"), Ok(Price(6_500_050)));
+        assert_eq!(instrument.parse_quantity("0.25"), Ok(Quantity(25_000_000)));
+        assert!(instrument.parse_price("65000.505").is_err());
+
+        assert_eq!(instrument.price_to_f64(Price(6_500_050)), 65000.5);
+        assert_eq!(instrument.quantity_to_f64(Quantity(25_000_000)), 0.25);
+    }
+
+    #[test]
+    fn test_precision_is_limited() {
+        assert!(Instrument::with_precision("BTC-USD", MAX_DECIMALS, MAX_DECIMALS).is_ok());
+        assert_eq!(
+            Instrument::with_precision("BTC-USD", 2, 39),
+            Err("BTC-USD has 39 quantity decimal places, more than the 19 supported".to_string())
+        );
+
+        let mut json = serde_json::to_value(Instrument::new("BTC-USD")).unwrap();
+        json["price_decimals"] = 40.into();
+        let error = serde_json::from_value::<Instrument>(json).unwrap_err();
+        assert!(error.to_string().contains("40 price decimal places"));
+    }
+}
@@ -1,9 +1,23 @@
 // Export model components
 pub mod order;
 pub mod trade;
+pub mod trade_adjustment;
 pub mod stats;
+pub mod execution;
+pub mod analytics;
+pub mod price;
+pub mod instrument;
+pub mod protection;
+pub mod book_event;
 
 // Re-export common types
 pub use order::{Order, OrderSide, OrderType, OrderStatus};
 pub use trade::Trade;
-pub use stats::OrderBookStats;
+pub use trade_adjustment::{AdjustmentError, LastTrade, TradeAdjustment};
+pub use stats::{OrderBookStats, RollingWindow, SessionStats};
+pub use execution::{ExecutionEstimate, LadderLevel};
+pub use analytics::{AnalyticsConfig, BookAnalytics};
+pub use price::{Price, Quantity};
+pub use instrument::Instrument;
+pub use protection::{CollarAction, CollarLimit, CollarReference, MarketOrderProtection};
+pub use book_event::BookEvent;
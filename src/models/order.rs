@@ -1,7 +1,10 @@
 use std::cmp::Ordering;
 use std::fmt;
+use std::str::FromStr;
 use serde::{Deserialize, Serialize};
 
+use crate::models::price::{Price, Quantity};
+
 /// Represents the side of an order (buy or sell)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum OrderSide {
@@ -9,6 +12,16 @@ pub enum OrderSide {
     Sell,
 }
 
+impl OrderSide {
+    /// Returns the other side of the book
+    pub fn opposite(self) -> Self {
+        match self {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        }
+    }
+}
+
 impl fmt::Display for OrderSide {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -18,6 +31,18 @@ impl fmt::Display for OrderSide {
     }
 }
 
+impl FromStr for OrderSide {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "buy" | "b" | "bid" => Ok(OrderSide::Buy),
+            "sell" | "s" | "ask" => Ok(OrderSide::Sell),
+            other => Err(format!("Unknown order side: {}", other)),
+        }
+    }
+}
+
 /// Different types of orders that can be placed
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum OrderType {
@@ -26,9 +51,9 @@ pub enum OrderType {
     /// Execute immediately at the best available price
     Market,
     /// Becomes a market order when the stop price is reached
-    Stop(u64),
+    Stop(Price),
     /// Becomes a limit order when the stop price is reached
-    StopLimit(u64, u64), // (stop price, limit price)
+    StopLimit(Price, Price), // (stop price, limit price)
     /// Immediate-or-Cancel: Execute immediately and cancel any unfilled portion
     IOC,
     /// Fill-or-Kill: Execute the entire order immediately or cancel
@@ -48,11 +73,58 @@ impl fmt::Display for OrderType {
     }
 }
 
+/// Parses order types in their display form (e.g. `StopLimit(100, 95)`)
+/// or in a delimiter-friendly form (e.g. `stop_limit:100:95`)
+impl FromStr for OrderType {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (name, args) = match s.find(['(', ':']) {
+            Some(pos) => (&s[..pos], &s[pos + 1..]),
+            None => (s, ""),
+        };
+        
+        let prices = args
+            .trim_end_matches(')')
+            .split([',', ':'])
+            .map(str::trim)
+            .filter(|arg| !arg.is_empty())
+            .map(str::parse::<Price>)
+            .collect::<Result<Vec<Price>, String>>()?;
+        
+        let name = name.trim().to_ascii_lowercase().replace('_', "");
+        match (name.as_str(), prices.as_slice()) {
+            ("limit", []) => Ok(OrderType::Limit),
+            ("market", []) => Ok(OrderType::Market),
+            ("ioc", []) => Ok(OrderType::IOC),
+            ("fok", []) => Ok(OrderType::FOK),
+            ("stop", [stop]) => Ok(OrderType::Stop(*stop)),
+            ("stoplimit", [stop, limit]) => Ok(OrderType::StopLimit(*stop, *limit)),
+            _ => Err(format!("Unknown order type: {}", s)),
+        }
+    }
+}
+
 /// Current status of an order
+///
+/// Orders move through the lifecycle below; `Order` only allows the
+/// transitions returned by `OrderStatus::can_transition_to`.
+///
+/// ```text
+/// PendingNew -> New | Rejected | Canceled
+/// New -> Triggered | PartiallyFilled | Filled | Canceled | Expired | Replaced
+/// Triggered -> PartiallyFilled | Filled | Canceled | Expired | Replaced
+/// PartiallyFilled -> PartiallyFilled | Filled | Canceled | Expired | Replaced
+/// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum OrderStatus {
-    /// New order, not yet processed
+    /// Created but not yet accepted by an order book
+    PendingNew,
+    /// Accepted order, not yet filled
     New,
+    /// Stop order whose stop price has been reached
+    Triggered,
     /// Partially filled order
     PartiallyFilled,
     /// Completely filled order
@@ -61,19 +133,109 @@ pub enum OrderStatus {
     Canceled,
     /// Rejected order (e.g., invalid parameters)
     Rejected,
+    /// Order removed when its time in force ran out
+    Expired,
+    /// Order superseded by a cancel/replace
+    Replaced,
+}
+
+impl OrderStatus {
+    /// Returns true if no further transitions are possible
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Filled
+                | OrderStatus::Canceled
+                | OrderStatus::Rejected
+                | OrderStatus::Expired
+                | OrderStatus::Replaced
+        )
+    }
+
+    /// Returns true if an order in this status may move to `next`
+    pub fn can_transition_to(self, next: OrderStatus) -> bool {
+        use OrderStatus::*;
+
+        match self {
+            PendingNew => matches!(next, New | Rejected | Canceled),
+            New => matches!(next, Triggered | PartiallyFilled | Filled | Canceled | Expired | Replaced),
+            Triggered | PartiallyFilled => {
+                matches!(next, PartiallyFilled | Filled | Canceled | Expired | Replaced)
+            }
+            Filled | Canceled | Rejected | Expired | Replaced => false,
+        }
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Errors raised when an order update violates its lifecycle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderError {
+    /// The order cannot move from its current status to the requested one
+    InvalidTransition {
+        order_id: u64,
+        from: OrderStatus,
+        to: OrderStatus,
+    },
+    /// A fill is larger than the order's remaining quantity
+    Overfill {
+        order_id: u64,
+        requested: Quantity,
+        remaining: Quantity,
+    },
+    /// No order with this ID exists
+    UnknownOrder { order_id: u64 },
+    /// An order with this ID already exists
+    DuplicateOrder { order_id: u64 },
+    /// The order is not resting in the book, so it cannot be amended
+    NotAmendable { order_id: u64 },
+    /// An amended quantity does not exceed what has already been filled
+    InvalidQuantity { order_id: u64, quantity: Quantity },
 }
 
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderError::InvalidTransition { order_id, from, to } => {
+                write!(f, "Order {} cannot move from {} to {}", order_id, from, to)
+            }
+            OrderError::Overfill { order_id, requested, remaining } => write!(
+                f,
+                "Cannot fill {} on order {} with {} remaining",
+                requested, order_id, remaining
+            ),
+            OrderError::UnknownOrder { order_id } => write!(f, "Unknown order {}", order_id),
+            OrderError::DuplicateOrder { order_id } => write!(f, "Duplicate order ID {}", order_id),
+            OrderError::NotAmendable { order_id } => {
+                write!(f, "Order {} is not resting in the book", order_id)
+            }
+            OrderError::InvalidQuantity { order_id, quantity } => write!(
+                f,
+                "Quantity {} is not above the filled quantity of order {}",
+                quantity, order_id
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OrderError {}
+
 /// Represents a trading order
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Order {
     /// Unique order identifier
     pub id: u64,
     /// Price in the smallest currency unit (e.g., cents)
-    pub price: u64,
+    pub price: Price,
     /// Original quantity of the order
-    pub quantity: u64,
+    pub quantity: Quantity,
     /// Remaining quantity to be filled
-    pub remaining_quantity: u64,
+    pub remaining_quantity: Quantity,
     /// Order side (buy or sell)
     pub side: OrderSide,
     /// Type of the order (limit, market, etc.)
@@ -91,26 +253,58 @@ pub struct Order {
 }
 
 impl Order {
+    /// Creates a new order of any type
+    ///
+    /// Market and stop orders ignore `price`; stop-limit orders use their limit price.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        id: u64,
+        order_type: OrderType,
+        price: impl Into<Price>,
+        quantity: impl Into<Quantity>,
+        side: OrderSide,
+        user_id: u64,
+        timestamp: u64,
+        client_order_id: Option<String>,
+        symbol: String,
+    ) -> Self {
+        let mut order = match order_type {
+            OrderType::Market | OrderType::Stop(_) => {
+                Self::new_market(id, quantity, side, user_id, timestamp, client_order_id, symbol)
+            }
+            OrderType::StopLimit(_, limit_price) => {
+                Self::new_limit(id, limit_price, quantity, side, user_id, timestamp, client_order_id, symbol)
+            }
+            OrderType::Limit | OrderType::IOC | OrderType::FOK => {
+                Self::new_limit(id, price, quantity, side, user_id, timestamp, client_order_id, symbol)
+            }
+        };
+        order.order_type = order_type;
+        order
+    }
+
     /// Creates a new limit order
+    #[allow(clippy::too_many_arguments)]
     pub fn new_limit(
         id: u64,
-        price: u64,
-        quantity: u64,
+        price: impl Into<Price>,
+        quantity: impl Into<Quantity>,
         side: OrderSide,
         user_id: u64,
         timestamp: u64,
         client_order_id: Option<String>,
         symbol: String,
     ) -> Self {
+        let quantity = quantity.into();
         Self {
             id,
-            price,
+            price: price.into(),
             quantity,
             remaining_quantity: quantity,
             side,
             order_type: OrderType::Limit,
             timestamp,
-            status: OrderStatus::New,
+            status: OrderStatus::PendingNew,
             user_id,
             client_order_id,
             symbol,
@@ -120,28 +314,29 @@ impl Order {
     /// Creates a new market order
     pub fn new_market(
         id: u64,
-        quantity: u64,
+        quantity: impl Into<Quantity>,
         side: OrderSide,
         user_id: u64,
         timestamp: u64,
         client_order_id: Option<String>,
         symbol: String,
     ) -> Self {
+        let quantity = quantity.into();
         Self {
             id,
             // Market orders don't have a specific price, but we set a default
-            // For buy orders: u64::MAX (willing to pay any price)
-            // For sell orders: 0 (willing to sell at any price)
+            // For buy orders: Price::MAX (willing to pay any price)
+            // For sell orders: zero (willing to sell at any price)
             price: match side {
-                OrderSide::Buy => u64::MAX,
-                OrderSide::Sell => 0,
+                OrderSide::Buy => Price::MAX,
+                OrderSide::Sell => Price::ZERO,
             },
             quantity,
             remaining_quantity: quantity,
             side,
             order_type: OrderType::Market,
             timestamp,
-            status: OrderStatus::New,
+            status: OrderStatus::PendingNew,
             user_id,
             client_order_id,
             symbol,
@@ -150,7 +345,7 @@ impl Order {
 
     /// Check if the order is fully filled
     pub fn is_filled(&self) -> bool {
-        self.remaining_quantity == 0
+        self.remaining_quantity.is_zero()
     }
 
     /// Check if the order is a buy order
@@ -163,33 +358,85 @@ impl Order {
         self.side == OrderSide::Sell
     }
 
-    /// Mark the order as partially filled
-    pub fn fill_partial(&mut self, filled_quantity: u64) {
-        assert!(
-            filled_quantity <= self.remaining_quantity,
-            "Cannot fill more than remaining quantity"
-        );
+    /// Check if the order can still trade or be canceled
+    pub fn is_active(&self) -> bool {
+        !self.status.is_terminal()
+    }
+
+    /// Moves the order to a new status if the lifecycle allows it
+    pub fn transition(&mut self, next: OrderStatus) -> Result<(), OrderError> {
+        if !self.status.can_transition_to(next) {
+            return Err(OrderError::InvalidTransition {
+                order_id: self.id,
+                from: self.status,
+                to: next,
+            });
+        }
         
-        self.remaining_quantity -= filled_quantity;
+        self.status = next;
+        Ok(())
+    }
+
+    /// Mark the order as accepted by an order book
+    pub fn accept(&mut self) -> Result<(), OrderError> {
+        self.transition(OrderStatus::New)
+    }
+
+    /// Mark the order as rejected before it was accepted
+    pub fn reject(&mut self) -> Result<(), OrderError> {
+        self.transition(OrderStatus::Rejected)
+    }
+
+    /// Mark a stop order as triggered
+    pub fn trigger(&mut self) -> Result<(), OrderError> {
+        self.transition(OrderStatus::Triggered)
+    }
+
+    /// Mark the order as partially filled
+    /// Returns an error if the order is not active or the fill exceeds the remaining quantity
+    pub fn fill_partial(&mut self, filled_quantity: Quantity) -> Result<(), OrderError> {
+        if filled_quantity > self.remaining_quantity {
+            return Err(OrderError::Overfill {
+                order_id: self.id,
+                requested: filled_quantity,
+                remaining: self.remaining_quantity,
+            });
+        }
+        if filled_quantity.is_zero() {
+            return Ok(());
+        }
         
-        if self.remaining_quantity > 0 {
-            self.status = OrderStatus::PartiallyFilled;
+        let remaining_quantity = self.remaining_quantity - filled_quantity;
+        if remaining_quantity.is_zero() {
+            self.transition(OrderStatus::Filled)?;
         } else {
-            self.status = OrderStatus::Filled;
+            self.transition(OrderStatus::PartiallyFilled)?;
         }
+        
+        self.remaining_quantity = remaining_quantity;
+        Ok(())
     }
 
     /// Mark the order as fully filled
-    pub fn fill_complete(&mut self) {
-        self.remaining_quantity = 0;
-        self.status = OrderStatus::Filled;
+    pub fn fill_complete(&mut self) -> Result<(), OrderError> {
+        self.transition(OrderStatus::Filled)?;
+        self.remaining_quantity = Quantity::ZERO;
+        Ok(())
     }
 
     /// Mark the order as canceled
-    pub fn cancel(&mut self) {
-        if self.status != OrderStatus::Filled {
-            self.status = OrderStatus::Canceled;
-        }
+    pub fn cancel(&mut self) -> Result<(), OrderError> {
+        self.transition(OrderStatus::Canceled)
+    }
+
+    /// Mark the order as expired at the end of its time in force
+    pub fn expire(&mut self) -> Result<(), OrderError> {
+        self.transition(OrderStatus::Expired)
+    }
+
+    /// Mark the order as replaced by a cancel/replace request
+    pub fn replace(&mut self) -> Result<(), OrderError> {
+        self.transition(OrderStatus::Replaced)
     }
 
     /// Check if this order can match with another order
@@ -201,6 +448,7 @@ impl Order {
         match (self.side, other.side) {
             (OrderSide::Buy, OrderSide::Sell) => self.price >= other.price,
             (OrderSide::Sell, OrderSide::Buy) => self.price <= other.price,
+            _ => false,
         }
     }
 }
@@ -257,14 +505,25 @@ mod tests {
         );
         assert_eq!(buy_limit.side, OrderSide::Buy);
         assert_eq!(buy_limit.order_type, OrderType::Limit);
-        assert_eq!(buy_limit.remaining_quantity, 10);
+        assert_eq!(buy_limit.remaining_quantity, Quantity(10));
         
         let sell_market = Order::new_market(
             2, 5, OrderSide::Sell, 1002, 123456790, None, "BTC-USD".to_string()
         );
         assert_eq!(sell_market.side, OrderSide::Sell);
         assert_eq!(sell_market.order_type, OrderType::Market);
-        assert_eq!(sell_market.price, 0);  // Sell at any price
+        assert_eq!(sell_market.price, Price::ZERO);  // Sell at any price
+        
+        let stop_limit = Order::new(
+            3, OrderType::StopLimit(Price(105), Price(100)), 0, 5, OrderSide::Buy, 1003, 123456791, None, "BTC-USD".to_string()
+        );
+        assert_eq!(stop_limit.price, Price(100));
+        assert_eq!(stop_limit.order_type, OrderType::StopLimit(Price(105), Price(100)));
+        
+        let stop = Order::new(
+            4, OrderType::Stop(Price(95)), 90, 5, OrderSide::Buy, 1004, 123456792, None, "BTC-USD".to_string()
+        );
+        assert_eq!(stop.price, Price::MAX);
     }
 
     #[test]
@@ -272,16 +531,143 @@ mod tests {
         let mut order = Order::new_limit(
             1, 100, 10, OrderSide::Buy, 1001, 123456789, None, "BTC-USD".to_string()
         );
+        assert_eq!(order.status, OrderStatus::PendingNew);
+        order.accept().unwrap();
         
-        order.fill_partial(4);
-        assert_eq!(order.remaining_quantity, 6);
+        order.fill_partial(Quantity(4)).unwrap();
+        assert_eq!(order.remaining_quantity, Quantity(6));
         assert_eq!(order.status, OrderStatus::PartiallyFilled);
         
-        order.fill_partial(6);
-        assert_eq!(order.remaining_quantity, 0);
+        order.fill_partial(Quantity(6)).unwrap();
+        assert_eq!(order.remaining_quantity, Quantity::ZERO);
         assert_eq!(order.status, OrderStatus::Filled);
     }
 
+    const ALL_STATUSES: [OrderStatus; 9] = [
+        OrderStatus::PendingNew,
+        OrderStatus::New,
+        OrderStatus::Triggered,
+        OrderStatus::PartiallyFilled,
+        OrderStatus::Filled,
+        OrderStatus::Canceled,
+        OrderStatus::Rejected,
+        OrderStatus::Expired,
+        OrderStatus::Replaced,
+    ];
+
+    #[test]
+    fn test_status_transition_table() {
+        use OrderStatus::*;
+
+        let legal = [
+            (PendingNew, New),
+            (PendingNew, Rejected),
+            (PendingNew, Canceled),
+            (New, Triggered),
+            (New, PartiallyFilled),
+            (New, Filled),
+            (New, Canceled),
+            (New, Expired),
+            (New, Replaced),
+            (Triggered, PartiallyFilled),
+            (Triggered, Filled),
+            (Triggered, Canceled),
+            (Triggered, Expired),
+            (Triggered, Replaced),
+            (PartiallyFilled, PartiallyFilled),
+            (PartiallyFilled, Filled),
+            (PartiallyFilled, Canceled),
+            (PartiallyFilled, Expired),
+            (PartiallyFilled, Replaced),
+        ];
+
+        // Every (from, to) pair is checked against the table
+        for from in ALL_STATUSES {
+            for to in ALL_STATUSES {
+                let expected = legal.contains(&(from, to));
+                assert_eq!(from.can_transition_to(to), expected, "{} -> {}", from, to);
+
+                let mut order = Order::new_limit(1, 100, 10, OrderSide::Buy, 1001, 0, None, "BTC-USD".to_string());
+                order.status = from;
+                let result = order.transition(to);
+                if expected {
+                    assert_eq!(result, Ok(()));
+                    assert_eq!(order.status, to);
+                } else {
+                    assert_eq!(result, Err(OrderError::InvalidTransition { order_id: 1, from, to }));
+                    assert_eq!(order.status, from);
+                }
+            }
+
+            // Terminal statuses are exactly those without outgoing transitions
+            let has_exit = ALL_STATUSES.iter().any(|&to| from.can_transition_to(to));
+            assert_eq!(from.is_terminal(), !has_exit, "{}", from);
+        }
+    }
+
+    #[test]
+    fn test_lifecycle_helpers() {
+        let new_order = || Order::new_limit(1, 100, 10, OrderSide::Sell, 1001, 0, None, "BTC-USD".to_string());
+
+        let mut order = new_order();
+        order.reject().unwrap();
+        assert_eq!(order.status, OrderStatus::Rejected);
+        assert!(!order.is_active());
+        assert!(order.accept().is_err());
+
+        let mut order = new_order();
+        order.accept().unwrap();
+        order.trigger().unwrap();
+        assert!(order.trigger().is_err());
+        order.fill_partial(Quantity(3)).unwrap();
+        order.expire().unwrap();
+        assert_eq!(order.status, OrderStatus::Expired);
+        assert!(order.cancel().is_err());
+
+        let mut order = new_order();
+        order.accept().unwrap();
+        order.replace().unwrap();
+        assert_eq!(order.status, OrderStatus::Replaced);
+
+        let mut order = new_order();
+        order.accept().unwrap();
+        order.fill_complete().unwrap();
+        assert_eq!(order.remaining_quantity, Quantity::ZERO);
+        assert!(order.cancel().is_err());
+        assert!(order.fill_complete().is_err());
+    }
+
+    #[test]
+    fn test_illegal_fills_return_errors() {
+        let mut order = Order::new_limit(1, 100, 10, OrderSide::Buy, 1001, 0, None, "BTC-USD".to_string());
+
+        // Orders must be accepted before they can fill
+        assert_eq!(
+            order.fill_partial(Quantity(1)),
+            Err(OrderError::InvalidTransition {
+                order_id: 1,
+                from: OrderStatus::PendingNew,
+                to: OrderStatus::PartiallyFilled,
+            })
+        );
+
+        order.accept().unwrap();
+        let err = order.fill_partial(Quantity(11)).unwrap_err();
+        assert_eq!(
+            err,
+            OrderError::Overfill { order_id: 1, requested: Quantity(11), remaining: Quantity(10) }
+        );
+        assert_eq!(err.to_string(), "Cannot fill 11 on order 1 with 10 remaining");
+
+        // Failed updates leave the order untouched
+        assert_eq!(order.remaining_quantity, Quantity(10));
+        assert_eq!(order.status, OrderStatus::New);
+
+        order.cancel().unwrap();
+        assert!(order.fill_partial(Quantity(1)).is_err());
+        assert_eq!(order.remaining_quantity, Quantity(10));
+    }
+
     #[test]
     fn test_order_matching() {
         let buy = Order::new_limit(
@@ -298,6 +684,24 @@ mod tests {
         assert!(!buy.can_match_with(&sell_high)); // Buy at 100, sell at 110 - shouldn't match
     }
 
+    #[test]
+    fn test_parse_side_and_type() {
+        assert_eq!("buy".parse::<OrderSide>(), Ok(OrderSide::Buy));
+        assert_eq!("SELL".parse::<OrderSide>(), Ok(OrderSide::Sell));
+        assert!("hold".parse::<OrderSide>().is_err());
+        
+        assert_eq!("limit".parse::<OrderType>(), Ok(OrderType::Limit));
+        assert_eq!("IOC".parse::<OrderType>(), Ok(OrderType::IOC));
+        assert_eq!("stop:100".parse::<OrderType>(), Ok(OrderType::Stop(Price(100))));
+        assert_eq!("stop_limit:100:95".parse::<OrderType>(), Ok(OrderType::StopLimit(Price(100), Price(95))));
+        assert!("stop".parse::<OrderType>().is_err());
+        
+        // Display output parses back to the same type
+        for order_type in [OrderType::Market, OrderType::Stop(Price(7)), OrderType::StopLimit(Price(10), Price(9))] {
+            assert_eq!(order_type.to_string().parse::<OrderType>(), Ok(order_type));
+        }
+    }
+
     #[test]
     fn test_order_comparison() {
         // Buy orders with same timestamp, different prices
@@ -309,7 +713,7 @@ mod tests {
         );
         
         // Higher buy price should come first
-        assert!(buy2 > buy1);
+        assert!(buy2 < buy1);
         
         // Buy orders with same price, different timestamps
         let buy3 = Order::new_limit(
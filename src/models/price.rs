@@ -0,0 +1,317 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// A price in the smallest unit of an instrument's quote currency
+///
+/// The number of decimal places is defined by the instrument
+/// (e.g. 2 for `USD` cents, 8 for satoshi-priced pairs). Prices serialize
+/// and display as their raw integer value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Price(pub u64);
+
+/// A quantity in the smallest tradable unit of an instrument's base asset
+///
+/// Instruments with fractional quantities (e.g. 0.001 BTC) use a scale
+/// with one or more decimal places. Quantities serialize and display as
+/// their raw integer value. Addition saturates at `u64::MAX` so running
+/// totals such as volume never wrap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Quantity(pub u64);
+
+impl Price {
+    /// The lowest representable price
+    pub const ZERO: Price = Price(0);
+    /// The highest representable price
+    pub const MAX: Price = Price(u64::MAX);
+
+    /// Returns the raw integer value
+    pub fn raw(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the price as a floating point number of raw units
+    pub fn as_f64(self) -> f64 {
+        self.0 as f64
+    }
+
+    /// Subtracts another price, clamping at zero
+    pub fn saturating_sub(self, other: Price) -> Price {
+        Price(self.0.saturating_sub(other.0))
+    }
+
+    /// Returns the notional value (price * quantity) of a fill
+    ///
+    /// The product of two `u64` values always fits in a `u128`, so this never overflows.
+    pub fn notional(self, quantity: Quantity) -> u128 {
+        self.0 as u128 * quantity.0 as u128
+    }
+
+    /// Parses a decimal string (e.g. "101.25") at the given scale
+    pub fn from_decimal_str(s: &str, decimals: u32) -> Result<Price, String> {
+        parse_decimal(s, decimals).map(Price)
+    }
+
+    /// Formats the price as a decimal string at the given scale
+    pub fn to_decimal_string(self, decimals: u32) -> String {
+        format_decimal(self.0, decimals)
+    }
+}
+
+impl Quantity {
+    /// An empty quantity
+    pub const ZERO: Quantity = Quantity(0);
+
+    /// Returns the raw integer value
+    pub fn raw(self) -> u64 {
+        self.0
+    }
+
+    /// Returns true if the quantity is zero
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Subtracts another quantity, clamping at zero
+    pub fn saturating_sub(self, other: Quantity) -> Quantity {
+        Quantity(self.0.saturating_sub(other.0))
+    }
+
+    /// Adds another quantity, returning None on overflow
+    pub fn checked_add(self, other: Quantity) -> Option<Quantity> {
+        self.0.checked_add(other.0).map(Quantity)
+    }
+
+    /// Parses a decimal string (e.g. "0.125") at the given scale
+    pub fn from_decimal_str(s: &str, decimals: u32) -> Result<Quantity, String> {
+        parse_decimal(s, decimals).map(Quantity)
+    }
+
+    /// Formats the quantity as a decimal string at the given scale
+    pub fn to_decimal_string(self, decimals: u32) -> String {
+        format_decimal(self.0, decimals)
+    }
+}
+
+impl From<u64> for Price {
+    fn from(value: u64) -> Self {
+        Price(value)
+    }
+}
+
+impl From<u64> for Quantity {
+    fn from(value: u64) -> Self {
+        Quantity(value)
+    }
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// Parses a raw integer value
+impl FromStr for Price {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim()
+            .parse::<u64>()
+            .map(Price)
+            .map_err(|e| format!("Invalid price '{}': {}", s, e))
+    }
+}
+
+/// Parses a raw integer value
+impl FromStr for Quantity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim()
+            .parse::<u64>()
+            .map(Quantity)
+            .map_err(|e| format!("Invalid quantity '{}': {}", s, e))
+    }
+}
+
+impl Add for Quantity {
+    type Output = Quantity;
+
+    fn add(self, other: Quantity) -> Quantity {
+        Quantity(self.0.saturating_add(other.0))
+    }
+}
+
+impl Sub for Quantity {
+    type Output = Quantity;
+
+    fn sub(self, other: Quantity) -> Quantity {
+        Quantity(self.0 - other.0)
+    }
+}
+
+impl AddAssign for Quantity {
+    fn add_assign(&mut self, other: Quantity) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Quantity {
+    fn sub_assign(&mut self, other: Quantity) {
+        self.0 -= other.0;
+    }
+}
+
+impl Sum for Quantity {
+    fn sum<I: Iterator<Item = Quantity>>(iter: I) -> Quantity {
+        iter.fold(Quantity::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Quantity> for Quantity {
+    fn sum<I: Iterator<Item = &'a Quantity>>(iter: I) -> Quantity {
+        iter.copied().sum()
+    }
+}
+
+/// Parses a non-negative decimal string into raw units at the given scale
+///
+/// More fractional digits than the scale allows is an error rather than
+/// silently rounding.
+pub fn parse_decimal(s: &str, decimals: u32) -> Result<u64, String> {
+    let s = s.trim();
+    let (whole, fraction) = match s.split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (s, ""),
+    };
+
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(format!("Invalid decimal '{}'", s));
+    }
+    if !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid decimal '{}'", s));
+    }
+
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals as usize {
+        return Err(format!("'{}' has more than {} decimal places", s, decimals));
+    }
+
+    let scale = 10u64
+        .checked_pow(decimals)
+        .ok_or_else(|| format!("Unsupported scale: {} decimal places", decimals))?;
+    let whole_units = if whole.is_empty() { 0 } else { whole.parse::<u64>().map_err(|e| e.to_string())? };
+    let fraction_units = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<u64>().map_err(|e| e.to_string())? * 10u64.pow(decimals - fraction.len() as u32)
+    };
+
+    whole_units
+        .checked_mul(scale)
+        .and_then(|units| units.checked_add(fraction_units))
+        .ok_or_else(|| format!("'{}' is out of range", s))
+}
+
+/// Formats raw units as a decimal string with exactly `decimals` fractional digits
+pub fn format_decimal(units: u64, decimals: u32) -> String {
+    if decimals == 0 {
+        return units.to_string();
+    }
+
+    // Padding the digits instead of dividing by a power of ten works for any number of decimals
+    let digits = format!("{:0>width$}", units, width = decimals as usize + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+    format!("{}.{}", whole, fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_decimal() {
+        assert_eq!(Price::from_decimal_str("101.25", 2), Ok(Price(10125)));
+        assert_eq!(Price::from_decimal_str("101.5", 2), Ok(Price(10150)));
+        assert_eq!(Price::from_decimal_str("101", 2), Ok(Price(10100)));
+        assert_eq!(Price::from_decimal_str(".5", 2), Ok(Price(50)));
+        assert_eq!(Price::from_decimal_str("65000.12345678", 8), Ok(Price(6_500_012_345_678)));
+        assert_eq!(Quantity::from_decimal_str("0.001", 8), Ok(Quantity(100_000)));
+
+        // Trailing zeros beyond the scale are harmless
+        assert_eq!(Price::from_decimal_str("1.2500", 2), Ok(Price(125)));
+
+        assert!(Price::from_decimal_str("1.234", 2).is_err());
+        assert!(Price::from_decimal_str("-1", 2).is_err());
+        assert!(Price::from_decimal_str("abc", 2).is_err());
+        assert!(Price::from_decimal_str(".", 2).is_err());
+        assert!(Price::from_decimal_str("184467440737095516.16", 2).is_err());
+    }
+
+    #[test]
+    fn test_format_decimal() {
+        assert_eq!(Price(10125).to_decimal_string(2), "101.25");
+        assert_eq!(Price(5).to_decimal_string(2), "0.05");
+        assert_eq!(Price(42).to_decimal_string(0), "42");
+        assert_eq!(Quantity(100_000).to_decimal_string(8), "0.00100000");
+        assert_eq!(Price::MAX.to_decimal_string(19), "1.8446744073709551615");
+        assert_eq!(Price(12).to_decimal_string(40), format!("0.{}12", "0".repeat(38)));
+    }
+
+    #[test]
+    fn test_decimal_round_trip() {
+        for (units, decimals) in [(0, 2), (1, 8), (123_456_789, 4), (u64::MAX, 6)] {
+            let text = format_decimal(units, decimals);
+            assert_eq!(parse_decimal(&text, decimals), Ok(units));
+        }
+    }
+
+    #[test]
+    fn test_quantity_arithmetic() {
+        let mut quantity = Quantity(10) + Quantity(5);
+        quantity -= Quantity(3);
+        assert_eq!(quantity, Quantity(12));
+        assert_eq!(Quantity(3).saturating_sub(Quantity(5)), Quantity::ZERO);
+
+        let total: Quantity = [Quantity(1), Quantity(2), Quantity(3)].iter().sum();
+        assert_eq!(total, Quantity(6));
+    }
+
+    #[test]
+    fn test_quantity_addition_saturates() {
+        let mut volume = Quantity(u64::MAX - 1);
+        volume += Quantity(5);
+        assert_eq!(volume, Quantity(u64::MAX));
+
+        let total: Quantity = [Quantity(u64::MAX), Quantity(u64::MAX)].iter().sum();
+        assert_eq!(total, Quantity(u64::MAX));
+        assert_eq!(Quantity(u64::MAX).checked_add(Quantity(1)), None);
+    }
+
+    #[test]
+    fn test_notional_does_not_overflow() {
+        assert_eq!(Price(10125).notional(Quantity(3)), 30375);
+        assert_eq!(
+            Price::MAX.notional(Quantity(u64::MAX)),
+            u64::MAX as u128 * u64::MAX as u128
+        );
+    }
+
+    #[test]
+    fn test_serializes_as_raw_integer() {
+        assert_eq!(serde_json::to_string(&Price(10125)).unwrap(), "10125");
+        assert_eq!(serde_json::from_str::<Quantity>("7").unwrap(), Quantity(7));
+    }
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::order::OrderSide;
+use crate::models::price::Price;
+
+/// How far from the reference price a market order may execute
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CollarLimit {
+    /// A fixed number of ticks
+    Ticks(u64),
+    /// A percentage of the reference price, in basis points (100 = 1%)
+    BasisPoints(u64),
+}
+
+/// The price a market order collar is measured from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CollarReference {
+    /// The best price on the opposite side when the order arrives
+    BestPrice,
+    /// The last trade price, falling back to the best opposite price before the first trade
+    LastTrade,
+}
+
+/// What happens to the quantity left over once a market order reaches its collar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CollarAction {
+    /// Cancel the remaining quantity
+    Cancel,
+    /// Rest the remaining quantity as a limit order at the collar price
+    ///
+    /// An engine with a ledger reserves the remainder at the collar price,
+    /// canceling it if the user cannot pay for it.
+    ConvertToLimit,
+}
+
+/// Price protection for market orders
+///
+/// Limits how far a single market order can sweep the book, so a
+/// fat-fingered order cannot empty a thin book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarketOrderProtection {
+    /// Maximum distance from the reference price
+    pub limit: CollarLimit,
+    /// Price the distance is measured from
+    pub reference: CollarReference,
+    /// Handling of quantity that cannot execute within the collar
+    pub action: CollarAction,
+    /// Size of one tick in raw price units
+    pub tick_size: Price,
+}
+
+impl MarketOrderProtection {
+    /// Creates a collar measured from the best price that cancels the remainder
+    pub fn new(limit: CollarLimit) -> Self {
+        Self {
+            limit,
+            reference: CollarReference::BestPrice,
+            action: CollarAction::Cancel,
+            tick_size: Price(1),
+        }
+    }
+
+    /// Creates a collar of a maximum number of ticks
+    pub fn with_max_ticks(ticks: u64, tick_size: Price) -> Self {
+        Self::new(CollarLimit::Ticks(ticks)).with_tick_size(tick_size)
+    }
+
+    /// Creates a collar of a maximum percentage, in basis points
+    pub fn with_max_basis_points(basis_points: u64) -> Self {
+        Self::new(CollarLimit::BasisPoints(basis_points))
+    }
+
+    /// Sets the reference price the collar is measured from
+    pub fn with_reference(mut self, reference: CollarReference) -> Self {
+        self.reference = reference;
+        self
+    }
+
+    /// Sets what happens to the quantity beyond the collar
+    pub fn with_action(mut self, action: CollarAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// Sets the tick size used by `CollarLimit::Ticks`
+    pub fn with_tick_size(mut self, tick_size: Price) -> Self {
+        self.tick_size = tick_size;
+        self
+    }
+
+    /// Returns the worst price a market order on the given side may execute at
+    ///
+    /// Buy collars sit above the reference price and sell collars below it,
+    /// clamped to the representable price range.
+    pub fn collar_price(&self, side: OrderSide, reference: Price) -> Price {
+        let offset = match self.limit {
+            CollarLimit::Ticks(ticks) => ticks as u128 * self.tick_size.0 as u128,
+            CollarLimit::BasisPoints(basis_points) => reference.0 as u128 * basis_points as u128 / 10_000,
+        };
+        let offset = u64::try_from(offset).unwrap_or(u64::MAX);
+
+        match side {
+            OrderSide::Buy => Price(reference.0.saturating_add(offset)),
+            OrderSide::Sell => Price(reference.0.saturating_sub(offset)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_collar() {
+        let protection = MarketOrderProtection::with_max_ticks(3, Price(5));
+        assert_eq!(protection.collar_price(OrderSide::Buy, Price(10000)), Price(10015));
+        assert_eq!(protection.collar_price(OrderSide::Sell, Price(10000)), Price(9985));
+    }
+
+    #[test]
+    fn test_percentage_collar() {
+        // 2.5% of 10000
+        let protection = MarketOrderProtection::with_max_basis_points(250);
+        assert_eq!(protection.collar_price(OrderSide::Buy, Price(10000)), Price(10250));
+        assert_eq!(protection.collar_price(OrderSide::Sell, Price(10000)), Price(9750));
+    }
+
+    #[test]
+    fn test_collar_is_clamped() {
+        let protection = MarketOrderProtection::with_max_ticks(u64::MAX, Price(u64::MAX));
+        assert_eq!(protection.collar_price(OrderSide::Buy, Price(10)), Price::MAX);
+        assert_eq!(protection.collar_price(OrderSide::Sell, Price(10)), Price::ZERO);
+
+        let defaults = MarketOrderProtection::new(CollarLimit::Ticks(1));
+        assert_eq!(defaults.reference, CollarReference::BestPrice);
+        assert_eq!(defaults.action, CollarAction::Cancel);
+    }
+}
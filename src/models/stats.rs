@@ -1,18 +1,237 @@
+use std::collections::VecDeque;
+
 use serde::{Deserialize, Serialize};
 
+use crate::models::instrument::Instrument;
+use crate::models::order::OrderSide;
+use crate::models::price::{Price, Quantity};
+use crate::models::trade::Trade;
+use crate::models::trade_adjustment::TradeAdjustment;
+
+/// Length of a trading session unless started at another time: one day from UTC midnight
+pub const SESSION_NANOS: u64 = 86_400_000_000_000;
+
+/// Length of the rolling window: 24 hours
+pub const ROLLING_WINDOW_NANOS: u64 = 86_400_000_000_000;
+
+/// Width of the buckets the rolling window is kept in: five minutes
+const ROLLING_BUCKET_NANOS: u64 = 300_000_000_000;
+
+/// Statistics of the trades in one trading session
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionStats {
+    /// Start of the session in nanoseconds since the Unix epoch
+    pub start: u64,
+    /// Price of the first trade
+    pub open: Option<Price>,
+    /// Highest trade price
+    pub high: Option<Price>,
+    /// Lowest trade price
+    pub low: Option<Price>,
+    /// Price of the latest trade
+    pub close: Option<Price>,
+    /// Volume traded
+    pub volume: Quantity,
+    /// Volume of trades caused by incoming buy orders
+    pub buy_volume: Quantity,
+    /// Volume of trades caused by incoming sell orders
+    pub sell_volume: Quantity,
+    /// Sum of price times quantity over the trades, in raw units
+    pub turnover: u128,
+    /// Number of trades
+    pub trade_count: u64,
+}
+
+impl SessionStats {
+    /// Creates empty statistics for a session starting at `start`
+    pub fn new(start: u64) -> Self {
+        Self {
+            start,
+            ..Default::default()
+        }
+    }
+
+    /// Returns true if `timestamp` falls within the session
+    pub fn contains(&self, timestamp: u64) -> bool {
+        timestamp >= self.start && timestamp - self.start < SESSION_NANOS
+    }
+
+    /// Returns the volume-weighted average price, rounded to the nearest raw unit
+    pub fn vwap(&self) -> Option<Price> {
+        let volume = self.volume.0 as u128;
+        (volume > 0).then(|| Price((self.turnover.saturating_add(volume / 2) / volume) as u64))
+    }
+
+    fn add(&mut self, trade: &Trade) {
+        self.open.get_or_insert(trade.price);
+        self.high = Some(self.high.map_or(trade.price, |high| high.max(trade.price)));
+        self.low = Some(self.low.map_or(trade.price, |low| low.min(trade.price)));
+        self.close = Some(trade.price);
+        self.add_volume(trade);
+        self.trade_count += 1;
+    }
+
+    fn add_volume(&mut self, trade: &Trade) {
+        self.volume += trade.quantity;
+        match trade.aggressor_side {
+            OrderSide::Buy => self.buy_volume += trade.quantity,
+            OrderSide::Sell => self.sell_volume += trade.quantity,
+        }
+        self.turnover = self.turnover.saturating_add(trade.value());
+    }
+
+    fn remove_volume(&mut self, trade: &Trade) {
+        self.volume = self.volume.saturating_sub(trade.quantity);
+        let side_volume = match trade.aggressor_side {
+            OrderSide::Buy => &mut self.buy_volume,
+            OrderSide::Sell => &mut self.sell_volume,
+        };
+        *side_volume = side_volume.saturating_sub(trade.quantity);
+        self.turnover = self.turnover.saturating_sub(trade.value());
+    }
+}
+
+/// Trades in one bucket of the rolling window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct RollingBucket {
+    start: u64,
+    /// Timestamp, ID and price of each trade, in time order
+    trades: Vec<(u64, u64, Price)>,
+    volume: Quantity,
+    turnover: u128,
+}
+
+/// Volume, turnover and price change over the last 24 hours
+///
+/// Trades are kept in five-minute buckets, so the window reaches back
+/// between 23 hours 55 minutes and 24 hours. Buckets keep the price of each
+/// trade, so a bust or correction can move the earliest and latest prices.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RollingWindow {
+    /// End of the window: the latest trade or time it was advanced to
+    now: u64,
+    /// Buckets holding trades, oldest first
+    buckets: VecDeque<RollingBucket>,
+}
+
+impl RollingWindow {
+    /// Moves the end of the window to `now`, dropping buckets that fall out of it
+    pub fn advance_to(&mut self, now: u64) {
+        self.now = self.now.max(now);
+        while self
+            .buckets
+            .front()
+            .is_some_and(|bucket| bucket.start + ROLLING_WINDOW_NANOS <= self.now)
+        {
+            self.buckets.pop_front();
+        }
+    }
+
+    /// Returns the volume traded in the window
+    pub fn volume(&self) -> Quantity {
+        self.buckets.iter().map(|bucket| bucket.volume).sum()
+    }
+
+    /// Returns the sum of price times quantity over the window, in raw units
+    pub fn turnover(&self) -> u128 {
+        self.buckets
+            .iter()
+            .fold(0u128, |turnover, bucket| turnover.saturating_add(bucket.turnover))
+    }
+
+    /// Returns the change from the earliest to the latest trade price in the window
+    pub fn price_change(&self) -> Option<i128> {
+        let (_, _, first) = self.buckets.front()?.trades.first()?;
+        let (_, _, last) = self.buckets.back()?.trades.last()?;
+        Some(last.0 as i128 - first.0 as i128)
+    }
+
+    /// Returns the price change as a percentage of the earliest price in the window
+    pub fn price_change_percent(&self) -> Option<f64> {
+        let (_, _, first) = *self.buckets.front()?.trades.first()?;
+        let change = self.price_change()?;
+        (first != Price::ZERO).then(|| change as f64 * 100.0 / first.as_f64())
+    }
+
+    fn add(&mut self, trade: &Trade) {
+        self.advance_to(trade.timestamp);
+        let start = trade.timestamp - trade.timestamp % ROLLING_BUCKET_NANOS;
+        if start + ROLLING_WINDOW_NANOS <= self.now {
+            return;
+        }
+        let point = (trade.timestamp, trade.id, trade.price);
+        match self.buckets.binary_search_by_key(&start, |bucket| bucket.start) {
+            Ok(index) => {
+                let bucket = &mut self.buckets[index];
+                let position = bucket.trades.partition_point(|&(timestamp, _, _)| timestamp <= trade.timestamp);
+                bucket.trades.insert(position, point);
+                bucket.volume += trade.quantity;
+                bucket.turnover = bucket.turnover.saturating_add(trade.value());
+            }
+            Err(index) => self.buckets.insert(
+                index,
+                RollingBucket {
+                    start,
+                    trades: vec![point],
+                    volume: trade.quantity,
+                    turnover: trade.value(),
+                },
+            ),
+        }
+    }
+
+    fn adjust(&mut self, previous: &Trade, corrected: Option<&Trade>) {
+        let start = previous.timestamp - previous.timestamp % ROLLING_BUCKET_NANOS;
+        let Ok(index) = self.buckets.binary_search_by_key(&start, |bucket| bucket.start) else {
+            return;
+        };
+        let bucket = &mut self.buckets[index];
+        let Some(position) = bucket.trades.iter().position(|&(_, trade_id, _)| trade_id == previous.id) else {
+            return;
+        };
+        bucket.volume = bucket.volume.saturating_sub(previous.quantity);
+        bucket.turnover = bucket.turnover.saturating_sub(previous.value());
+        match corrected {
+            Some(corrected) => {
+                bucket.trades[position].2 = corrected.price;
+                bucket.volume += corrected.quantity;
+                bucket.turnover = bucket.turnover.saturating_add(corrected.value());
+            }
+            None => {
+                bucket.trades.remove(position);
+                if bucket.trades.is_empty() {
+                    self.buckets.remove(index);
+                }
+            }
+        }
+    }
+}
+
 /// Statistics about the current state of the order book
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct OrderBookStats {
     /// Symbol/ticker these statistics are for
     pub symbol: String,
+    /// Instrument precision and currency used for display
+    #[serde(default)]
+    pub instrument: Instrument,
     /// Current best bid price
-    pub best_bid: Option<u64>,
+    pub best_bid: Option<Price>,
     /// Current best ask price
-    pub best_ask: Option<u64>,
+    pub best_ask: Option<Price>,
     /// Last trade price
-    pub last_trade_price: Option<u64>,
+    pub last_trade_price: Option<Price>,
+    /// ID of the last trade
+    #[serde(default)]
+    pub last_trade_id: Option<u64>,
     /// Total volume traded
-    pub volume: u64,
+    pub volume: Quantity,
+    /// Volume of trades caused by incoming buy orders
+    #[serde(default)]
+    pub buy_volume: Quantity,
+    /// Volume of trades caused by incoming sell orders
+    #[serde(default)]
+    pub sell_volume: Quantity,
     /// Total number of trades executed
     pub trade_count: u64,
     /// Number of buy orders in the book
@@ -21,19 +240,34 @@ pub struct OrderBookStats {
     pub ask_order_count: usize,
     /// Timestamp of the last update
     pub last_update_time: u64,
+    /// Timestamp of the latest trade seen
+    #[serde(default)]
+    pub last_trade_time: u64,
+    /// Statistics of the current trading session
+    #[serde(default)]
+    pub session: SessionStats,
+    /// Volume and price change over the last 24 hours
+    #[serde(default)]
+    pub rolling: RollingWindow,
 }
 
 impl OrderBookStats {
     /// Creates a new OrderBookStats for the given symbol
     pub fn new(symbol: &str) -> Self {
+        Self::with_instrument(Instrument::new(symbol))
+    }
+    
+    /// Creates a new OrderBookStats for the given instrument
+    pub fn with_instrument(instrument: Instrument) -> Self {
         Self {
-            symbol: symbol.to_string(),
+            symbol: instrument.symbol.clone(),
+            instrument,
             ..Default::default()
         }
     }
     
     /// Returns the current spread (difference between best ask and best bid)
-    pub fn spread(&self) -> Option<u64> {
+    pub fn spread(&self) -> Option<Price> {
         match (self.best_ask, self.best_bid) {
             (Some(ask), Some(bid)) => Some(ask.saturating_sub(bid)),
             _ => None,
@@ -43,18 +277,104 @@ impl OrderBookStats {
     /// Returns the midpoint price (average of best bid and best ask)
     pub fn midpoint(&self) -> Option<f64> {
         match (self.best_ask, self.best_bid) {
-            (Some(ask), Some(bid)) => Some((ask as f64 + bid as f64) / 2.0),
+            (Some(ask), Some(bid)) => Some((ask.as_f64() + bid.as_f64()) / 2.0),
             _ => None,
         }
     }
 
+    /// Starts a new trading session at `timestamp`, resetting the session statistics
+    ///
+    /// Later sessions start every 24 hours from it. Without a call, sessions
+    /// start at UTC midnight.
+    pub fn start_session(&mut self, timestamp: u64) {
+        self.session = SessionStats::new(timestamp);
+    }
+
+    /// Moves the statistics to `now`, starting a new session if the current one
+    /// has ended and dropping old trades from the rolling window
+    pub fn advance_to(&mut self, now: u64) {
+        let start = self.session.start;
+        if now.saturating_sub(start) >= SESSION_NANOS {
+            self.start_session(now - (now - start) % SESSION_NANOS);
+        }
+        self.rolling.advance_to(now);
+    }
+
+    /// Returns a copy of the statistics moved to `now`
+    ///
+    /// Sessions and the rolling window otherwise only move on with trades,
+    /// so readers use this to avoid showing a session that has ended.
+    pub fn as_of(&self, now: u64) -> Self {
+        let mut stats = self.clone();
+        stats.advance_to(now);
+        stats
+    }
+
     /// Updates the statistics with a new trade
-    pub fn update_with_trade(&mut self, price: u64, quantity: u64) {
-        self.last_trade_price = Some(price);
-        self.volume += quantity;
+    pub fn update_with_trade(&mut self, trade: &Trade) {
+        self.advance_to(trade.timestamp);
+        if self.session.contains(trade.timestamp) {
+            self.session.add(trade);
+        }
+        self.rolling.add(trade);
+        self.last_trade_price = Some(trade.price);
+        self.last_trade_id = Some(trade.id);
+        self.last_trade_time = self.last_trade_time.max(trade.timestamp);
+        self.volume += trade.quantity;
+        match trade.aggressor_side {
+            OrderSide::Buy => self.buy_volume += trade.quantity,
+            OrderSide::Sell => self.sell_volume += trade.quantity,
+        }
         self.trade_count += 1;
     }
 
+    /// Takes a busted or corrected trade out of the statistics and adds its correction
+    ///
+    /// If it was the last trade, the last trade price becomes that of the
+    /// latest trade still standing. Session and rolling volumes follow the
+    /// adjustment, and the session closes at the latest trade still standing
+    /// within it, but its open, high and low are left as traded.
+    pub fn update_with_adjustment(&mut self, adjustment: &TradeAdjustment) {
+        let previous = &adjustment.previous;
+        if self.session.contains(previous.timestamp) {
+            self.session.remove_volume(previous);
+            match &adjustment.corrected {
+                Some(corrected) => self.session.add_volume(corrected),
+                None => self.session.trade_count = self.session.trade_count.saturating_sub(1),
+            }
+            if self.session.trade_count == 0 {
+                self.session.close = None;
+            } else if let Some(last) = adjustment.last_trade.filter(|last| self.session.contains(last.timestamp)) {
+                self.session.close = Some(last.price);
+            }
+        }
+        self.rolling.adjust(previous, adjustment.corrected.as_ref());
+
+        self.volume = self.volume.saturating_sub(previous.quantity);
+        let side_volume = match previous.aggressor_side {
+            OrderSide::Buy => &mut self.buy_volume,
+            OrderSide::Sell => &mut self.sell_volume,
+        };
+        *side_volume = side_volume.saturating_sub(previous.quantity);
+        match &adjustment.corrected {
+            Some(corrected) => {
+                self.volume += corrected.quantity;
+                *side_volume += corrected.quantity;
+            }
+            None => self.trade_count = self.trade_count.saturating_sub(1),
+        }
+
+        if self.last_trade_id == Some(adjustment.trade_id) {
+            self.last_trade_id = adjustment.last_trade.map(|last| last.id);
+            self.last_trade_price = adjustment.last_trade.map(|last| last.price);
+        }
+    }
+
+    /// Returns the share of volume caused by incoming buy orders, between 0 and 1
+    pub fn buy_ratio(&self) -> Option<f64> {
+        (!self.volume.is_zero()).then(|| self.buy_volume.0 as f64 / self.volume.0 as f64)
+    }
+
     /// Updates the order counts
     pub fn update_order_counts(&mut self, bid_count: usize, ask_count: usize) {
         self.bid_order_count = bid_count;
@@ -64,7 +384,7 @@ impl OrderBookStats {
     /// Format the best bid price for display
     pub fn formatted_best_bid(&self) -> String {
         match self.best_bid {
-            Some(price) => format!("${:.2}", price as f64 / 100.0),
+            Some(price) => self.instrument.format_price(price),
             None => "None".to_string(),
         }
     }
@@ -72,7 +392,7 @@ impl OrderBookStats {
     /// Format the best ask price for display
     pub fn formatted_best_ask(&self) -> String {
         match self.best_ask {
-            Some(price) => format!("${:.2}", price as f64 / 100.0),
+            Some(price) => self.instrument.format_price(price),
             None => "None".to_string(),
         }
     }
@@ -80,21 +400,61 @@ impl OrderBookStats {
     /// Format the spread for display
     pub fn formatted_spread(&self) -> String {
         match self.spread() {
-            Some(spread) => format!("${:.2}", spread as f64 / 100.0),
+            Some(spread) => self.instrument.format_price(spread),
             None => "None".to_string(),
         }
     }
     
-    /// Generate a summary of the current market state
+    /// Format the 24-hour price change for display, with its percentage
+    pub fn formatted_price_change(&self) -> String {
+        match (self.rolling.price_change(), self.rolling.price_change_percent()) {
+            (Some(change), Some(percent)) => format!(
+                "{}{} ({:+.2}%)",
+                if change < 0 { "-" } else { "+" },
+                self.instrument.format_price(Price(u64::try_from(change.unsigned_abs()).unwrap_or(u64::MAX))),
+                percent
+            ),
+            _ => "None".to_string(),
+        }
+    }
+    
+    /// Generate a summary of the market state as of the latest trade seen
+    ///
+    /// Use `summary_at` to show the state at another time, such as the wall clock.
     pub fn summary(&self) -> String {
+        self.summary_at(self.last_trade_time)
+    }
+    
+    /// Generate a summary of the market state at `now`
+    pub fn summary_at(&self, now: u64) -> String {
+        self.as_of(now).format_summary()
+    }
+    
+    fn format_summary(&self) -> String {
+        let price = |price: Option<Price>| price.map_or_else(|| "None".to_string(), |price| self.instrument.format_price(price));
+        let turnover = Price(u64::try_from(self.instrument.quote_units(self.session.turnover)).unwrap_or(u64::MAX));
         format!(
-            "{} - Bid: {}, Ask: {}, Spread: {}, Volume: {}, Trades: {}",
+            "{} - Bid: {}, Ask: {}, Spread: {}, Volume: {}, Trades: {}\n\
+             Session - Open: {}, High: {}, Low: {}, Close: {}, VWAP: {}, Volume: {} (buy {}, sell {}), Turnover: {}, Trades: {}\n\
+             24h - Volume: {}, Change: {}",
             self.symbol,
             self.formatted_best_bid(),
             self.formatted_best_ask(),
             self.formatted_spread(),
-            self.volume,
-            self.trade_count
+            self.instrument.format_quantity(self.volume),
+            self.trade_count,
+            price(self.session.open),
+            price(self.session.high),
+            price(self.session.low),
+            price(self.session.close),
+            price(self.session.vwap()),
+            self.instrument.format_quantity(self.session.volume),
+            self.instrument.format_quantity(self.session.buy_volume),
+            self.instrument.format_quantity(self.session.sell_volume),
+            self.instrument.format_price(turnover),
+            self.session.trade_count,
+            self.instrument.format_quantity(self.rolling.volume()),
+            self.formatted_price_change()
         )
     }
 }
@@ -102,13 +462,19 @@ impl OrderBookStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::trade_adjustment::LastTrade;
+
+    fn trade(price: u64, quantity: u64, aggressor_side: OrderSide) -> Trade {
+        Trade::new(1, price, quantity, 0, 11, 12, 1001, 1002, aggressor_side, "BTC-USD".to_string())
+    }
 
     #[test]
     fn test_stats_creation() {
         let stats = OrderBookStats::new("BTC-USD");
         assert_eq!(stats.symbol, "BTC-USD");
-        assert_eq!(stats.volume, 0);
+        assert_eq!(stats.volume, Quantity::ZERO);
         assert_eq!(stats.trade_count, 0);
+        assert_eq!(stats.instrument.quote_asset, "USD");
     }
 
     #[test]
@@ -118,11 +484,11 @@ mod tests {
         // No spread when no prices exist
         assert!(stats.spread().is_none());
         
-        stats.best_bid = Some(9900);
-        stats.best_ask = Some(10100);
+        stats.best_bid = Some(Price(9900));
+        stats.best_ask = Some(Price(10100));
         
         // Spread should be 200
-        assert_eq!(stats.spread(), Some(200));
+        assert_eq!(stats.spread(), Some(Price(200)));
     }
 
     #[test]
@@ -132,8 +498,8 @@ mod tests {
         // No midpoint when no prices exist
         assert!(stats.midpoint().is_none());
         
-        stats.best_bid = Some(9900);
-        stats.best_ask = Some(10100);
+        stats.best_bid = Some(Price(9900));
+        stats.best_ask = Some(Price(10100));
         
         // Midpoint should be 10000.0
         assert_eq!(stats.midpoint(), Some(10000.0));
@@ -143,16 +509,216 @@ mod tests {
     fn test_trade_update() {
         let mut stats = OrderBookStats::new("BTC-USD");
         
-        stats.update_with_trade(10000, 5);
+        stats.update_with_trade(&trade(10000, 5, OrderSide::Buy));
         
-        assert_eq!(stats.last_trade_price, Some(10000));
-        assert_eq!(stats.volume, 5);
+        assert_eq!(stats.last_trade_price, Some(Price(10000)));
+        assert_eq!(stats.volume, Quantity(5));
         assert_eq!(stats.trade_count, 1);
         
-        stats.update_with_trade(10100, 3);
+        stats.update_with_trade(&trade(10100, 3, OrderSide::Sell));
         
-        assert_eq!(stats.last_trade_price, Some(10100));
-        assert_eq!(stats.volume, 8);
+        assert_eq!(stats.last_trade_price, Some(Price(10100)));
+        assert_eq!(stats.volume, Quantity(8));
         assert_eq!(stats.trade_count, 2);
+        assert_eq!((stats.buy_volume, stats.sell_volume), (Quantity(5), Quantity(3)));
+        assert_eq!(stats.buy_ratio(), Some(0.625));
+        assert!(OrderBookStats::new("BTC-USD").buy_ratio().is_none());
+    }
+    
+    #[test]
+    fn test_adjustments() {
+        let mut stats = OrderBookStats::new("BTC-USD");
+        let first = Trade::new(1, 10000, 5, 1, 11, 12, 1001, 1002, OrderSide::Buy, "BTC-USD".to_string());
+        let second = Trade::new(2, 10100, 3, 2, 13, 14, 1001, 1002, OrderSide::Sell, "BTC-USD".to_string());
+        stats.update_with_trade(&first);
+        stats.update_with_trade(&second);
+
+        let mut corrected = first.clone();
+        corrected.quantity = Quantity(4);
+        let mut adjustment = TradeAdjustment {
+            id: 1,
+            trade_id: 1,
+            symbol: "BTC-USD".to_string(),
+            reason: String::new(),
+            timestamp: 3,
+            previous: first,
+            corrected: Some(corrected),
+            last_trade: Some(LastTrade { id: 2, timestamp: 2, price: Price(10100) }),
+        };
+        stats.update_with_adjustment(&adjustment);
+        assert_eq!((stats.volume, stats.buy_volume, stats.trade_count), (Quantity(7), Quantity(4), 2));
+        assert_eq!(stats.last_trade_price, Some(Price(10100)));
+
+        // Busting the last trade falls back to the one before it
+        adjustment.trade_id = 2;
+        adjustment.previous = second;
+        adjustment.corrected = None;
+        adjustment.last_trade = Some(LastTrade { id: 1, timestamp: 1, price: Price(10000) });
+        stats.update_with_adjustment(&adjustment);
+        assert_eq!((stats.volume, stats.sell_volume, stats.trade_count), (Quantity(4), Quantity::ZERO, 1));
+        assert_eq!((stats.last_trade_id, stats.last_trade_price), (Some(1), Some(Price(10000))));
+    }
+    
+    #[test]
+    fn test_session_statistics() {
+        const HOUR: u64 = 3_600_000_000_000;
+        let at = |id: u64, price: u64, quantity: u64, timestamp: u64, side: OrderSide| {
+            Trade::new(id, price, quantity, timestamp, 10 + id, 20 + id, 1001, 1002, side, "BTC-USD".to_string())
+        };
+        let mut stats = OrderBookStats::new("BTC-USD");
+        stats.update_with_trade(&at(1, 10000, 2, 20 * HOUR, OrderSide::Buy));
+        stats.update_with_trade(&at(2, 10400, 1, 22 * HOUR, OrderSide::Sell));
+        stats.update_with_trade(&at(3, 9800, 1, 23 * HOUR, OrderSide::Buy));
+
+        let session = &stats.session;
+        assert_eq!(session.start, 0);
+        assert_eq!((session.open, session.high, session.low, session.close), (Some(Price(10000)), Some(Price(10400)), Some(Price(9800)), Some(Price(9800))));
+        assert_eq!((session.volume, session.buy_volume, session.sell_volume), (Quantity(4), Quantity(3), Quantity(1)));
+        assert_eq!((session.turnover, session.trade_count), (40_200, 3));
+        assert_eq!(session.vwap(), Some(Price(10050)));
+
+        // The next UTC day starts a new session, while the rolling window still holds the trades
+        stats.update_with_trade(&at(4, 10200, 1, 25 * HOUR, OrderSide::Buy));
+        assert_eq!(stats.session.start, 24 * HOUR);
+        assert_eq!((stats.session.open, stats.session.trade_count), (Some(Price(10200)), 1));
+        assert_eq!(stats.rolling.volume(), Quantity(5));
+        assert_eq!(stats.rolling.price_change(), Some(200));
+        assert_eq!(stats.formatted_price_change(), "+$2.00 (+2.00%)");
+
+        stats.advance_to(44 * HOUR + 1);
+        assert_eq!((stats.rolling.volume(), stats.rolling.turnover()), (Quantity(3), 30_400));
+        assert_eq!(stats.rolling.price_change(), Some(-200));
+        assert!(stats.summary_at(44 * HOUR + 1).contains("Session - Open: $102.00"));
+        assert!(stats.summary_at(44 * HOUR + 1).contains("24h - Volume: 3, Change: -$2.00 (-1.92%)"));
+
+        // Busting the session's only trade empties it but keeps its prices
+        let adjustment = TradeAdjustment {
+            id: 1,
+            trade_id: 4,
+            symbol: "BTC-USD".to_string(),
+            reason: String::new(),
+            timestamp: 44 * HOUR,
+            previous: at(4, 10200, 1, 25 * HOUR, OrderSide::Buy),
+            corrected: None,
+            last_trade: Some(LastTrade { id: 3, timestamp: 23 * HOUR, price: Price(9800) }),
+        };
+        stats.update_with_adjustment(&adjustment);
+        assert_eq!((stats.session.volume, stats.session.trade_count, stats.session.close), (Quantity::ZERO, 0, None));
+        assert_eq!(stats.rolling.volume(), Quantity(2));
+
+        stats.start_session(46 * HOUR);
+        assert_eq!(stats.session, SessionStats::new(46 * HOUR));
+        stats.advance_to(70 * HOUR);
+        assert_eq!(stats.session.start, 70 * HOUR);
+        assert_eq!(stats.rolling.price_change(), None);
+    }
+    
+    #[test]
+    fn test_session_close_after_adjustments() {
+        const HOUR: u64 = 3_600_000_000_000;
+        let at = |id: u64, price: u64, timestamp: u64| {
+            Trade::new(id, price, 1, timestamp, 10 + id, 20 + id, 1001, 1002, OrderSide::Buy, "BTC-USD".to_string())
+        };
+        let bust = |trade: Trade, last_trade: &Trade| TradeAdjustment {
+            id: 1,
+            trade_id: trade.id,
+            symbol: "BTC-USD".to_string(),
+            reason: String::new(),
+            timestamp: 27 * HOUR,
+            previous: trade,
+            corrected: None,
+            last_trade: Some(LastTrade::from(last_trade)),
+        };
+        let mut stats = OrderBookStats::new("BTC-USD");
+        for trade in [at(3, 9800, 23 * HOUR), at(4, 10200, 25 * HOUR), at(5, 10300, 26 * HOUR)] {
+            stats.update_with_trade(&trade);
+        }
+        // A late trade from the previous day becomes the last trade, but not the session close
+        stats.update_with_trade(&at(6, 9700, 23 * HOUR + HOUR / 2));
+        assert_eq!((stats.last_trade_price, stats.session.close), (Some(Price(9700)), Some(Price(10300))));
+
+        stats.update_with_adjustment(&bust(at(5, 10300, 26 * HOUR), &at(4, 10200, 25 * HOUR)));
+        assert_eq!((stats.session.close, stats.session.trade_count), (Some(Price(10200)), 1));
+
+        // The latest trade left is from the previous session, so the session has no close
+        stats.update_with_adjustment(&bust(at(4, 10200, 25 * HOUR), &at(6, 9700, 23 * HOUR + HOUR / 2)));
+        assert_eq!((stats.session.close, stats.session.trade_count), (None, 0));
+        assert_eq!((stats.last_trade_id, stats.last_trade_price), (Some(6), Some(Price(9700))));
+    }
+    
+    #[test]
+    fn test_rolling_window_adjustments() {
+        const HOUR: u64 = 3_600_000_000_000;
+        let at = |id: u64, price: u64, timestamp: u64| {
+            Trade::new(id, price, 1, timestamp, 10 + id, 20 + id, 1001, 1002, OrderSide::Buy, "BTC-USD".to_string())
+        };
+        let trades = [at(1, 10000, HOUR), at(2, 10100, HOUR + 1), at(3, 10200, HOUR + 2), at(4, 10400, 3 * HOUR)];
+        let mut window = RollingWindow::default();
+        for trade in &trades {
+            window.add(trade);
+        }
+        assert_eq!((window.volume(), window.price_change()), (Quantity(4), Some(400)));
+
+        // Busting the first trade in the window moves its start to the next one
+        window.adjust(&trades[0], None);
+        assert_eq!((window.volume(), window.price_change()), (Quantity(3), Some(300)));
+
+        // Busting the last trade drops its emptied bucket and ends the window at the trade before it
+        window.adjust(&trades[3], None);
+        assert_eq!(window.buckets.len(), 1);
+        assert_eq!((window.volume(), window.price_change()), (Quantity(2), Some(100)));
+
+        let mut corrected = trades[2].clone();
+        corrected.price = Price(10300);
+        window.adjust(&trades[2], Some(&corrected));
+        assert_eq!((window.turnover(), window.price_change()), (20_400, Some(200)));
+        window.adjust(&corrected, None);
+        assert_eq!(window.price_change(), Some(0));
+
+        // A trade the window does not hold leaves it unchanged
+        window.adjust(&trades[0], None);
+        assert_eq!(window.volume(), Quantity(1));
+        window.adjust(&trades[1], None);
+        assert_eq!((window.volume(), window.price_change()), (Quantity::ZERO, None));
+        assert!(window.buckets.is_empty());
+    }
+    
+    #[test]
+    fn test_session_advances_on_read() {
+        const HOUR: u64 = 3_600_000_000_000;
+        let mut stats = OrderBookStats::new("BTC-USD");
+        stats.update_with_trade(&Trade::new(1, 10000, 2, HOUR, 11, 12, 1001, 1002, OrderSide::Buy, "BTC-USD".to_string()));
+        assert!(stats.summary_at(2 * HOUR).contains("Session - Open: $100.00"));
+
+        // Reading after the session has ended shows the new, empty session
+        let next = stats.as_of(24 * HOUR + HOUR / 2);
+        assert_eq!(next.session, SessionStats::new(24 * HOUR));
+        assert_eq!(next.rolling.volume(), Quantity(2));
+        assert_eq!(stats.session.trade_count, 1);
+        assert!(stats.summary_at(25 * HOUR).contains("Session - Open: None"));
+        assert!(stats.summary_at(crate::utils::time::current_timestamp_nanos()).contains("24h - Volume: 0"));
+
+        // Without a time, the summary is as of the latest trade, whatever the clock says
+        assert!(stats.summary().contains("Session - Open: $100.00"));
+        assert!(stats.summary().contains("24h - Volume: 2"));
+        stats.update_with_trade(&Trade::new(2, 10100, 1, 25 * HOUR, 13, 14, 1001, 1002, OrderSide::Sell, "BTC-USD".to_string()));
+        stats.update_with_trade(&Trade::new(3, 9900, 1, 2 * HOUR, 15, 16, 1001, 1002, OrderSide::Sell, "BTC-USD".to_string()));
+        assert_eq!(stats.last_trade_time, 25 * HOUR);
+        assert!(stats.summary().contains("Session - Open: $101.00"));
+    }
+    
+    #[test]
+    fn test_formatting_uses_instrument() {
+        let mut stats = OrderBookStats::with_instrument(Instrument::with_precision("ETH-BTC", 8, 3).unwrap());
+        stats.best_bid = Some(Price(5_120_000));
+        stats.best_ask = Some(Price(5_130_000));
+        stats.update_with_trade(&trade(5_125_000, 1_500, OrderSide::Buy));
+        
+        assert_eq!(stats.formatted_best_bid(), "0.05120000 BTC");
+        assert_eq!(stats.formatted_spread(), "0.00010000 BTC");
+        assert!(stats.summary().contains("Volume: 1.500"));
+        
+        let usd = OrderBookStats::new("BTC-USD");
+        assert_eq!(usd.formatted_best_ask(), "None");
     }
 }
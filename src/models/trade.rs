@@ -1,14 +1,18 @@
 use serde::{Deserialize, Serialize};
 
+use crate::models::instrument::Instrument;
+use crate::models::order::OrderSide;
+use crate::models::price::{Price, Quantity};
+
 /// Represents a completed trade
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Trade {
     /// Unique trade identifier
     pub id: u64,
     /// Price at which the trade executed
-    pub price: u64,
+    pub price: Price,
     /// Quantity traded
-    pub quantity: u64,
+    pub quantity: Quantity,
     /// Timestamp of the trade
     pub timestamp: u64,
     /// Buy order ID
@@ -19,54 +23,98 @@ pub struct Trade {
     pub buy_user_id: u64,
     /// Sell order user ID
     pub sell_user_id: u64,
+    /// Side of the incoming order that caused the trade
+    pub aggressor_side: OrderSide,
+    /// ID of the order that was resting in the book
+    pub maker_order_id: u64,
+    /// ID of the incoming order; zero when it came from outside the book
+    pub taker_order_id: u64,
     /// Symbol/ticker this trade is for (e.g., "BTC-USD")
     pub symbol: String,
+    /// Fee charged to the buyer in the quote asset's smallest unit; negative for a rebate
+    #[serde(default)]
+    pub buy_fee: i64,
+    /// Fee charged to the seller in the quote asset's smallest unit; negative for a rebate
+    #[serde(default)]
+    pub sell_fee: i64,
 }
 
 impl Trade {
-    /// Creates a new trade
+    /// Creates a new trade caused by an incoming order on `aggressor_side`
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: u64,
-        price: u64,
-        quantity: u64,
+        price: impl Into<Price>,
+        quantity: impl Into<Quantity>,
         timestamp: u64,
         buy_order_id: u64,
         sell_order_id: u64,
         buy_user_id: u64,
         sell_user_id: u64,
+        aggressor_side: OrderSide,
         symbol: String,
     ) -> Self {
+        let (maker_order_id, taker_order_id) = match aggressor_side {
+            OrderSide::Buy => (sell_order_id, buy_order_id),
+            OrderSide::Sell => (buy_order_id, sell_order_id),
+        };
         Self {
             id,
-            price,
-            quantity,
+            price: price.into(),
+            quantity: quantity.into(),
             timestamp,
             buy_order_id,
             sell_order_id,
             buy_user_id,
             sell_user_id,
+            aggressor_side,
+            maker_order_id,
+            taker_order_id,
             symbol,
+            buy_fee: 0,
+            sell_fee: 0,
         }
     }
 
     /// Returns the total value of the trade (price * quantity)
-    pub fn value(&self) -> u64 {
-        self.price * self.quantity
+    pub fn value(&self) -> u128 {
+        self.price.notional(self.quantity)
+    }
+    
+    /// Returns the user whose order was resting in the book
+    pub fn maker_user_id(&self) -> u64 {
+        match self.aggressor_side {
+            OrderSide::Buy => self.sell_user_id,
+            OrderSide::Sell => self.buy_user_id,
+        }
+    }
+    
+    /// Returns the user whose incoming order caused the trade
+    pub fn taker_user_id(&self) -> u64 {
+        match self.aggressor_side {
+            OrderSide::Buy => self.buy_user_id,
+            OrderSide::Sell => self.sell_user_id,
+        }
     }
     
-    /// Convert price from internal representation (e.g., cents) to display format (dollars)
-    pub fn formatted_price(&self) -> f64 {
-        self.price as f64 / 100.0
+    /// Returns the fees collected on the trade, net of rebates
+    pub fn fee_revenue(&self) -> i128 {
+        self.buy_fee as i128 + self.sell_fee as i128
+    }
+    
+    /// Format the price for display using the instrument's precision and currency
+    pub fn formatted_price(&self, instrument: &Instrument) -> String {
+        instrument.format_price(self.price)
     }
     
     /// Generate a simple string representation of the trade
-    pub fn summary(&self) -> String {
+    pub fn summary(&self, instrument: &Instrument) -> String {
         format!(
-            "Trade #{}: {} {} @ ${:.2} (B: #{}, S: #{})",
+            "Trade #{}: {} {} @ {} (B: #{}, S: #{})",
             self.id,
-            self.quantity,
+            instrument.format_quantity(self.quantity),
             self.symbol,
-            self.formatted_price(),
+            self.formatted_price(instrument),
             self.buy_order_id,
             self.sell_order_id
         )
@@ -80,31 +128,53 @@ mod tests {
     #[test]
     fn test_trade_creation() {
         let trade = Trade::new(
-            1, 10000, 5, 123456789, 101, 102, 1001, 1002, "BTC-USD".to_string()
+            1, 10000, 5, 123456789, 101, 102, 1001, 1002, OrderSide::Buy, "BTC-USD".to_string()
         );
         
         assert_eq!(trade.id, 1);
-        assert_eq!(trade.price, 10000);
-        assert_eq!(trade.quantity, 5);
+        assert_eq!(trade.price, Price(10000));
+        assert_eq!(trade.quantity, Quantity(5));
         assert_eq!(trade.buy_order_id, 101);
         assert_eq!(trade.sell_order_id, 102);
+        assert_eq!((trade.maker_order_id, trade.taker_order_id), (102, 101));
+        assert_eq!((trade.maker_user_id(), trade.taker_user_id()), (1002, 1001));
     }
 
     #[test]
     fn test_trade_value() {
         let trade = Trade::new(
-            1, 10000, 5, 123456789, 101, 102, 1001, 1002, "BTC-USD".to_string()
+            1, 10000, 5, 123456789, 101, 102, 1001, 1002, OrderSide::Buy, "BTC-USD".to_string()
         );
         
         assert_eq!(trade.value(), 50000);
+        
+        // Large crypto prices times large quantities exceed u64
+        let trade = Trade::new(
+            2, u64::MAX, 3, 123456789, 101, 102, 1001, 1002, OrderSide::Buy, "BTC-USD".to_string()
+        );
+        assert_eq!(trade.value(), u64::MAX as u128 * 3);
     }
 
     #[test]
     fn test_formatted_price() {
         let trade = Trade::new(
-            1, 10000, 5, 123456789, 101, 102, 1001, 1002, "BTC-USD".to_string()
+            1, 10000, 5, 123456789, 101, 102, 1001, 1002, OrderSide::Buy, "BTC-USD".to_string()
+        );
+        
+        assert_eq!(trade.formatted_price(&Instrument::new("BTC-USD")), "$100.00");
+        
+        // Crypto-quoted instruments use their own precision and asset name
+        let instrument = Instrument::with_precision("ETH-BTC", 8, 0).unwrap();
+        assert_eq!(trade.formatted_price(&instrument), "0.00010000 BTC");
+    }
+    
+    #[test]
+    fn test_trade_summary() {
+        let trade = Trade::new(
+            7, 6_500_012, 25, 123456789, 101, 102, 1001, 1002, OrderSide::Buy, "BTC-USD".to_string()
         );
+        let instrument = Instrument::with_precision("BTC-USD", 2, 2).unwrap();
         
-        assert_eq!(trade.formatted_price(), 100.0);
+        assert_eq!(trade.summary(&instrument), "Trade #7: 0.25 BTC-USD @ $65000.12 (B: #101, S: #102)");
     }
 }
@@ -0,0 +1,149 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::instrument::Instrument;
+use crate::models::price::{Price, Quantity};
+use crate::models::trade::Trade;
+
+/// A bust or correction of an earlier trade
+///
+/// Adjustments never change the trade they refer to: they are recorded next
+/// to it, carrying the trade as it stood before and after, so the original
+/// execution and every later change stay on record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TradeAdjustment {
+    /// Identifier of the adjustment, in the order adjustments were recorded
+    pub id: u64,
+    /// ID of the adjusted trade
+    pub trade_id: u64,
+    /// Symbol/ticker of the adjusted trade
+    pub symbol: String,
+    /// Why the trade was adjusted
+    pub reason: String,
+    /// Timestamp of the adjustment
+    pub timestamp: u64,
+    /// The trade as it stood before the adjustment
+    pub previous: Trade,
+    /// The trade with its corrected price and quantity, or None if it was busted
+    pub corrected: Option<Trade>,
+    /// The latest trade in the symbol that still stands afterwards
+    pub last_trade: Option<LastTrade>,
+}
+
+/// The latest trade standing in a symbol once a trade is adjusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastTrade {
+    /// ID of the trade
+    pub id: u64,
+    /// Timestamp of the trade
+    pub timestamp: u64,
+    /// Price of the trade, as adjusted
+    pub price: Price,
+}
+
+impl From<&Trade> for LastTrade {
+    fn from(trade: &Trade) -> Self {
+        Self {
+            id: trade.id,
+            timestamp: trade.timestamp,
+            price: trade.price,
+        }
+    }
+}
+
+impl TradeAdjustment {
+    /// Returns true if the trade was busted rather than corrected
+    pub fn is_bust(&self) -> bool {
+        self.corrected.is_none()
+    }
+
+    /// Returns the change in traded quantity caused by the adjustment
+    pub fn quantity_change(&self) -> i128 {
+        let corrected = self.corrected.as_ref().map_or(0, |trade| trade.quantity.0 as i128);
+        corrected - self.previous.quantity.0 as i128
+    }
+
+    /// Returns a human-readable summary of the adjustment
+    pub fn summary(&self, instrument: &Instrument) -> String {
+        match &self.corrected {
+            Some(corrected) => format!(
+                "Trade #{} corrected from {} @ {} to {} @ {}: {}",
+                self.trade_id,
+                instrument.format_quantity(self.previous.quantity),
+                self.previous.formatted_price(instrument),
+                instrument.format_quantity(corrected.quantity),
+                corrected.formatted_price(instrument),
+                self.reason
+            ),
+            None => format!("Trade #{} busted: {}", self.trade_id, self.reason),
+        }
+    }
+}
+
+/// Errors raised when a trade cannot be adjusted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdjustmentError {
+    /// No trade with this ID exists
+    UnknownTrade { trade_id: u64 },
+    /// The trade was already busted
+    Busted { trade_id: u64 },
+    /// The trade changed after the adjustment was made, so it no longer applies
+    Stale { trade_id: u64 },
+    /// A correction has a zero price or quantity, or changes nothing
+    InvalidCorrection {
+        trade_id: u64,
+        price: Price,
+        quantity: Quantity,
+    },
+}
+
+impl fmt::Display for AdjustmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdjustmentError::UnknownTrade { trade_id } => write!(f, "Unknown trade {}", trade_id),
+            AdjustmentError::Busted { trade_id } => write!(f, "Trade {} was already busted", trade_id),
+            AdjustmentError::Stale { trade_id } => write!(f, "Trade {} changed since the adjustment was made", trade_id),
+            AdjustmentError::InvalidCorrection { trade_id, price, quantity } => write!(
+                f,
+                "Invalid correction of trade {} to {} @ {}",
+                trade_id, quantity, price
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AdjustmentError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::order::OrderSide;
+
+    #[test]
+    fn test_bust_and_correction() {
+        let previous = Trade::new(7, 10000, 5, 1, 11, 12, 1001, 1002, OrderSide::Buy, "BTC-USD".to_string());
+        let mut corrected = previous.clone();
+        corrected.price = Price(9900);
+        corrected.quantity = Quantity(3);
+        let mut adjustment = TradeAdjustment {
+            id: 1,
+            trade_id: 7,
+            symbol: "BTC-USD".to_string(),
+            reason: "Wrong price".to_string(),
+            timestamp: 2,
+            previous,
+            corrected: Some(corrected),
+            last_trade: Some(LastTrade { id: 7, timestamp: 1, price: Price(9900) }),
+        };
+        assert!(!adjustment.is_bust());
+        assert_eq!(adjustment.quantity_change(), -2);
+        let instrument = Instrument::with_precision("BTC-USD", 2, 0).unwrap();
+        assert_eq!(adjustment.summary(&instrument), "Trade #7 corrected from 5 @ $100.00 to 3 @ $99.00: Wrong price");
+
+        adjustment.corrected = None;
+        assert!(adjustment.is_bust());
+        assert_eq!(adjustment.quantity_change(), -5);
+        assert_eq!(adjustment.summary(&instrument), "Trade #7 busted: Wrong price");
+    }
+}
@@ -1,13 +1,12 @@
 use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
-use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::io::{self, BufReader, BufWriter};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 
-use log::{debug, error, info, warn};
-use serde::{Deserialize, Serialize};
+use log::{debug, error, info};
 
-use crate::models::order::{Order, OrderSide, OrderStatus, OrderType};
+use crate::models::order::{Order, OrderSide, OrderStatus};
 
 /// Represents a store for persisting and retrieving order data
 pub struct OrderStore {
@@ -104,13 +103,11 @@ impl OrderStore {
             .collect()
     }
     
-    /// Returns active orders (not filled or canceled)
+    /// Returns active orders (not in a terminal status)
     pub fn get_active_orders(&self) -> Vec<&Order> {
         self.orders
             .values()
-            .filter(|order| {
-                order.status != OrderStatus::Filled && order.status != OrderStatus::Canceled
-            })
+            .filter(|order| order.is_active())
             .collect()
     }
 
@@ -160,7 +157,7 @@ impl OrderStore {
                 }
                 Err(e) => {
                     error!("Failed to write orders to {}: {}", file_path, e);
-                    Err(io::Error::new(io::ErrorKind::Other, e))
+                    Err(io::Error::other(e))
                 }
             }
         } else {
@@ -253,7 +250,7 @@ impl ThreadSafeOrderStore {
             Ok(mut store) => store.add_or_update_order(order),
             Err(e) => {
                 error!("Failed to acquire lock: {}", e);
-                Err(io::Error::new(io::ErrorKind::Other, "Lock acquisition failed"))
+                Err(io::Error::other("Lock acquisition failed"))
             }
         }
     }
@@ -264,7 +261,7 @@ impl ThreadSafeOrderStore {
             Ok(mut store) => store.add_orders(orders),
             Err(e) => {
                 error!("Failed to acquire lock: {}", e);
-                Err(io::Error::new(io::ErrorKind::Other, "Lock acquisition failed"))
+                Err(io::Error::other("Lock acquisition failed"))
             }
         }
     }
@@ -275,13 +272,22 @@ impl ThreadSafeOrderStore {
             Ok(store) => store.flush(),
             Err(e) => {
                 error!("Failed to acquire lock: {}", e);
-                Err(io::Error::new(io::ErrorKind::Other, "Lock acquisition failed"))
+                Err(io::Error::other("Lock acquisition failed"))
             }
         }
     }
 
-    /// Creates a new clone of this store that can be shared with another thread
-    pub fn clone(&self) -> Self {
+}
+
+impl Default for ThreadSafeOrderStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Creates a new clone of this store that can be shared with another thread
+impl Clone for ThreadSafeOrderStore {
+    fn clone(&self) -> Self {
         Self {
             store: Arc::clone(&self.store),
         }
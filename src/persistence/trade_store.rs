@@ -1,18 +1,63 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::fs::{File, OpenOptions};
-use std::io::{self, BufReader, BufWriter, Read, Write};
-use std::path::Path;
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
-use log::{debug, error, info, warn};
-use serde::{Deserialize, Serialize};
+use log::{debug, error, info};
+use serde_json::Value;
 
+use crate::models::price::{Price, Quantity};
 use crate::models::trade::Trade;
+use crate::models::trade_adjustment::{AdjustmentError, LastTrade, TradeAdjustment};
+
+/// Errors raised when an adjustment cannot be recorded
+#[derive(Debug)]
+pub enum RecordError {
+    /// The adjustment does not apply to the trade as it stands
+    Rejected(AdjustmentError),
+    /// The adjustment was recorded but writing it to disk failed
+    Io(io::Error),
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::Rejected(e) => write!(f, "{}", e),
+            RecordError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+impl From<AdjustmentError> for RecordError {
+    fn from(e: AdjustmentError) -> Self {
+        RecordError::Rejected(e)
+    }
+}
+
+impl From<io::Error> for RecordError {
+    fn from(e: io::Error) -> Self {
+        RecordError::Io(e)
+    }
+}
 
 /// Represents a store for persisting and retrieving trade data
+///
+/// Trades are kept as they executed. Busts and corrections are recorded as
+/// `TradeAdjustment`s next to them, in a second file ending in
+/// `.adjustments.json`. The queries and statistics return trades as
+/// executed; their `effective_` variants see only the trades that still
+/// stand, as adjusted.
 pub struct TradeStore {
     /// In-memory cache of trades, indexed by trade ID
     trades: HashMap<u64, Trade>,
+    /// Busts and corrections in the order they were recorded
+    adjustments: Vec<TradeAdjustment>,
+    /// Latest state of each adjusted trade, None once busted
+    adjusted: HashMap<u64, Option<Trade>>,
     /// Optional file path for persistence
     file_path: Option<String>,
     /// Whether to automatically flush to disk on each write
@@ -24,6 +69,8 @@ impl TradeStore {
     pub fn new() -> Self {
         Self {
             trades: HashMap::new(),
+            adjustments: Vec::new(),
+            adjusted: HashMap::new(),
             file_path: None,
             auto_flush: false,
         }
@@ -32,9 +79,9 @@ impl TradeStore {
     /// Creates a new trade store with file persistence
     pub fn with_file(file_path: &str, auto_flush: bool) -> io::Result<Self> {
         let mut store = Self {
-            trades: HashMap::new(),
             file_path: Some(file_path.to_string()),
             auto_flush,
+            ..Self::new()
         };
 
         // Try to load existing trades from file
@@ -88,6 +135,11 @@ impl TradeStore {
             .collect()
     }
 
+    /// Returns up to `limit` of the most recent trades for a symbol, newest first
+    pub fn get_recent_trades(&self, symbol: &str, limit: usize) -> Vec<&Trade> {
+        most_recent(self.get_trades_by_symbol(symbol), limit)
+    }
+
     /// Returns trades for a specific user
     pub fn get_trades_by_user(&self, user_id: u64) -> Vec<&Trade> {
         self.trades
@@ -95,19 +147,194 @@ impl TradeStore {
             .filter(|trade| trade.buy_user_id == user_id || trade.sell_user_id == user_id)
             .collect()
     }
+    
+    /// Returns the trades for a symbol that still stand, as adjusted
+    pub fn effective_trades_by_symbol(&self, symbol: &str) -> Vec<&Trade> {
+        self.effective_trades()
+            .filter(|trade| trade.symbol == symbol)
+            .collect()
+    }
+    
+    /// Returns up to `limit` of the most recent trades for a symbol that still stand, newest first
+    pub fn effective_recent_trades(&self, symbol: &str, limit: usize) -> Vec<&Trade> {
+        most_recent(self.effective_trades_by_symbol(symbol), limit)
+    }
+    
+    /// Returns the trades of a specific user that still stand, as adjusted
+    pub fn effective_trades_by_user(&self, user_id: u64) -> Vec<&Trade> {
+        self.effective_trades()
+            .filter(|trade| trade.buy_user_id == user_id || trade.sell_user_id == user_id)
+            .collect()
+    }
 
-    /// Loads trades from the configured file
+    /// Returns a trade as it stands after any adjustments, or None if it was busted
+    pub fn effective_trade(&self, trade_id: u64) -> Option<&Trade> {
+        match self.adjusted.get(&trade_id) {
+            Some(adjusted) => adjusted.as_ref(),
+            None => self.trades.get(&trade_id),
+        }
+    }
+    
+    /// Returns every adjustment in the order it was recorded
+    pub fn adjustments(&self) -> &[TradeAdjustment] {
+        &self.adjustments
+    }
+    
+    /// Returns the adjustments of one trade, oldest first
+    pub fn adjustments_for(&self, trade_id: u64) -> Vec<&TradeAdjustment> {
+        self.adjustments
+            .iter()
+            .filter(|adjustment| adjustment.trade_id == trade_id)
+            .collect()
+    }
+    
+    /// Returns an adjustment busting a trade, to record with `add_adjustment`
+    ///
+    /// The adjustment is numbered when it is recorded.
+    pub fn bust_trade(&self, trade_id: u64, reason: &str, timestamp: u64) -> Result<TradeAdjustment, AdjustmentError> {
+        self.adjustment(trade_id, None, reason, timestamp)
+    }
+    
+    /// Returns an adjustment correcting the price and quantity of a trade, to record with `add_adjustment`
+    ///
+    /// The corrected trade keeps the previous fees; an engine recomputes them
+    /// when it applies the correction.
+    pub fn correct_trade(
+        &self,
+        trade_id: u64,
+        price: Price,
+        quantity: Quantity,
+        reason: &str,
+        timestamp: u64,
+    ) -> Result<TradeAdjustment, AdjustmentError> {
+        self.adjustment(trade_id, Some((price, quantity)), reason, timestamp)
+    }
+    
+    fn adjustment(
+        &self,
+        trade_id: u64,
+        correction: Option<(Price, Quantity)>,
+        reason: &str,
+        timestamp: u64,
+    ) -> Result<TradeAdjustment, AdjustmentError> {
+        if !self.trades.contains_key(&trade_id) {
+            return Err(AdjustmentError::UnknownTrade { trade_id });
+        }
+        let previous = self
+            .effective_trade(trade_id)
+            .ok_or(AdjustmentError::Busted { trade_id })?
+            .clone();
+        
+        let corrected = match correction {
+            Some((price, quantity)) => {
+                if price == Price::ZERO || quantity.is_zero() || (price, quantity) == (previous.price, previous.quantity) {
+                    return Err(AdjustmentError::InvalidCorrection { trade_id, price, quantity });
+                }
+                let mut corrected = previous.clone();
+                corrected.price = price;
+                corrected.quantity = quantity;
+                Some(corrected)
+            }
+            None => None,
+        };
+        
+        let last_trade = self.last_trade(&previous.symbol, trade_id, corrected.as_ref());
+        Ok(TradeAdjustment {
+            id: 0,
+            trade_id,
+            symbol: previous.symbol.clone(),
+            reason: reason.to_string(),
+            timestamp,
+            previous,
+            corrected,
+            last_trade,
+        })
+    }
+    
+    /// Returns the latest trade in a symbol that stands once a trade is adjusted
+    fn last_trade(&self, symbol: &str, trade_id: u64, corrected: Option<&Trade>) -> Option<LastTrade> {
+        self.effective_trades()
+            .filter(|trade| trade.symbol == symbol && trade.id != trade_id)
+            .chain(corrected)
+            .max_by_key(|trade| (trade.timestamp, trade.id))
+            .map(LastTrade::from)
+    }
+    
+    /// Records a bust or correction of a trade in the store, returning it as recorded
+    ///
+    /// The adjustment must start from the trade as it currently stands, so
+    /// one built before another adjustment of the same trade is rejected.
+    /// It is numbered, and the latest trade left standing is taken, as it is
+    /// recorded.
+    pub fn add_adjustment(&mut self, mut adjustment: TradeAdjustment) -> Result<TradeAdjustment, RecordError> {
+        let trade_id = adjustment.trade_id;
+        match self.trades.get(&trade_id) {
+            Some(trade) if trade.symbol == adjustment.symbol && adjustment.previous.id == trade_id => {}
+            _ => return Err(AdjustmentError::UnknownTrade { trade_id }.into()),
+        }
+        match self.effective_trade(trade_id) {
+            None => return Err(AdjustmentError::Busted { trade_id }.into()),
+            Some(current) if *current != adjustment.previous => return Err(AdjustmentError::Stale { trade_id }.into()),
+            Some(_) => {}
+        }
+        
+        adjustment.id = self.adjustments.len() as u64 + 1;
+        adjustment.last_trade = self.last_trade(&adjustment.symbol, trade_id, adjustment.corrected.as_ref());
+        self.adjusted.insert(trade_id, adjustment.corrected.clone());
+        self.adjustments.push(adjustment.clone());
+        
+        if self.auto_flush {
+            self.flush()?;
+        }
+        
+        Ok(adjustment)
+    }
+    
+    /// Returns every trade that still stands, as adjusted
+    pub fn effective_trades(&self) -> impl Iterator<Item = &Trade> {
+        self.trades
+            .keys()
+            .filter_map(|&trade_id| self.effective_trade(trade_id))
+    }
+    
+    /// Loads trades from the configured file, rewriting it if any trades had
+    /// to be migrated from an older format
     fn load_from_file(&mut self) -> io::Result<()> {
         if let Some(file_path) = &self.file_path {
             let file = File::open(file_path)?;
             let reader = BufReader::new(file);
 
-            match serde_json::from_reader::<_, Vec<Trade>>(reader) {
-                Ok(trades) => {
+            let parsed = serde_json::from_reader::<_, Vec<Value>>(reader).and_then(|records| {
+                let mut migrated = 0;
+                let mut trades = Vec::with_capacity(records.len());
+                for mut record in records {
+                    if migrate_record(&mut record) {
+                        migrated += 1;
+                    }
+                    trades.push(serde_json::from_value::<Trade>(record)?);
+                }
+                Ok((trades, migrated))
+            });
+            match parsed {
+                Ok((trades, migrated)) => {
                     for trade in trades {
                         self.trades.insert(trade.id, trade);
                     }
                     info!("Loaded {} trades from {}", self.trades.len(), file_path);
+                    let adjustments_path = adjustments_path(file_path);
+                    if adjustments_path.exists() {
+                        let reader = BufReader::new(File::open(&adjustments_path)?);
+                        let adjustments: Vec<TradeAdjustment> = serde_json::from_reader(reader)
+                            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                        for adjustment in adjustments {
+                            self.adjusted.insert(adjustment.trade_id, adjustment.corrected.clone());
+                            self.adjustments.push(adjustment);
+                        }
+                    }
+                    if migrated > 0 {
+                        info!("Migrated {} trades in {} to the current format", migrated, file_path);
+                        self.flush()?;
+                    }
                     Ok(())
                 }
                 Err(e) => {
@@ -123,7 +350,7 @@ impl TradeStore {
         }
     }
 
-    /// Writes all trades to the configured file
+    /// Writes all trades, and any adjustments, to the configured files
     pub fn flush(&self) -> io::Result<()> {
         if let Some(file_path) = &self.file_path {
             let file = OpenOptions::new()
@@ -136,15 +363,22 @@ impl TradeStore {
             let trades: Vec<&Trade> = self.trades.values().collect();
             
             match serde_json::to_writer_pretty(writer, &trades) {
-                Ok(_) => {
-                    debug!("Wrote {} trades to {}", trades.len(), file_path);
-                    Ok(())
-                }
+                Ok(_) => debug!("Wrote {} trades to {}", trades.len(), file_path),
                 Err(e) => {
                     error!("Failed to write trades to {}: {}", file_path, e);
-                    Err(io::Error::new(io::ErrorKind::Other, e))
+                    return Err(io::Error::other(e));
                 }
             }
+            
+            let adjustments_path = adjustments_path(file_path);
+            if self.adjustments.is_empty() && !adjustments_path.exists() {
+                return Ok(());
+            }
+            let writer = BufWriter::new(File::create(&adjustments_path)?);
+            serde_json::to_writer_pretty(writer, &self.adjustments).map_err(|e| {
+                error!("Failed to write trade adjustments to {}: {}", adjustments_path.display(), e);
+                io::Error::other(e)
+            })
         } else {
             Err(io::Error::new(
                 io::ErrorKind::NotFound,
@@ -161,6 +395,8 @@ impl TradeStore {
     /// Clears all trades from the store
     pub fn clear(&mut self) -> io::Result<()> {
         self.trades.clear();
+        self.adjustments.clear();
+        self.adjusted.clear();
         
         if self.auto_flush {
             self.flush()?;
@@ -170,36 +406,33 @@ impl TradeStore {
     }
     
     /// Get statistics about total volume by symbol
-    pub fn volume_by_symbol(&self) -> HashMap<String, u64> {
-        let mut volumes = HashMap::new();
-        
-        for trade in self.trades.values() {
-            *volumes.entry(trade.symbol.clone()).or_insert(0) += trade.quantity;
-        }
-        
-        volumes
+    pub fn volume_by_symbol(&self) -> HashMap<String, Quantity> {
+        volume_by_symbol(self.trades.values())
+    }
+    
+    /// Get fees collected net of rebates by symbol
+    pub fn fee_revenue_by_symbol(&self) -> HashMap<String, i128> {
+        fee_revenue_by_symbol(self.trades.values())
     }
     
     /// Get average price by symbol
     pub fn average_price_by_symbol(&self) -> HashMap<String, f64> {
-        let mut total_values = HashMap::new();
-        let mut total_quantities = HashMap::new();
-        
-        for trade in self.trades.values() {
-            *total_values.entry(trade.symbol.clone()).or_insert(0) += trade.price * trade.quantity;
-            *total_quantities.entry(trade.symbol.clone()).or_insert(0) += trade.quantity;
-        }
-        
-        let mut avg_prices = HashMap::new();
-        for (symbol, total_value) in total_values {
-            if let Some(&quantity) = total_quantities.get(&symbol) {
-                if quantity > 0 {
-                    avg_prices.insert(symbol, total_value as f64 / quantity as f64);
-                }
-            }
-        }
-        
-        avg_prices
+        average_price_by_symbol(self.trades.values())
+    }
+    
+    /// Get total volume by symbol of the trades that still stand, as adjusted
+    pub fn effective_volume_by_symbol(&self) -> HashMap<String, Quantity> {
+        volume_by_symbol(self.effective_trades())
+    }
+    
+    /// Get fees net of rebates by symbol of the trades that still stand, as adjusted
+    pub fn effective_fee_revenue_by_symbol(&self) -> HashMap<String, i128> {
+        fee_revenue_by_symbol(self.effective_trades())
+    }
+    
+    /// Get average price by symbol of the trades that still stand, as adjusted
+    pub fn effective_average_price_by_symbol(&self) -> HashMap<String, f64> {
+        average_price_by_symbol(self.effective_trades())
     }
 }
 
@@ -209,6 +442,92 @@ impl Default for TradeStore {
     }
 }
 
+/// Returns up to `limit` of `trades`, newest first
+fn most_recent(mut trades: Vec<&Trade>, limit: usize) -> Vec<&Trade> {
+    trades.sort_by_key(|trade| std::cmp::Reverse((trade.timestamp, trade.id)));
+    trades.truncate(limit);
+    trades
+}
+
+/// Sums the quantity traded in each symbol
+fn volume_by_symbol<'a>(trades: impl Iterator<Item = &'a Trade>) -> HashMap<String, Quantity> {
+    let mut volumes = HashMap::new();
+    
+    for trade in trades {
+        *volumes.entry(trade.symbol.clone()).or_insert(Quantity::ZERO) += trade.quantity;
+    }
+    
+    volumes
+}
+
+/// Sums fees collected net of rebates in each symbol
+fn fee_revenue_by_symbol<'a>(trades: impl Iterator<Item = &'a Trade>) -> HashMap<String, i128> {
+    let mut revenue = HashMap::new();
+    
+    for trade in trades {
+        *revenue.entry(trade.symbol.clone()).or_insert(0) += trade.fee_revenue();
+    }
+    
+    revenue
+}
+
+/// Averages the price traded in each symbol, weighted by quantity
+fn average_price_by_symbol<'a>(trades: impl Iterator<Item = &'a Trade>) -> HashMap<String, f64> {
+    // Notional and quantity are summed in u128 so large prices and volumes cannot overflow
+    let mut total_values: HashMap<String, u128> = HashMap::new();
+    let mut total_quantities: HashMap<String, u128> = HashMap::new();
+    
+    for trade in trades {
+        let total_value = total_values.entry(trade.symbol.clone()).or_insert(0);
+        *total_value = total_value.saturating_add(trade.value());
+        *total_quantities.entry(trade.symbol.clone()).or_insert(0) += trade.quantity.0 as u128;
+    }
+    
+    let mut avg_prices = HashMap::new();
+    for (symbol, total_value) in total_values {
+        if let Some(&quantity) = total_quantities.get(&symbol) {
+            if quantity > 0 {
+                avg_prices.insert(symbol, total_value as f64 / quantity as f64);
+            }
+        }
+    }
+    
+    avg_prices
+}
+
+/// Returns the file holding the adjustments of a trade file
+fn adjustments_path(file_path: &str) -> PathBuf {
+    Path::new(file_path).with_extension("adjustments.json")
+}
+
+/// Adds the aggressor side and maker and taker order IDs to a trade written
+/// before trades recorded them, returning true if the record was changed
+///
+/// Order IDs are assigned in arrival order, so the later of the two orders is
+/// taken to be the incoming one. A zero ID marks an order from outside the
+/// book, which is always the incoming one.
+fn migrate_record(record: &mut Value) -> bool {
+    let Some(fields) = record.as_object_mut() else {
+        return false;
+    };
+    if fields.contains_key("aggressor_side") {
+        return false;
+    }
+    
+    let buy_order_id = fields.get("buy_order_id").and_then(Value::as_u64).unwrap_or(0);
+    let sell_order_id = fields.get("sell_order_id").and_then(Value::as_u64).unwrap_or(0);
+    let buy_is_taker = sell_order_id != 0 && (buy_order_id == 0 || buy_order_id > sell_order_id);
+    let (aggressor_side, maker_order_id, taker_order_id) = if buy_is_taker {
+        ("Buy", sell_order_id, buy_order_id)
+    } else {
+        ("Sell", buy_order_id, sell_order_id)
+    };
+    fields.insert("aggressor_side".to_string(), Value::from(aggressor_side));
+    fields.insert("maker_order_id".to_string(), Value::from(maker_order_id));
+    fields.insert("taker_order_id".to_string(), Value::from(taker_order_id));
+    true
+}
+
 /// Thread-safe wrapper around TradeStore
 pub struct ThreadSafeTradeStore {
     store: Arc<Mutex<TradeStore>>,
@@ -235,7 +554,7 @@ impl ThreadSafeTradeStore {
             Ok(mut store) => store.add_trade(trade),
             Err(e) => {
                 error!("Failed to acquire lock: {}", e);
-                Err(io::Error::new(io::ErrorKind::Other, "Lock acquisition failed"))
+                Err(io::Error::other("Lock acquisition failed"))
             }
         }
     }
@@ -246,7 +565,18 @@ impl ThreadSafeTradeStore {
             Ok(mut store) => store.add_trades(trades),
             Err(e) => {
                 error!("Failed to acquire lock: {}", e);
-                Err(io::Error::new(io::ErrorKind::Other, "Lock acquisition failed"))
+                Err(io::Error::other("Lock acquisition failed"))
+            }
+        }
+    }
+
+    /// Records a bust or correction of a trade in the store, returning it as recorded
+    pub fn add_adjustment(&self, adjustment: TradeAdjustment) -> Result<TradeAdjustment, RecordError> {
+        match self.store.lock() {
+            Ok(mut store) => store.add_adjustment(adjustment),
+            Err(e) => {
+                error!("Failed to acquire lock: {}", e);
+                Err(io::Error::other("Lock acquisition failed").into())
             }
         }
     }
@@ -257,15 +587,115 @@ impl ThreadSafeTradeStore {
             Ok(store) => store.flush(),
             Err(e) => {
                 error!("Failed to acquire lock: {}", e);
-                Err(io::Error::new(io::ErrorKind::Other, "Lock acquisition failed"))
+                Err(io::Error::other("Lock acquisition failed"))
             }
         }
     }
 
-    /// Creates a new clone of this store that can be shared with another thread
-    pub fn clone(&self) -> Self {
+}
+
+impl Default for ThreadSafeTradeStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Creates a new clone of this store that can be shared with another thread
+impl Clone for ThreadSafeTradeStore {
+    fn clone(&self) -> Self {
         Self {
             store: Arc::clone(&self.store),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::models::order::OrderSide;
+
+    #[test]
+    fn test_legacy_file_is_migrated() {
+        let path = std::env::temp_dir().join(format!("rustflow_legacy_trades_{}.json", std::process::id()));
+        let legacy = r#"[
+            {"id": 1, "price": 10000, "quantity": 5, "timestamp": 1, "buy_order_id": 7, "sell_order_id": 4,
+             "buy_user_id": 1001, "sell_user_id": 1002, "symbol": "BTC-USD"},
+            {"id": 2, "price": 10100, "quantity": 2, "timestamp": 2, "buy_order_id": 8, "sell_order_id": 9,
+             "buy_user_id": 1001, "sell_user_id": 1002, "symbol": "BTC-USD"},
+            {"id": 3, "price": 10200, "quantity": 1, "timestamp": 3, "buy_order_id": 0, "sell_order_id": 10,
+             "buy_user_id": 0, "sell_user_id": 1002, "symbol": "BTC-USD"}
+        ]"#;
+        fs::write(&path, legacy).unwrap();
+        
+        let store = TradeStore::with_file(path.to_str().unwrap(), false).unwrap();
+        let sides: Vec<(OrderSide, u64, u64)> = (1..=3)
+            .map(|id| store.get_trade(id).unwrap())
+            .map(|trade| (trade.aggressor_side, trade.maker_order_id, trade.taker_order_id))
+            .collect();
+        assert_eq!(sides, vec![(OrderSide::Buy, 4, 7), (OrderSide::Sell, 8, 9), (OrderSide::Buy, 10, 0)]);
+        
+        // The file was rewritten, so reloading needs no migration
+        let mut records: Vec<Value> = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(records.iter_mut().all(|record| !migrate_record(record)));
+        let reloaded = TradeStore::with_file(path.to_str().unwrap(), false).unwrap();
+        assert_eq!(reloaded.get_trade(1), store.get_trade(1));
+        
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_adjustments_are_persisted() {
+        let path = std::env::temp_dir().join(format!("rustflow_adjusted_trades_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut store = TradeStore::with_file(path, true).unwrap();
+        for (id, price, quantity) in [(1, 10000, 5), (2, 10100, 3)] {
+            let trade = Trade::new(id, price, quantity, id, 10 + id, 20 + id, 1001, 1002, OrderSide::Buy, "BTC-USD".to_string());
+            store.add_trade(trade).unwrap();
+        }
+        
+        let correction = store.correct_trade(1, Price(10050), Quantity(5), "Wrong price", 3).unwrap();
+        assert_eq!(correction.last_trade, Some(LastTrade { id: 2, timestamp: 2, price: Price(10100) }));
+        store.add_adjustment(correction).unwrap();
+        let bust = store.bust_trade(2, "Erroneous", 4).unwrap();
+        assert_eq!(bust.last_trade, Some(LastTrade { id: 1, timestamp: 1, price: Price(10050) }));
+        assert_eq!(store.add_adjustment(bust.clone()).unwrap().id, 2);
+        assert!(matches!(store.add_adjustment(bust), Err(RecordError::Rejected(AdjustmentError::Busted { trade_id: 2 }))));
+        assert_eq!(
+            store.correct_trade(1, Price(10050), Quantity(5), "", 5),
+            Err(AdjustmentError::InvalidCorrection { trade_id: 1, price: Price(10050), quantity: Quantity(5) })
+        );
+        
+        // Of two corrections built from the same trade, only the first is recorded
+        let first = store.correct_trade(1, Price(10040), Quantity(4), "Wrong price", 5).unwrap();
+        let second = store.correct_trade(1, Price(10030), Quantity(5), "Wrong price", 5).unwrap();
+        assert_eq!(store.add_adjustment(first).unwrap().id, 3);
+        assert!(matches!(store.add_adjustment(second), Err(RecordError::Rejected(AdjustmentError::Stale { trade_id: 1 }))));
+        
+        // The latest trade is taken when the adjustment is recorded, not when it was built
+        let late = store.bust_trade(1, "Erroneous", 6).unwrap();
+        assert_eq!(late.last_trade, None);
+        store.add_trade(Trade::new(3, 10200, 1, 6, 13, 23, 1001, 1002, OrderSide::Buy, "BTC-USD".to_string())).unwrap();
+        let late = store.add_adjustment(late).unwrap();
+        assert_eq!((late.id, late.last_trade), (4, Some(LastTrade { id: 3, timestamp: 6, price: Price(10200) })));
+        
+        let reloaded = TradeStore::with_file(path, false).unwrap();
+        assert_eq!(reloaded.adjustments(), store.adjustments());
+        assert_eq!(reloaded.adjustments_for(1).len(), 3);
+        assert_eq!(reloaded.get_trade(1).unwrap().price, Price(10000));
+        assert!(reloaded.effective_trade(1).is_none());
+        assert!(reloaded.effective_trade(2).is_none());
+        assert_eq!(reloaded.effective_volume_by_symbol()["BTC-USD"], Quantity(1));
+        assert_eq!(reloaded.effective_trades_by_user(1001).len(), 1);
+        assert_eq!(reloaded.effective_recent_trades("BTC-USD", 5)[0].id, 3);
+        
+        // The other queries see the trades as executed
+        assert_eq!(reloaded.volume_by_symbol()["BTC-USD"], Quantity(9));
+        assert_eq!(reloaded.get_trades_by_user(1001).len(), 3);
+        assert_eq!(reloaded.get_recent_trades("BTC-USD", 5).len(), 3);
+        
+        fs::remove_file(path).unwrap();
+        fs::remove_file(adjustments_path(path)).unwrap();
+    }
+}
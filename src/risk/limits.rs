@@ -0,0 +1,148 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::price::{Price, Quantity};
+
+/// Pre-trade limits; a limit left as `None` is not checked
+///
+/// Notional values are in raw units, the raw price times the raw quantity,
+/// like `Trade::value`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskLimits {
+    /// Largest quantity of a single order
+    pub max_order_quantity: Option<Quantity>,
+    /// Largest notional of a single order
+    pub max_order_notional: Option<u128>,
+    /// Furthest a priced order may be from the last trade price, in basis points (100 = 1%)
+    pub price_collar_bps: Option<u64>,
+    /// Most orders a user may have open across all books
+    pub max_open_orders: Option<usize>,
+    /// Largest net position, long or short, a user may build in one symbol
+    pub max_position: Option<Quantity>,
+    /// Largest notional a user may trade in one UTC day across all books
+    pub max_daily_notional: Option<u128>,
+}
+
+impl RiskLimits {
+    /// Creates limits that check nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the quantity of a single order
+    pub fn with_max_order_quantity(mut self, quantity: Quantity) -> Self {
+        self.max_order_quantity = Some(quantity);
+        self
+    }
+
+    /// Limits the notional of a single order
+    pub fn with_max_order_notional(mut self, notional: u128) -> Self {
+        self.max_order_notional = Some(notional);
+        self
+    }
+
+    /// Rejects priced orders further than `basis_points` from the last trade price
+    pub fn with_price_collar_bps(mut self, basis_points: u64) -> Self {
+        self.price_collar_bps = Some(basis_points);
+        self
+    }
+
+    /// Limits the number of open orders per user
+    pub fn with_max_open_orders(mut self, orders: usize) -> Self {
+        self.max_open_orders = Some(orders);
+        self
+    }
+
+    /// Limits the net position per user and symbol
+    pub fn with_max_position(mut self, quantity: Quantity) -> Self {
+        self.max_position = Some(quantity);
+        self
+    }
+
+    /// Limits the notional traded per user and day
+    pub fn with_max_daily_notional(mut self, notional: u128) -> Self {
+        self.max_daily_notional = Some(notional);
+        self
+    }
+
+    /// Returns these limits with any unset limit taken from `fallback`
+    pub fn or(self, fallback: RiskLimits) -> RiskLimits {
+        RiskLimits {
+            max_order_quantity: self.max_order_quantity.or(fallback.max_order_quantity),
+            max_order_notional: self.max_order_notional.or(fallback.max_order_notional),
+            price_collar_bps: self.price_collar_bps.or(fallback.price_collar_bps),
+            max_open_orders: self.max_open_orders.or(fallback.max_open_orders),
+            max_position: self.max_position.or(fallback.max_position),
+            max_daily_notional: self.max_daily_notional.or(fallback.max_daily_notional),
+        }
+    }
+}
+
+/// Which orders a set of limits applies to
+///
+/// The most specific scope wins for each limit: user and symbol, then user,
+/// then symbol, then the defaults.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RiskScope {
+    /// Every order without a more specific limit
+    Default,
+    /// Orders in one symbol
+    Symbol(String),
+    /// Orders of one user
+    User(u64),
+    /// Orders of one user in one symbol
+    UserSymbol(u64, String),
+}
+
+/// Why a pre-trade check rejected an order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RiskReject {
+    /// The order is larger than the maximum order quantity
+    OrderQuantity { quantity: Quantity, limit: Quantity },
+    /// The order is worth more than the maximum order notional
+    OrderNotional { notional: u128, limit: u128 },
+    /// The order price is outside the collar around the last trade price
+    PriceCollar {
+        price: Price,
+        reference: Price,
+        limit_bps: u64,
+    },
+    /// The user already has the maximum number of open orders
+    OpenOrders { open: usize, limit: usize },
+    /// Filling the order and the user's open orders on its side could take the position beyond the limit
+    Position { position: i128, limit: Quantity },
+    /// Filling the order and the user's open orders could take the traded notional today beyond the limit
+    DailyNotional { notional: u128, limit: u128 },
+}
+
+impl fmt::Display for RiskReject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskReject::OrderQuantity { quantity, limit } => {
+                write!(f, "Order quantity {} exceeds the limit of {}", quantity, limit)
+            }
+            RiskReject::OrderNotional { notional, limit } => {
+                write!(f, "Order notional {} exceeds the limit of {}", notional, limit)
+            }
+            RiskReject::PriceCollar { price, reference, limit_bps } => write!(
+                f,
+                "Price {} is more than {} bps from the last trade price {}",
+                price, limit_bps, reference
+            ),
+            RiskReject::OpenOrders { open, limit } => {
+                write!(f, "User has {} open orders, the limit is {}", open, limit)
+            }
+            RiskReject::Position { position, limit } => {
+                write!(f, "Position could reach {}, beyond the limit of {}", position, limit)
+            }
+            RiskReject::DailyNotional { notional, limit } => write!(
+                f,
+                "Daily traded notional could reach {}, above the limit of {}",
+                notional, limit
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RiskReject {}
@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+
+use crate::core::order_book::OrderBook;
+use crate::models::order::{Order, OrderSide, OrderType};
+use crate::models::price::{Price, Quantity};
+use crate::models::trade::Trade;
+use crate::models::trade_adjustment::TradeAdjustment;
+use crate::risk::limits::{RiskLimits, RiskReject, RiskScope};
+
+/// Nanoseconds in a day, for resetting daily notional at midnight UTC
+const NANOS_PER_DAY: u64 = 86_400_000_000_000;
+
+/// What an open order could still add to its user's position and traded notional
+#[derive(Debug, Clone, Copy)]
+struct WorkingOrder {
+    side: OrderSide,
+    remaining: Quantity,
+    /// Remaining quantity valued at the order's limit or stop price
+    notional: u128,
+}
+
+/// Evaluates orders against pre-trade limits before they reach a book
+///
+/// Limits can be set for everyone, per symbol, per user and per user and
+/// symbol, and changed at any time. The manager follows the open orders,
+/// positions and daily traded notional of each user from the orders and
+/// trades it is told about. Position and daily notional limits count the
+/// open orders of the user as if they filled too.
+#[derive(Debug, Default)]
+pub struct RiskManager {
+    defaults: RiskLimits,
+    symbol_limits: HashMap<String, RiskLimits>,
+    user_limits: HashMap<u64, RiskLimits>,
+    user_symbol_limits: HashMap<(u64, String), RiskLimits>,
+    /// Open orders of each user by symbol and order ID
+    open_orders: HashMap<u64, HashMap<(String, u64), WorkingOrder>>,
+    /// Net position of each user and symbol; positive is long
+    positions: HashMap<(u64, String), i128>,
+    /// Day and notional traded that day of each user
+    daily_notional: HashMap<u64, (u64, u128)>,
+}
+
+impl RiskManager {
+    /// Creates a manager without any limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a manager applying `limits` to every order
+    pub fn with_limits(limits: RiskLimits) -> Self {
+        Self {
+            defaults: limits,
+            ..Self::default()
+        }
+    }
+
+    /// Replaces the limits of a scope
+    pub fn set_limits(&mut self, scope: RiskScope, limits: RiskLimits) {
+        match scope {
+            RiskScope::Default => self.defaults = limits,
+            RiskScope::Symbol(symbol) => {
+                self.symbol_limits.insert(symbol, limits);
+            }
+            RiskScope::User(user_id) => {
+                self.user_limits.insert(user_id, limits);
+            }
+            RiskScope::UserSymbol(user_id, symbol) => {
+                self.user_symbol_limits.insert((user_id, symbol), limits);
+            }
+        }
+    }
+
+    /// Removes the limits of a scope, so the next less specific scope applies
+    pub fn clear_limits(&mut self, scope: &RiskScope) {
+        match scope {
+            RiskScope::Default => self.defaults = RiskLimits::new(),
+            RiskScope::Symbol(symbol) => {
+                self.symbol_limits.remove(symbol);
+            }
+            RiskScope::User(user_id) => {
+                self.user_limits.remove(user_id);
+            }
+            RiskScope::UserSymbol(user_id, symbol) => {
+                self.user_symbol_limits.remove(&(*user_id, symbol.clone()));
+            }
+        }
+    }
+
+    /// Returns the limits that apply to orders of a user in a symbol
+    pub fn limits_for(&self, user_id: u64, symbol: &str) -> RiskLimits {
+        let mut limits = self.defaults;
+        if let Some(symbol_limits) = self.symbol_limits.get(symbol) {
+            limits = symbol_limits.or(limits);
+        }
+        if let Some(user_limits) = self.user_limits.get(&user_id) {
+            limits = user_limits.or(limits);
+        }
+        if let Some(user_symbol_limits) = self.user_symbol_limits.get(&(user_id, symbol.to_string())) {
+            limits = user_symbol_limits.or(limits);
+        }
+        limits
+    }
+
+    /// Checks a new order against the limits before it is matched in `book`
+    pub fn check_order(&self, order: &Order, book: &OrderBook) -> Result<(), RiskReject> {
+        let limits = self.limits_for(order.user_id, &order.symbol);
+        if let Some(limit) = limits.max_open_orders {
+            let open = self.open_order_count(order.user_id);
+            if open >= limit {
+                return Err(RiskReject::OpenOrders { open, limit });
+            }
+        }
+        self.check(order, book, &limits)
+    }
+
+    /// Checks an order as it would be after an amendment
+    ///
+    /// The order already counts as open, and only its remaining quantity can
+    /// still add to the user's position and traded notional.
+    pub fn check_amend(&self, amended: &Order, book: &OrderBook) -> Result<(), RiskReject> {
+        let limits = self.limits_for(amended.user_id, &amended.symbol);
+        self.check(amended, book, &limits)
+    }
+
+    fn check(&self, order: &Order, book: &OrderBook, limits: &RiskLimits) -> Result<(), RiskReject> {
+        if let Some(limit) = limits.max_order_quantity {
+            if order.quantity > limit {
+                return Err(RiskReject::OrderQuantity {
+                    quantity: order.quantity,
+                    limit,
+                });
+            }
+        }
+
+        let price = limit_price(order);
+        if let (Some(limit_bps), Some(price), Some(reference)) =
+            (limits.price_collar_bps, price, book.stats().last_trade_price)
+        {
+            // |price - reference| / reference > limit_bps / 10_000
+            let distance = price.0.abs_diff(reference.0) as u128 * 10_000;
+            if distance > reference.0 as u128 * limit_bps as u128 {
+                return Err(RiskReject::PriceCollar { price, reference, limit_bps });
+            }
+        }
+
+        // Orders without a price are valued at the price they would trade at first
+        let valuation = price.or_else(|| reference_price(order.side, book));
+        if let (Some(limit), Some(valuation)) = (limits.max_order_notional, valuation) {
+            let notional = valuation.notional(order.quantity);
+            if notional > limit {
+                return Err(RiskReject::OrderNotional { notional, limit });
+            }
+        }
+
+        // Open orders on the same side could fill before this one
+        if let Some(limit) = limits.max_position {
+            let working: u128 = self
+                .working_orders(order)
+                .filter(|((symbol, _), working)| *symbol == order.symbol && working.side == order.side)
+                .map(|(_, working)| working.remaining.0 as u128)
+                .sum();
+            let quantity = order.remaining_quantity.0 as i128 + working as i128;
+            let position = self.position(order.user_id, &order.symbol)
+                + match order.side {
+                    OrderSide::Buy => quantity,
+                    OrderSide::Sell => -quantity,
+                };
+            if position.unsigned_abs() > limit.0 as u128 {
+                return Err(RiskReject::Position { position, limit });
+            }
+        }
+
+        if let (Some(limit), Some(valuation)) = (limits.max_daily_notional, valuation) {
+            let working: u128 = self.working_orders(order).map(|(_, working)| working.notional).sum();
+            let notional = self.daily_notional(order.user_id, order.timestamp)
+                + working
+                + valuation.notional(order.remaining_quantity);
+            if notional > limit {
+                return Err(RiskReject::DailyNotional { notional, limit });
+            }
+        }
+        Ok(())
+    }
+
+    /// Follows an order after it was processed, counting it as open while it is active
+    pub fn track_order(&mut self, order: &Order) {
+        let key = (order.symbol.clone(), order.id);
+        if order.is_active() {
+            let price = match order.order_type {
+                OrderType::Stop(stop_price) => Some(stop_price),
+                _ => limit_price(order),
+            };
+            let working = WorkingOrder {
+                side: order.side,
+                remaining: order.remaining_quantity,
+                notional: price.map_or(0, |price| price.notional(order.remaining_quantity)),
+            };
+            self.open_orders.entry(order.user_id).or_default().insert(key, working);
+        } else if let Some(orders) = self.open_orders.get_mut(&order.user_id) {
+            orders.remove(&key);
+            if orders.is_empty() {
+                self.open_orders.remove(&order.user_id);
+            }
+        }
+    }
+
+    /// Updates the positions and daily notional of both sides of a trade
+    pub fn on_trade(&mut self, trade: &Trade) {
+        let quantity = trade.quantity.0 as i128;
+        *self.positions.entry((trade.buy_user_id, trade.symbol.clone())).or_default() += quantity;
+        *self.positions.entry((trade.sell_user_id, trade.symbol.clone())).or_default() -= quantity;
+
+        let day = trade.timestamp / NANOS_PER_DAY;
+        for user_id in [trade.buy_user_id, trade.sell_user_id] {
+            let entry = self.daily_notional.entry(user_id).or_insert((day, 0));
+            if entry.0 != day {
+                *entry = (day, 0);
+            }
+            entry.1 += trade.value();
+        }
+    }
+
+    /// Takes a busted or corrected trade back out of positions and daily notional and adds its correction
+    ///
+    /// Daily notional only changes if the trade was on the day being counted.
+    pub fn on_adjustment(&mut self, adjustment: &TradeAdjustment) {
+        let changes = std::iter::once((&adjustment.previous, -1))
+            .chain(adjustment.corrected.iter().map(|trade| (trade, 1)));
+        for (trade, sign) in changes {
+            let quantity = sign * trade.quantity.0 as i128;
+            *self.positions.entry((trade.buy_user_id, trade.symbol.clone())).or_default() += quantity;
+            *self.positions.entry((trade.sell_user_id, trade.symbol.clone())).or_default() -= quantity;
+
+            let day = trade.timestamp / NANOS_PER_DAY;
+            for user_id in [trade.buy_user_id, trade.sell_user_id] {
+                if let Some(entry) = self.daily_notional.get_mut(&user_id).filter(|entry| entry.0 == day) {
+                    entry.1 = if sign > 0 {
+                        entry.1 + trade.value()
+                    } else {
+                        entry.1.saturating_sub(trade.value())
+                    };
+                }
+            }
+        }
+    }
+
+    /// Returns the number of open orders of a user across all symbols
+    pub fn open_order_count(&self, user_id: u64) -> usize {
+        self.open_orders.get(&user_id).map_or(0, HashMap::len)
+    }
+    
+    /// Returns the open orders of an order's user other than the order itself
+    fn working_orders<'a>(&'a self, order: &'a Order) -> impl Iterator<Item = (&'a (String, u64), &'a WorkingOrder)> {
+        self.open_orders
+            .get(&order.user_id)
+            .into_iter()
+            .flatten()
+            .filter(move |((symbol, order_id), _)| (symbol.as_str(), *order_id) != (order.symbol.as_str(), order.id))
+    }
+
+    /// Returns the net position of a user in a symbol; positive is long
+    pub fn position(&self, user_id: u64, symbol: &str) -> i128 {
+        self.positions
+            .get(&(user_id, symbol.to_string()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns the notional a user traded on the UTC day containing `timestamp`
+    pub fn daily_notional(&self, user_id: u64, timestamp: u64) -> u128 {
+        match self.daily_notional.get(&user_id) {
+            Some(&(day, notional)) if day == timestamp / NANOS_PER_DAY => notional,
+            _ => 0,
+        }
+    }
+}
+
+/// Returns the price an order is limited to, if it has one
+fn limit_price(order: &Order) -> Option<Price> {
+    match order.order_type {
+        OrderType::Market | OrderType::Stop(_) => None,
+        OrderType::Limit | OrderType::StopLimit(..) | OrderType::IOC | OrderType::FOK => Some(order.price),
+    }
+}
+
+/// Returns the best opposite price, or the last trade price when that side is empty
+fn reference_price(side: OrderSide, book: &OrderBook) -> Option<Price> {
+    let best = match side {
+        OrderSide::Buy => book.best_ask(),
+        OrderSide::Sell => book.best_bid(),
+    };
+    best.or(book.stats().last_trade_price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::order::OrderStatus;
+
+    const DAY: u64 = NANOS_PER_DAY;
+
+    fn limit(id: u64, side: OrderSide, price: u64, quantity: u64, user_id: u64) -> Order {
+        Order::new_limit(id, price, quantity, side, user_id, DAY + id, None, "BTC-USD".to_string())
+    }
+
+    fn trade(buy_user_id: u64, sell_user_id: u64, price: u64, quantity: u64, timestamp: u64) -> Trade {
+        Trade::new(1, price, quantity, timestamp, 1, 2, buy_user_id, sell_user_id, OrderSide::Buy, "BTC-USD".to_string())
+    }
+
+    #[test]
+    fn test_most_specific_limits_win() {
+        let mut risk = RiskManager::with_limits(RiskLimits::new().with_max_order_quantity(Quantity(100)).with_max_open_orders(5));
+        risk.set_limits(RiskScope::Symbol("BTC-USD".to_string()), RiskLimits::new().with_max_order_quantity(Quantity(10)));
+        risk.set_limits(RiskScope::User(7), RiskLimits::new().with_max_open_orders(1));
+        risk.set_limits(
+            RiskScope::UserSymbol(7, "BTC-USD".to_string()),
+            RiskLimits::new().with_max_order_quantity(Quantity(50)),
+        );
+
+        let limits = risk.limits_for(7, "BTC-USD");
+        assert_eq!((limits.max_order_quantity, limits.max_open_orders), (Some(Quantity(50)), Some(1)));
+        assert_eq!(risk.limits_for(8, "BTC-USD").max_order_quantity, Some(Quantity(10)));
+        assert_eq!(risk.limits_for(8, "ETH-USD").max_order_quantity, Some(Quantity(100)));
+
+        risk.clear_limits(&RiskScope::UserSymbol(7, "BTC-USD".to_string()));
+        assert_eq!(risk.limits_for(7, "BTC-USD").max_order_quantity, Some(Quantity(10)));
+    }
+
+    #[test]
+    fn test_order_size_and_collar() {
+        let mut book = OrderBook::new("BTC-USD");
+        let risk = RiskManager::with_limits(
+            RiskLimits::new()
+                .with_max_order_quantity(Quantity(100))
+                .with_max_order_notional(500_000)
+                .with_price_collar_bps(500),
+        );
+
+        assert_eq!(
+            risk.check_order(&limit(1, OrderSide::Buy, 1_000, 101, 1), &book),
+            Err(RiskReject::OrderQuantity { quantity: Quantity(101), limit: Quantity(100) })
+        );
+        assert_eq!(
+            risk.check_order(&limit(2, OrderSide::Buy, 10_000, 60, 1), &book),
+            Err(RiskReject::OrderNotional { notional: 600_000, limit: 500_000 })
+        );
+        // Without a trade there is nothing to collar against
+        assert!(risk.check_order(&limit(3, OrderSide::Buy, 1, 1, 1), &book).is_ok());
+
+        book.process_order(limit(4, OrderSide::Sell, 1_000, 1, 2));
+        book.process_order(limit(5, OrderSide::Buy, 1_000, 1, 3));
+        assert!(risk.check_order(&limit(6, OrderSide::Buy, 1_050, 1, 1), &book).is_ok());
+        assert_eq!(
+            risk.check_order(&limit(7, OrderSide::Sell, 949, 1, 1), &book),
+            Err(RiskReject::PriceCollar { price: Price(949), reference: Price(1_000), limit_bps: 500 })
+        );
+
+        // Market orders are valued at the opposite best price, or the last trade
+        book.process_order(limit(8, OrderSide::Sell, 9_000, 100, 2));
+        let market = Order::new_market(9, 60, OrderSide::Buy, 1, DAY, None, "BTC-USD".to_string());
+        assert_eq!(
+            risk.check_order(&market, &book),
+            Err(RiskReject::OrderNotional { notional: 540_000, limit: 500_000 })
+        );
+        let market = Order::new_market(10, 60, OrderSide::Sell, 1, DAY, None, "BTC-USD".to_string());
+        assert!(risk.check_order(&market, &book).is_ok());
+    }
+
+    #[test]
+    fn test_open_orders_position_and_daily_notional() {
+        let book = OrderBook::new("BTC-USD");
+        let mut risk = RiskManager::with_limits(
+            RiskLimits::new()
+                .with_max_open_orders(2)
+                .with_max_position(Quantity(10))
+                .with_max_daily_notional(12_000),
+        );
+
+        let mut first = limit(1, OrderSide::Buy, 1_000, 1, 1);
+        first.status = OrderStatus::New;
+        risk.track_order(&first);
+        risk.track_order(&limit(2, OrderSide::Buy, 1_000, 1, 1));
+        assert_eq!(
+            risk.check_order(&limit(3, OrderSide::Buy, 1_000, 1, 1), &book),
+            Err(RiskReject::OpenOrders { open: 2, limit: 2 })
+        );
+        first.status = OrderStatus::Filled;
+        risk.track_order(&first);
+        assert_eq!(risk.open_order_count(1), 1);
+
+        risk.on_trade(&trade(1, 2, 1_000, 8, DAY));
+        assert_eq!((risk.position(1, "BTC-USD"), risk.position(2, "BTC-USD")), (8, -8));
+        // The open buy of 1 counts as well
+        assert_eq!(
+            risk.check_order(&limit(4, OrderSide::Buy, 1_000, 3, 1), &book),
+            Err(RiskReject::Position { position: 12, limit: Quantity(10) })
+        );
+        // Selling reduces the position
+        assert!(risk.check_order(&limit(5, OrderSide::Sell, 1_000, 2, 1), &book).is_ok());
+
+        risk.on_trade(&trade(3, 2, 1_000, 10, DAY + 1));
+        assert_eq!((risk.daily_notional(2, DAY), risk.daily_notional(3, DAY)), (18_000, 10_000));
+        assert_eq!(
+            risk.check_order(&limit(6, OrderSide::Sell, 1_000, 3, 3), &book),
+            Err(RiskReject::DailyNotional { notional: 13_000, limit: 12_000 })
+        );
+        // The next day starts from zero
+        assert_eq!(risk.daily_notional(3, 2 * DAY), 0);
+        let mut tomorrow = limit(7, OrderSide::Sell, 1_000, 3, 3);
+        tomorrow.timestamp = 2 * DAY;
+        assert!(risk.check_order(&tomorrow, &book).is_ok());
+    }
+
+    #[test]
+    fn test_open_orders_count_towards_position_and_notional() {
+        let book = OrderBook::new("BTC-USD");
+        let mut risk = RiskManager::with_limits(RiskLimits::new().with_max_position(Quantity(10)).with_max_daily_notional(12_000));
+
+        let mut resting = limit(1, OrderSide::Buy, 1_000, 6, 1);
+        resting.status = OrderStatus::New;
+        risk.track_order(&resting);
+        assert_eq!(
+            risk.check_order(&limit(2, OrderSide::Buy, 1_000, 5, 1), &book),
+            Err(RiskReject::Position { position: 11, limit: Quantity(10) })
+        );
+        // Orders on the other side do not add to the position
+        assert!(risk.check_order(&limit(3, OrderSide::Sell, 1_000, 5, 1), &book).is_ok());
+        assert_eq!(
+            risk.check_order(&limit(4, OrderSide::Sell, 1_000, 7, 1), &book),
+            Err(RiskReject::DailyNotional { notional: 13_000, limit: 12_000 })
+        );
+
+        // An amendment replaces the order's own exposure
+        resting.quantity = Quantity(10);
+        resting.remaining_quantity = Quantity(10);
+        assert!(risk.check_amend(&resting, &book).is_ok());
+
+        // A partial fill moves exposure from the order to the position
+        resting.remaining_quantity = Quantity(2);
+        risk.track_order(&resting);
+        risk.on_trade(&trade(1, 2, 1_000, 4, DAY));
+        assert_eq!(
+            risk.check_order(&limit(5, OrderSide::Buy, 1_000, 5, 1), &book),
+            Err(RiskReject::Position { position: 11, limit: Quantity(10) })
+        );
+        assert!(risk.check_order(&limit(6, OrderSide::Buy, 1_000, 4, 1), &book).is_ok());
+
+        // Stop orders are valued at their stop price
+        let mut stop = Order::new(7, OrderType::Stop(Price(2_000)), 0, 3, OrderSide::Sell, 1, DAY, None, "BTC-USD".to_string());
+        stop.status = OrderStatus::New;
+        risk.track_order(&stop);
+        assert_eq!(
+            risk.check_order(&limit(8, OrderSide::Buy, 1_000, 1, 1), &book),
+            Err(RiskReject::DailyNotional { notional: 13_000, limit: 12_000 })
+        );
+    }
+
+    #[test]
+    fn test_adjustments_reverse_positions_and_notional() {
+        let mut risk = RiskManager::new();
+        let previous = trade(1, 2, 1_000, 8, DAY);
+        risk.on_trade(&previous);
+
+        let mut corrected = previous.clone();
+        corrected.quantity = Quantity(5);
+        let mut adjustment = TradeAdjustment {
+            id: 1,
+            trade_id: previous.id,
+            symbol: previous.symbol.clone(),
+            reason: String::new(),
+            timestamp: DAY + 1,
+            previous,
+            corrected: Some(corrected.clone()),
+            last_trade: None,
+        };
+        risk.on_adjustment(&adjustment);
+        assert_eq!((risk.position(1, "BTC-USD"), risk.position(2, "BTC-USD")), (5, -5));
+        assert_eq!(risk.daily_notional(1, DAY), 5_000);
+
+        adjustment.previous = corrected;
+        adjustment.corrected = None;
+        risk.on_adjustment(&adjustment);
+        assert_eq!((risk.position(1, "BTC-USD"), risk.daily_notional(2, DAY)), (0, 0));
+    }
+}
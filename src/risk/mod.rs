@@ -0,0 +1,7 @@
+// Export risk components
+pub mod limits;
+pub mod manager;
+
+// Re-export main components
+pub use limits::{RiskLimits, RiskReject, RiskScope};
+pub use manager::RiskManager;
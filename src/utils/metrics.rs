@@ -37,7 +37,7 @@ impl Histogram {
         // Update counts
         *self.counts.entry(bucket).or_insert(0) += 1;
         self.count += 1;
-        self.sum += value;
+        self.sum = self.sum.saturating_add(value);
         
         // Update min/max
         self.min = match self.min {
@@ -137,7 +137,7 @@ impl Histogram {
         }
         
         self.count += other.count;
-        self.sum += other.sum;
+        self.sum = self.sum.saturating_add(other.sum);
         
         self.min = match (self.min, other.min) {
             (None, None) => None,
@@ -193,6 +193,11 @@ impl Timer {
         }
     }
     
+    /// Returns the name of the timer
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    
     /// Stops the timer and records the elapsed time
     pub fn stop(self) -> Duration {
         let elapsed = self.start.elapsed();
@@ -249,7 +254,7 @@ impl MetricsRegistry {
     
     /// Resets all metrics
     pub fn reset(&mut self) {
-        for (_, histogram) in &self.histograms {
+        for histogram in self.histograms.values() {
             if let Ok(mut histogram) = histogram.lock() {
                 *histogram = Histogram::new();
             }
@@ -387,6 +392,6 @@ mod tests {
         assert_eq!(hist.bucket_for(7), 4);
         assert_eq!(hist.bucket_for(8), 8);
         assert_eq!(hist.bucket_for(100), 64);
-        assert_eq!(hist.bucket_for(1000), 1024);
+        assert_eq!(hist.bucket_for(1000), 512);
     }
 }
@@ -1,5 +1,5 @@
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, Utc};
 
 /// Returns the current timestamp in nanoseconds
 pub fn current_timestamp_nanos() -> u64 {
@@ -38,10 +38,8 @@ pub fn format_timestamp_nanos(timestamp: u64) -> String {
     let secs = (timestamp / 1_000_000_000) as i64;
     let nsecs = (timestamp % 1_000_000_000) as u32;
     
-    let naive = NaiveDateTime::from_timestamp_opt(secs, nsecs)
+    let datetime: DateTime<Utc> = DateTime::from_timestamp(secs, nsecs)
         .expect("Invalid timestamp");
-    
-    let datetime: DateTime<Utc> = DateTime::from_utc(naive, Utc);
     datetime.format("%Y-%m-%d %H:%M:%S.%f").to_string()
 }
 
@@ -50,10 +48,8 @@ pub fn format_timestamp_millis(timestamp: u64) -> String {
     let secs = (timestamp / 1_000) as i64;
     let nsecs = ((timestamp % 1_000) * 1_000_000) as u32;
     
-    let naive = NaiveDateTime::from_timestamp_opt(secs, nsecs)
+    let datetime: DateTime<Utc> = DateTime::from_timestamp(secs, nsecs)
         .expect("Invalid timestamp");
-    
-    let datetime: DateTime<Utc> = DateTime::from_utc(naive, Utc);
     datetime.format("%Y-%m-%d %H:%M:%S.%f").to_string()
 }
 
@@ -69,6 +65,40 @@ where
     (result, elapsed)
 }
 
+/// A manually driven clock used when replaying historical data.
+///
+/// Time only moves forward: advancing to an earlier timestamp leaves the
+/// clock where it is, so replayed orders keep a consistent time priority.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SimulatedClock {
+    /// Current simulated time in nanoseconds
+    now: u64,
+}
+
+impl SimulatedClock {
+    /// Creates a new simulated clock starting at the given timestamp
+    pub fn new(start: u64) -> Self {
+        Self { now: start }
+    }
+    
+    /// Returns the current simulated timestamp in nanoseconds
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+    
+    /// Moves the clock forward to the given timestamp and returns the new time
+    pub fn advance_to(&mut self, timestamp: u64) -> u64 {
+        self.now = self.now.max(timestamp);
+        self.now
+    }
+    
+    /// Moves the clock forward by the given number of nanoseconds
+    pub fn advance_by(&mut self, nanos: u64) -> u64 {
+        self.now = self.now.saturating_add(nanos);
+        self.now
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,4 +136,17 @@ mod tests {
         assert_eq!(result, 42);
         assert!(duration.as_millis() >= 5);
     }
+    
+    #[test]
+    fn test_simulated_clock() {
+        let mut clock = SimulatedClock::new(1_000);
+        assert_eq!(clock.now(), 1_000);
+        
+        assert_eq!(clock.advance_to(2_000), 2_000);
+        
+        // The clock never moves backwards
+        assert_eq!(clock.advance_to(1_500), 2_000);
+        
+        assert_eq!(clock.advance_by(250), 2_250);
+    }
 }
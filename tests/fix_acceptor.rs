@@ -0,0 +1,444 @@
+//! Drives the FIX acceptor over loopback TCP with a minimal FIX 4.4 initiator
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use rustflow::fix::message::{msg_type, sending_time, tags};
+use rustflow::fix::{FixAcceptor, FixConfig, FixMessage, FixReader};
+use rustflow::{EngineHandle, Instrument};
+
+const ACCEPTOR: &str = "RUSTFLOW";
+
+struct Initiator {
+    comp_id: String,
+    reader: FixReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+    next_seq_num: u64,
+}
+
+impl Initiator {
+    async fn connect(addr: SocketAddr, comp_id: &str) -> Self {
+        let (reader, writer) = TcpStream::connect(addr).await.unwrap().into_split();
+        Self {
+            comp_id: comp_id.to_string(),
+            reader: FixReader::new(reader),
+            writer,
+            next_seq_num: 1,
+        }
+    }
+
+    /// Connects and logs on, returning the acceptor's Logon reply
+    async fn logon(addr: SocketAddr, comp_id: &str, next_seq_num: u64) -> (Self, FixMessage) {
+        let mut initiator = Self::connect(addr, comp_id).await;
+        initiator.next_seq_num = next_seq_num;
+        initiator.send(logon(30)).await;
+        let reply = initiator.recv().await;
+        assert_eq!(reply.msg_type(), msg_type::LOGON, "{}", reply);
+        (initiator, reply)
+    }
+
+    async fn send(&mut self, message: FixMessage) {
+        let seq_num = self.next_seq_num;
+        self.next_seq_num += 1;
+        self.send_as(message, seq_num).await;
+    }
+
+    async fn send_as(&mut self, mut message: FixMessage, seq_num: u64) {
+        message.set_header(&self.comp_id, ACCEPTOR, seq_num, &sending_time());
+        self.writer.write_all(&message.encode()).await.unwrap();
+    }
+
+    async fn recv(&mut self) -> FixMessage {
+        tokio::time::timeout(Duration::from_secs(5), self.reader.next_message())
+            .await
+            .expect("Timed out waiting for the acceptor")
+            .unwrap()
+            .expect("Acceptor closed the connection")
+    }
+
+    /// Receives the next message of a type, skipping heartbeats
+    async fn expect(&mut self, expected: &str) -> FixMessage {
+        loop {
+            let message = self.recv().await;
+            if message.msg_type() == expected {
+                return message;
+            }
+            assert_eq!(message.msg_type(), msg_type::HEARTBEAT, "Expected {}, got {}", expected, message);
+        }
+    }
+
+    async fn expect_report(&mut self, exec_type: &str) -> FixMessage {
+        let report = self.expect(msg_type::EXECUTION_REPORT).await;
+        assert_eq!(report.get(tags::EXEC_TYPE), Some(exec_type), "{}", report);
+        report
+    }
+
+    async fn expect_closed(&mut self) {
+        let next = tokio::time::timeout(Duration::from_secs(5), self.reader.next_message())
+            .await
+            .expect("Timed out waiting for the acceptor to disconnect");
+        assert!(matches!(next, Ok(None) | Err(_)), "Expected disconnect, got {:?}", next);
+    }
+}
+
+fn logon(heart_bt_int: u64) -> FixMessage {
+    FixMessage::new(msg_type::LOGON)
+        .with(tags::ENCRYPT_METHOD, 0)
+        .with(tags::HEART_BT_INT, heart_bt_int)
+}
+
+fn limit_order(cl_ord_id: &str, side: &str, price: &str, quantity: &str, time_in_force: &str) -> FixMessage {
+    FixMessage::new(msg_type::NEW_ORDER_SINGLE)
+        .with(tags::CL_ORD_ID, cl_ord_id)
+        .with(tags::SYMBOL, "BTC-USD")
+        .with(tags::SIDE, side)
+        .with(tags::TRANSACT_TIME, sending_time())
+        .with(tags::ORDER_QTY, quantity)
+        .with(tags::ORD_TYPE, "2")
+        .with(tags::PRICE, price)
+        .with(tags::TIME_IN_FORCE, time_in_force)
+}
+
+fn config() -> FixConfig {
+    FixConfig::new(ACCEPTOR)
+        .with_counterparty("MAKER", 1)
+        .with_counterparty("TAKER", 2)
+        .with_instrument(Instrument::with_precision("BTC-USD", 2, 4).unwrap())
+}
+
+async fn start_acceptor(config: FixConfig) -> (SocketAddr, JoinHandle<std::io::Result<()>>) {
+    let acceptor = FixAcceptor::new(EngineHandle::spawn(), config);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let task = tokio::spawn(async move { acceptor.serve(listener).await });
+    (addr, task)
+}
+
+#[tokio::test]
+async fn test_logon_test_request_and_logout() {
+    let (addr, _) = start_acceptor(config()).await;
+
+    let mut initiator = Initiator::connect(addr, "MAKER").await;
+    initiator.send(logon(30).with(tags::RESET_SEQ_NUM_FLAG, "Y")).await;
+    let reply = initiator.recv().await;
+    assert_eq!(reply.msg_type(), msg_type::LOGON);
+    assert_eq!(reply.seq_num(), Some(1));
+    assert_eq!(reply.get(tags::SENDER_COMP_ID), Some(ACCEPTOR));
+    assert_eq!(reply.get(tags::TARGET_COMP_ID), Some("MAKER"));
+    assert_eq!(reply.get(tags::HEART_BT_INT), Some("30"));
+    assert_eq!(reply.get(tags::RESET_SEQ_NUM_FLAG), Some("Y"));
+
+    initiator
+        .send(FixMessage::new(msg_type::TEST_REQUEST).with(tags::TEST_REQ_ID, "ping"))
+        .await;
+    let heartbeat = initiator.recv().await;
+    assert_eq!(heartbeat.msg_type(), msg_type::HEARTBEAT);
+    assert_eq!(heartbeat.get(tags::TEST_REQ_ID), Some("ping"));
+    assert_eq!(heartbeat.seq_num(), Some(2));
+
+    initiator.send(FixMessage::new(msg_type::LOGOUT)).await;
+    assert_eq!(initiator.recv().await.msg_type(), msg_type::LOGOUT);
+    initiator.expect_closed().await;
+}
+
+#[tokio::test]
+async fn test_unknown_counterparty_is_disconnected() {
+    let (addr, _) = start_acceptor(config()).await;
+
+    let mut initiator = Initiator::connect(addr, "STRANGER").await;
+    initiator.send(logon(30)).await;
+    initiator.expect_closed().await;
+}
+
+#[tokio::test]
+async fn test_heartbeats_and_test_requests_on_idle_sessions() {
+    let (addr, _) = start_acceptor(config()).await;
+
+    let mut initiator = Initiator::connect(addr, "MAKER").await;
+    initiator.send(logon(1)).await;
+    assert_eq!(initiator.recv().await.msg_type(), msg_type::LOGON);
+
+    // A silent counterparty first gets heartbeats, then a TestRequest
+    assert_eq!(initiator.recv().await.msg_type(), msg_type::HEARTBEAT);
+    let test_request = initiator.expect(msg_type::TEST_REQUEST).await;
+    let test_req_id = test_request.get(tags::TEST_REQ_ID).unwrap().to_string();
+    initiator
+        .send(FixMessage::new(msg_type::HEARTBEAT).with(tags::TEST_REQ_ID, test_req_id))
+        .await;
+
+    initiator.send(FixMessage::new(msg_type::LOGOUT)).await;
+    initiator.expect(msg_type::LOGOUT).await;
+}
+
+#[tokio::test]
+async fn test_fills_cancels_and_rejects_are_reported() {
+    let (addr, _) = start_acceptor(config()).await;
+    let (mut maker, _) = Initiator::logon(addr, "MAKER", 1).await;
+    let (mut taker, _) = Initiator::logon(addr, "TAKER", 1).await;
+
+    maker.send(limit_order("m-1", "2", "101.25", "1.5", "1")).await;
+    let resting = maker.expect_report("0").await;
+    assert_eq!(resting.get(tags::CL_ORD_ID), Some("m-1"));
+    assert_eq!(resting.get(tags::ORD_STATUS), Some("0"));
+    assert_eq!(resting.get(tags::LEAVES_QTY), Some("1.5000"));
+    let maker_order_id = resting.get(tags::ORDER_ID).unwrap().to_string();
+
+    // IOC buy for 2: fills 1.5 and cancels the rest
+    taker.send(limit_order("t-1", "1", "101.25", "2", "3")).await;
+    assert_eq!(taker.expect_report("0").await.get(tags::ORDER_QTY), Some("2.0000"));
+    let fill = taker.expect_report("F").await;
+    assert_eq!(fill.get(tags::ORD_STATUS), Some("1"));
+    assert_eq!(fill.get(tags::LAST_PX), Some("101.25"));
+    assert_eq!(fill.get(tags::LAST_QTY), Some("1.5000"));
+    assert_eq!(fill.get(tags::CUM_QTY), Some("1.5000"));
+    assert_eq!(fill.get(tags::AVG_PX), Some("101.25"));
+    let canceled = taker.expect_report("4").await;
+    assert_eq!(canceled.get(tags::ORD_STATUS), Some("4"));
+    assert_eq!(canceled.get(tags::LEAVES_QTY), Some("0.0000"));
+    assert_eq!(canceled.get(tags::CUM_QTY), Some("1.5000"));
+
+    let maker_fill = maker.expect_report("F").await;
+    assert_eq!(maker_fill.get(tags::ORDER_ID), Some(maker_order_id.as_str()));
+    assert_eq!(maker_fill.get(tags::ORD_STATUS), Some("2"));
+    assert_eq!(maker_fill.get(tags::LEAVES_QTY), Some("0.0000"));
+
+    // Business rejects come back as ExecutionReports
+    taker.send(limit_order("t-1", "1", "100", "1", "1")).await;
+    let duplicate = taker.expect_report("8").await;
+    assert_eq!(duplicate.get(tags::ORD_REJ_REASON), Some("6"));
+    assert_eq!(duplicate.get(tags::ORDER_ID), Some("NONE"));
+    taker.send(limit_order("t-2", "1", "100", "0", "1")).await;
+    assert_eq!(taker.expect_report("8").await.get(tags::ORD_REJ_REASON), Some("13"));
+
+    // Malformed messages get session-level Rejects
+    let mut missing_qty = limit_order("t-3", "1", "100", "1", "1");
+    missing_qty.remove(tags::ORDER_QTY);
+    taker.send(missing_qty).await;
+    let reject = taker.expect(msg_type::REJECT).await;
+    assert_eq!(reject.get(tags::REF_TAG_ID), Some("38"));
+    assert_eq!(reject.get(tags::SESSION_REJECT_REASON), Some("1"));
+    taker.send(limit_order("t-4", "7", "100", "1", "1")).await;
+    assert_eq!(taker.expect(msg_type::REJECT).await.get(tags::REF_TAG_ID), Some("54"));
+    taker.send(FixMessage::new("ZZ")).await;
+    assert_eq!(
+        taker.expect(msg_type::REJECT).await.get(tags::SESSION_REJECT_REASON),
+        Some("11")
+    );
+}
+
+#[tokio::test]
+async fn test_cancel_and_replace() {
+    let (addr, _) = start_acceptor(config()).await;
+    let (mut initiator, _) = Initiator::logon(addr, "MAKER", 1).await;
+
+    initiator.send(limit_order("a-1", "1", "99.50", "2", "0")).await;
+    let order_id = initiator.expect_report("0").await.get(tags::ORDER_ID).unwrap().to_string();
+
+    let replace = FixMessage::new(msg_type::ORDER_CANCEL_REPLACE_REQUEST)
+        .with(tags::ORIG_CL_ORD_ID, "a-1")
+        .with(tags::CL_ORD_ID, "a-2")
+        .with(tags::SYMBOL, "BTC-USD")
+        .with(tags::SIDE, "1")
+        .with(tags::ORDER_QTY, "3")
+        .with(tags::ORD_TYPE, "2")
+        .with(tags::PRICE, "99.75");
+    initiator.send(replace.clone()).await;
+    let replaced = initiator.expect_report("5").await;
+    assert_eq!(replaced.get(tags::ORDER_ID), Some(order_id.as_str()));
+    assert_eq!(replaced.get(tags::CL_ORD_ID), Some("a-2"));
+    assert_eq!(replaced.get(tags::ORIG_CL_ORD_ID), Some("a-1"));
+    assert_eq!(replaced.get(tags::PRICE), Some("99.75"));
+    assert_eq!(replaced.get(tags::LEAVES_QTY), Some("3.0000"));
+
+    // The old ClOrdID can no longer be reused
+    initiator.send(replace).await;
+    let reject = initiator.expect(msg_type::ORDER_CANCEL_REJECT).await;
+    assert_eq!(reject.get(tags::CXL_REJ_REASON), Some("6"));
+    assert_eq!(reject.get(tags::CXL_REJ_RESPONSE_TO), Some("2"));
+
+    let cancel = |orig_cl_ord_id: &str, cl_ord_id: &str| {
+        FixMessage::new(msg_type::ORDER_CANCEL_REQUEST)
+            .with(tags::ORIG_CL_ORD_ID, orig_cl_ord_id)
+            .with(tags::CL_ORD_ID, cl_ord_id)
+            .with(tags::SYMBOL, "BTC-USD")
+            .with(tags::SIDE, "1")
+    };
+    initiator.send(cancel("a-2", "a-3")).await;
+    let canceled = initiator.expect_report("4").await;
+    assert_eq!(canceled.get(tags::CL_ORD_ID), Some("a-3"));
+    assert_eq!(canceled.get(tags::ORD_STATUS), Some("4"));
+
+    initiator.send(cancel("a-3", "a-4")).await;
+    let too_late = initiator.expect(msg_type::ORDER_CANCEL_REJECT).await;
+    assert_eq!(too_late.get(tags::CXL_REJ_REASON), Some("0"));
+    assert_eq!(too_late.get(tags::ORD_STATUS), Some("4"));
+    assert_eq!(too_late.get(tags::CXL_REJ_RESPONSE_TO), Some("1"));
+
+    initiator.send(cancel("missing", "a-5")).await;
+    let unknown = initiator.expect(msg_type::ORDER_CANCEL_REJECT).await;
+    assert_eq!(unknown.get(tags::CXL_REJ_REASON), Some("1"));
+    assert_eq!(unknown.get(tags::ORDER_ID), Some("NONE"));
+}
+
+#[tokio::test]
+async fn test_resend_request_replays_reports_and_gap_fills_admin() {
+    let (addr, _) = start_acceptor(config()).await;
+    let (mut initiator, _) = Initiator::logon(addr, "MAKER", 1).await;
+
+    // Seq 1 is the Logon, 2 and 3 are reports, 4 is a heartbeat
+    initiator.send(limit_order("r-1", "1", "99", "1", "0")).await;
+    let first = initiator.expect_report("0").await;
+    initiator.send(limit_order("r-2", "1", "98", "1", "0")).await;
+    let second = initiator.expect_report("0").await;
+    initiator.send(FixMessage::new(msg_type::TEST_REQUEST).with(tags::TEST_REQ_ID, "t")).await;
+    assert_eq!(initiator.recv().await.seq_num(), Some(4));
+
+    initiator
+        .send(
+            FixMessage::new(msg_type::RESEND_REQUEST)
+                .with(tags::BEGIN_SEQ_NO, 1)
+                .with(tags::END_SEQ_NO, 0),
+        )
+        .await;
+
+    let gap_fill = initiator.recv().await;
+    assert_eq!(gap_fill.msg_type(), msg_type::SEQUENCE_RESET);
+    assert_eq!(gap_fill.seq_num(), Some(1));
+    assert_eq!(gap_fill.get(tags::GAP_FILL_FLAG), Some("Y"));
+    assert_eq!(gap_fill.get(tags::NEW_SEQ_NO), Some("2"));
+
+    for original in [first, second] {
+        let resent = initiator.recv().await;
+        assert_eq!(resent.seq_num(), original.seq_num());
+        assert_eq!(resent.get(tags::POSS_DUP_FLAG), Some("Y"));
+        assert_eq!(resent.get(tags::ORIG_SENDING_TIME), original.get(tags::SENDING_TIME));
+        assert_eq!(resent.get(tags::CL_ORD_ID), original.get(tags::CL_ORD_ID));
+    }
+
+    let trailing = initiator.recv().await;
+    assert_eq!(trailing.msg_type(), msg_type::SEQUENCE_RESET);
+    assert_eq!(trailing.seq_num(), Some(4));
+    assert_eq!(trailing.get(tags::NEW_SEQ_NO), Some("5"));
+}
+
+#[tokio::test]
+async fn test_sequence_gap_is_recovered() {
+    let (addr, _) = start_acceptor(config()).await;
+    let (mut initiator, _) = Initiator::logon(addr, "MAKER", 1).await;
+
+    // Seq 2..4 are "lost"; the acceptor queues 5 and asks for the gap
+    initiator
+        .send_as(FixMessage::new(msg_type::TEST_REQUEST).with(tags::TEST_REQ_ID, "after-gap"), 5)
+        .await;
+    let resend_request = initiator.recv().await;
+    assert_eq!(resend_request.msg_type(), msg_type::RESEND_REQUEST);
+    assert_eq!(resend_request.get(tags::BEGIN_SEQ_NO), Some("2"));
+    assert_eq!(resend_request.get(tags::END_SEQ_NO), Some("0"));
+
+    // Replay seq 2 as an order and gap fill 3..4; the queued TestRequest then runs
+    initiator
+        .send_as(limit_order("g-1", "1", "99", "1", "0").with(tags::POSS_DUP_FLAG, "Y"), 2)
+        .await;
+    assert_eq!(initiator.expect_report("0").await.get(tags::CL_ORD_ID), Some("g-1"));
+    initiator
+        .send_as(
+            FixMessage::new(msg_type::SEQUENCE_RESET)
+                .with(tags::POSS_DUP_FLAG, "Y")
+                .with(tags::GAP_FILL_FLAG, "Y")
+                .with(tags::NEW_SEQ_NO, 5),
+            3,
+        )
+        .await;
+    let heartbeat = initiator.recv().await;
+    assert_eq!(heartbeat.msg_type(), msg_type::HEARTBEAT);
+    assert_eq!(heartbeat.get(tags::TEST_REQ_ID), Some("after-gap"));
+
+    // Normal flow resumes at 6; a duplicate below that is a fatal error
+    initiator.next_seq_num = 6;
+    initiator.send(FixMessage::new(msg_type::TEST_REQUEST).with(tags::TEST_REQ_ID, "x")).await;
+    assert_eq!(initiator.recv().await.get(tags::TEST_REQ_ID), Some("x"));
+    initiator.send_as(FixMessage::new(msg_type::HEARTBEAT), 3).await;
+    let logout = initiator.recv().await;
+    assert_eq!(logout.msg_type(), msg_type::LOGOUT);
+    assert!(logout.get(tags::TEXT).unwrap().starts_with("MsgSeqNum too low"));
+    initiator.expect_closed().await;
+}
+
+#[tokio::test]
+async fn test_reports_for_disconnected_sessions_are_resent() {
+    let (addr, _) = start_acceptor(config()).await;
+    let (mut maker, _) = Initiator::logon(addr, "MAKER", 1).await;
+    maker.send(limit_order("m-1", "2", "100", "1", "0")).await;
+    maker.expect_report("0").await;
+    maker.send(FixMessage::new(msg_type::LOGOUT)).await;
+    maker.expect(msg_type::LOGOUT).await;
+    maker.expect_closed().await;
+
+    let (mut taker, _) = Initiator::logon(addr, "TAKER", 1).await;
+    taker.send(limit_order("t-1", "1", "100", "1", "0")).await;
+    taker.expect_report("0").await;
+    taker.expect_report("F").await;
+
+    // The maker's fill was stored at seq 4, so the Logon reply is seq 5
+    let (mut maker, reply) = Initiator::logon(addr, "MAKER", maker.next_seq_num).await;
+    assert_eq!(reply.seq_num(), Some(5));
+    maker
+        .send(
+            FixMessage::new(msg_type::RESEND_REQUEST)
+                .with(tags::BEGIN_SEQ_NO, 4)
+                .with(tags::END_SEQ_NO, 4),
+        )
+        .await;
+    let fill = maker.recv().await;
+    assert_eq!(fill.seq_num(), Some(4));
+    assert_eq!(fill.get(tags::EXEC_TYPE), Some("F"));
+    assert_eq!(fill.get(tags::CL_ORD_ID), Some("m-1"));
+    assert_eq!(fill.get(tags::POSS_DUP_FLAG), Some("Y"));
+}
+
+#[tokio::test]
+async fn test_sequence_numbers_survive_restart() {
+    let dir = std::env::temp_dir().join(format!("rustflow_fix_acceptor_{}", std::process::id()));
+    let dir = dir.to_str().unwrap().to_string();
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let (addr, acceptor) = start_acceptor(config().with_store_dir(&dir)).await;
+    let (mut initiator, _) = Initiator::logon(addr, "MAKER", 1).await;
+    initiator.send(limit_order("p-1", "1", "99", "1", "0")).await;
+    let report = initiator.expect_report("0").await;
+    assert_eq!(report.seq_num(), Some(2));
+    initiator.send(FixMessage::new(msg_type::LOGOUT)).await;
+    initiator.expect(msg_type::LOGOUT).await;
+    initiator.expect_closed().await;
+    acceptor.abort();
+
+    // A fresh acceptor continues both sequences from disk
+    let (addr, _) = start_acceptor(config().with_store_dir(&dir)).await;
+    let mut stale = Initiator::connect(addr, "MAKER").await;
+    stale.send(logon(30)).await;
+    let logout = stale.recv().await;
+    assert_eq!(logout.msg_type(), msg_type::LOGOUT);
+    assert_eq!(logout.seq_num(), Some(4));
+    assert!(logout.get(tags::TEXT).unwrap().contains("expecting 4"));
+    stale.expect_closed().await;
+
+    let (mut initiator, reply) = Initiator::logon(addr, "MAKER", 4).await;
+    assert_eq!(reply.seq_num(), Some(5));
+    initiator
+        .send(
+            FixMessage::new(msg_type::RESEND_REQUEST)
+                .with(tags::BEGIN_SEQ_NO, 2)
+                .with(tags::END_SEQ_NO, 2),
+        )
+        .await;
+    let resent = initiator.recv().await;
+    assert_eq!(resent.get(tags::CL_ORD_ID), Some("p-1"));
+    assert_eq!(resent.get(tags::POSS_DUP_FLAG), Some("Y"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,181 @@
+//! Integration tests driving the gRPC server with the generated client
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::net::TcpListener;
+use tonic::transport::Channel;
+use tonic::{Code, Streaming};
+
+use rustflow::core::handle::EngineHandle;
+use rustflow::grpc::proto::{self, market_data_update::Update};
+use rustflow::grpc::{GrpcServer, MatchingEngineClient};
+
+async fn start() -> MatchingEngineClient<Channel> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr: SocketAddr = listener.local_addr().unwrap();
+    let server = GrpcServer::new(EngineHandle::spawn());
+    tokio::spawn(async move { server.serve(listener).await });
+
+    MatchingEngineClient::connect(format!("http://{}", addr)).await.unwrap()
+}
+
+fn limit(user_id: u64, side: proto::Side, price: u64, quantity: u64) -> proto::SubmitOrderRequest {
+    proto::SubmitOrderRequest {
+        user_id,
+        symbol: "BTC-USD".to_string(),
+        side: side.into(),
+        order_type: proto::OrderType::Limit.into(),
+        price,
+        stop_price: 0,
+        quantity,
+        client_order_id: None,
+    }
+}
+
+async fn next<T>(stream: &mut Streaming<T>) -> T {
+    tokio::time::timeout(Duration::from_secs(5), stream.message())
+        .await
+        .expect("Timed out waiting for a stream message")
+        .unwrap()
+        .expect("Stream ended")
+}
+
+#[tokio::test]
+async fn test_order_entry_calls() {
+    let mut client = start().await;
+
+    let resting = client.submit_order(limit(1, proto::Side::Sell, 10100, 5)).await.unwrap().into_inner();
+    let resting = resting.order.unwrap();
+    assert_eq!(resting.status(), proto::OrderStatus::New);
+
+    let mut ioc = limit(2, proto::Side::Buy, 10100, 2);
+    ioc.order_type = proto::OrderType::Ioc.into();
+    ioc.client_order_id = Some("c-2".to_string());
+    let fill = client.submit_order(ioc).await.unwrap().into_inner();
+    assert_eq!(fill.order.as_ref().unwrap().status(), proto::OrderStatus::Filled);
+    assert_eq!(fill.order.unwrap().client_order_id.as_deref(), Some("c-2"));
+    assert_eq!(fill.trades.len(), 1);
+    assert_eq!(fill.trades[0].sell_order_id, resting.id);
+
+    let request = proto::OrderRequest {
+        symbol: "BTC-USD".to_string(),
+        order_id: resting.id,
+    };
+    let order = client.get_order(request.clone()).await.unwrap().into_inner();
+    assert_eq!(order.remaining_quantity, 3);
+
+    let amend = proto::AmendOrderRequest {
+        symbol: "BTC-USD".to_string(),
+        order_id: resting.id,
+        price: Some(10200),
+        quantity: None,
+    };
+    let amended = client.amend_order(amend).await.unwrap().into_inner();
+    assert_eq!(amended.order.unwrap().price, 10200);
+
+    let book = client
+        .get_book(proto::BookRequest {
+            symbol: "BTC-USD".to_string(),
+            levels: 0,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(book.asks, vec![proto::PriceLevel { price: 10200, quantity: 3 }]);
+
+    let canceled = client.cancel_order(request.clone()).await.unwrap().into_inner();
+    assert_eq!(canceled.status(), proto::OrderStatus::Canceled);
+    let status = client.cancel_order(request).await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+
+    let missing = proto::OrderRequest {
+        symbol: "BTC-USD".to_string(),
+        order_id: 999,
+    };
+    assert_eq!(client.get_order(missing).await.unwrap_err().code(), Code::NotFound);
+    let status = client.submit_order(limit(1, proto::Side::Buy, 100, 0)).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    let status = client.submit_order(limit(1, proto::Side::Unspecified, 100, 1)).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_market_data_stream() {
+    let mut client = start().await;
+    client.submit_order(limit(1, proto::Side::Sell, 10100, 5)).await.unwrap();
+
+    let request = proto::MarketDataRequest {
+        symbol: "BTC-USD".to_string(),
+    };
+    let mut stream = client.subscribe_market_data(request).await.unwrap().into_inner();
+
+    // The stream starts with a snapshot of the book
+    match next(&mut stream).await.update {
+        Some(Update::Book(book)) => {
+            assert!(book.bids.is_empty());
+            assert_eq!(book.asks, vec![proto::PriceLevel { price: 10100, quantity: 5 }]);
+        }
+        other => panic!("Expected a book snapshot, got {:?}", other),
+    }
+
+    // Other symbols are filtered out
+    let mut eth = limit(3, proto::Side::Buy, 100, 1);
+    eth.symbol = "ETH-USD".to_string();
+    client.submit_order(eth).await.unwrap();
+
+    client.submit_order(limit(2, proto::Side::Buy, 10100, 2)).await.unwrap();
+    match next(&mut stream).await.update {
+        Some(Update::Trade(trade)) => {
+            assert_eq!(trade.symbol, "BTC-USD");
+            assert_eq!((trade.price, trade.quantity), (10100, 2));
+        }
+        other => panic!("Expected a trade, got {:?}", other),
+    }
+    match next(&mut stream).await.update {
+        Some(Update::Book(book)) => {
+            assert_eq!(book.symbol, "BTC-USD");
+            assert_eq!(book.asks, vec![proto::PriceLevel { price: 10100, quantity: 3 }]);
+        }
+        other => panic!("Expected a book update, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_execution_stream_reports_both_sides() {
+    let mut client = start().await;
+    let request = |user_id| proto::ExecutionsRequest { user_id };
+    let mut seller = client.subscribe_executions(request(1)).await.unwrap().into_inner();
+    let mut buyer = client.subscribe_executions(request(2)).await.unwrap().into_inner();
+
+    let resting = client.submit_order(limit(1, proto::Side::Sell, 10100, 5)).await.unwrap().into_inner();
+    let resting_id = resting.order.unwrap().id;
+    let report = next(&mut seller).await;
+    assert_eq!((report.order_id, report.status()), (resting_id, proto::OrderStatus::New));
+
+    let taker = client.submit_order(limit(2, proto::Side::Buy, 10100, 8)).await.unwrap().into_inner();
+    let taker_id = taker.order.unwrap().id;
+
+    // The incoming order fills and rests the remainder
+    let report = next(&mut buyer).await;
+    assert_eq!(report.order_id, taker_id);
+    assert_eq!(report.status(), proto::OrderStatus::PartiallyFilled);
+    assert_eq!((report.last_price, report.last_quantity), (Some(10100), Some(5)));
+    assert_eq!(report.leaves_quantity, 3);
+    assert_eq!(report.trade_id, Some(taker.trades[0].id));
+
+    // The resting order is reported as filled to its owner
+    let report = next(&mut seller).await;
+    assert_eq!(report.order_id, resting_id);
+    assert_eq!(report.status(), proto::OrderStatus::Filled);
+    assert_eq!(report.last_quantity, Some(5));
+
+    let cancel = proto::OrderRequest {
+        symbol: "BTC-USD".to_string(),
+        order_id: taker_id,
+    };
+    client.cancel_order(cancel).await.unwrap();
+    let report = next(&mut buyer).await;
+    assert_eq!((report.order_id, report.status()), (taker_id, proto::OrderStatus::Canceled));
+    assert_eq!(report.last_quantity, None);
+}